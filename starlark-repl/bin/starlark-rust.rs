@@ -24,7 +24,7 @@ use starlark::stdlib::global_environment_with_extensions;
 use starlark::syntax::dialect::Dialect;
 use starlark::syntax::parser::{parse, parse_file};
 use starlark::values::Value;
-use starlark_repl::{print_function, repl};
+use starlark_repl::repl;
 use std::process::exit;
 use std::sync::{Arc, Mutex};
 use structopt::clap::AppSettings;
@@ -77,9 +77,7 @@ fn main() {
     let command = opt.command;
     let ast = opt.ast;
 
-    let (mut global, mut type_values) = global_environment_with_extensions();
-
-    print_function(&mut global, &mut type_values);
+    let (mut global, type_values) = global_environment_with_extensions();
     global.freeze();
 
     let dialect = if opt.build_file {
@@ -38,9 +38,6 @@
 //! ```
 use codemap;
 
-#[macro_use]
-extern crate starlark;
-
 use codemap_diagnostic::{ColorConfig, Emitter};
 use linefeed::{Interface, ReadResult};
 use starlark::environment::{Environment, TypeValues};
@@ -49,8 +46,6 @@ use starlark::eval::simple::SimpleFileLoader;
 use starlark::syntax::dialect::Dialect;
 use starlark::syntax::lexer::{BufferedLexer, LexerIntoIter, LexerItem};
 use starlark::syntax::parser::parse_lexer;
-use starlark::values::none::NoneType;
-use starlark::values::Value;
 use std::env;
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
@@ -94,28 +89,6 @@ fn print_eval<T1: Iterator<Item = LexerItem>, T2: LexerIntoIter<T1>>(
     }
 }
 
-starlark_module! {print_function =>
-    /// print: print an object string representation to stderr.
-    ///
-    /// Examples:
-    /// ```python
-    /// print("some message")  # Will print "some message" to stderr
-    /// ```
-    print(*args) {
-        let mut r = String::new();
-        let mut first = true;
-        for arg in args {
-            if !first {
-                r.push_str(" ");
-            }
-            first = false;
-            r.push_str(&arg.to_str());
-        }
-        eprintln!("{}", r);
-        Ok(Value::new(NoneType::None))
-    }
-}
-
 /// A REPL (Read-Eval-Print Loop) for Starlark.
 ///
 /// This method run a REPL until the user hit Ctrl+D. It can be used for interactive use where the
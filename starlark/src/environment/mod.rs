@@ -17,10 +17,15 @@
 //! is the list of variable in the current scope. It can be frozen, after which all values from
 //! this environment become immutable.
 
-use crate::values::error::{RuntimeError, ValueError};
+use crate::eval::debug::{DebugAction, DebugFrame};
+use crate::eval::metrics::MetricsRecorder;
+#[cfg(feature = "provenance")]
+use crate::eval::provenance::ProvenanceRecorder;
+use crate::eval::trace::TraceEvent;
+use crate::values::error::{RuntimeError, ValueError, DEFAULT_MAX_ERROR_REPR_LENGTH};
 use crate::values::*;
 use std::cell::RefCell;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::rc::Rc;
 
 // TODO: move that code in some common error code list?
@@ -28,6 +33,9 @@ use std::rc::Rc;
 const FROZEN_ENV_ERROR_CODE: &str = "CM00";
 const NOT_FOUND_ERROR_CODE: &str = "CM01";
 const LOCAL_VARIABLE_REFERENCED_BEFORE_ASSIGNMENT: &str = "CM03";
+const CANNOT_SHADOW_BUILTIN_ERROR_CODE: &str = "CM04";
+const CANNOT_ASSIGN_PREDECLARED_ERROR_CODE: &str = "CM05";
+const DISABLED_BUILTIN_ERROR_CODE: &str = "CM06";
 pub(crate) const LOAD_NOT_SUPPORTED_ERROR_CODE: &str = "CM02";
 const CANNOT_IMPORT_ERROR_CODE: &str = "CE02";
 
@@ -41,6 +49,12 @@ pub enum EnvironmentError {
     LocalVariableReferencedBeforeAssignment(String),
     /// Cannot import private symbol, i.e. underscore prefixed
     CannotImportPrivateSymbol(String),
+    /// Assigning a variable defined in a parent environment, when shadowing is forbidden
+    CannotShadowBuiltin(String),
+    /// Assigning a predeclared name
+    CannotAssignPredeclared(String),
+    /// Referencing a variable of a parent environment which is disabled
+    BuiltinDisabled(String),
 }
 
 impl Into<RuntimeError> for EnvironmentError {
@@ -50,6 +64,11 @@ impl Into<RuntimeError> for EnvironmentError {
                 EnvironmentError::TryingToMutateFrozenEnvironment => FROZEN_ENV_ERROR_CODE,
                 EnvironmentError::VariableNotFound(..) => NOT_FOUND_ERROR_CODE,
                 EnvironmentError::CannotImportPrivateSymbol(..) => CANNOT_IMPORT_ERROR_CODE,
+                EnvironmentError::CannotShadowBuiltin(..) => CANNOT_SHADOW_BUILTIN_ERROR_CODE,
+                EnvironmentError::CannotAssignPredeclared(..) => {
+                    CANNOT_ASSIGN_PREDECLARED_ERROR_CODE
+                }
+                EnvironmentError::BuiltinDisabled(..) => DISABLED_BUILTIN_ERROR_CODE,
                 EnvironmentError::LocalVariableReferencedBeforeAssignment(..) => {
                     LOCAL_VARIABLE_REFERENCED_BEFORE_ASSIGNMENT
                 }
@@ -65,6 +84,13 @@ impl Into<RuntimeError> for EnvironmentError {
                 EnvironmentError::CannotImportPrivateSymbol(ref s) => {
                     format!("Symbol '{}' is private", s)
                 }
+                EnvironmentError::CannotShadowBuiltin(ref s) => {
+                    format!("'{}' is a builtin", s)
+                }
+                EnvironmentError::CannotAssignPredeclared(ref s) => {
+                    format!("'{}' is predeclared", s)
+                }
+                EnvironmentError::BuiltinDisabled(..) => "Disabled builtin".to_owned(),
             },
             message: match self {
                 EnvironmentError::TryingToMutateFrozenEnvironment => {
@@ -77,6 +103,15 @@ impl Into<RuntimeError> for EnvironmentError {
                 EnvironmentError::CannotImportPrivateSymbol(s) => {
                     format!("Cannot import private symbol '{}'", s)
                 }
+                EnvironmentError::CannotShadowBuiltin(s) => {
+                    format!("Cannot assign to '{}' which shadows a builtin", s)
+                }
+                EnvironmentError::CannotAssignPredeclared(s) => {
+                    format!("Cannot assign to predeclared name '{}'", s)
+                }
+                EnvironmentError::BuiltinDisabled(s) => {
+                    format!("'{}' is disabled in this context", s)
+                }
             },
         }
     }
@@ -105,24 +140,97 @@ struct EnvironmentContent {
     ///
     /// These bindings include methods for native types, e.g. `string.isalnum`.
     variables: HashMap<String, Value>,
+    /// Read-only bindings, looked up after `variables` and before the parent environment.
+    predeclared: HashMap<String, Value>,
+    /// Names of the `variables` bound by `load` statements and not reassigned since.
+    imported: HashSet<String>,
     /// Optional function which can be used to construct set literals (i.e. `{foo, bar}`).
     /// If not set, attempts to use set literals will raise an error.
-    set_constructor: SetConstructor,
+    set_constructor: OptionalHook<SetConstructorFn>,
+    /// Optional function applied to each `load` path before it is passed to the file loader.
+    load_path_rewriter: OptionalHook<LoadPathRewriterFn>,
+    /// Optional function called for each statement evaluated in this environment.
+    tracer: OptionalHook<TracerFn>,
+    /// Optional function called before each statement evaluated in this environment.
+    debug_hook: OptionalHook<DebugHookFn>,
+    /// Optional function receiving the output of `print()` instead of stderr.
+    print_handler: OptionalHook<PrintHandlerFn>,
+    /// Optional function called for each value made immutable when this environment is frozen.
+    freeze_observer: OptionalHook<FreezeObserverFn>,
+    /// Optional recorder of where mutable values are created and mutated.
+    #[cfg(feature = "provenance")]
+    provenance_recorder: Option<ProvenanceRecorder>,
+    /// Optional recorder of the resources used by evaluations in this environment.
+    metrics_recorder: Option<MetricsRecorder>,
+    /// Maximum length of the representations of values in error messages.
+    max_error_repr_length: Option<usize>,
+    /// When set, assigning a variable already defined in a parent environment (e.g. a builtin
+    /// like `list`) is an error instead of shadowing it.
+    forbid_shadowing: bool,
+    /// Restriction of the variables of the parent environments which can be referenced.
+    builtin_filter: Option<BuiltinFilter>,
+}
+
+/// The names of the variables of the parent environments which can be referenced from an
+/// environment, see [`Environment::deny_builtins`] and [`Environment::allow_only_builtins`].
+#[derive(Debug)]
+enum BuiltinFilter {
+    Allow(HashSet<String>),
+    Deny(HashSet<String>),
+}
+
+impl BuiltinFilter {
+    fn allows(&self, name: &str) -> bool {
+        match self {
+            BuiltinFilter::Allow(names) => names.contains(name),
+            BuiltinFilter::Deny(names) => !names.contains(name),
+        }
+    }
 }
 
-// Newtype so that EnvironmentContent can derive Debug.
-struct SetConstructor(Option<Box<dyn Fn(Vec<Value>) -> ValueResult>>);
+/// An optional function, e.g. a hook called during evaluation, which can be formatted with
+/// `Debug` so that EnvironmentContent and CallStack can derive it.
+#[derive(Clone)]
+pub(crate) struct OptionalHook<T>(pub(crate) Option<T>);
 
-impl std::fmt::Debug for SetConstructor {
+impl<T> Default for OptionalHook<T> {
+    fn default() -> Self {
+        OptionalHook(None)
+    }
+}
+
+impl<T> std::fmt::Debug for OptionalHook<T> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
         if self.0.is_some() {
-            write!(f, "<set constructor>")
+            write!(f, "Some(<function>)")
         } else {
-            write!(f, "<no set constructor>")
+            write!(f, "None")
         }
     }
 }
 
+type SetConstructorFn = Box<dyn Fn(Vec<Value>) -> ValueResult>;
+
+type LoadPathRewriterFn = Box<dyn Fn(&str) -> String>;
+
+pub(crate) type TracerFn = Rc<dyn Fn(&TraceEvent)>;
+
+pub(crate) type PrintHandlerFn = Rc<dyn Fn(&str)>;
+
+pub(crate) type FreezeObserverFn = Rc<dyn Fn(&FreezeEvent)>;
+
+/// Description of a value made immutable by [`Environment::freeze`], passed to the function
+/// set with [`Environment::with_freeze_observer`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FreezeEvent {
+    /// Type of the value, as returned by [`Value::get_type`].
+    pub value_type: &'static str,
+    /// Length of the value, `None` if it has no length.
+    pub length: Option<i64>,
+}
+
+pub(crate) type DebugHookFn = Rc<dyn Fn(&DebugFrame) -> DebugAction>;
+
 impl Environment {
     /// Create a new environment
     pub fn new(name: &str) -> Environment {
@@ -132,7 +240,20 @@ impl Environment {
                 frozen: false,
                 parent: None,
                 variables: HashMap::new(),
-                set_constructor: SetConstructor(None),
+                predeclared: HashMap::new(),
+                imported: HashSet::new(),
+                set_constructor: OptionalHook(None),
+                load_path_rewriter: OptionalHook(None),
+                tracer: OptionalHook(None),
+                debug_hook: OptionalHook(None),
+                print_handler: OptionalHook(None),
+                freeze_observer: OptionalHook(None),
+                #[cfg(feature = "provenance")]
+                provenance_recorder: None,
+                metrics_recorder: None,
+                max_error_repr_length: None,
+                forbid_shadowing: false,
+                builtin_filter: None,
             })),
         }
     }
@@ -146,7 +267,20 @@ impl Environment {
                 frozen: false,
                 parent: Some(self.clone()),
                 variables: HashMap::new(),
-                set_constructor: SetConstructor(None),
+                predeclared: HashMap::new(),
+                imported: HashSet::new(),
+                set_constructor: OptionalHook(None),
+                load_path_rewriter: OptionalHook(None),
+                tracer: OptionalHook(None),
+                debug_hook: OptionalHook(None),
+                print_handler: OptionalHook(None),
+                freeze_observer: OptionalHook(None),
+                #[cfg(feature = "provenance")]
+                provenance_recorder: None,
+                metrics_recorder: None,
+                max_error_repr_length: None,
+                forbid_shadowing: false,
+                builtin_filter: None,
             })),
         }
     }
@@ -154,7 +288,8 @@ impl Environment {
     /// Create a new child environment
     /// Freeze the environment, all its value will become immutable after that
     pub fn freeze(&self) -> &Self {
-        self.env.borrow_mut().freeze();
+        let observer = self.freeze_observer();
+        self.env.borrow_mut().freeze(observer.as_ref());
         self
     }
 
@@ -173,6 +308,38 @@ impl Environment {
         self.env.borrow().get(name)
     }
 
+    /// Declare a read-only binding of `name` to `value` in that environment, e.g. for
+    /// constants describing the context of an evaluation like Bazel's predeclared names.
+    ///
+    /// A predeclared name is resolved after the variables of the environment and before the
+    /// variables of its parents (e.g. builtins). Assigning it in this environment is an error
+    /// and it is not listed by [`names`](Environment::names). The value is frozen.
+    pub fn predeclare(&self, name: &str, value: Value) -> Result<(), EnvironmentError> {
+        self.env.borrow_mut().predeclare(name, value)
+    }
+
+    /// The names of the variables set in this environment, sorted, excluding predeclared
+    /// names and the variables of the parents.
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.env.borrow().variables.keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    /// The names of the variables assigned in this environment, sorted, i.e. the
+    /// [`names`](Environment::names) excluding those bound by `load` statements.
+    pub fn assigned_names(&self) -> Vec<String> {
+        let env = self.env.borrow();
+        let mut names: Vec<String> = env
+            .variables
+            .keys()
+            .filter(|n| !env.imported.contains(*n))
+            .cloned()
+            .collect();
+        names.sort();
+        names
+    }
+
     pub fn import_symbol(
         &self,
         env: &Environment,
@@ -184,7 +351,11 @@ impl Environment {
             Some('_') | None => Err(EnvironmentError::CannotImportPrivateSymbol(
                 symbol.to_owned(),
             )),
-            _ => self.set(new_name, env.get(symbol)?),
+            _ => {
+                self.set(new_name, env.get(symbol)?)?;
+                self.env.borrow_mut().imported.insert(new_name.to_owned());
+                Ok(())
+            }
         }
     }
 
@@ -205,7 +376,205 @@ impl Environment {
     /// The `Value` returned by this function is expected to be a one-dimensional collection
     /// containing no duplicates.
     pub fn with_set_constructor(&self, constructor: Box<dyn Fn(Vec<Value>) -> ValueResult>) {
-        self.env.borrow_mut().set_constructor = SetConstructor(Some(constructor));
+        self.env.borrow_mut().set_constructor = OptionalHook(Some(constructor));
+    }
+
+    /// Set the function which will be used to rewrite the path of each `load` statement
+    /// evaluated in this `Environment`, e.g. to map `@repo//path` labels to vendored files.
+    ///
+    /// The rewriter is called once per `load` with the raw path, and the returned path is
+    /// passed to the [`FileLoader`](crate::eval::FileLoader). If this function is not called,
+    /// the parent's rewriter is used; without any rewriter paths are passed through unchanged.
+    pub fn with_load_path_rewriter(&self, rewriter: Box<dyn Fn(&str) -> String>) {
+        self.env.borrow_mut().load_path_rewriter = OptionalHook(Some(rewriter));
+    }
+
+    pub(crate) fn rewrite_load_path(&self, path: &str) -> String {
+        match self.env.borrow().load_path_rewriter.0 {
+            Some(ref rewriter) => rewriter(path),
+            None => match self.get_parent() {
+                Some(parent) => parent.rewrite_load_path(path),
+                None => path.to_owned(),
+            },
+        }
+    }
+
+    /// Set the function which will be called for each statement evaluated in this
+    /// `Environment`, including statements of functions defined in it.
+    ///
+    /// Before a statement executes, the tracer receives a [`TraceEvent::Statement`] with the
+    /// span of the statement and a short description of it. If the statement completes
+    /// without error, it then receives a [`TraceEvent::Result`] with the value of the statement.
+    /// If this function is not called, the parent's tracer is used. Without any tracer,
+    /// statements are evaluated without tracing overhead.
+    pub fn with_tracer(&self, tracer: Box<dyn Fn(&TraceEvent)>) {
+        self.env.borrow_mut().tracer = OptionalHook(Some(tracer.into()));
+    }
+
+    pub(crate) fn tracer(&self) -> Option<TracerFn> {
+        match self.env.borrow().tracer.0 {
+            Some(ref tracer) => Some(tracer.clone()),
+            None => self.get_parent().and_then(|parent| parent.tracer()),
+        }
+    }
+
+    /// Set the function which will be called before each statement evaluated in this
+    /// `Environment`, including statements of functions defined in it, to implement a
+    /// debugger. See the [`debug`](crate::eval::debug) module for an example.
+    ///
+    /// The evaluation is paused while the hook runs. The hook can inspect the [`DebugFrame`]
+    /// of the statement, then resume the evaluation by returning [`DebugAction::Continue`] or
+    /// stop it with an error by returning [`DebugAction::Abort`]. If this function is not
+    /// called, the parent's debug hook is used. Without any debug hook, statements are
+    /// evaluated without debugging overhead.
+    pub fn with_debug_hook(&self, hook: Box<dyn Fn(&DebugFrame) -> DebugAction>) {
+        self.env.borrow_mut().debug_hook = OptionalHook(Some(hook.into()));
+    }
+
+    pub(crate) fn debug_hook(&self) -> Option<DebugHookFn> {
+        match self.env.borrow().debug_hook.0 {
+            Some(ref hook) => Some(hook.clone()),
+            None => self.get_parent().and_then(|parent| parent.debug_hook()),
+        }
+    }
+
+    /// Set the function which will receive the messages printed by `print()` during the
+    /// evaluation of modules in this `Environment`, instead of writing them to stderr.
+    ///
+    /// The handler receives each message without a trailing newline. If this function is not
+    /// called, the parent's handler is used.
+    pub fn with_print_handler(&self, handler: Box<dyn Fn(&str)>) {
+        self.env.borrow_mut().print_handler = OptionalHook(Some(handler.into()));
+    }
+
+    pub(crate) fn print_handler(&self) -> Option<PrintHandlerFn> {
+        match self.env.borrow().print_handler.0 {
+            Some(ref handler) => Some(handler.clone()),
+            None => self.get_parent().and_then(|parent| parent.print_handler()),
+        }
+    }
+
+    /// Replace the print handler set on this environment itself, returning the previous one.
+    pub(crate) fn replace_print_handler(
+        &self,
+        handler: Option<PrintHandlerFn>,
+    ) -> Option<PrintHandlerFn> {
+        std::mem::replace(&mut self.env.borrow_mut().print_handler.0, handler)
+    }
+
+    /// Record where the mutable values are created and mutated during the evaluation of
+    /// modules in this `Environment`, including in the functions they call, into `recorder`.
+    ///
+    /// Lists, dicts and sets are tagged with the span of the expression creating them, and the
+    /// span of each call or statement mutating them is appended to their
+    /// [`Provenance`](crate::eval::provenance::Provenance), which can be queried with
+    /// [`ProvenanceRecorder::provenance`]. The recorder keeps every recorded value alive, so it
+    /// is only meant for debugging. If this function is not called, the parent's recorder is
+    /// used.
+    #[cfg(feature = "provenance")]
+    pub fn with_provenance_recorder(&self, recorder: ProvenanceRecorder) {
+        self.env.borrow_mut().provenance_recorder = Some(recorder);
+    }
+
+    #[cfg(feature = "provenance")]
+    pub(crate) fn provenance_recorder(&self) -> Option<ProvenanceRecorder> {
+        match self.env.borrow().provenance_recorder {
+            Some(ref recorder) => Some(recorder.clone()),
+            None => self
+                .get_parent()
+                .and_then(|parent| parent.provenance_recorder()),
+        }
+    }
+
+    /// Count the statements executed, the call depth and the values allocated during the
+    /// evaluation of modules in this `Environment`, including in the functions they call and
+    /// the modules they load, into `recorder`.
+    ///
+    /// Without any recorder, evaluations only pay for checking that none is active. If this
+    /// function is not called, the parent's recorder is used.
+    pub fn with_metrics_recorder(&self, recorder: MetricsRecorder) {
+        self.env.borrow_mut().metrics_recorder = Some(recorder);
+    }
+
+    pub(crate) fn metrics_recorder(&self) -> Option<MetricsRecorder> {
+        match self.env.borrow().metrics_recorder {
+            Some(ref recorder) => Some(recorder.clone()),
+            None => self
+                .get_parent()
+                .and_then(|parent| parent.metrics_recorder()),
+        }
+    }
+
+    /// Set the approximate maximum length of the representation of a value in the error
+    /// messages of evaluations in this `Environment`, longer representations are cut with
+    /// [`Value::to_repr_truncated`].
+    ///
+    /// If this function is not called, the parent's limit is used, and
+    /// [`DEFAULT_MAX_ERROR_REPR_LENGTH`](crate::values::error::DEFAULT_MAX_ERROR_REPR_LENGTH)
+    /// without any parent.
+    pub fn set_max_error_repr_length(&self, max_len: usize) {
+        self.env.borrow_mut().max_error_repr_length = Some(max_len);
+    }
+
+    pub(crate) fn max_error_repr_length(&self) -> usize {
+        match self.env.borrow().max_error_repr_length {
+            Some(max_len) => max_len,
+            None => match self.get_parent() {
+                Some(parent) => parent.max_error_repr_length(),
+                None => DEFAULT_MAX_ERROR_REPR_LENGTH,
+            },
+        }
+    }
+
+    /// Set the function which will be called for each value made immutable when this
+    /// `Environment` is frozen, e.g. to track which values are shared at module boundaries.
+    ///
+    /// The observer receives a [`FreezeEvent`] describing each variable of the environment
+    /// and each value it contains which was mutable before, i.e. lists and dictionaries but
+    /// not strings or integers. If this function is not called, the parent's observer is used.
+    /// Without any observer, environments are frozen without observation overhead.
+    pub fn with_freeze_observer(&self, observer: Box<dyn Fn(&FreezeEvent)>) {
+        self.env.borrow_mut().freeze_observer = OptionalHook(Some(observer.into()));
+    }
+
+    fn freeze_observer(&self) -> Option<FreezeObserverFn> {
+        match self.env.borrow().freeze_observer.0 {
+            Some(ref observer) => Some(observer.clone()),
+            None => self
+                .get_parent()
+                .and_then(|parent| parent.freeze_observer()),
+        }
+    }
+
+    /// Forbid assignments in this `Environment` to names which are defined in its transitive
+    /// parents, e.g. builtins from the global environment.
+    ///
+    /// By default a variable assigned in this environment shadows the variable with the same
+    /// name from the parents for all subsequent references. With shadowing forbidden, such an
+    /// assignment raises an error instead.
+    pub fn forbid_shadowing(&self) {
+        self.env.borrow_mut().forbid_shadowing = true;
+    }
+
+    /// Disable the builtins `names`, i.e. the variables with these names defined in the
+    /// transitive parents of this `Environment`, replacing any previous list of disabled or
+    /// allowed builtins.
+    ///
+    /// Referencing a disabled builtin from this environment is an error, e.g. to sandbox an
+    /// evaluation. Other environments sharing the same parents, and the methods of values,
+    /// are not affected. Variables assigned in this environment can still use these names.
+    pub fn deny_builtins(&self, names: &[&str]) {
+        self.env.borrow_mut().builtin_filter = Some(BuiltinFilter::Deny(
+            names.iter().map(|n| (*n).to_owned()).collect(),
+        ));
+    }
+
+    /// Disable all the builtins except `names`, like [`deny_builtins`](Environment::deny_builtins)
+    /// with all the other variables of the transitive parents of this `Environment`.
+    pub fn allow_only_builtins(&self, names: &[&str]) {
+        self.env.borrow_mut().builtin_filter = Some(BuiltinFilter::Allow(
+            names.iter().map(|n| (*n).to_owned()).collect(),
+        ));
     }
 
     pub(crate) fn make_set(&self, values: Vec<Value>) -> ValueResult {
@@ -225,11 +594,11 @@ impl Environment {
 impl EnvironmentContent {
     /// Create a new child environment
     /// Freeze the environment, all its value will become immutable after that
-    pub fn freeze(&mut self) {
+    pub fn freeze(&mut self, observer: Option<&FreezeObserverFn>) {
         if !self.frozen {
             self.frozen = true;
             for v in self.variables.values_mut() {
-                v.freeze();
+                v.freeze_observed(observer);
             }
         }
     }
@@ -238,8 +607,25 @@ impl EnvironmentContent {
     pub fn set(&mut self, name: &str, value: Value) -> Result<(), EnvironmentError> {
         if self.frozen {
             Err(EnvironmentError::TryingToMutateFrozenEnvironment)
+        } else if self.predeclared.contains_key(name) {
+            Err(EnvironmentError::CannotAssignPredeclared(name.to_owned()))
+        } else if self.forbid_shadowing && self.parent.iter().any(|p| p.get(name).is_ok()) {
+            Err(EnvironmentError::CannotShadowBuiltin(name.to_owned()))
         } else {
             self.variables.insert(name.to_string(), value);
+            self.imported.remove(name);
+            Ok(())
+        }
+    }
+
+    /// Declare a read-only binding in that environment.
+    pub fn predeclare(&mut self, name: &str, mut value: Value) -> Result<(), EnvironmentError> {
+        if self.frozen {
+            Err(EnvironmentError::TryingToMutateFrozenEnvironment)
+        } else {
+            value.freeze();
+            self.variables.remove(name);
+            self.predeclared.insert(name.to_owned(), value);
             Ok(())
         }
     }
@@ -248,9 +634,19 @@ impl EnvironmentContent {
     pub fn get(&self, name: &str) -> Result<Value, EnvironmentError> {
         if self.variables.contains_key(name) {
             Ok(self.variables[name].clone())
+        } else if let Some(value) = self.predeclared.get(name) {
+            Ok(value.clone())
         } else {
             match self.parent {
-                Some(ref p) => p.get(name),
+                Some(ref p) => {
+                    let value = p.get(name)?;
+                    match self.builtin_filter {
+                        Some(ref filter) if !filter.allows(name) => {
+                            Err(EnvironmentError::BuiltinDisabled(name.to_owned()))
+                        }
+                        _ => Ok(value),
+                    }
+                }
                 None => Err(EnvironmentError::VariableNotFound(name.to_owned())),
             }
         }
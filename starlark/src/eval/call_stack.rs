@@ -13,9 +13,10 @@
 // limitations under the License.
 //! Starlark call stack.
 
+use crate::environment::{OptionalHook, PrintHandlerFn};
 use crate::values::error::ValueError;
 use crate::values::{FunctionId, Value};
-use codemap::{CodeMap, Pos};
+use codemap::{CodeMap, Pos, Span};
 use std::cell::Cell;
 use std::fmt;
 use std::sync::{Arc, Mutex};
@@ -33,6 +34,10 @@ impl fmt::Debug for Frame {
 #[derive(Clone, Debug, Default)]
 pub struct CallStack {
     stack: Vec<Frame>,
+    /// Name of the environment of the module being evaluated.
+    module_name: Option<String>,
+    /// Print handler of the environment of the module being evaluated.
+    print_handler: OptionalHook<PrintHandlerFn>,
 }
 
 impl CallStack {
@@ -46,6 +51,58 @@ impl CallStack {
         self.stack.pop().unwrap();
     }
 
+    /// Number of frames in the stack.
+    pub fn len(&self) -> usize {
+        self.stack.len()
+    }
+
+    /// Test if the stack has no frame.
+    pub fn is_empty(&self) -> bool {
+        self.stack.is_empty()
+    }
+
+    /// Shorten the stack to `len` frames, keeping the outermost ones.
+    ///
+    /// Has no effect if the stack already has `len` frames or less.
+    pub fn truncate(&mut self, len: usize) {
+        self.stack.truncate(len);
+    }
+
+    /// Remove all the frames, keeping the allocated capacity so that the stack
+    /// can be reused for another evaluation.
+    pub fn clear(&mut self) {
+        self.stack.clear();
+    }
+
+    /// The name of the environment of the module being evaluated with this call stack, as
+    /// given by [`Environment::name`](crate::environment::Environment::name), or `None`
+    /// outside the evaluation of a module.
+    ///
+    /// Functions called from another module, e.g. loaded ones, see the name of the module
+    /// being evaluated, not of the module they are defined in.
+    pub fn module_name(&self) -> Option<&str> {
+        self.module_name.as_deref()
+    }
+
+    pub(crate) fn set_module_name(&mut self, name: String) {
+        self.module_name = Some(name);
+    }
+
+    pub(crate) fn set_print_handler(&mut self, handler: Option<PrintHandlerFn>) {
+        self.print_handler = OptionalHook(handler);
+    }
+
+    /// Output `message` as `print()` does: pass it to the print handler of the environment
+    /// of the module being evaluated, see
+    /// [`Environment::with_print_handler`](crate::environment::Environment::with_print_handler),
+    /// or write it to stderr if there is no handler.
+    pub fn print(&self, message: &str) {
+        match self.print_handler.0 {
+            Some(ref handler) => handler(message),
+            None => eprintln!("{}", message),
+        }
+    }
+
     /// Test if call stack contains a function with given id.
     pub fn contains(&self, function_id: FunctionId) -> bool {
         self.stack
@@ -53,6 +110,16 @@ impl CallStack {
             .any(|&Frame(ref f, _, _)| f.function_id() == function_id)
     }
 
+    /// The span of the line of the innermost call, in the code map of the evaluation making
+    /// it, or `None` if the stack has no frame.
+    pub(crate) fn call_span(&self) -> Option<Span> {
+        self.stack.last().map(|Frame(_, map, pos)| {
+            let map = map.lock().unwrap();
+            let file = map.find_file(*pos);
+            file.line_span(file.find_line(*pos))
+        })
+    }
+
     /// Print call stack as multiline string
     /// with each line beginning with newline.
     pub fn print_with_newline_before<'a>(&'a self) -> impl fmt::Display + 'a {
@@ -0,0 +1,97 @@
+// Copyright 2019 The Starlark in Rust Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Evaluation capturing the output of `print()` separately from the result.
+//!
+//! [`eval_capturing_prints`] evaluates a module like [`eval`](crate::eval::eval) and returns
+//! the messages printed during the evaluation together with the value or the error, so that
+//! logs can be shown alongside a failure:
+//!
+//! ```
+//! # use starlark::eval::capture::eval_capturing_prints;
+//! # use starlark::eval::noload::NoLoadFileLoader;
+//! # use starlark::stdlib::global_environment;
+//! # use starlark::syntax::dialect::Dialect;
+//! # use codemap::CodeMap;
+//! # use std::sync::{Arc, Mutex};
+//! let (global, type_values) = global_environment();
+//! let captured = eval_capturing_prints(
+//!     &Arc::new(Mutex::new(CodeMap::new())),
+//!     "my.bzl",
+//!     "print('checking', 1)\nfail('bad config')",
+//!     Dialect::Bzl,
+//!     &mut global.child("my.bzl"),
+//!     &type_values,
+//!     NoLoadFileLoader,
+//! );
+//! assert_eq!(vec!["checking 1"], captured.prints);
+//! assert!(captured.result.unwrap_err().message.starts_with("fail(): bad config"));
+//! ```
+
+use crate::environment::{Environment, TypeValues};
+use crate::eval::FileLoader;
+use crate::syntax::dialect::Dialect;
+use crate::values::Value;
+use codemap::CodeMap;
+use codemap_diagnostic::Diagnostic;
+use std::cell::RefCell;
+use std::mem;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+
+/// The outcome of [`eval_capturing_prints`].
+#[derive(Debug)]
+pub struct CapturedEval {
+    /// The messages printed with `print()` during the evaluation, in order, without trailing
+    /// newlines. Messages printed before an error are kept.
+    pub prints: Vec<String>,
+    /// The value of the last statement of the module, or the error which stopped the
+    /// evaluation.
+    pub result: Result<Value, Diagnostic>,
+}
+
+/// Evaluate a string content like [`eval`](crate::eval::eval), capturing the messages printed
+/// with `print()` instead of writing them to stderr.
+///
+/// The print handler of `env`, if any, is restored after the evaluation. Messages printed by
+/// modules loaded with `load()` are captured if their environment inherits the print handler
+/// from `env`, i.e. if it is a child of `env`, and written to stderr otherwise.
+///
+/// # Arguments
+///
+/// * map: the codemap object used for diagnostics
+/// * path: the name of the file being evaluated, for diagnostics
+/// * content: the content to evaluate
+/// * dialect: Starlark language dialect
+/// * env: the environment to mutate during the evaluation
+/// * file_loader: the [`FileLoader`] to react to `load()` statements.
+pub fn eval_capturing_prints<T: FileLoader + 'static>(
+    map: &Arc<Mutex<CodeMap>>,
+    path: &str,
+    content: &str,
+    dialect: Dialect,
+    env: &mut Environment,
+    type_values: &TypeValues,
+    file_loader: T,
+) -> CapturedEval {
+    let prints = Rc::new(RefCell::new(Vec::new()));
+    let sink = prints.clone();
+    let previous = env.replace_print_handler(Some(Rc::new(move |message: &str| {
+        sink.borrow_mut().push(message.to_owned())
+    })));
+    let result = super::eval(map, path, content, dialect, env, type_values, file_loader);
+    env.replace_print_handler(previous);
+    let prints = mem::take(&mut *prints.borrow_mut());
+    CapturedEval { prints, result }
+}
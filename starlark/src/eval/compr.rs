@@ -17,8 +17,8 @@
 use crate::eval::eval_expr;
 use crate::eval::expr::AstClauseCompiled;
 use crate::eval::expr::ClauseCompiled;
+use crate::eval::for_each_element;
 use crate::eval::set_expr;
-use crate::eval::t;
 use crate::eval::EvalException;
 use crate::eval::EvaluationContext;
 
@@ -39,13 +39,12 @@ pub(crate) fn eval_one_dimensional_comprehension<
             }
             ClauseCompiled::For(ref var, ref iter) => {
                 let iterable = eval_expr(iter, context)?;
-                for item in &t(iterable.iter(), iter)? {
+                for_each_element(&iterable, iter.span, context, |item, context| {
                     set_expr(var, context, item)?;
 
                     eval_one_dimensional_comprehension(expr, tl, context)?;
-                }
-
-                Ok(())
+                    Ok(true)
+                })
             }
         }
     } else {
@@ -0,0 +1,178 @@
+// Copyright 2019 The Starlark in Rust Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Debugger support, see
+//! [`Environment::with_debug_hook`](crate::environment::Environment::with_debug_hook).
+//!
+//! The debug hook is called before each statement with the [`DebugFrame`] of the statement.
+//! The evaluation is paused while the hook runs, so a debugger can block in the hook (e.g.
+//! waiting for a "step" or "continue" command of its user) and inspect the frame, then
+//! resume the evaluation by returning [`DebugAction::Continue`].
+//!
+//! While paused, the local variables of the current function can be read and modified with
+//! [`DebugFrame::get_local`] and [`DebugFrame::set_local`].
+//!
+//! Stepping through two statements:
+//!
+//! ```
+//! # use starlark::environment::{Environment, TypeValues};
+//! # use starlark::eval::debug::DebugAction;
+//! # use starlark::eval::noload::eval;
+//! # use starlark::syntax::dialect::Dialect;
+//! # use codemap::CodeMap;
+//! # use std::cell::RefCell;
+//! # use std::rc::Rc;
+//! # use std::sync::{Arc, Mutex};
+//! let steps = Rc::new(RefCell::new(Vec::new()));
+//! let env = Environment::new("global");
+//! let recorded = steps.clone();
+//! env.with_debug_hook(Box::new(move |frame| {
+//!     // A real debugger would wait for a command of its user here.
+//!     let x = frame.env().get("x").ok().map(|x| x.to_repr());
+//!     recorded.borrow_mut().push(format!("{}, x = {:?}", frame.description(), x));
+//!     DebugAction::Continue
+//! }));
+//! let mut env = env.child("my.bzl");
+//! eval(
+//!     &Arc::new(Mutex::new(CodeMap::new())),
+//!     "my.bzl",
+//!     "x = 1\ny = x + 1",
+//!     Dialect::Bzl,
+//!     &mut env,
+//!     &TypeValues::default(),
+//! )
+//! .unwrap();
+//! assert_eq!(
+//!     vec!["assign, x = None", "assign, x = Some(\"1\")"],
+//!     *steps.borrow()
+//! );
+//! ```
+
+use crate::environment::{Environment, EnvironmentError};
+use crate::eval::{EvaluationContextEnvironment, IndexedLocals};
+use crate::values::error::{RuntimeError, ValueError, INCORRECT_PARAMETER_TYPE_ERROR_CODE};
+use crate::values::Value;
+use codemap::Span;
+
+/// What the evaluator should do after the debug hook returns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DebugAction {
+    /// Execute the statement and continue the evaluation.
+    Continue,
+    /// Stop the evaluation with an error, without executing the statement.
+    Abort,
+}
+
+/// The state of the evaluation when the debug hook is called, i.e. right before executing a
+/// statement.
+pub struct DebugFrame<'a> {
+    pub(crate) span: Span,
+    pub(crate) description: &'a str,
+    pub(crate) env: &'a EvaluationContextEnvironment<'a>,
+}
+
+impl<'a> DebugFrame<'a> {
+    /// The span of the statement about to be executed.
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    /// A short description of the statement about to be executed, e.g. `assign` or `def f`.
+    pub fn description(&self) -> &str {
+        self.description
+    }
+
+    /// The environment of the module being evaluated, holding its global variables.
+    pub fn env(&self) -> &Environment {
+        self.env.env()
+    }
+
+    /// Whether the statement is in a function rather than at the top level of the module.
+    pub fn is_local(&self) -> bool {
+        match self.env {
+            EvaluationContextEnvironment::Module(..) => false,
+            EvaluationContextEnvironment::Local(..) => true,
+        }
+    }
+
+    /// The local variables of the current function which have been assigned, with their
+    /// values, in declaration order. Empty at the top level of the module.
+    pub fn locals(&self) -> Vec<(String, Value)> {
+        match self.env {
+            EvaluationContextEnvironment::Module(..) => Vec::new(),
+            EvaluationContextEnvironment::Local(_, ref locals) => locals.assigned(),
+        }
+    }
+
+    /// The names of all local variables of the current function, including the ones which
+    /// are not assigned yet, in declaration order. Empty at the top level of the module,
+    /// where variables are globals of [`env`](DebugFrame::env).
+    pub fn local_names(&self) -> Vec<String> {
+        match self.env {
+            EvaluationContextEnvironment::Module(..) => Vec::new(),
+            EvaluationContextEnvironment::Local(_, ref locals) => locals
+                .local_defs
+                .top_level_names()
+                .into_iter()
+                .map(|(name, _)| name.to_owned())
+                .collect(),
+        }
+    }
+
+    /// Get the value of the local variable `name` of the current function.
+    pub fn get_local(&self, name: &str) -> Result<Value, EnvironmentError> {
+        let (locals, slot) = self.local_slot(name)?;
+        locals.get_slot(slot, name)
+    }
+
+    /// Set the value of the local variable `name` of the current function, e.g. to let a
+    /// debugger user patch a value before resuming the evaluation.
+    ///
+    /// Only existing local variables can be set, and a variable which has a value can only be
+    /// set to a value of the same type, as the code following the statement may rely on it.
+    pub fn set_local(&self, name: &str, value: Value) -> Result<(), ValueError> {
+        let (locals, slot) = self.local_slot(name)?;
+        if let Ok(current) = locals.get_slot(slot, name) {
+            if current.get_type() != value.get_type() {
+                return Err(RuntimeError {
+                    code: INCORRECT_PARAMETER_TYPE_ERROR_CODE,
+                    message: format!(
+                        "Cannot set local variable '{}' of type '{}' to a value of type '{}'",
+                        name,
+                        current.get_type(),
+                        value.get_type()
+                    ),
+                    label: "Incorrect type".to_owned(),
+                }
+                .into());
+            }
+        }
+        locals.set_slot(slot, name, value);
+        Ok(())
+    }
+
+    fn local_slot(&self, name: &str) -> Result<(&IndexedLocals<'_>, usize), EnvironmentError> {
+        let not_found = || EnvironmentError::VariableNotFound(name.to_owned());
+        match self.env {
+            EvaluationContextEnvironment::Module(..) => Err(not_found()),
+            EvaluationContextEnvironment::Local(_, ref locals) => {
+                let slot = locals
+                    .local_defs
+                    .top_level_name_to_slot(name)
+                    .ok_or_else(not_found)?;
+                Ok((locals, slot))
+            }
+        }
+    }
+}
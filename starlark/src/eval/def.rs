@@ -18,6 +18,7 @@ use crate::environment::{Environment, TypeValues};
 use crate::eval::call_stack::CallStack;
 use crate::eval::compiler::LocalCompiler;
 use crate::eval::eval_block;
+use crate::eval::eval_expr;
 use crate::eval::expr::AstExprCompiled;
 use crate::eval::expr::ExprCompiled;
 use crate::eval::locals::Locals;
@@ -37,7 +38,7 @@ use crate::syntax::ast::Expr;
 use crate::syntax::ast::Parameter;
 use crate::syntax::ast::Statement;
 use crate::values::error::ValueError;
-use crate::values::function::FunctionParameter;
+use crate::values::function::FunctionArg;
 use crate::values::function::FunctionSignature;
 use crate::values::function::FunctionType;
 use crate::values::function::StrOrRepr;
@@ -46,7 +47,6 @@ use crate::values::{function, Immutable, TypedValue, Value, ValueResult};
 use codemap::{CodeMap, Spanned};
 use codemap_diagnostic::Diagnostic;
 use linked_hash_map::LinkedHashMap;
-use std::convert::TryInto;
 use std::fmt;
 use std::iter;
 use std::sync::{Arc, Mutex};
@@ -55,17 +55,31 @@ use std::sync::{Arc, Mutex};
 pub(crate) enum ParameterCompiled {
     Normal(AstString),
     WithDefaultValue(AstString, AstExprCompiled),
+    /// Default value which refers to earlier parameters, evaluated in the function frame
+    /// when the argument is omitted. The earlier parameters it refers to are listed.
+    LazyDefaultValue(AstString, AstExprCompiled, Vec<String>),
     Args(AstString),
     KWArgs(AstString),
 }
 pub(crate) type AstParameterCompiled = Spanned<ParameterCompiled>;
 
 impl ParameterCompiled {
-    fn compile(param: AstParameter) -> Result<AstParameterCompiled, Diagnostic> {
+    fn compile(
+        param: AstParameter,
+        referred_parameters: Vec<String>,
+        compiler: &mut LocalCompiler,
+    ) -> Result<AstParameterCompiled, Diagnostic> {
         Ok(Spanned {
             span: param.span,
             node: match param.node {
                 Parameter::Normal(n) => ParameterCompiled::Normal(n),
+                Parameter::WithDefaultValue(n, d) if !referred_parameters.is_empty() => {
+                    ParameterCompiled::LazyDefaultValue(
+                        n,
+                        ExprCompiled::compile(d, compiler)?,
+                        referred_parameters,
+                    )
+                }
                 Parameter::WithDefaultValue(n, d) => {
                     ParameterCompiled::WithDefaultValue(n, ExprCompiled::compile_global(d)?)
                 }
@@ -74,6 +88,16 @@ impl ParameterCompiled {
             },
         })
     }
+
+    fn name(&self) -> &str {
+        match self {
+            ParameterCompiled::Normal(ref n)
+            | ParameterCompiled::WithDefaultValue(ref n, _)
+            | ParameterCompiled::LazyDefaultValue(ref n, ..)
+            | ParameterCompiled::Args(ref n)
+            | ParameterCompiled::KWArgs(ref n) => &n.node,
+        }
+    }
 }
 
 /// `def` AST with post-processing suitable for faster excecution
@@ -98,10 +122,34 @@ impl DefCompiled {
             locals_builder.register_local(p.name());
         }
 
-        let params = params
-            .into_iter()
-            .map(ParameterCompiled::compile)
-            .collect::<Result<_, _>>()?;
+        // Defaults are evaluated once when the function is defined, unless they refer to
+        // an earlier parameter, in which case they are evaluated on each call after
+        // binding all preceding parameters.
+        let referred_parameters: Vec<Vec<String>> = params
+            .iter()
+            .enumerate()
+            .map(|(i, p)| match p.node {
+                Parameter::WithDefaultValue(_, ref d) => {
+                    let mut identifiers = Vec::new();
+                    Expr::collect_identifiers(d, &mut identifiers);
+                    params[..i]
+                        .iter()
+                        .map(|p| p.name())
+                        .filter(|n| identifiers.contains(n))
+                        .map(str::to_owned)
+                        .collect()
+                }
+                _ => Vec::new(),
+            })
+            .collect();
+
+        for (p, referred) in params.iter().zip(&referred_parameters) {
+            if let Parameter::WithDefaultValue(_, ref d) = p.node {
+                if !referred.is_empty() {
+                    Expr::collect_locals(d, &mut locals_builder);
+                }
+            }
+        }
 
         DefCompiled::collect_locals(&suite, &mut locals_builder);
 
@@ -111,6 +159,12 @@ impl DefCompiled {
 
         let mut local_compiler = LocalCompiler::new(&mut locals_query);
 
+        let params = params
+            .into_iter()
+            .zip(referred_parameters)
+            .map(|(p, referred)| ParameterCompiled::compile(p, referred, &mut local_compiler))
+            .collect::<Result<_, _>>()?;
+
         let suite = BlockCompiled::compile_local(suite, &mut local_compiler)?;
 
         Ok(DefCompiled {
@@ -192,6 +246,10 @@ impl Def {
             map,
         })
     }
+
+    pub(crate) fn signature(&self) -> &FunctionSignature {
+        &self.signature
+    }
 }
 
 impl TypedValue for Def {
@@ -231,6 +289,8 @@ impl TypedValue for Def {
             ),
             type_values,
             map: self.map.clone(),
+            tracer: self.captured_env.tracer(),
+            debug_hook: self.captured_env.debug_hook(),
         };
 
         let mut parser = function::ParameterParser::new(
@@ -242,23 +302,14 @@ impl TypedValue for Def {
             kwargs,
         )?;
 
-        for (i, (s, positional_only)) in self.signature.iter().enumerate() {
-            let (name, v) = match s {
-                FunctionParameter::Normal(ref name) => {
-                    (name, parser.next_normal(name, positional_only)?)
-                }
-                FunctionParameter::WithDefaultValue(ref name, ref default_value) => (
-                    name,
-                    parser.next_with_default_value(name, positional_only, default_value),
-                ),
-                FunctionParameter::ArgsArray(ref name) => (name, parser.next_args_array().into()),
-                FunctionParameter::KWArgsDict(ref name) => {
-                    (name, parser.next_kwargs_dict().try_into().unwrap())
-                }
-                FunctionParameter::Optional(..) => {
-                    unreachable!("optional parameters only exist in native functions")
+        for (i, p) in self.stmt.params.iter().enumerate() {
+            let v = match (parser.next_arg()?, &p.node) {
+                (FunctionArg::Optional(None), ParameterCompiled::LazyDefaultValue(_, ref d, _)) => {
+                    eval_expr(d, &mut ctx).map_err(|e| ValueError::DiagnosedError(e.into()))?
                 }
+                (arg, _) => arg.into(),
             };
+            let name = p.node.name();
 
             // tricky part: we know that we assign locals for function parameters
             // sequentially starting from 0
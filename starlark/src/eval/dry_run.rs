@@ -0,0 +1,553 @@
+// Copyright 2019 The Starlark in Rust Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Validation of a module's function calls without evaluating it.
+//!
+//! [`check_calls`] checks the arguments of each call to a native function of the environment
+//! or to a function defined with `def` in the module against the function's signature, and
+//! reports the calls which would fail with missing, extra or unknown keyword arguments.
+//!
+//! Calls whose function is not known statically, e.g. `x.append(1)`, `fs[0]()` or a call to
+//! a variable which is assigned in the module, are not checked.
+//!
+//! ```
+//! # use starlark::eval::dry_run::check_calls;
+//! # use starlark::stdlib::global_environment;
+//! # use starlark::syntax::dialect::Dialect;
+//! # use codemap::CodeMap;
+//! # use std::sync::{Arc, Mutex};
+//! let (env, _) = global_environment();
+//! let diagnostics = check_calls(
+//!     &Arc::new(Mutex::new(CodeMap::new())),
+//!     "my.bzl",
+//!     "def f(a, b = 1):\n  return a + b\nx = f(1, 2, 3)\ny = len(x, c = 1)",
+//!     Dialect::Bzl,
+//!     &env,
+//! )
+//! .unwrap();
+//! assert_eq!(
+//!     vec![
+//!         "Too many positional arguments for call to f",
+//!         "Unexpected keyword argument c for call to len",
+//!     ],
+//!     diagnostics.iter().map(|d| d.message.as_str()).collect::<Vec<_>>()
+//! );
+//! ```
+
+use crate::environment::Environment;
+use crate::eval::def::Def;
+use crate::syntax::ast::{
+    AssignTargetExpr, AstAssignTargetExpr, AstExpr, AstParameter, AstStatement, AstString,
+    AugmentedAssignTargetExpr, Clause, Expr, Parameter, Statement,
+};
+use crate::syntax::dialect::Dialect;
+use crate::syntax::lexer::Lexer;
+use crate::syntax::parser::parse_ast;
+use crate::values::function::{
+    FunctionParameter, FunctionSignature, NativeClosure, NativeFunction,
+    EXTRA_PARAMETER_ERROR_CODE, NOT_ENOUGH_PARAMS_ERROR_CODE,
+};
+use crate::values::Value;
+use codemap::{CodeMap, Span};
+use codemap_diagnostic::{Diagnostic, Level, SpanLabel, SpanStyle};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+/// Parse `content` and check its function calls against the signatures of the called
+/// functions, without evaluating it.
+///
+/// Return the diagnostics of the calls which would fail because of their arguments, or the
+/// parse error if `content` cannot be parsed.
+///
+/// # Arguments
+///
+/// * map: the codemap object used for diagnostics
+/// * path: the name of the file being checked, for diagnostics
+/// * content: the content to check
+/// * dialect: starlark syntax dialect
+/// * env: the environment the module would be evaluated in, to resolve builtins
+pub fn check_calls(
+    map: &Arc<Mutex<CodeMap>>,
+    path: &str,
+    content: &str,
+    dialect: Dialect,
+    env: &Environment,
+) -> Result<Vec<Diagnostic>, Diagnostic> {
+    let stmt = parse_ast(map, path, content, dialect, Lexer::new(content))?;
+    let mut checker = CallChecker::new(&stmt, env);
+    checker.check_stmt(&stmt, &HashSet::new());
+    Ok(checker.diagnostics)
+}
+
+struct CallChecker<'a> {
+    env: &'a Environment,
+    /// Signatures of the functions defined with `def` in the module.
+    defs: HashMap<&'a str, FunctionSignature>,
+    /// Names bound at the top level of the module by anything else than a single `def`.
+    globals: HashSet<&'a str>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl<'a> CallChecker<'a> {
+    fn new(module: &'a AstStatement, env: &'a Environment) -> CallChecker<'a> {
+        let mut defs = HashMap::new();
+        let mut globals = HashSet::new();
+        collect_globals(module, &mut defs, &mut globals);
+        let defs = defs
+            .into_iter()
+            .filter(|(name, _)| !globals.contains(name))
+            .map(|(name, params)| (name, def_signature(params)))
+            .collect();
+        CallChecker {
+            env,
+            defs,
+            globals,
+            diagnostics: Vec::new(),
+        }
+    }
+
+    fn check_stmt(&mut self, stmt: &'a AstStatement, locals: &HashSet<&'a str>) {
+        match stmt.node {
+            Statement::Break | Statement::Continue | Statement::Pass | Statement::Load(..) => {}
+            Statement::Return(ref expr) => {
+                if let Some(expr) = expr {
+                    self.check_expr(expr, locals);
+                }
+            }
+            Statement::Expression(ref expr) => self.check_expr(expr, locals),
+            Statement::Assign(ref target, ref expr) => {
+                let mut exprs = Vec::new();
+                assign_target_exprs(target, &mut exprs);
+                for e in exprs {
+                    self.check_expr(e, locals);
+                }
+                self.check_expr(expr, locals);
+            }
+            Statement::AugmentedAssign(ref target, _, ref expr) => {
+                match target.node {
+                    AugmentedAssignTargetExpr::Identifier(..) => {}
+                    AugmentedAssignTargetExpr::Dot(ref object, _) => {
+                        self.check_expr(object, locals)
+                    }
+                    AugmentedAssignTargetExpr::ArrayIndirection(ref array, ref index) => {
+                        self.check_expr(array, locals);
+                        self.check_expr(index, locals);
+                    }
+                }
+                self.check_expr(expr, locals);
+            }
+            Statement::Statements(ref stmts) => {
+                for stmt in stmts {
+                    self.check_stmt(stmt, locals);
+                }
+            }
+            Statement::If(ref cond, ref then_block) => {
+                self.check_expr(cond, locals);
+                self.check_stmt(then_block, locals);
+            }
+            Statement::IfElse(ref cond, ref then_block, ref else_block) => {
+                self.check_expr(cond, locals);
+                self.check_stmt(then_block, locals);
+                self.check_stmt(else_block, locals);
+            }
+            Statement::For(ref target, ref iter, ref body) => {
+                let mut exprs = Vec::new();
+                assign_target_exprs(target, &mut exprs);
+                for e in exprs {
+                    self.check_expr(e, locals);
+                }
+                self.check_expr(iter, locals);
+                self.check_stmt(body, locals);
+            }
+            Statement::Def(_, ref params, ref body) => {
+                let mut def_locals = locals.clone();
+                def_locals.extend(params.iter().map(|p| p.node.name()));
+                collect_locals(body, &mut def_locals);
+                for p in params {
+                    if let Parameter::WithDefaultValue(_, ref default) = p.node {
+                        self.check_expr(default, &def_locals);
+                    }
+                }
+                self.check_stmt(body, &def_locals);
+            }
+        }
+    }
+
+    fn check_expr(&mut self, expr: &'a AstExpr, locals: &HashSet<&'a str>) {
+        let mut scope;
+        let mut locals = locals;
+        match expr.node {
+            Expr::Call(ref func, ref args, ref named, ref star, ref star_star) => {
+                if let Expr::Identifier(ref name) = func.node {
+                    if let Some(signature) = self.signature(name, locals) {
+                        self.check_call(
+                            expr.span,
+                            name,
+                            &signature,
+                            args.len(),
+                            named,
+                            star.is_some(),
+                            star_star.is_some(),
+                        );
+                    }
+                }
+            }
+            Expr::ListComprehension(_, ref clauses)
+            | Expr::SetComprehension(_, ref clauses)
+            | Expr::DictComprehension(_, ref clauses) => {
+                scope = locals.clone();
+                for clause in clauses {
+                    if let Clause::For(ref target, _) = clause.node {
+                        assign_target_names(target, &mut scope);
+                    }
+                }
+                locals = &scope;
+            }
+            _ => {}
+        }
+        let mut exprs = Vec::new();
+        Expr::collect_children(expr, &mut exprs);
+        for e in exprs {
+            self.check_expr(e, locals);
+        }
+    }
+
+    /// The signature of the function named `name` if it is known statically.
+    fn signature(&self, name: &str, locals: &HashSet<&str>) -> Option<FunctionSignature> {
+        if locals.contains(name) || self.globals.contains(name) {
+            return None;
+        }
+        if let Some(signature) = self.defs.get(name) {
+            return Some(signature.clone());
+        }
+        value_signature(&self.env.get(name).ok()?)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn check_call(
+        &mut self,
+        span: Span,
+        name: &AstString,
+        signature: &FunctionSignature,
+        positional: usize,
+        named: &[(AstString, AstExpr)],
+        args: bool,
+        kwargs: bool,
+    ) {
+        let mut positional = positional;
+        let mut named: Vec<&AstString> = named.iter().map(|(n, _)| n).collect();
+        let mut filled_by_position = Vec::new();
+        for (param, positional_only) in signature.iter() {
+            let (param_name, required) = match param {
+                FunctionParameter::Normal(ref n)
+                | FunctionParameter::KWOnlyNormal(ref n)
+                | FunctionParameter::Typed(ref n, _) => (n, true),
+                FunctionParameter::Optional(ref n)
+                | FunctionParameter::WithDefaultValue(ref n, _)
+                | FunctionParameter::LazyDefaultValue(ref n)
+                | FunctionParameter::TypedOptional(ref n, _)
+                | FunctionParameter::TypedWithDefaultValue(ref n, ..)
+                | FunctionParameter::KWOnlyOptional(ref n)
+                | FunctionParameter::KWOnlyWithDefaultValue(ref n, _) => (n, false),
+                FunctionParameter::ArgsArray(..) => {
+                    positional = 0;
+                    continue;
+                }
+                FunctionParameter::KWArgsDict(..) => {
+                    named.clear();
+                    continue;
+                }
+            };
+            if positional > 0 && !param.is_keyword_only() {
+                positional -= 1;
+                if !positional_only {
+                    filled_by_position.push(param_name.as_str());
+                }
+            } else if let Some(i) = named
+                .iter()
+                .position(|n| !positional_only && n.node == *param_name)
+            {
+                named.remove(i);
+            } else if required && !args && !kwargs {
+                self.report(
+                    span,
+                    NOT_ENOUGH_PARAMS_ERROR_CODE,
+                    format!(
+                        "Missing parameter {} for call to {}",
+                        param_name.trim_start_matches('$'),
+                        name.node
+                    ),
+                    "Not enough parameters in function call",
+                );
+            }
+        }
+        if positional > 0 {
+            self.report(
+                span,
+                EXTRA_PARAMETER_ERROR_CODE,
+                format!("Too many positional arguments for call to {}", name.node),
+                "Extraneous parameter in function call",
+            );
+        }
+        for n in named {
+            let message = if filled_by_position.contains(&n.node.as_str()) {
+                format!(
+                    "Multiple values for parameter {} in call to {}",
+                    n.node, name.node
+                )
+            } else {
+                format!(
+                    "Unexpected keyword argument {} for call to {}",
+                    n.node, name.node
+                )
+            };
+            self.report(
+                n.span,
+                EXTRA_PARAMETER_ERROR_CODE,
+                message,
+                "Extraneous parameter in function call",
+            );
+        }
+    }
+
+    fn report(&mut self, span: Span, code: &str, message: String, label: &str) {
+        self.diagnostics.push(Diagnostic {
+            level: Level::Error,
+            message,
+            code: Some(code.to_owned()),
+            spans: vec![SpanLabel {
+                span,
+                label: Some(label.to_owned()),
+                style: SpanStyle::Primary,
+            }],
+        });
+    }
+}
+
+/// The signature of a native function or of a function defined with `def`.
+fn value_signature(value: &Value) -> Option<FunctionSignature> {
+    if let Some(f) = value.downcast_ref::<NativeFunction>() {
+        Some(f.signature().clone())
+    } else if let Some(f) = value.downcast_ref::<NativeClosure>() {
+        Some(f.signature().clone())
+    } else {
+        value.downcast_ref::<Def>().map(|f| f.signature().clone())
+    }
+}
+
+/// The signature of a `def` from its parameters, ignoring the values of default parameters.
+fn def_signature(params: &[AstParameter]) -> FunctionSignature {
+    let params = params
+        .iter()
+        .map(|p| match p.node {
+            Parameter::Normal(ref n) => FunctionParameter::Normal(n.node.clone()),
+            Parameter::WithDefaultValue(ref n, _) => FunctionParameter::Optional(n.node.clone()),
+            Parameter::Args(ref n) => FunctionParameter::ArgsArray(n.node.clone()),
+            Parameter::KWArgs(ref n) => FunctionParameter::KWArgsDict(n.node.clone()),
+        })
+        .collect();
+    FunctionSignature::new(params, 0)
+}
+
+/// Collect the functions defined with `def` at the top level of the module, and the other
+/// names bound at the top level, including the names of functions defined more than once.
+fn collect_globals<'a>(
+    stmt: &'a AstStatement,
+    defs: &mut HashMap<&'a str, &'a [AstParameter]>,
+    globals: &mut HashSet<&'a str>,
+) {
+    match stmt.node {
+        Statement::Def(ref name, ref params, _) => {
+            if defs.insert(&name.node, params).is_some() {
+                globals.insert(&name.node);
+            }
+        }
+        Statement::Load(_, ref symbols) => {
+            globals.extend(symbols.iter().map(|(local, _)| local.node.as_str()));
+        }
+        Statement::Statements(ref stmts) => {
+            for stmt in stmts {
+                collect_globals(stmt, defs, globals);
+            }
+        }
+        Statement::If(_, ref then_block) => collect_globals(then_block, defs, globals),
+        Statement::IfElse(_, ref then_block, ref else_block) => {
+            collect_globals(then_block, defs, globals);
+            collect_globals(else_block, defs, globals);
+        }
+        _ => collect_locals(stmt, globals),
+    }
+}
+
+/// Collect the names assigned by a statement, without descending into `def` bodies.
+fn collect_locals<'a>(stmt: &'a AstStatement, names: &mut HashSet<&'a str>) {
+    match stmt.node {
+        Statement::Assign(ref target, _) => assign_target_names(target, names),
+        Statement::AugmentedAssign(ref target, ..) => {
+            if let AugmentedAssignTargetExpr::Identifier(ref name) = target.node {
+                names.insert(&name.node);
+            }
+        }
+        Statement::For(ref target, _, ref body) => {
+            assign_target_names(target, names);
+            collect_locals(body, names);
+        }
+        Statement::Statements(ref stmts) => {
+            for stmt in stmts {
+                collect_locals(stmt, names);
+            }
+        }
+        Statement::If(_, ref then_block) => collect_locals(then_block, names),
+        Statement::IfElse(_, ref then_block, ref else_block) => {
+            collect_locals(then_block, names);
+            collect_locals(else_block, names);
+        }
+        Statement::Def(ref name, ..) => {
+            names.insert(&name.node);
+        }
+        Statement::Load(_, ref symbols) => {
+            names.extend(symbols.iter().map(|(local, _)| local.node.as_str()));
+        }
+        Statement::Break
+        | Statement::Continue
+        | Statement::Pass
+        | Statement::Return(..)
+        | Statement::Expression(..) => {}
+    }
+}
+
+fn assign_target_names<'a>(target: &'a AstAssignTargetExpr, names: &mut HashSet<&'a str>) {
+    match target.node {
+        AssignTargetExpr::Identifier(ref name) => {
+            names.insert(&name.node);
+        }
+        AssignTargetExpr::Subtargets(ref subtargets) => {
+            for s in subtargets {
+                assign_target_names(s, names);
+            }
+        }
+        AssignTargetExpr::Dot(..) | AssignTargetExpr::ArrayIndirection(..) => {}
+    }
+}
+
+fn assign_target_exprs<'a>(target: &'a AstAssignTargetExpr, exprs: &mut Vec<&'a AstExpr>) {
+    match target.node {
+        AssignTargetExpr::Identifier(..) => {}
+        AssignTargetExpr::Dot(ref object, _) => exprs.push(object),
+        AssignTargetExpr::ArrayIndirection(ref array, ref index) => {
+            exprs.push(array);
+            exprs.push(index);
+        }
+        AssignTargetExpr::Subtargets(ref subtargets) => {
+            for s in subtargets {
+                assign_target_exprs(s, exprs);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stdlib::global_environment;
+
+    fn check(content: &str) -> Vec<(String, String)> {
+        let (env, _) = global_environment();
+        let map = Arc::new(Mutex::new(CodeMap::new()));
+        check_calls(&map, "test.bzl", content, Dialect::Bzl, &env)
+            .unwrap()
+            .into_iter()
+            .map(|d| (d.code.unwrap(), d.message))
+            .collect()
+    }
+
+    fn messages(content: &str) -> Vec<String> {
+        check(content).into_iter().map(|(_, m)| m).collect()
+    }
+
+    #[test]
+    fn test_wrong_arity() {
+        assert_eq!(
+            vec![
+                (
+                    "CF00".to_owned(),
+                    "Missing parameter a for call to len".to_owned()
+                ),
+                (
+                    "CF05".to_owned(),
+                    "Too many positional arguments for call to len".to_owned()
+                ),
+            ],
+            check("len()\nlen([], [])")
+        );
+        assert_eq!(
+            vec![
+                "Missing parameter b for call to f",
+                "Too many positional arguments for call to f",
+            ],
+            messages("def f(a, b, c = 1):\n  pass\nf(1)\nf(1, 2, 3, 4)\nf(1, 2, 3)")
+        );
+        assert_eq!(
+            vec!["Missing parameter a for call to g"],
+            messages("def g(a):\n  return [x for x in [g()]]\n")
+        );
+    }
+
+    #[test]
+    fn test_unknown_keyword() {
+        assert_eq!(
+            vec![
+                (
+                    "CF05".to_owned(),
+                    "Unexpected keyword argument x for call to f".to_owned()
+                ),
+                (
+                    "CF05".to_owned(),
+                    "Multiple values for parameter a in call to f".to_owned()
+                ),
+            ],
+            check("def f(a, b = 1):\n  pass\nf(1, x = 2)\nf(1, a = 2)\nf(b = 2, a = 1)")
+        );
+        // Positional-only parameters of native functions cannot be passed by name.
+        assert_eq!(
+            vec![
+                "Missing parameter a for call to len",
+                "Unexpected keyword argument a for call to len",
+            ],
+            messages("len(a = [])")
+        );
+        assert_eq!(
+            Vec::<String>::new(),
+            messages("def f(a, **kwargs):\n  pass\nf(1, x = 2)")
+        );
+    }
+
+    #[test]
+    fn test_unknown_functions_are_skipped() {
+        assert_eq!(
+            Vec::<String>::new(),
+            messages(concat!(
+                "def f(len):\n  return len(1, 2, 3)\n",
+                "def g(*args):\n  return len(*args)\n",
+                "h = len\nh(1, 2)\n",
+                "[len][0](1, 2)\n",
+                "[1].append(1, 2)\n",
+                "x = [l() for l in [len]]\n",
+                "unknown(1, 2)\n",
+                // Nothing is evaluated
+                "fail('not evaluated')\n",
+            ))
+        );
+    }
+}
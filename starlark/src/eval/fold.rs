@@ -0,0 +1,487 @@
+// Copyright 2019 The Starlark in Rust Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Constant folding of compiled expressions.
+//!
+//! Only operations whose operands are all constants are folded, so no expression
+//! with side effects is ever evaluated ahead of time. Operations which fail
+//! (e.g. `1 // 0`) are left unchanged to report the error at runtime. Names
+//! such as `True` are not constants since they can be rebound, except the
+//! global names injected with [`Module::fold_constants_with`](crate::eval::module::Module::fold_constants_with).
+
+use crate::eval::bin_op;
+use crate::eval::def::ParameterCompiled;
+use crate::eval::expr::AssignTargetExprCompiled;
+use crate::eval::expr::AstAssignTargetExprCompiled;
+use crate::eval::expr::AstClauseCompiled;
+use crate::eval::expr::AstExprCompiled;
+use crate::eval::expr::AugmentedAssignTargetExprCompiled;
+use crate::eval::expr::ClauseCompiled;
+use crate::eval::expr::ExprCompiled;
+use crate::eval::expr::GlobalOrSlot;
+use crate::eval::stmt::AstStatementCompiled;
+use crate::eval::stmt::BlockCompiled;
+use crate::eval::stmt::StatementCompiled;
+use crate::syntax::ast::BinOp;
+use crate::values::bigint::BigInt;
+use crate::values::none::NoneType;
+use crate::values::{Value, ValueResult};
+use codemap::Spanned;
+use std::collections::HashMap;
+use std::mem;
+
+/// Fold the statements of `block`, replacing `if` statements with a constant condition by
+/// the statements of the branch which would be executed.
+pub(crate) fn fold_block(block: &mut BlockCompiled, constants: &HashMap<String, Value>) {
+    let mut folded = Vec::with_capacity(block.0.len());
+    for mut stmt in mem::take(&mut block.0) {
+        fold_stmt(&mut stmt, constants);
+        let span = stmt.span;
+        match stmt.node {
+            StatementCompiled::IfElse(cond, then_block, else_block) => {
+                match constant(&cond).map(Value::to_bool) {
+                    Some(taken) => {
+                        let branch = if taken { then_block } else { else_block };
+                        if branch.0.is_empty() {
+                            // An empty branch evaluates to `None`
+                            let none = Spanned {
+                                node: ExprCompiled::Value(Value::new(NoneType::None)),
+                                span,
+                            };
+                            folded.push(Spanned {
+                                node: StatementCompiled::Expression(Box::new(none)),
+                                span,
+                            });
+                        } else {
+                            folded.extend(branch.0);
+                        }
+                    }
+                    None => folded.push(Spanned {
+                        node: StatementCompiled::IfElse(cond, then_block, else_block),
+                        span,
+                    }),
+                }
+            }
+            node => folded.push(Spanned { node, span }),
+        }
+    }
+    block.0 = folded;
+}
+
+fn fold_stmt(stmt: &mut AstStatementCompiled, constants: &HashMap<String, Value>) {
+    match stmt.node {
+        StatementCompiled::Break
+        | StatementCompiled::Continue
+        | StatementCompiled::Return(None)
+        | StatementCompiled::Load(..) => {}
+        StatementCompiled::Return(Some(ref mut e)) | StatementCompiled::Expression(ref mut e) => {
+            fold_expr(e, constants)
+        }
+        StatementCompiled::Assign(ref mut target, ref mut e) => {
+            fold_assign_target(target, constants);
+            fold_expr(e, constants);
+        }
+        StatementCompiled::AugmentedAssign(ref mut target, _, ref mut e) => {
+            match target.node {
+                AugmentedAssignTargetExprCompiled::Slot(..) => {}
+                AugmentedAssignTargetExprCompiled::Dot(ref mut object, _) => {
+                    fold_expr(object, constants)
+                }
+                AugmentedAssignTargetExprCompiled::ArrayIndirection(ref mut a, ref mut i) => {
+                    fold_expr(a, constants);
+                    fold_expr(i, constants);
+                }
+            }
+            fold_expr(e, constants);
+        }
+        StatementCompiled::IfElse(ref mut cond, ref mut then_block, ref mut else_block) => {
+            fold_expr(cond, constants);
+            fold_block(then_block, constants);
+            fold_block(else_block, constants);
+        }
+        StatementCompiled::For(ref mut target, ref mut over, ref mut body) => {
+            fold_assign_target(target, constants);
+            fold_expr(over, constants);
+            fold_block(body, constants);
+        }
+        StatementCompiled::Def(ref mut def) => {
+            for p in &mut def.params {
+                match p.node {
+                    ParameterCompiled::WithDefaultValue(_, ref mut e)
+                    | ParameterCompiled::LazyDefaultValue(_, ref mut e, _) => {
+                        fold_expr(e, constants)
+                    }
+                    ParameterCompiled::Normal(..)
+                    | ParameterCompiled::Args(..)
+                    | ParameterCompiled::KWArgs(..) => {}
+                }
+            }
+            fold_block(&mut def.suite, constants);
+        }
+    }
+}
+
+fn fold_assign_target(
+    target: &mut AstAssignTargetExprCompiled,
+    constants: &HashMap<String, Value>,
+) {
+    match target.node {
+        AssignTargetExprCompiled::Name(..) => {}
+        AssignTargetExprCompiled::Dot(ref mut object, _) => fold_expr(object, constants),
+        AssignTargetExprCompiled::ArrayIndirection(ref mut a, ref mut i) => {
+            fold_expr(a, constants);
+            fold_expr(i, constants);
+        }
+        AssignTargetExprCompiled::Subtargets(ref mut targets) => {
+            for t in targets {
+                fold_assign_target(t, constants);
+            }
+        }
+    }
+}
+
+fn fold_clauses(clauses: &mut Vec<AstClauseCompiled>, constants: &HashMap<String, Value>) {
+    for clause in clauses {
+        match clause.node {
+            ClauseCompiled::For(ref mut target, ref mut over) => {
+                fold_assign_target(target, constants);
+                fold_expr(over, constants);
+            }
+            ClauseCompiled::If(ref mut cond) => fold_expr(cond, constants),
+        }
+    }
+}
+
+fn fold_all(exprs: &mut Vec<AstExprCompiled>, constants: &HashMap<String, Value>) {
+    for e in exprs {
+        fold_expr(e, constants);
+    }
+}
+
+fn fold_opt(expr: &mut Option<AstExprCompiled>, constants: &HashMap<String, Value>) {
+    if let Some(ref mut e) = expr {
+        fold_expr(e, constants);
+    }
+}
+
+fn constant(expr: &AstExprCompiled) -> Option<&Value> {
+    match expr.node {
+        ExprCompiled::Value(ref v) => Some(v),
+        _ => None,
+    }
+}
+
+/// Largest length of a sequence and number of bits of an integer created by a folded
+/// operation: larger results are only computed if the operation is evaluated.
+const MAX_FOLDED_SIZE: i128 = 1 << 12;
+
+/// Whether the result of `l op r` is small enough to be folded, without computing it.
+fn small_result(op: BinOp, l: &Value, r: &Value) -> bool {
+    match op {
+        BinOp::Multiplication => {
+            // A sequence repeated `n` times, in any order
+            let (n, seq) = if l.get_type() == "int" {
+                (l, r)
+            } else {
+                (r, l)
+            };
+            match (n.downcast_ref::<i64>(), seq.get_type()) {
+                (Some(n), "string") | (Some(n), "tuple") | (Some(n), "list") => {
+                    i128::from(*n) * i128::from(seq.length().unwrap()) <= MAX_FOLDED_SIZE
+                }
+                _ => true,
+            }
+        }
+        BinOp::Power => match (BigInt::from_value(l), r.downcast_ref::<i64>()) {
+            (Some(base), Some(exp)) => {
+                i128::from(base.bit_length()) * i128::from(*exp) <= MAX_FOLDED_SIZE
+            }
+            _ => true,
+        },
+        _ => true,
+    }
+}
+
+/// Only keep results of immutable types: a folded value is shared by all evaluations.
+fn constant_result(result: ValueResult) -> Option<ExprCompiled> {
+    match result {
+        Ok(v) => match v.get_type() {
+            "NoneType" | "bool" | "int" | "string" => Some(ExprCompiled::Value(v)),
+            _ => None,
+        },
+        Err(..) => None,
+    }
+}
+
+/// Fold subexpressions of `expr`, then `expr` itself if possible.
+pub(crate) fn fold_expr(expr: &mut AstExprCompiled, constants: &HashMap<String, Value>) {
+    match expr.node {
+        ExprCompiled::Name(ref name) => {
+            if let GlobalOrSlot::Global(ref name) = name.node {
+                if let Some(folded) = constants
+                    .get(name)
+                    .and_then(|v| constant_result(Ok(v.clone())))
+                {
+                    expr.node = folded;
+                }
+            }
+            return;
+        }
+        ExprCompiled::Value(..) => return,
+        ExprCompiled::Tuple(ref mut v) | ExprCompiled::List(ref mut v) => fold_all(v, constants),
+        ExprCompiled::Set(ref mut v) => fold_all(v, constants),
+        ExprCompiled::Dot(ref mut e, _)
+        | ExprCompiled::Not(ref mut e)
+        | ExprCompiled::Minus(ref mut e)
+        | ExprCompiled::Plus(ref mut e)
+        | ExprCompiled::Local(ref mut e, _) => fold_expr(e, constants),
+        ExprCompiled::Call(ref mut f, ref mut pos, ref mut named, ref mut args, ref mut kwargs) => {
+            fold_expr(f, constants);
+            fold_all(pos, constants);
+            for (_, e) in named {
+                fold_expr(e, constants);
+            }
+            fold_opt(args, constants);
+            fold_opt(kwargs, constants);
+        }
+        ExprCompiled::ArrayIndirection(ref mut a, ref mut b)
+        | ExprCompiled::And(ref mut a, ref mut b)
+        | ExprCompiled::Or(ref mut a, ref mut b)
+        | ExprCompiled::Op(_, ref mut a, ref mut b) => {
+            fold_expr(a, constants);
+            fold_expr(b, constants);
+        }
+        ExprCompiled::Slice(ref mut a, ref mut start, ref mut stop, ref mut stride) => {
+            fold_expr(a, constants);
+            fold_opt(start, constants);
+            fold_opt(stop, constants);
+            fold_opt(stride, constants);
+        }
+        ExprCompiled::If(ref mut cond, ref mut a, ref mut b) => {
+            fold_expr(cond, constants);
+            fold_expr(a, constants);
+            fold_expr(b, constants);
+        }
+        ExprCompiled::Dict(ref mut pairs) => {
+            for (k, v) in pairs {
+                fold_expr(k, constants);
+                fold_expr(v, constants);
+            }
+        }
+        ExprCompiled::ListComprehension(ref mut e, ref mut clauses)
+        | ExprCompiled::SetComprehension(ref mut e, ref mut clauses) => {
+            fold_expr(e, constants);
+            fold_clauses(clauses, constants);
+        }
+        ExprCompiled::DictComprehension((ref mut k, ref mut v), ref mut clauses) => {
+            fold_expr(k, constants);
+            fold_expr(v, constants);
+            fold_clauses(clauses, constants);
+        }
+    }
+
+    let folded = match expr.node {
+        ExprCompiled::Not(ref e) => {
+            constant(e).map(|v| ExprCompiled::Value(Value::new(!v.to_bool())))
+        }
+        ExprCompiled::Minus(ref e) => constant(e).and_then(|v| constant_result(v.minus())),
+        ExprCompiled::Plus(ref e) => constant(e).and_then(|v| constant_result(v.plus())),
+        ExprCompiled::Op(op, ref l, ref r) => match (constant(l), constant(r)) {
+            (Some(l), Some(r)) if small_result(op, l, r) => {
+                constant_result(bin_op(op, l.clone(), r.clone()))
+            }
+            _ => None,
+        },
+        _ => None,
+    };
+    if let Some(folded) = folded {
+        expr.node = folded;
+        return;
+    }
+
+    // Short-circuit operators and conditional expressions with a constant condition
+    // are replaced by the branch which would be evaluated.
+    let placeholder = ExprCompiled::Value(Value::new(NoneType::None));
+    match mem::replace(&mut expr.node, placeholder) {
+        ExprCompiled::And(l, r) => match constant(&l).map(Value::to_bool) {
+            Some(true) => *expr = r,
+            Some(false) => *expr = l,
+            None => expr.node = ExprCompiled::And(l, r),
+        },
+        ExprCompiled::Or(l, r) => match constant(&l).map(Value::to_bool) {
+            Some(true) => *expr = l,
+            Some(false) => *expr = r,
+            None => expr.node = ExprCompiled::Or(l, r),
+        },
+        ExprCompiled::If(cond, a, b) => match constant(&cond).map(Value::to_bool) {
+            Some(true) => *expr = a,
+            Some(false) => *expr = b,
+            None => expr.node = ExprCompiled::If(cond, a, b),
+        },
+        node => expr.node = node,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::eval::expr::ExprCompiled;
+    use crate::eval::module::Module;
+    use crate::eval::stmt::StatementCompiled;
+    use crate::syntax::dialect::Dialect;
+    use crate::syntax::parser::parse;
+    use crate::values::Value;
+    use codemap::CodeMap;
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+
+    fn parse_module(program: &str) -> Module {
+        parse(
+            &Arc::new(Mutex::new(CodeMap::new())),
+            "fold.bzl",
+            program,
+            Dialect::Bzl,
+        )
+        .unwrap()
+    }
+
+    /// The kinds of the statements of the body of `def f(x)` after folding with `DEBUG`
+    /// injected.
+    fn folded_with_debug(debug: bool, body: &str) -> Vec<&'static str> {
+        let mut constants = HashMap::new();
+        constants.insert("DEBUG".to_owned(), Value::new(debug));
+        let mut module = parse_module(&format!("def f(x):\n{}", body));
+        module.fold_constants_with(&constants);
+        match (module.0).0[0].node {
+            StatementCompiled::Def(ref def) => def
+                .suite
+                .0
+                .iter()
+                .map(|stmt| match stmt.node {
+                    StatementCompiled::IfElse(..) => "if",
+                    StatementCompiled::Return(..) => "return",
+                    StatementCompiled::Expression(ref e) => match e.node {
+                        ExprCompiled::Value(..) => "const",
+                        _ => "expression",
+                    },
+                    _ => "other",
+                })
+                .collect(),
+            _ => unreachable!(),
+        }
+    }
+
+    fn folded(expr: &str) -> String {
+        let mut module = parse_module(expr);
+        module.fold_constants();
+        match (module.0).0[0].node {
+            StatementCompiled::Expression(ref e) => match e.node {
+                ExprCompiled::Value(ref v) => format!("const {}", v.to_repr()),
+                ExprCompiled::Name(..) => "name".to_owned(),
+                ExprCompiled::Op(..) => "op".to_owned(),
+                ExprCompiled::Call(..) => "call".to_owned(),
+                ExprCompiled::And(..) | ExprCompiled::Or(..) => "logic".to_owned(),
+                ExprCompiled::List(..) => "list".to_owned(),
+                _ => "other".to_owned(),
+            },
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn fold_literals() {
+        assert_eq!("const 7", folded("1 + 2 * 3"));
+        assert_eq!("const -2", folded("-(1 + 1)"));
+        assert_eq!("const \"ab\"", folded("'a' + 'b'"));
+        assert_eq!("const \"aaa\"", folded("'a' * 3"));
+        assert_eq!("const True", folded("1 < 2"));
+        assert_eq!("const False", folded("not 'x'"));
+        assert_eq!("const \"x=1\"", folded("'x=%d' % 1"));
+        assert_eq!("const True", folded("'b' in 'abc'"));
+        assert_eq!(
+            "const 9223372036854775808",
+            folded("9223372036854775807 + 1")
+        );
+    }
+
+    #[test]
+    fn fold_short_circuit() {
+        assert_eq!("name", folded("1 == 1 and x"));
+        assert_eq!("const 0", folded("0 and x"));
+        assert_eq!("const 1", folded("1 or f()"));
+        assert_eq!("call", folded("0 or f()"));
+        assert_eq!("name", folded("(x if 2 > 1 else fail('unreachable'))"));
+        assert_eq!("const 3", folded("(fail('unreachable') if '' else 1 + 2)"));
+    }
+
+    #[test]
+    fn no_fold() {
+        // Errors are reported at runtime
+        assert_eq!("op", folded("1 // 0"));
+        assert_eq!("op", folded("2 ** -1"));
+        assert_eq!("op", folded("1 + 'a'"));
+        // Names may be rebound and calls may have side effects
+        assert_eq!("logic", folded("True and x"));
+        assert_eq!("logic", folded("f() and 1"));
+        assert_eq!("op", folded("f() + 1"));
+        // Mutable values must not be shared between evaluations
+        assert_eq!("list", folded("[1 + 1]"));
+        // Large results are only computed when evaluated
+        assert_eq!("op", folded("'a' * 10 ** 12"));
+        assert_eq!("op", folded("(1,) * 5000"));
+        assert_eq!("op", folded("2 ** 5000"));
+        assert_eq!(
+            format!("const \"{}\"", "ab".repeat(2048)),
+            folded("2048 * 'ab'")
+        );
+    }
+
+    #[test]
+    fn prune_injected_constants() {
+        let body = "  if DEBUG:\n    fail('debug')\n  return x\n";
+        assert_eq!(vec!["const", "return"], folded_with_debug(false, body));
+        assert_eq!(vec!["expression", "return"], folded_with_debug(true, body));
+
+        let body = "  if not DEBUG:\n    x += 1\n    return x\n  else:\n    fail('debug')\n";
+        assert_eq!(vec!["other", "return"], folded_with_debug(false, body));
+        assert_eq!(vec!["expression"], folded_with_debug(true, body));
+
+        // Conditions which are not constant are kept
+        assert_eq!(
+            vec!["if"],
+            folded_with_debug(false, "  if x and DEBUG:\n    g()\n")
+        );
+        assert_eq!(
+            vec!["if"],
+            folded_with_debug(false, "  if OTHER:\n    g()\n")
+        );
+        // Shadowed constants are not replaced
+        assert_eq!(
+            vec!["other", "if"],
+            folded_with_debug(false, "  DEBUG = x\n  if DEBUG:\n    g()\n")
+        );
+        // Without injected constants, only constant expressions are pruned
+        let mut module = parse_module("def f():\n  if DEBUG:\n    g()\n  if 1 > 2:\n    g()\n");
+        module.fold_constants();
+        match (module.0).0[0].node {
+            StatementCompiled::Def(ref def) => match def.suite.0[..] {
+                [ref first, ref second] => match (&first.node, &second.node) {
+                    (StatementCompiled::IfElse(..), StatementCompiled::Expression(..)) => {}
+                    stmts => panic!("{:?}", stmts),
+                },
+                ref stmts => panic!("{:?}", stmts),
+            },
+            _ => unreachable!(),
+        }
+    }
+}
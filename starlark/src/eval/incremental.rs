@@ -0,0 +1,319 @@
+// Copyright 2019 The Starlark in Rust Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Evaluation of a module in chunks of top-level statements, returning control to the host
+//! between chunks.
+//!
+//! The interpreter is synchronous, so a host running on an async executor would block its
+//! thread for the whole evaluation of a module. With an [`IncrementalEvaluation`] the host
+//! can instead run a few statements, yield to the executor, and resume later, e.g. calling
+//! [`step`](IncrementalEvaluation::step) in a loop in an async task and awaiting a yield
+//! between calls.
+//!
+//! The budget of a step counts all the statements executed, including those nested in
+//! loops and in the functions called. Control is only returned between top-level statements
+//! of the module, so a top-level statement executing more statements than the budget of a
+//! step on its own fails the evaluation with a `CE09` error instead of blocking the host.
+//!
+//! Driving an evaluation two statements at a time from a host loop:
+//!
+//! ```
+//! # use starlark::eval::incremental::IncrementalEvaluation;
+//! # use starlark::eval::module::Module;
+//! # use starlark::eval::noload::NoLoadFileLoader;
+//! # use starlark::stdlib::global_environment;
+//! # use starlark::syntax::dialect::Dialect;
+//! # use codemap::CodeMap;
+//! # use std::sync::{Arc, Mutex};
+//! # use std::task::Poll;
+//! let map = Arc::new(Mutex::new(CodeMap::new()));
+//! let (global, type_values) = global_environment();
+//! let env = global.freeze().child("my.bzl");
+//! let module = Module::parse(
+//!     &map,
+//!     "my.bzl",
+//!     "a = 1\nb = a + 1\nc = b * 10\nd = c + a\nd",
+//!     Dialect::Bzl,
+//! )
+//! .unwrap();
+//! let mut evaluation =
+//!     IncrementalEvaluation::new(&map, module, &env, &type_values, NoLoadFileLoader);
+//! let mut chunks = 0;
+//! let result = loop {
+//!     chunks += 1;
+//!     match evaluation.step(2) {
+//!         // A real host would yield to its executor here.
+//!         Poll::Pending => continue,
+//!         Poll::Ready(result) => break result,
+//!     }
+//! };
+//! assert_eq!(21, result.unwrap().to_int().unwrap());
+//! assert_eq!(3, chunks);
+//! assert_eq!(20, env.get("c").unwrap().to_int().unwrap());
+//! ```
+
+use crate::environment::{Environment, TypeValues};
+use crate::eval::call_stack::CallStack;
+use crate::eval::module::Module;
+use crate::eval::{eval_stmt, EvaluationContext, EvaluationContextEnvironment, FileLoader};
+use crate::values::error::activate_max_error_repr_length;
+use crate::values::none::NoneType;
+use crate::values::Value;
+use codemap::CodeMap;
+use codemap_diagnostic::Diagnostic;
+use std::cell::Cell;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+use std::task::Poll;
+
+// The budget is thread-local because statements are also executed by the functions called by
+// the module, which are evaluated without the context of the module.
+thread_local!(static STATEMENT_BUDGET: Cell<Option<usize>> = const { Cell::new(None) });
+
+/// Count a statement execution against the budget of the running step, if any. Return `false`
+/// if the budget is exhausted.
+pub(crate) fn consume_statement() -> bool {
+    STATEMENT_BUDGET.with(|budget| match budget.get() {
+        None => true,
+        Some(0) => false,
+        Some(n) => {
+            budget.set(Some(n - 1));
+            true
+        }
+    })
+}
+
+/// Restores the previous budget on drop.
+#[must_use]
+struct BudgetGuard {
+    previous: Option<usize>,
+}
+
+impl BudgetGuard {
+    fn set(budget: usize) -> BudgetGuard {
+        let previous = STATEMENT_BUDGET.with(|b| b.replace(Some(budget)));
+        BudgetGuard { previous }
+    }
+
+    /// The remaining budget.
+    fn remaining(&self) -> usize {
+        STATEMENT_BUDGET.with(Cell::get).unwrap_or(0)
+    }
+}
+
+impl Drop for BudgetGuard {
+    fn drop(&mut self) {
+        STATEMENT_BUDGET.with(|b| b.set(self.previous));
+    }
+}
+
+/// The state of the evaluation of a module, which can be resumed with
+/// [`step`](IncrementalEvaluation::step) until it completes.
+pub struct IncrementalEvaluation<'a> {
+    map: Arc<Mutex<CodeMap>>,
+    module: Module,
+    env: Environment,
+    type_values: &'a TypeValues,
+    file_loader: Rc<dyn FileLoader>,
+    call_stack: CallStack,
+    /// Index of the next top-level statement to evaluate.
+    next: usize,
+    /// Value of the last evaluated statement.
+    value: Value,
+    /// Set when the evaluation completed, successfully or not.
+    result: Option<Result<Value, Diagnostic>>,
+}
+
+impl<'a> IncrementalEvaluation<'a> {
+    /// Prepare the evaluation of `module` in `env`, without evaluating any statement yet.
+    ///
+    /// The arguments are the same as for
+    /// [`eval_parsed_module`](crate::eval::eval_parsed_module).
+    pub fn new<T: FileLoader + 'static>(
+        map: &Arc<Mutex<CodeMap>>,
+        module: Module,
+        env: &Environment,
+        type_values: &'a TypeValues,
+        file_loader: T,
+    ) -> IncrementalEvaluation<'a> {
+        let mut call_stack = CallStack::default();
+        call_stack.set_module_name(env.name());
+        call_stack.set_print_handler(env.print_handler());
+        IncrementalEvaluation {
+            map: map.clone(),
+            module,
+            env: env.clone(),
+            type_values,
+            file_loader: Rc::new(file_loader),
+            call_stack,
+            next: 0,
+            value: Value::new(NoneType::None),
+            result: None,
+        }
+    }
+
+    /// Evaluate top-level statements until `max_statements` statements, including the nested
+    /// ones, have been executed.
+    ///
+    /// A top-level statement executing more than `max_statements` statements on its own stops
+    /// the evaluation with an error.
+    ///
+    /// Return `Poll::Pending` if statements remain to be evaluated, or `Poll::Ready` with the
+    /// value of the last statement of the module, or the error which stopped the evaluation,
+    /// once the evaluation is complete. Calling `step` again after that returns the same
+    /// result.
+    pub fn step(&mut self, max_statements: usize) -> Poll<Result<Value, Diagnostic>> {
+        if let Some(ref result) = self.result {
+            return Poll::Ready(result.clone());
+        }
+        let statements = &(self.module.0).0;
+        #[cfg(feature = "provenance")]
+        let _provenance = self.env.provenance_recorder().map(|r| r.activate());
+        let _metrics = self.env.metrics_recorder().map(|r| r.activate());
+        let _error_repr = activate_max_error_repr_length(self.env.max_error_repr_length());
+        let mut context = EvaluationContext {
+            env: EvaluationContextEnvironment::Module(self.env.clone(), self.file_loader.clone()),
+            type_values: self.type_values,
+            call_stack: &mut self.call_stack,
+            map: self.map.clone(),
+            tracer: self.env.tracer(),
+            debug_hook: self.env.debug_hook(),
+        };
+        let mut executed = 0;
+        while self.next < statements.len() && executed < max_statements {
+            let budget = BudgetGuard::set(max_statements);
+            match eval_stmt(&statements[self.next], &mut context) {
+                Ok(value) => self.value = value,
+                Err(e) => {
+                    self.result = Some(Err(e.into()));
+                    return Poll::Ready(self.result.clone().unwrap());
+                }
+            }
+            executed += max_statements - budget.remaining();
+            self.next += 1;
+        }
+        if self.next == statements.len() {
+            self.result = Some(Ok(self.value.clone()));
+            Poll::Ready(Ok(self.value.clone()))
+        } else {
+            Poll::Pending
+        }
+    }
+
+    /// Whether the evaluation is complete.
+    pub fn is_done(&self) -> bool {
+        self.result.is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eval::noload::NoLoadFileLoader;
+    use crate::stdlib::global_environment;
+    use crate::syntax::dialect::Dialect;
+
+    fn evaluation<'a>(
+        map: &Arc<Mutex<CodeMap>>,
+        code: &str,
+        env: &Environment,
+        type_values: &'a TypeValues,
+    ) -> IncrementalEvaluation<'a> {
+        let module = Module::parse(map, "test.bzl", code, Dialect::Bzl).unwrap();
+        IncrementalEvaluation::new(map, module, env, type_values, NoLoadFileLoader)
+    }
+
+    #[test]
+    fn test_step_by_statement() {
+        let map = Arc::new(Mutex::new(CodeMap::new()));
+        let (global, type_values) = global_environment();
+        let env = global.freeze().child("test");
+        let code = "x = [1]\ndef f(n):\n  x.append(n)\n  return len(x)\nf(2)\nf(3)";
+        let mut evaluation = evaluation(&map, code, &env, &type_values);
+        assert!(evaluation.step(0).is_pending());
+        assert!(env.get("x").is_err());
+        assert!(evaluation.step(1).is_pending());
+        assert_eq!("[1]", env.get("x").unwrap().to_repr());
+        // `f(2)` executes 3 statements
+        assert!(evaluation.step(3).is_pending());
+        assert_eq!("[1, 2]", env.get("x").unwrap().to_repr());
+        assert!(!evaluation.is_done());
+        match evaluation.step(10) {
+            Poll::Ready(Ok(v)) => assert_eq!(3, v.to_int().unwrap()),
+            r => panic!("Unexpected result {:?}", r),
+        }
+        assert!(evaluation.is_done());
+        // The result is kept once the evaluation is done
+        match evaluation.step(1) {
+            Poll::Ready(Ok(v)) => assert_eq!(3, v.to_int().unwrap()),
+            r => panic!("Unexpected result {:?}", r),
+        }
+        assert_eq!("[1, 2, 3]", env.get("x").unwrap().to_repr());
+    }
+
+    #[test]
+    fn test_budget_counts_nested_statements() {
+        let map = Arc::new(Mutex::new(CodeMap::new()));
+        let (global, type_values) = global_environment();
+        let env = global.freeze().child("test");
+        let code = "x = []\n\
+                    def f(n):\n  for i in range(n):\n    x.append(i)\n\
+                    f(3)\n\
+                    f(1000000)\n\
+                    y = 1";
+        let mut evaluation = evaluation(&map, code, &env, &type_values);
+        // `x = []` and `def f`, then `f(3)` which executes 5 statements
+        assert!(evaluation.step(2).is_pending());
+        assert!(evaluation.step(5).is_pending());
+        assert_eq!("[0, 1, 2]", env.get("x").unwrap().to_repr());
+        // The loop of `f(1000000)` is stopped partway instead of blocking the host
+        match evaluation.step(10) {
+            Poll::Ready(Err(d)) => assert_eq!(Some("CE09".to_owned()), d.code),
+            r => panic!("Unexpected result {:?}", r),
+        }
+        // The call, the loop and 8 iterations were executed
+        assert_eq!(11, env.get("x").unwrap().length().unwrap());
+        assert!(env.get("y").is_err());
+    }
+
+    #[test]
+    fn test_empty_module() {
+        let map = Arc::new(Mutex::new(CodeMap::new()));
+        let (global, type_values) = global_environment();
+        let env = global.freeze().child("test");
+        let mut evaluation = evaluation(&map, "", &env, &type_values);
+        match evaluation.step(1) {
+            Poll::Ready(Ok(v)) => assert_eq!("None", v.to_repr()),
+            r => panic!("Unexpected result {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_error_stops_evaluation() {
+        let map = Arc::new(Mutex::new(CodeMap::new()));
+        let (global, type_values) = global_environment();
+        let env = global.freeze().child("test");
+        let mut evaluation = evaluation(&map, "a = 1\nb = 1 // 0\nc = 3", &env, &type_values);
+        assert!(evaluation.step(1).is_pending());
+        match evaluation.step(1) {
+            Poll::Ready(Err(d)) => assert_eq!(Some("CV08".to_owned()), d.code),
+            r => panic!("Unexpected result {:?}", r),
+        }
+        match evaluation.step(1) {
+            Poll::Ready(Err(d)) => assert_eq!(Some("CV08".to_owned()), d.code),
+            r => panic!("Unexpected result {:?}", r),
+        }
+        assert!(env.get("c").is_err());
+    }
+}
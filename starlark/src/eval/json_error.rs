@@ -0,0 +1,198 @@
+// Copyright 2019 The Starlark in Rust Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Machine-readable rendering of evaluation errors as JSON.
+//!
+//! A [`Diagnostic`] is rendered as an object with the fields:
+//!
+//!  * `level`: `"bug"`, `"error"`, `"warning"`, `"note"` or `"help"`,
+//!  * `code`: the error code, e.g. `"CV08"`, or `null`,
+//!  * `message`: the error message,
+//!  * `label`, `file` and `span`: the label, file name and location of the primary span,
+//!    or `null` if the diagnostic has none,
+//!  * `backtrace`: the other spans of the diagnostic, each an object with `label`, `file`
+//!    and `span` fields.
+//!
+//! A span is an object `{"start": {"line": l, "column": c}, "end": {...}}` where lines and
+//! columns start at 1. A [`ValueError`](crate::values::error::ValueError) can be rendered
+//! after converting it to a diagnostic with
+//! [`SyntaxError::to_diagnostic`](crate::syntax::errors::SyntaxError::to_diagnostic).
+
+use codemap::{CodeMap, Span};
+use codemap_diagnostic::{Diagnostic, Level, SpanLabel, SpanStyle};
+use std::fmt::Write;
+
+/// Render `diagnostic` as a JSON object, resolving its spans with `codemap`.
+pub fn diagnostic_to_json(diagnostic: &Diagnostic, codemap: &CodeMap) -> String {
+    let primary = diagnostic
+        .spans
+        .iter()
+        .position(|s| s.style == SpanStyle::Primary);
+    let mut out = String::new();
+    out.push('{');
+    write!(out, "\"level\":\"{}\"", level_name(diagnostic.level)).unwrap();
+    out.push_str(",\"code\":");
+    write_opt_string(&mut out, diagnostic.code.as_deref());
+    out.push_str(",\"message\":");
+    write_string(&mut out, &diagnostic.message);
+    match primary {
+        Some(i) => {
+            out.push(',');
+            write_span_label_fields(&mut out, &diagnostic.spans[i], codemap);
+        }
+        None => out.push_str(",\"label\":null,\"file\":null,\"span\":null"),
+    }
+    out.push_str(",\"backtrace\":[");
+    let others = diagnostic
+        .spans
+        .iter()
+        .enumerate()
+        .filter(|&(i, _)| Some(i) != primary);
+    for (n, (_, span_label)) in others.enumerate() {
+        if n != 0 {
+            out.push(',');
+        }
+        out.push('{');
+        write_span_label_fields(&mut out, span_label, codemap);
+        out.push('}');
+    }
+    out.push_str("]}");
+    out
+}
+
+fn level_name(level: Level) -> &'static str {
+    match level {
+        Level::Bug => "bug",
+        Level::Error => "error",
+        Level::Warning => "warning",
+        Level::Note => "note",
+        Level::Help => "help",
+    }
+}
+
+fn write_span_label_fields(out: &mut String, span_label: &SpanLabel, codemap: &CodeMap) {
+    out.push_str("\"label\":");
+    write_opt_string(out, span_label.label.as_deref());
+    let loc = codemap.look_up_span(span_label.span);
+    out.push_str(",\"file\":");
+    write_string(out, loc.file.name());
+    out.push_str(",\"span\":");
+    write_span(out, span_label.span, codemap);
+}
+
+fn write_span(out: &mut String, span: Span, codemap: &CodeMap) {
+    let loc = codemap.look_up_span(span);
+    write!(
+        out,
+        "{{\"start\":{{\"line\":{},\"column\":{}}},\"end\":{{\"line\":{},\"column\":{}}}}}",
+        loc.begin.line + 1,
+        loc.begin.column + 1,
+        loc.end.line + 1,
+        loc.end.column + 1,
+    )
+    .unwrap();
+}
+
+fn write_opt_string(out: &mut String, s: Option<&str>) {
+    match s {
+        Some(s) => write_string(out, s),
+        None => out.push_str("null"),
+    }
+}
+
+pub(crate) fn write_string(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => write!(out, "\\u{:04x}", c as u32).unwrap(),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::environment::Environment;
+    use crate::eval::noload::eval;
+    use crate::stdlib::global_environment;
+    use crate::syntax::dialect::Dialect;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn test_runtime_error_json() {
+        let map = Arc::new(Mutex::new(CodeMap::new()));
+        let (global, type_values) = global_environment();
+        let mut env: Environment = global.freeze().child("test");
+        let diagnostic = eval(
+            &map,
+            "test.bzl",
+            "x = 1\ny = \"a\\tb\" + x\n",
+            Dialect::Bzl,
+            &mut env,
+            &type_values,
+        )
+        .unwrap_err();
+        assert_eq!(
+            concat!(
+                "{\"level\":\"error\",\"code\":\"CV02\",",
+                "\"message\":\"Type of parameters mismatch\",",
+                "\"label\":\"Type of parameters mismatch\",",
+                "\"file\":\"test.bzl\",",
+                "\"span\":{\"start\":{\"line\":2,\"column\":5},\"end\":{\"line\":2,\"column\":15}},",
+                "\"backtrace\":[]}"
+            ),
+            diagnostic_to_json(&diagnostic, &map.lock().unwrap())
+        );
+    }
+
+    #[test]
+    fn test_secondary_spans_json() {
+        let mut map = CodeMap::new();
+        let file = map.add_file("a.bzl".to_owned(), "f()\ng()\n".to_owned());
+        let diagnostic = Diagnostic {
+            level: Level::Warning,
+            message: "two\nlines".to_owned(),
+            code: None,
+            spans: vec![
+                SpanLabel {
+                    span: file.span.subspan(4, 7),
+                    style: SpanStyle::Secondary,
+                    label: Some("called here".to_owned()),
+                },
+                SpanLabel {
+                    span: file.span.subspan(0, 3),
+                    style: SpanStyle::Primary,
+                    label: None,
+                },
+            ],
+        };
+        assert_eq!(
+            concat!(
+                "{\"level\":\"warning\",\"code\":null,\"message\":\"two\\nlines\",",
+                "\"label\":null,\"file\":\"a.bzl\",",
+                "\"span\":{\"start\":{\"line\":1,\"column\":1},\"end\":{\"line\":1,\"column\":4}},",
+                "\"backtrace\":[{\"label\":\"called here\",\"file\":\"a.bzl\",",
+                "\"span\":{\"start\":{\"line\":2,\"column\":1},\"end\":{\"line\":2,\"column\":4}}}]}"
+            ),
+            diagnostic_to_json(&diagnostic, &map)
+        );
+    }
+}
@@ -77,6 +77,18 @@ impl Locals {
     pub fn top_level_name_to_slot(&self, name: &str) -> Option<usize> {
         self.locals.local_index(name, &[])
     }
+
+    /// Names and slots of the variables of the top-level scope, ordered by slot
+    pub(crate) fn top_level_names(&self) -> Vec<(&str, usize)> {
+        let mut names: Vec<(&str, usize)> = self
+            .locals
+            .name_to_slot
+            .iter()
+            .map(|(name, slot)| (name.as_str(), *slot))
+            .collect();
+        names.sort_by_key(|&(_, slot)| slot);
+        names
+    }
 }
 
 impl LocalsBuilder {
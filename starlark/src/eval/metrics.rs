@@ -0,0 +1,133 @@
+// Copyright 2019 The Starlark in Rust Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Measurement of the resources used by evaluations, see
+//! [`Environment::with_metrics_recorder`](crate::environment::Environment::with_metrics_recorder).
+//!
+//! Enforcing a quota on the number of statements executed:
+//!
+//! ```
+//! # use starlark::eval::metrics::MetricsRecorder;
+//! # use starlark::eval::noload::eval;
+//! # use starlark::stdlib::global_environment;
+//! # use starlark::syntax::dialect::Dialect;
+//! # use codemap::CodeMap;
+//! # use std::sync::{Arc, Mutex};
+//! let (global, type_values) = global_environment();
+//! let mut env = global.freeze().child("my.bzl");
+//! let recorder = MetricsRecorder::new();
+//! env.with_metrics_recorder(recorder.clone());
+//! eval(
+//!     &Arc::new(Mutex::new(CodeMap::new())),
+//!     "my.bzl",
+//!     "x = [1, 2]\ny = len(x)",
+//!     Dialect::Bzl,
+//!     &mut env,
+//!     &type_values,
+//! )
+//! .unwrap();
+//! let metrics = recorder.metrics();
+//! assert_eq!(2, metrics.statements);
+//! assert_eq!(1, metrics.peak_call_depth);
+//! assert!(metrics.statements <= 1000, "quota exceeded");
+//! ```
+
+use std::cell::{Cell, RefCell};
+use std::cmp;
+use std::rc::Rc;
+
+/// Resources used by evaluations.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Metrics {
+    /// The number of statements executed, including those of the called functions.
+    pub statements: u64,
+    /// The maximum number of nested calls, `0` if no function was called.
+    pub peak_call_depth: usize,
+    /// The number of values allocated on the heap: `None`, booleans and integers are not
+    /// counted.
+    pub values_allocated: u64,
+}
+
+/// Accumulates the [`Metrics`] of the evaluations in an environment.
+///
+/// Clones share the same counters, so a clone can be given to the environment and the
+/// original queried after the evaluation, e.g. to enforce a quota.
+#[derive(Clone, Debug, Default)]
+pub struct MetricsRecorder {
+    metrics: Rc<Cell<Metrics>>,
+}
+
+impl MetricsRecorder {
+    pub fn new() -> MetricsRecorder {
+        MetricsRecorder::default()
+    }
+
+    /// The metrics accumulated so far.
+    pub fn metrics(&self) -> Metrics {
+        self.metrics.get()
+    }
+
+    /// Reset all the counters to zero.
+    pub fn reset(&self) {
+        self.metrics.set(Metrics::default());
+    }
+
+    /// Make this recorder the one receiving the counts until the returned guard is dropped.
+    pub(crate) fn activate(&self) -> ActiveGuard {
+        let previous = ACTIVE.with(|active| active.replace(Some(self.metrics.clone())));
+        ActiveGuard { previous }
+    }
+}
+
+// The active recorder is thread-local because values are allocated deep in the values, where
+// no evaluation context is available.
+thread_local!(static ACTIVE: RefCell<Option<Rc<Cell<Metrics>>>> = const { RefCell::new(None) });
+
+/// Restores the previously active recorder on drop.
+#[must_use]
+pub(crate) struct ActiveGuard {
+    previous: Option<Rc<Cell<Metrics>>>,
+}
+
+impl Drop for ActiveGuard {
+    fn drop(&mut self) {
+        let previous = self.previous.take();
+        ACTIVE.with(|active| *active.borrow_mut() = previous);
+    }
+}
+
+fn update(f: impl FnOnce(&mut Metrics)) {
+    ACTIVE.with(|active| {
+        if let Some(ref metrics) = *active.borrow() {
+            let mut m = metrics.get();
+            f(&mut m);
+            metrics.set(m);
+        }
+    });
+}
+
+/// Count a statement execution, if a recorder is active.
+pub(crate) fn record_statement() {
+    update(|m| m.statements += 1);
+}
+
+/// Count a call made at `depth`, if a recorder is active.
+pub(crate) fn record_call(depth: usize) {
+    update(|m| m.peak_call_depth = cmp::max(m.peak_call_depth, depth));
+}
+
+/// Count a value allocation, if a recorder is active.
+pub(crate) fn record_allocation() {
+    update(|m| m.values_allocated += 1);
+}
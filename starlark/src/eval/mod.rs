@@ -19,9 +19,10 @@
 //! All evaluation function can evaluate the full Starlark language (i.e. Bazel's
 //! .bzl files) or the BUILD file dialect (i.e. used to interpret Bazel's BUILD file).
 //! The BUILD dialect does not allow `def` statements.
-use crate::environment::{Environment, EnvironmentError, TypeValues};
+use crate::environment::{DebugHookFn, Environment, EnvironmentError, TracerFn, TypeValues};
 use crate::eval::call_stack::CallStack;
 use crate::eval::compr::eval_one_dimensional_comprehension;
+use crate::eval::debug::{DebugAction, DebugFrame};
 use crate::eval::def::Def;
 use crate::eval::def::ParameterCompiled;
 use crate::eval::expr::AssignTargetExprCompiled;
@@ -31,19 +32,21 @@ use crate::eval::expr::AstExprCompiled;
 use crate::eval::expr::AugmentedAssignTargetExprCompiled;
 use crate::eval::expr::ExprCompiled;
 use crate::eval::expr::GlobalOrSlot;
-use crate::eval::locals::Locals;
+use crate::eval::locals::{Locals, LocalsBuilder, LocalsQuery};
 use crate::eval::module::Module;
 use crate::eval::stmt::AstStatementCompiled;
 use crate::eval::stmt::BlockCompiled;
 use crate::eval::stmt::StatementCompiled;
+use crate::eval::trace::TraceEvent;
 use crate::syntax::ast::BinOp;
 use crate::syntax::ast::*;
 use crate::syntax::dialect::Dialect;
 use crate::syntax::errors::SyntaxError;
-use crate::syntax::lexer::{LexerIntoIter, LexerItem};
-use crate::syntax::parser::{parse, parse_file, parse_lexer};
+use crate::syntax::lexer::{Lexer, LexerIntoIter, LexerItem};
+use crate::syntax::parser::{parse, parse_ast, parse_file, parse_lexer};
 use crate::values::dict::Dictionary;
-use crate::values::error::ValueError;
+use crate::values::error::{activate_max_error_repr_length, ValueError};
+use crate::values::function::FunctionError;
 use crate::values::function::FunctionParameter;
 use crate::values::function::FunctionSignature;
 use crate::values::function::WrappedMethod;
@@ -54,6 +57,7 @@ use codemap_diagnostic::{Diagnostic, Level, SpanLabel, SpanStyle};
 use linked_hash_map::LinkedHashMap;
 use std::cell::RefCell;
 use std::cmp::Ordering;
+use std::collections::HashMap;
 use std::rc::Rc;
 use std::sync::{Arc, Mutex};
 
@@ -78,6 +82,14 @@ pub const INCORRECT_LEFT_VALUE_ERROR_CODE: &str = "CE03";
 pub const INCORRECT_UNPACK_ERROR_CODE: &str = "CE04";
 #[doc(hidden)]
 pub const RECURSION_ERROR_CODE: &str = "CE05";
+#[doc(hidden)]
+pub const DEBUGGER_ABORT_ERROR_CODE: &str = "CE06";
+#[doc(hidden)]
+pub const NOT_AN_EXPRESSION_ERROR_CODE: &str = "CE07";
+#[doc(hidden)]
+pub const AMBIGUOUS_DEFAULT_VALUE_ERROR_CODE: &str = "CE08";
+#[doc(hidden)]
+pub const STEP_BUDGET_EXCEEDED_ERROR_CODE: &str = "CE09";
 
 #[doc(hidden)]
 #[derive(Debug, Clone)]
@@ -127,6 +139,37 @@ impl<T> AsSpan for Box<Spanned<T>> {
     }
 }
 
+/// Call `f` with each element of `iterable`, for a `for` loop or a comprehension over the
+/// expression at `span`, until it returns `false` or an error.
+///
+/// The elements of the types which [iterate by index](Value::iterates_by_index) are computed
+/// with the call stack of the context, each one just before `f` is called with it.
+pub(crate) fn for_each_element(
+    iterable: &Value,
+    span: Span,
+    context: &mut EvaluationContext,
+    mut f: impl FnMut(Value, &mut EvaluationContext) -> Result<bool, EvalException>,
+) -> Result<(), EvalException> {
+    if iterable.iterates_by_index() {
+        for i in 0..t(iterable.length(), &span)? {
+            let v = t(
+                iterable.at_with_call_stack(Value::new(i), context.call_stack, context.type_values),
+                &span,
+            )?;
+            if !f(v, context)? {
+                break;
+            }
+        }
+    } else {
+        for v in &t(iterable.iter(), &span)? {
+            if !f(v, context)? {
+                break;
+            }
+        }
+    }
+    Ok(())
+}
+
 /// Convert syntax error to spanned evaluation exception
 fn t<T, E: SyntaxError, S: AsSpan>(r: Result<T, E>, spanned: &S) -> Result<T, EvalException> {
     match r {
@@ -217,9 +260,8 @@ pub trait FileLoader: 'static {
 
 /// Starlark `def` or comprehension local variables
 pub(crate) struct IndexedLocals<'a> {
-    // This field is not used at runtime, but could be used for debugging or
-    // for better diagnostics in the future
-    _local_defs: &'a Locals,
+    // Mapping of local variable names to slots
+    local_defs: &'a Locals,
     /// Local variables are stored in this array. Names to slots are  mapped
     /// during analysis phase. Note access by index is much faster than by name.
     locals: RefCell<Vec<Option<Value>>>,
@@ -228,7 +270,7 @@ pub(crate) struct IndexedLocals<'a> {
 impl<'a> IndexedLocals<'a> {
     fn new(local_defs: &'a Locals) -> IndexedLocals<'a> {
         IndexedLocals {
-            _local_defs: local_defs,
+            local_defs,
             locals: RefCell::new(vec![None; local_defs.len()]),
         }
     }
@@ -245,6 +287,16 @@ impl<'a> IndexedLocals<'a> {
     fn set_slot(&self, slot: usize, _name: &str, value: Value) {
         self.locals.borrow_mut()[slot] = Some(value);
     }
+
+    /// Top-level local variables which have a value
+    fn assigned(&self) -> Vec<(String, Value)> {
+        let locals = self.locals.borrow();
+        self.local_defs
+            .top_level_names()
+            .into_iter()
+            .filter_map(|(name, slot)| locals[slot].clone().map(|v| (name.to_owned(), v)))
+            .collect()
+    }
 }
 
 /// Stacked environment for [`EvaluationContext`].
@@ -312,7 +364,7 @@ impl<'a> EvaluationContextEnvironment<'a> {
     fn top_level_local_to_slot(&self, name: &str) -> usize {
         match self {
             EvaluationContextEnvironment::Local(_, locals) => {
-                locals._local_defs.top_level_name_to_slot(name).unwrap()
+                locals.local_defs.top_level_name_to_slot(name).unwrap()
             }
             _ => unreachable!("slot in non-indexed environment"),
         }
@@ -337,6 +389,10 @@ pub(crate) struct EvaluationContext<'a> {
     type_values: &'a TypeValues,
     call_stack: &'a mut CallStack,
     map: Arc<Mutex<CodeMap>>,
+    // Tracer of the environment, resolved once per context.
+    tracer: Option<TracerFn>,
+    // Debug hook of the environment, resolved once per context.
+    debug_hook: Option<DebugHookFn>,
 }
 
 fn eval_bin_op(
@@ -349,34 +405,36 @@ fn eval_bin_op(
     let l = eval_expr(l, context)?;
     let r = eval_expr(r, context)?;
 
-    t(
-        match op {
-            BinOp::EqualsTo => l.equals(&r).map(Value::new),
-            BinOp::Different => l.equals(&r).map(|b| Value::new(!b)),
-            BinOp::LowerThan => l.compare(&r).map(|c| Value::new(c == Ordering::Less)),
-            BinOp::GreaterThan => l.compare(&r).map(|c| Value::new(c == Ordering::Greater)),
-            BinOp::LowerOrEqual => l.compare(&r).map(|c| Value::new(c != Ordering::Greater)),
-            BinOp::GreaterOrEqual => l.compare(&r).map(|c| Value::new(c != Ordering::Less)),
-            BinOp::In => r.is_in(&l).map(Value::new),
-            BinOp::NotIn => r.is_in(&l).map(|r| Value::new(!r)),
-            BinOp::Substraction => l.sub(r),
-            BinOp::Addition => l.add(r),
-            BinOp::Multiplication => l.mul(r),
-            BinOp::Percent => l.percent(r),
-            BinOp::Division => {
-                // No types currently support / so always error.
-                let err = ValueError::OperationNotSupported {
-                    op: "/".to_string(),
-                    left: l.get_type().to_string(),
-                    right: Some(r.get_type().to_string()),
-                };
-                return Err(EvalException::DiagnosedError(err.to_diagnostic(expr.span)));
-            }
-            BinOp::FloorDivision => l.floor_div(r),
-            BinOp::Pipe => l.pipe(r),
-        },
-        expr,
-    )
+    t(bin_op(op, l, r), expr)
+}
+
+/// Apply a binary operator to evaluated operands.
+pub(crate) fn bin_op(op: BinOp, l: Value, r: Value) -> ValueResult {
+    match op {
+        BinOp::EqualsTo => l.equals(&r).map(Value::new),
+        BinOp::Different => l.equals(&r).map(|b| Value::new(!b)),
+        BinOp::LowerThan => l.compare(&r).map(|c| Value::new(c == Ordering::Less)),
+        BinOp::GreaterThan => l.compare(&r).map(|c| Value::new(c == Ordering::Greater)),
+        BinOp::LowerOrEqual => l.compare(&r).map(|c| Value::new(c != Ordering::Greater)),
+        BinOp::GreaterOrEqual => l.compare(&r).map(|c| Value::new(c != Ordering::Less)),
+        BinOp::In => r.is_in(&l).map(Value::new),
+        BinOp::NotIn => r.is_in(&l).map(|r| Value::new(!r)),
+        BinOp::Substraction => l.sub(r),
+        BinOp::Addition => l.add(r),
+        BinOp::Multiplication => l.mul(r),
+        BinOp::Percent => l.percent(r),
+        BinOp::Division => {
+            // No types currently support / so always error.
+            Err(ValueError::OperationNotSupported {
+                op: "/".to_string(),
+                left: l.get_type().to_string(),
+                right: Some(r.get_type().to_string()),
+            })
+        }
+        BinOp::FloorDivision => l.floor_div(r),
+        BinOp::Power => l.pow(r),
+        BinOp::Pipe => l.pipe(r),
+    }
 }
 
 fn eval_slice<'a>(
@@ -415,7 +473,13 @@ fn eval_call<'a>(
     let npos = eval_vector(pos, context)?;
     let mut nnamed = LinkedHashMap::new();
     for &(ref k, ref v) in named.iter() {
-        nnamed.insert(k.node.clone(), eval_expr(v, context)?);
+        let v = eval_expr(v, context)?;
+        if nnamed.insert(k.node.clone(), v).is_some() {
+            let e = FunctionError::DuplicateParameter {
+                name: k.node.clone(),
+            };
+            return t(Err(ValueError::from(e)), this);
+        }
     }
     let nargs = if let Some(ref x) = args {
         Some(eval_expr(x, context)?)
@@ -436,6 +500,9 @@ fn eval_call<'a>(
         context
             .call_stack
             .push(f.clone(), context.map.clone(), this.span.low());
+        metrics::record_call(context.call_stack.len());
+        #[cfg(feature = "provenance")]
+        let _provenance = provenance::enter(this.span);
         let r = t(
             eval_expr(e, context)?.call(
                 context.call_stack,
@@ -446,7 +513,8 @@ fn eval_call<'a>(
                 nkwargs,
             ),
             this,
-        );
+        )
+        .map(|v| created(v, this.span));
         context.call_stack.pop();
         r
     }
@@ -499,7 +567,10 @@ fn set_transformed<'a>(
     }
 }
 
-fn eval_transformed<'a>(transformed: &TransformedExpr, context: &EvaluationContext) -> EvalResult {
+fn eval_transformed<'a>(
+    transformed: &TransformedExpr,
+    context: &mut EvaluationContext,
+) -> EvalResult {
     match transformed {
         TransformedExpr::Dot(ref left, ref s, ref span) => {
             if let Some(v) = context.type_values.get_type_value(left, &s) {
@@ -513,7 +584,10 @@ fn eval_transformed<'a>(transformed: &TransformedExpr, context: &EvaluationConte
                 t(left.get_attr(&s), span)
             }
         }
-        TransformedExpr::ArrayIndirection(ref e, ref idx, ref span) => t(e.at(idx.clone()), span),
+        TransformedExpr::ArrayIndirection(ref e, ref idx, ref span) => t(
+            e.at_with_call_stack(idx.clone(), context.call_stack, context.type_values),
+            span,
+        ),
         TransformedExpr::Slot(slot, ident) => t(context.env.get_slot(*slot, &ident.node), ident),
     }
 }
@@ -566,12 +640,27 @@ fn eval_expr_local(
         ),
         type_values: context.type_values,
         map: context.map.clone(),
+        tracer: context.tracer.clone(),
+        debug_hook: context.debug_hook.clone(),
     };
     eval_expr(expr, &mut ctx)
 }
 
+// Record `value` as created by the expression at `span` for the provenance recorder, if any.
+#[cfg(feature = "provenance")]
+fn created(value: Value, span: Span) -> Value {
+    provenance::record_creation(&value, span);
+    value
+}
+
+#[cfg(not(feature = "provenance"))]
+fn created(value: Value, _span: Span) -> Value {
+    value
+}
+
 // Evaluate the AST element, i.e. mutate the environment and return an evaluation result
 fn eval_expr(expr: &AstExprCompiled, context: &mut EvaluationContext) -> EvalResult {
+    let span = expr.span;
     match expr.node {
         ExprCompiled::Tuple(ref v) => {
             let r = eval_vector(v, context)?;
@@ -583,7 +672,11 @@ fn eval_expr(expr: &AstExprCompiled, context: &mut EvaluationContext) -> EvalRes
         }
         ExprCompiled::ArrayIndirection(ref e, ref idx) => {
             let idx = eval_expr(idx, context)?;
-            t(eval_expr(e, context)?.at(idx), expr)
+            let e = eval_expr(e, context)?;
+            t(
+                e.at_with_call_stack(idx, context.call_stack, context.type_values),
+                expr,
+            )
         }
         ExprCompiled::Slice(ref a, ref start, ref stop, ref stride) => {
             eval_slice(expr, a, start, stop, stride, context)
@@ -622,7 +715,7 @@ fn eval_expr(expr: &AstExprCompiled, context: &mut EvaluationContext) -> EvalRes
         }
         ExprCompiled::List(ref v) => {
             let r = eval_vector(v, context)?;
-            Ok(Value::from(r))
+            Ok(created(Value::from(r), span))
         }
         ExprCompiled::Dict(ref v) => {
             let mut r = dict::Dictionary::new();
@@ -632,14 +725,14 @@ fn eval_expr(expr: &AstExprCompiled, context: &mut EvaluationContext) -> EvalRes
                     expr,
                 )?
             }
-            Ok(r)
+            Ok(created(r, span))
         }
         ExprCompiled::Set(ref v) => {
             let mut values = Vec::with_capacity(v.len());
             for s in v {
                 values.push(eval_expr(s, context)?);
             }
-            make_set(values, context, expr.span)
+            make_set(values, context, expr.span).map(|v| created(v, span))
         }
         ExprCompiled::ListComprehension(ref expr, ref clauses) => {
             let mut list = Vec::new();
@@ -651,7 +744,7 @@ fn eval_expr(expr: &AstExprCompiled, context: &mut EvaluationContext) -> EvalRes
                 clauses,
                 context,
             )?;
-            Ok(Value::from(list))
+            Ok(created(Value::from(list), span))
         }
         ExprCompiled::SetComprehension(ref expr, ref clauses) => {
             let mut values = Vec::new();
@@ -663,7 +756,7 @@ fn eval_expr(expr: &AstExprCompiled, context: &mut EvaluationContext) -> EvalRes
                 clauses,
                 context,
             )?;
-            make_set(values, context, expr.span)
+            make_set(values, context, expr.span).map(|v| created(v, span))
         }
         ExprCompiled::DictComprehension((ref k, ref v), ref clauses) => {
             let mut dict = Dictionary::new_typed();
@@ -677,7 +770,7 @@ fn eval_expr(expr: &AstExprCompiled, context: &mut EvaluationContext) -> EvalRes
                 clauses,
                 context,
             )?;
-            Ok(Value::new(dict))
+            Ok(created(Value::new(dict), span))
         }
         ExprCompiled::Local(ref expr, ref locals) => eval_expr_local(expr, locals, context),
     }
@@ -759,6 +852,61 @@ where
 }
 
 fn eval_stmt(stmt: &AstStatementCompiled, context: &mut EvaluationContext) -> EvalResult {
+    #[cfg(feature = "provenance")]
+    let _provenance = provenance::enter(stmt.span);
+    metrics::record_statement();
+    if !incremental::consume_statement() {
+        return Err(EvalException::DiagnosedError(Diagnostic {
+            level: Level::Error,
+            message: "Statement exceeds the budget of the incremental evaluation step".to_owned(),
+            code: Some(STEP_BUDGET_EXCEEDED_ERROR_CODE.to_owned()),
+            spans: vec![SpanLabel {
+                span: stmt.span,
+                style: SpanStyle::Primary,
+                label: Some("Budget exhausted here".to_owned()),
+            }],
+        }));
+    }
+    if context.tracer.is_none() && context.debug_hook.is_none() {
+        return eval_stmt_untraced(stmt, context);
+    }
+    let description = trace::describe(stmt);
+    if let Some(ref tracer) = context.tracer {
+        tracer(&TraceEvent::Statement {
+            span: stmt.span,
+            description: &description,
+        });
+    }
+    if let Some(ref debug_hook) = context.debug_hook {
+        let frame = DebugFrame {
+            span: stmt.span,
+            description: &description,
+            env: &context.env,
+        };
+        if debug_hook(&frame) == DebugAction::Abort {
+            return Err(EvalException::DiagnosedError(Diagnostic {
+                level: Level::Error,
+                message: "Evaluation aborted by the debugger".to_owned(),
+                code: Some(DEBUGGER_ABORT_ERROR_CODE.to_owned()),
+                spans: vec![SpanLabel {
+                    span: stmt.span,
+                    style: SpanStyle::Primary,
+                    label: Some("Aborted here".to_owned()),
+                }],
+            }));
+        }
+    }
+    let value = eval_stmt_untraced(stmt, context)?;
+    if let Some(ref tracer) = context.tracer {
+        tracer(&TraceEvent::Result {
+            span: stmt.span,
+            value: &value,
+        });
+    }
+    Ok(value)
+}
+
+fn eval_stmt_untraced(stmt: &AstStatementCompiled, context: &mut EvaluationContext) -> EvalResult {
     match stmt.node {
         StatementCompiled::Break => Err(EvalException::Break(stmt.span)),
         StatementCompiled::Continue => Err(EvalException::Continue(stmt.span)),
@@ -785,20 +933,16 @@ fn eval_stmt(stmt: &AstStatementCompiled, context: &mut EvaluationContext) -> Ev
         }
         StatementCompiled::For(ref e1, ref e2, ref st) => {
             let iterable = eval_expr(e2, context)?;
-            let mut result = Ok(Value::new(NoneType::None));
-            for v in &t(iterable.iter(), &e2.span)? {
+            for_each_element(&iterable, e2.span, context, |v, context| {
                 set_expr(e1, context, v)?;
                 match eval_block(st, context) {
-                    Err(EvalException::Break(..)) => break,
-                    Err(EvalException::Continue(..)) => (),
-                    Err(x) => {
-                        result = Err(x);
-                        break;
-                    }
-                    _ => (),
+                    Err(EvalException::Break(..)) => Ok(false),
+                    Err(EvalException::Continue(..)) => Ok(true),
+                    Err(x) => Err(x),
+                    _ => Ok(true),
                 }
-            }
-            result
+            })?;
+            Ok(Value::new(NoneType::None))
         }
         StatementCompiled::Def(ref stmt) => {
             let mut p = Vec::new();
@@ -808,6 +952,17 @@ fn eval_stmt(stmt: &AstStatementCompiled, context: &mut EvaluationContext) -> Ev
                     ParameterCompiled::WithDefaultValue(ref n, ref v) => {
                         FunctionParameter::WithDefaultValue(n.node.clone(), eval_expr(v, context)?)
                     }
+                    ParameterCompiled::LazyDefaultValue(ref n, _, ref referred) => {
+                        // A name bound when the function is defined would be resolved to
+                        // the earlier parameter, unlike in other default values.
+                        let module_env = context.env.assert_module_env();
+                        if let Some(global) = referred.iter().find(|r| module_env.get(r).is_ok()) {
+                            return Err(EvalException::DiagnosedError(ambiguous_default_value(
+                                x.span, &n.node, global,
+                            )));
+                        }
+                        FunctionParameter::LazyDefaultValue(n.node.clone())
+                    }
                     ParameterCompiled::Args(ref n) => FunctionParameter::ArgsArray(n.node.clone()),
                     ParameterCompiled::KWArgs(ref n) => {
                         FunctionParameter::KWArgsDict(n.node.clone())
@@ -828,7 +983,8 @@ fn eval_stmt(stmt: &AstStatementCompiled, context: &mut EvaluationContext) -> Ev
             Ok(f)
         }
         StatementCompiled::Load(ref name, ref v) => {
-            let loadenv = context.env.loader().load(name, context.type_values)?;
+            let name = context.env.assert_module_env().rewrite_load_path(name);
+            let loadenv = context.env.loader().load(&name, context.type_values)?;
             for &(ref new_name, ref orig_name) in v.iter() {
                 t(
                     context.env.assert_module_env().import_symbol(
@@ -858,13 +1014,22 @@ fn eval_module(
     type_values: &TypeValues,
     map: Arc<Mutex<CodeMap>>,
     file_loader: Rc<dyn FileLoader>,
+    call_stack: &mut CallStack,
 ) -> EvalResult {
-    let mut call_stack = CallStack::default();
+    call_stack.clear();
+    call_stack.set_module_name(env.name());
+    call_stack.set_print_handler(env.print_handler());
+    #[cfg(feature = "provenance")]
+    let _provenance = env.provenance_recorder().map(|r| r.activate());
+    let _metrics = env.metrics_recorder().map(|r| r.activate());
+    let _error_repr = activate_max_error_repr_length(env.max_error_repr_length());
     let mut context = EvaluationContext {
         env: EvaluationContextEnvironment::Module(env.clone(), file_loader),
         type_values,
-        call_stack: &mut call_stack,
+        call_stack,
         map,
+        tracer: env.tracer(),
+        debug_hook: env.debug_hook(),
     };
     eval_block(&module.0, &mut context)
 }
@@ -901,6 +1066,7 @@ pub fn eval_lexer<
         type_values,
         map.clone(),
         Rc::new(file_loader),
+        &mut CallStack::default(),
     ) {
         Ok(v) => Ok(v),
         Err(p) => Err(p.into()),
@@ -934,12 +1100,159 @@ pub fn eval<T: FileLoader + 'static>(
         type_values,
         map.clone(),
         Rc::new(file_loader),
+        &mut CallStack::default(),
     ) {
         Ok(v) => Ok(v),
         Err(p) => Err(p.into()),
     }
 }
 
+/// Same as [`eval`], but use the provided `call_stack` instead of allocating a new one.
+///
+/// The call stack is cleared before the evaluation, so the same `CallStack` can be reused
+/// across many evaluations to avoid repeated allocations.
+#[allow(clippy::too_many_arguments)]
+pub fn eval_with_call_stack<T: FileLoader + 'static>(
+    map: &Arc<Mutex<CodeMap>>,
+    path: &str,
+    content: &str,
+    build: Dialect,
+    env: &mut Environment,
+    type_values: &TypeValues,
+    file_loader: T,
+    call_stack: &mut CallStack,
+) -> Result<Value, Diagnostic> {
+    match eval_module(
+        &parse(map, path, content, build)?,
+        env,
+        type_values,
+        map.clone(),
+        Rc::new(file_loader),
+        call_stack,
+    ) {
+        Ok(v) => Ok(v),
+        Err(p) => Err(p.into()),
+    }
+}
+
+/// Evaluate an already parsed [`Module`], mutate the environment accordingly and return the
+/// evaluated value.
+///
+/// This allows transforming the module before evaluation, e.g. with
+/// [`Module::fold_constants`], or evaluating a module parsed once with [`Module::parse`]
+/// several times, e.g. from a [`ModuleCache`](module::ModuleCache).
+///
+/// # Arguments
+///
+/// * map: the codemap object used to parse the module
+/// * module: the parsed module
+/// * env: the environment to mutate during the evaluation
+/// * file_loader: the [`FileLoader`] to react to `load()` statements.
+pub fn eval_parsed_module<T: FileLoader + 'static>(
+    map: &Arc<Mutex<CodeMap>>,
+    module: &Module,
+    env: &mut Environment,
+    type_values: &TypeValues,
+    file_loader: T,
+) -> Result<Value, Diagnostic> {
+    match eval_module(
+        module,
+        env,
+        type_values,
+        map.clone(),
+        Rc::new(file_loader),
+        &mut CallStack::default(),
+    ) {
+        Ok(v) => Ok(v),
+        Err(p) => Err(p.into()),
+    }
+}
+
+/// Evaluate a single expression, resolving the names of `locals` as local variables of the
+/// expression and the other names in `env`, and return its value.
+///
+/// The injected locals shadow the variables of `env` with the same name, and `env` is not
+/// modified by the evaluation. It is an error if `content` is not a single expression.
+///
+/// # Arguments
+///
+/// * map: the codemap object used for diagnostics
+/// * path: the name of the expression being evaluated, for diagnostics
+/// * content: the expression to evaluate
+/// * locals: the local variables of the expression
+/// * env: the environment resolving the other names of the expression
+pub fn eval_expr_with_locals(
+    map: &Arc<Mutex<CodeMap>>,
+    path: &str,
+    content: &str,
+    locals: &HashMap<String, Value>,
+    env: &Environment,
+    type_values: &TypeValues,
+) -> Result<Value, Diagnostic> {
+    let stmt = parse_ast(map, path, content, Dialect::Bzl, Lexer::new(content))?;
+    let expr = match stmt.node {
+        Statement::Expression(expr) => expr,
+        Statement::Statements(mut stmts) if stmts.len() == 1 => match stmts.pop().unwrap().node {
+            Statement::Expression(expr) => expr,
+            _ => return Err(not_an_expression(stmt.span)),
+        },
+        _ => return Err(not_an_expression(stmt.span)),
+    };
+
+    let mut locals_builder = LocalsBuilder::default();
+    for name in locals.keys() {
+        locals_builder.register_local(name);
+    }
+    Expr::collect_locals(&expr, &mut locals_builder);
+    let local_defs = locals_builder.build();
+    let expr = ExprCompiled::compile_local(expr, &mut LocalsQuery::new(&local_defs))?;
+
+    let indexed_locals = IndexedLocals::new(&local_defs);
+    for (name, value) in locals {
+        let slot = local_defs.top_level_name_to_slot(name).unwrap();
+        indexed_locals.set_slot(slot, name, value.clone());
+    }
+    let mut context = EvaluationContext {
+        env: EvaluationContextEnvironment::Local(env.clone(), indexed_locals),
+        type_values,
+        call_stack: &mut CallStack::default(),
+        map: map.clone(),
+        tracer: env.tracer(),
+        debug_hook: env.debug_hook(),
+    };
+    eval_expr(&expr, &mut context).map_err(EvalException::into)
+}
+
+fn not_an_expression(span: Span) -> Diagnostic {
+    Diagnostic {
+        level: Level::Error,
+        message: "Expected a single expression".to_owned(),
+        code: Some(NOT_AN_EXPRESSION_ERROR_CODE.to_owned()),
+        spans: vec![SpanLabel {
+            span,
+            style: SpanStyle::Primary,
+            label: None,
+        }],
+    }
+}
+
+fn ambiguous_default_value(span: Span, param: &str, name: &str) -> Diagnostic {
+    Diagnostic {
+        level: Level::Error,
+        message: format!(
+            "Default value of parameter {} refers to {}, which is both an earlier parameter \
+             and a variable of the module",
+            param, name
+        ),
+        code: Some(AMBIGUOUS_DEFAULT_VALUE_ERROR_CODE.to_owned()),
+        spans: vec![SpanLabel {
+            span,
+            style: SpanStyle::Primary,
+            label: Some(format!("ambiguous reference to {}", name)),
+        }],
+    }
+}
+
 /// Evaluate a file, mutate the environment accordingly and return the evaluated value.
 ///
 /// # Arguments
@@ -965,17 +1278,28 @@ pub fn eval_file<T: FileLoader + 'static>(
         type_values,
         map.clone(),
         Rc::new(file_loader),
+        &mut CallStack::default(),
     ) {
         Ok(v) => Ok(v),
         Err(p) => Err(p.into()),
     }
 }
 
+pub mod capture;
+pub mod dry_run;
+pub mod incremental;
 pub mod interactive;
+pub mod json_error;
+pub mod metrics;
 pub mod noload;
+#[cfg(feature = "provenance")]
+pub mod provenance;
+pub mod result;
 pub mod simple;
+pub mod trace;
 
 pub mod call_stack;
+pub mod debug;
 
 #[cfg(test)]
 #[macro_use]
@@ -988,6 +1312,7 @@ pub(crate) mod compiler;
 pub(crate) mod compr;
 pub(crate) mod def;
 pub(crate) mod expr;
+pub(crate) mod fold;
 pub(crate) mod locals;
 pub mod module;
 pub mod stmt;
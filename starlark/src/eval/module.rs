@@ -14,20 +14,172 @@
 
 //! Starlark module (`.bzl` or `BUILD` file parsed and post-processed)
 
+use crate::eval::fold;
 use crate::eval::stmt::BlockCompiled;
 use crate::syntax::ast::AstStatement;
 use crate::syntax::ast::Statement;
 use crate::syntax::dialect::Dialect;
+use crate::syntax::parser::parse;
+use crate::values::Value;
+use codemap::CodeMap;
 use codemap_diagnostic::Diagnostic;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex};
 
 /// Starlark module (`.bzl` or `BUILD` file parsed and post-processed)
 #[derive(Debug, Clone)]
 pub struct Module(pub(crate) BlockCompiled);
 
 impl Module {
+    /// Parse `content`, to be evaluated later with
+    /// [`eval_parsed_module`](crate::eval::eval_parsed_module), possibly several times.
+    ///
+    /// The spans of the module refer to `map`, so the module must be evaluated with the same
+    /// `map` for its diagnostics to be resolved.
+    ///
+    /// # Arguments
+    ///
+    /// * map: the codemap object used for diagnostics
+    /// * path: the name of the file being parsed, for diagnostics
+    /// * content: the content to parse
+    /// * dialect: starlark syntax dialect
+    pub fn parse(
+        map: &Arc<Mutex<CodeMap>>,
+        path: &str,
+        content: &str,
+        dialect: Dialect,
+    ) -> Result<Module, Diagnostic> {
+        parse(map, path, content, dialect)
+    }
+
     pub(crate) fn compile(stmt: AstStatement, _dialect: Dialect) -> Result<Module, Diagnostic> {
         Statement::validate_break_continue(&stmt)?;
         Statement::validate_augmented_assignment_in_module(&stmt)?;
         BlockCompiled::compile_global(stmt).map(Module)
     }
+
+    /// Fold operations on constant operands, e.g. `1 + 2` or `"a" + "b"`, and simplify
+    /// `and`, `or`, conditional expressions and `if` statements whose condition is constant.
+    ///
+    /// Operations which would fail or have side effects are kept to be evaluated at runtime.
+    pub fn fold_constants(&mut self) {
+        self.fold_constants_with(&HashMap::new());
+    }
+
+    /// Like [`fold_constants`](Module::fold_constants), also replacing the global names in
+    /// `constants` by their value, so that e.g. the body of `if DEBUG:` is pruned when `DEBUG`
+    /// is `False`.
+    ///
+    /// Only `None`, booleans, integers and strings are substituted. The same constants must be
+    /// [predeclared](crate::environment::Environment::predeclare) in the environment the module
+    /// is evaluated in: this prevents the module from assigning them, and resolves those which
+    /// are not substituted.
+    pub fn fold_constants_with(&mut self, constants: &HashMap<String, Value>) {
+        fold::fold_block(&mut self.0, constants);
+    }
+}
+
+/// Cache of parsed modules keyed by a hash of their source, to skip parsing when the same
+/// source is evaluated repeatedly.
+///
+/// The cached modules are parsed with the `CodeMap` passed to
+/// [`get_or_parse`](ModuleCache::get_or_parse), so a cache should be used with a single
+/// `CodeMap`, which must also be used to evaluate the modules.
+///
+/// ```
+/// # use starlark::eval::eval_parsed_module;
+/// # use starlark::eval::module::ModuleCache;
+/// # use starlark::eval::noload::NoLoadFileLoader;
+/// # use starlark::stdlib::global_environment;
+/// # use starlark::syntax::dialect::Dialect;
+/// # use codemap::CodeMap;
+/// # use std::sync::{Arc, Mutex};
+/// let map = Arc::new(Mutex::new(CodeMap::new()));
+/// let (global, type_values) = global_environment();
+/// let global = global.freeze();
+/// let mut cache = ModuleCache::default();
+/// for _ in 0..2 {
+///     // Only parsed on the first iteration.
+///     let module = cache.get_or_parse(&map, "my.bzl", "1 + 2", Dialect::Bzl).unwrap();
+///     let mut env = global.child("my.bzl");
+///     let result = eval_parsed_module(&map, module, &mut env, &type_values, NoLoadFileLoader);
+///     assert_eq!(3, result.unwrap().to_int().unwrap());
+/// }
+/// assert_eq!(1, cache.len());
+/// ```
+#[derive(Debug, Default)]
+pub struct ModuleCache {
+    /// Modules by source hash, with their path, dialect and content to detect hash collisions.
+    modules: HashMap<u64, Vec<CachedModule>>,
+}
+
+#[derive(Debug)]
+struct CachedModule {
+    path: String,
+    dialect: Dialect,
+    content: String,
+    module: Module,
+}
+
+impl ModuleCache {
+    /// The hash of a source, used as the cache key.
+    pub fn source_hash(path: &str, content: &str, dialect: Dialect) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        path.hash(&mut hasher);
+        content.hash(&mut hasher);
+        dialect.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Return the cached module for `content`, parsing and caching it if it is not cached
+    /// yet. Parse errors are not cached.
+    ///
+    /// See [`Module::parse`] for the arguments.
+    pub fn get_or_parse(
+        &mut self,
+        map: &Arc<Mutex<CodeMap>>,
+        path: &str,
+        content: &str,
+        dialect: Dialect,
+    ) -> Result<&Module, Diagnostic> {
+        let hash = ModuleCache::source_hash(path, content, dialect);
+        let cached = self.modules.get(&hash).and_then(|modules| {
+            modules
+                .iter()
+                .position(|m| m.path == path && m.content == content && m.dialect == dialect)
+        });
+        let modules = match cached {
+            Some(..) => &self.modules[&hash],
+            None => {
+                let module = Module::parse(map, path, content, dialect)?;
+                let modules = self.modules.entry(hash).or_default();
+                modules.push(CachedModule {
+                    path: path.to_owned(),
+                    dialect,
+                    content: content.to_owned(),
+                    module,
+                });
+                &*modules
+            }
+        };
+        let i = cached.unwrap_or(modules.len() - 1);
+        Ok(&modules[i].module)
+    }
+
+    /// The number of cached modules.
+    pub fn len(&self) -> usize {
+        self.modules.values().map(Vec::len).sum()
+    }
+
+    /// Whether the cache is empty.
+    pub fn is_empty(&self) -> bool {
+        self.modules.is_empty()
+    }
+
+    /// Remove all the cached modules.
+    pub fn clear(&mut self) {
+        self.modules.clear();
+    }
 }
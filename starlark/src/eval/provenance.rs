@@ -0,0 +1,176 @@
+// Copyright 2019 The Starlark in Rust Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Recording of where mutable values are created and mutated, see
+//! [`Environment::with_provenance_recorder`](crate::environment::Environment::with_provenance_recorder).
+//!
+//! This is a debugging aid, only available with the `provenance` feature: every mutable value
+//! created during the evaluation is kept alive by the recorder until the recorder is dropped.
+
+use crate::values::{DataPtr, Value};
+use codemap::Span;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::rc::Rc;
+
+/// Where a mutable value was created and mutated.
+///
+/// Spans can be resolved with the `CodeMap` of the evaluation.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Provenance {
+    /// The span of the expression which created the value: a list, dict or set literal or
+    /// comprehension, or the call which returned it.
+    pub created: Span,
+    /// The spans of the calls and statements which mutated the value, in order.
+    ///
+    /// A mutation is recorded when the value is accessed mutably, even if the operation then
+    /// fails, e.g. `list.remove` of a missing element.
+    pub mutations: Vec<Span>,
+}
+
+/// Records the [`Provenance`] of the mutable values created while evaluating in an
+/// environment.
+///
+/// Clones share the same records, so a clone can be given to the environment and the
+/// original queried after the evaluation.
+#[derive(Clone, Default)]
+pub struct ProvenanceRecorder {
+    // The values are kept to avoid the reuse of their address by another value.
+    records: Rc<RefCell<HashMap<DataPtr, (Value, Provenance)>>>,
+}
+
+impl fmt::Debug for ProvenanceRecorder {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<provenance of {} values>", self.records.borrow().len())
+    }
+}
+
+impl ProvenanceRecorder {
+    pub fn new() -> ProvenanceRecorder {
+        ProvenanceRecorder::default()
+    }
+
+    /// The provenance of `value`, or `None` if it was not created while this recorder was
+    /// active.
+    pub fn provenance(&self, value: &Value) -> Option<Provenance> {
+        self.records
+            .borrow()
+            .get(&value.data_ptr())
+            .map(|(_, provenance)| provenance.clone())
+    }
+
+    /// Make this recorder the one receiving the creations and mutations until the returned
+    /// guard is dropped.
+    pub(crate) fn activate(&self) -> ActiveGuard {
+        let previous = ACTIVE.with(|active| {
+            active.borrow_mut().replace(Active {
+                recorder: self.clone(),
+                span: None,
+            })
+        });
+        ActiveGuard { previous }
+    }
+}
+
+struct Active {
+    recorder: ProvenanceRecorder,
+    /// The span of the innermost call or statement being evaluated.
+    span: Option<Span>,
+}
+
+// The active recorder is thread-local because mutations are detected deep in the values,
+// where no evaluation context is available.
+thread_local!(static ACTIVE: RefCell<Option<Active>> = RefCell::new(None));
+
+/// Restores the previously active recorder on drop.
+#[must_use]
+pub(crate) struct ActiveGuard {
+    previous: Option<Active>,
+}
+
+impl Drop for ActiveGuard {
+    fn drop(&mut self) {
+        let previous = self.previous.take();
+        ACTIVE.with(|active| *active.borrow_mut() = previous);
+    }
+}
+
+/// Restores the previous span of the active recorder on drop.
+#[must_use]
+pub(crate) struct SpanGuard {
+    previous: Option<Span>,
+}
+
+impl Drop for SpanGuard {
+    fn drop(&mut self) {
+        let previous = self.previous;
+        ACTIVE.with(|active| {
+            if let Some(ref mut active) = *active.borrow_mut() {
+                active.span = previous;
+            }
+        });
+    }
+}
+
+/// Attribute the mutations to `span` until the returned guard is dropped, if a recorder is
+/// active.
+pub(crate) fn enter(span: Span) -> Option<SpanGuard> {
+    ACTIVE.with(|active| {
+        active.borrow_mut().as_mut().map(|active| SpanGuard {
+            previous: active.span.replace(span),
+        })
+    })
+}
+
+/// Record that `value` was created by the expression at `span`, if a recorder is active and
+/// `value` is mutable and not recorded yet.
+pub(crate) fn record_creation(value: &Value, span: Span) {
+    ACTIVE.with(|active| {
+        if let Some(ref active) = *active.borrow() {
+            if value.is_mutable() {
+                active
+                    .recorder
+                    .records
+                    .borrow_mut()
+                    .entry(value.data_ptr())
+                    .or_insert_with(|| {
+                        (
+                            value.clone(),
+                            Provenance {
+                                created: span,
+                                mutations: Vec::new(),
+                            },
+                        )
+                    });
+            }
+        }
+    });
+}
+
+/// Record a mutation of the value at `ptr` at the current span, if a recorder is active and
+/// the value is recorded.
+pub(crate) fn record_mutation(ptr: DataPtr) {
+    ACTIVE.with(|active| {
+        if let Some(Active {
+            ref recorder,
+            span: Some(span),
+        }) = *active.borrow()
+        {
+            if let Some((_, provenance)) = recorder.records.borrow_mut().get_mut(&ptr) {
+                provenance.mutations.push(span);
+            }
+        }
+    });
+}
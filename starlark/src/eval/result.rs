@@ -0,0 +1,148 @@
+// Copyright 2019 The Starlark in Rust Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Typed access to the result of an evaluation.
+
+use crate::environment::Environment;
+use crate::stdlib::macros::param::TryParamConvertFromValue;
+use crate::values::dict::Dictionary;
+use crate::values::error::{RuntimeError, ValueError, INCORRECT_PARAMETER_TYPE_ERROR_CODE};
+use crate::values::Value;
+
+/// The value of an evaluated module together with the environment it was evaluated in.
+///
+/// Globals of the module can be extracted with the typed accessors, e.g.
+///
+/// ```
+/// # use starlark::eval::noload::eval;
+/// # use starlark::eval::result::EvaluatedModule;
+/// # use starlark::stdlib::global_environment;
+/// # use starlark::syntax::dialect::Dialect;
+/// # use codemap::CodeMap;
+/// # use std::sync::{Arc, Mutex};
+/// let (global, type_values) = global_environment();
+/// let mut env = global.freeze().child("example");
+/// let value = eval(
+///     &Arc::new(Mutex::new(CodeMap::new())),
+///     "example.bzl",
+///     "name = 'x'\nsize = len(name)\nsize * 2",
+///     Dialect::Bzl,
+///     &mut env,
+///     &type_values,
+/// )
+/// .unwrap();
+/// let result = EvaluatedModule::new(value, env);
+/// assert_eq!(2, result.value().to_int().unwrap());
+/// assert_eq!("x", result.get_string("name").unwrap());
+/// assert_eq!(1, result.get_int("size").unwrap());
+/// assert!(result.get_int("name").is_err());
+/// ```
+#[derive(Clone, Debug)]
+pub struct EvaluatedModule {
+    value: Value,
+    env: Environment,
+}
+
+impl EvaluatedModule {
+    /// Wrap the value returned by an evaluation in `env`.
+    pub fn new(value: Value, env: Environment) -> EvaluatedModule {
+        EvaluatedModule { value, env }
+    }
+
+    /// The value of the last statement of the module.
+    pub fn value(&self) -> &Value {
+        &self.value
+    }
+
+    /// The environment the module was evaluated in.
+    pub fn env(&self) -> &Environment {
+        &self.env
+    }
+
+    /// Look up the global `name` and convert it to `T`.
+    ///
+    /// Fails if the global is not defined or cannot be converted to `T`.
+    pub fn get<T: TryParamConvertFromValue>(&self, name: &str) -> Result<T, ValueError> {
+        let value = self.env.get(name)?;
+        let object_type = value.get_type();
+        T::try_from(value).map_err(|_| incorrect_type(name, object_type, None))
+    }
+
+    /// Look up the global `name` which must be an `int`.
+    pub fn get_int(&self, name: &str) -> Result<i64, ValueError> {
+        self.get_typed(name, "int")
+    }
+
+    /// Look up the global `name` which must be a `string`.
+    pub fn get_string(&self, name: &str) -> Result<String, ValueError> {
+        self.get_typed(name, "string")
+    }
+
+    /// Look up the global `name` which must be a `bool`.
+    pub fn get_bool(&self, name: &str) -> Result<bool, ValueError> {
+        self.get_typed(name, "bool")
+    }
+
+    /// Look up the global `name` which must be a `list` whose elements convert to `T`.
+    pub fn get_list<T: TryParamConvertFromValue>(&self, name: &str) -> Result<Vec<T>, ValueError> {
+        self.get_typed(name, "list")
+    }
+
+    /// The globals assigned by the module as a `dict` from their names to their values,
+    /// sorted by name.
+    ///
+    /// Builtins, predeclared names and names bound by `load` statements are excluded, as are
+    /// private names, i.e. starting with an underscore, unless `include_private` is set.
+    pub fn globals(&self, include_private: bool) -> Value {
+        let mut globals = Dictionary::new_typed();
+        for name in self.env.assigned_names() {
+            if include_private || !name.starts_with('_') {
+                let value = self.env.get(&name).unwrap();
+                globals.insert(Value::new(name), value).unwrap();
+            }
+        }
+        Value::new(globals)
+    }
+
+    fn get_typed<T: TryParamConvertFromValue>(
+        &self,
+        name: &str,
+        expected: &str,
+    ) -> Result<T, ValueError> {
+        let value = self.env.get(name)?;
+        let object_type = value.get_type();
+        if object_type != expected {
+            return Err(incorrect_type(name, object_type, Some(expected)));
+        }
+        T::try_from(value).map_err(|_| incorrect_type(name, object_type, Some(expected)))
+    }
+}
+
+fn incorrect_type(name: &str, object_type: &str, expected: Option<&str>) -> ValueError {
+    RuntimeError {
+        code: INCORRECT_PARAMETER_TYPE_ERROR_CODE,
+        message: match expected {
+            Some(expected) if expected != object_type => format!(
+                "Global '{}' has type '{}', expected '{}'",
+                name, object_type, expected
+            ),
+            _ => format!(
+                "Global '{}' of type '{}' has an unexpected value",
+                name, object_type
+            ),
+        },
+        label: "Incorrect type".to_owned(),
+    }
+    .into()
+}
@@ -13,12 +13,35 @@
 // limitations under the License.
 
 use crate::environment::{Environment, TypeValues};
+use crate::eval::call_stack::CallStack;
+use crate::eval::capture::eval_capturing_prints;
+use crate::eval::debug::DebugAction;
+use crate::eval::module::{Module, ModuleCache};
+use crate::eval::result::EvaluatedModule;
 use crate::eval::testutil::starlark_no_diagnostic;
-use crate::eval::{eval, testutil, EvalException, FileLoader};
-use crate::eval::{noload, RECURSION_ERROR_CODE};
+use crate::eval::trace::TraceEvent;
+use crate::eval::{
+    eval, eval_expr_with_locals, eval_parsed_module, eval_with_call_stack, testutil, EvalException,
+    FileLoader,
+};
+use crate::eval::{
+    noload, AMBIGUOUS_DEFAULT_VALUE_ERROR_CODE, DEBUGGER_ABORT_ERROR_CODE,
+    NOT_AN_EXPRESSION_ERROR_CODE, RECURSION_ERROR_CODE,
+};
 use crate::syntax::dialect::Dialect;
+use crate::syntax::parser::parse;
+use crate::values::error::{
+    ValueError, INTEGER_OVERFLOW_ERROR_CODE, NEGATIVE_EXPONENT_ERROR_CODE, NOT_SUPPORTED_ERROR_CODE,
+};
+use crate::values::function::{
+    DUPLICATE_PARAMETER_ERROR_CODE, EXTRA_PARAMETER_ERROR_CODE, MULTIPLE_VALUES_ERROR_CODE,
+    NOT_ENOUGH_PARAMS_ERROR_CODE,
+};
 use crate::values::Value;
 use codemap::CodeMap;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
 use std::sync::{Arc, Mutex};
 
 #[test]
@@ -30,6 +53,50 @@ fn arithmetic_test() {
     starlark_ok!("(5 % 2 == 1)");
 }
 
+#[test]
+fn not_in_test() {
+    starlark_ok!("(1 not in [2, 3])");
+    starlark_ok!("(not 2 not in [2, 3])");
+    starlark_ok!("(1 not in [2, 3] and 2 not in [3])");
+    starlark_ok!("(2 not in [2] or 1 not in [2])");
+    starlark_ok!("('a' not in 'bcd' and 'b' not in {'a': 1})");
+    starlark_ok!("in_list = [1]\n(2 not in in_list)");
+}
+
+#[test]
+fn power_test() {
+    starlark_ok!("(2 ** 10 == 1024)");
+    starlark_ok!("(2 ** 3 ** 2 == 512)");
+    starlark_ok!("((2 ** 3) ** 2 == 64)");
+    starlark_ok!("(-2 ** 2 == -4)");
+    starlark_ok!("((-2) ** 2 == 4)");
+    starlark_ok!("(2 ** -0 == 1)");
+    starlark_ok!("(2 * 3 ** 2 == 18)");
+    starlark_ok!("((-1) ** 1001 == -1)");
+    starlark_ok!("(0 ** 0 == 1)");
+    starlark_fail!("2 ** -1", NEGATIVE_EXPONENT_ERROR_CODE);
+    starlark_ok!("(2 ** 63 == 9223372036854775807 + 1)");
+    starlark_ok!("(2 ** 64 == 2 ** 32 * 2 ** 32)");
+    starlark_ok!("((-2) ** 63 == -9223372036854775807 - 1)");
+    starlark_fail!("2 ** (2 ** 40)", INTEGER_OVERFLOW_ERROR_CODE);
+    starlark_fail!("'a' ** 2");
+}
+
+#[test]
+fn sequence_comparison_test() {
+    starlark_ok!("([1, 2] < [1, 3] and (1, 2, 3) < (1, 2, 4))");
+    starlark_ok!("([2] > [1, 5] and (0, 9) < (1,))");
+    // A prefix is smaller
+    starlark_ok!("([] < [0] and [1] < [1, 0] and (1, 2) <= (1, 2, 3))");
+    starlark_ok!("([1, 2] >= [1, 2] and not (1, 2) < (1, 2))");
+    // Elements are compared recursively and only until the first difference
+    starlark_ok!("([[1, 2], 'b'] < [[1, 3], 'a'] and ((1, 'b'), 2) > ((1, 'a'), 'x'))");
+    starlark_ok!("([1, 'a'] < [2, []] and ['a', 1] < ['a', 2])");
+    starlark_fail!("[1, 2] < [1, 'b']", NOT_SUPPORTED_ERROR_CODE);
+    starlark_fail!("((1,),) < (1,)", NOT_SUPPORTED_ERROR_CODE);
+    starlark_fail!("[1] < (1,)", NOT_SUPPORTED_ERROR_CODE);
+}
+
 #[test]
 fn alias_test() {
     starlark_ok!(
@@ -99,6 +166,222 @@ def rec6(): rec2()
     starlark_fail!("def f(a, **kwargs, *args): pass");
 }
 
+#[test]
+fn kwargs_forwarding_test() {
+    const F: &str = "
+def inner(a, b=2, *args, **kwargs):
+  return [a, b, args, kwargs]
+
+def wrap(*a, **k): return inner(*a, **k)
+
+def wrap2(*a, **k): return wrap(*a, **k)
+
+def keys(**k): return [n for n in k]
+
+def wrap_keys(*a, **k): return wrap_keys2(*a, **k)
+def wrap_keys2(*a, **k): return keys(*a, **k)
+
+def two(a, b): return [a, b]
+
+def wrap_two(*a, **k): return wrap_two2(*a, **k)
+def wrap_two2(*a, **k): return two(*a, **k)
+";
+    starlark_ok!(F, "(wrap2(1) == [1, 2, [], {}])");
+    starlark_ok!(F, "(wrap2(1, 3, 4, 5) == [1, 3, [4, 5], {}])");
+    starlark_ok!(F, "(wrap2(b=3, a=1) == [1, 3, [], {}])");
+    starlark_ok!(
+        F,
+        "(wrap2(1, z=1, y=2, x=3) == [1, 2, [], {'z': 1, 'y': 2, 'x': 3}])"
+    );
+    starlark_ok!(F, "(wrap2(*[1, 2], **{'c': 3}) == [1, 2, [], {'c': 3}])");
+    // Keywords keep their order through both layers
+    starlark_ok!(F, "(wrap_keys(d=1, c=2, b=3, a=4) == ['d', 'c', 'b', 'a'])");
+    starlark_ok!(F, "(wrap_keys(b=1, **{'z': 2, 'a': 3}) == ['b', 'z', 'a'])");
+    // Errors are raised by the inner call
+    starlark_fail!(F, "wrap2()", NOT_ENOUGH_PARAMS_ERROR_CODE);
+    starlark_fail!(F, "wrap2(b=1)", NOT_ENOUGH_PARAMS_ERROR_CODE);
+    starlark_fail!(F, "wrap_keys(1)", EXTRA_PARAMETER_ERROR_CODE);
+    starlark_ok!(F, "(wrap_two(b=1, a=2) == [2, 1])");
+    starlark_fail!(F, "wrap_two(1)", NOT_ENOUGH_PARAMS_ERROR_CODE);
+    starlark_fail!(F, "wrap_two(1, 2, 3)", EXTRA_PARAMETER_ERROR_CODE);
+    starlark_fail!(F, "wrap_two(1, 2, c=3)", EXTRA_PARAMETER_ERROR_CODE);
+    starlark_fail!(F, "wrap_two(1, 2, a=3)", MULTIPLE_VALUES_ERROR_CODE);
+}
+
+#[test]
+fn duplicate_kwargs_test() {
+    const F: &str = "
+def f(a=1, **kwargs): return [a, kwargs]
+";
+    starlark_ok!(F, "(f(a=2, **{'b': 3}) == [2, {'b': 3}])");
+    starlark_fail!(F, "f(b=1, b=2)", DUPLICATE_PARAMETER_ERROR_CODE);
+    starlark_fail!(F, "f(b=1, **{'b': 2})", DUPLICATE_PARAMETER_ERROR_CODE);
+    starlark_fail!(F, "f(a=1, **{'a': 2})", DUPLICATE_PARAMETER_ERROR_CODE);
+}
+
+#[test]
+fn multiple_values_test() {
+    const F: &str = "
+def normal(a, b): return [a, b]
+def default(a=1, b=2): return [a, b]
+def optional(a=[], **kwargs): return [a, kwargs]
+";
+    starlark_fail!(F, "normal(1, a=2)", MULTIPLE_VALUES_ERROR_CODE);
+    starlark_fail!(F, "normal(1, 2, b=3)", MULTIPLE_VALUES_ERROR_CODE);
+    starlark_fail!(F, "normal(*[1], **{'a': 2})", MULTIPLE_VALUES_ERROR_CODE);
+    starlark_fail!(F, "default(1, a=2)", MULTIPLE_VALUES_ERROR_CODE);
+    starlark_fail!(F, "optional(1, a=2)", MULTIPLE_VALUES_ERROR_CODE);
+    starlark_ok!(
+        F,
+        "(normal(1, b=2) == [1, 2] and default(1, b=3) == [1, 3])"
+    );
+    starlark_ok!(F, "(optional(1, b=2) == [1, {'b': 2}])");
+
+    let (global, type_values) = crate::stdlib::global_environment();
+    let mut env = global.freeze().child("test");
+    let err =
+        starlark_no_diagnostic(&mut env, "def f(a): pass\nf(1, a=2)", &type_values).unwrap_err();
+    assert_eq!("f() got multiple values for argument 'a'", err.message);
+    // A positional-only parameter does not conflict with a keyword of the same name
+    assert!(starlark_no_diagnostic(&mut env, "dict({}, a=1) == {'a': 1}", &type_values).unwrap());
+}
+
+#[test]
+fn missing_parameters_test() {
+    let (global, type_values) = crate::stdlib::global_environment();
+    let mut env = global.freeze().child("test");
+    let message = |env: &mut Environment, code| {
+        let err = starlark_no_diagnostic(env, code, &type_values).unwrap_err();
+        assert_eq!(Some(NOT_ENOUGH_PARAMS_ERROR_CODE.to_owned()), err.code);
+        err.message
+    };
+    starlark_no_diagnostic(&mut env, "def f(a, b, c, d=1, *e): pass", &type_values).unwrap();
+    assert!(message(&mut env, "f()").starts_with("Missing parameters a, b, c for call to"));
+    assert!(message(&mut env, "f(1)").starts_with("Missing parameters b, c for call to"));
+    assert!(message(&mut env, "f(1, c=2)").starts_with("Missing parameter b for call to"));
+    assert!(message(&mut env, "f(d=2, b=1)").starts_with("Missing parameters a, c for call to"));
+    // Positional-only parameters cannot be filled by name
+    assert!(message(&mut env, "len(a=1)").starts_with("Missing parameter a for call to"));
+}
+
+#[test]
+fn positional_count_test() {
+    let (global, type_values) = crate::stdlib::global_environment();
+    let mut env = global.freeze().child("test");
+    let error =
+        |env: &mut Environment, code| starlark_no_diagnostic(env, code, &type_values).unwrap_err();
+    starlark_no_diagnostic(
+        &mut env,
+        "def f(a, b): pass\ndef g(a, b=1): pass\ndef h(a): pass",
+        &type_values,
+    )
+    .unwrap();
+    let err = error(&mut env, "f(1, 2, 3, 4)");
+    assert_eq!(Some(EXTRA_PARAMETER_ERROR_CODE.to_owned()), err.code);
+    assert_eq!(
+        "f() takes 2 positional arguments but 4 were given",
+        err.message
+    );
+    assert_eq!(
+        "g() takes from 1 to 2 positional arguments but 3 were given",
+        error(&mut env, "g(1, 2, 3)").message
+    );
+    assert_eq!(
+        "h() takes 1 positional argument but 2 were given",
+        error(&mut env, "h(*[1, 2])").message
+    );
+    let err = error(&mut env, "f(1)");
+    assert_eq!(Some(NOT_ENOUGH_PARAMS_ERROR_CODE.to_owned()), err.code);
+    assert_eq!(
+        "Missing parameter b for call to <function f from test>(a, b): \
+         f() takes 2 positional arguments but 1 was given",
+        err.message
+    );
+    // Counts are not mentioned when all arguments are named
+    assert_eq!(
+        "Missing parameter a for call to <function f from test>(a, b)",
+        error(&mut env, "f(b=1)").message
+    );
+}
+
+#[test]
+fn lazy_default_test() {
+    const F: &str = "
+x = 10
+
+def f(a, b=a + 1): return (a, b)
+
+def g(a, b=[a]):
+  b += [0]
+  return b
+
+def chain(a, b=a * 2, c=b + a): return [a, b, c]
+
+def compr(xs, ys=[v * 2 for v in xs if v != 2]): return ys
+
+def later(a=x, x=1): return (a, x)
+";
+    starlark_ok!(F, "(f(1) == (1, 2))");
+    starlark_ok!(F, "(f(1, 5) == (1, 5))");
+    starlark_ok!(F, "(f(b=0, a=3) == (3, 0))");
+    // Lazy defaults are evaluated on each call
+    starlark_ok!(F, "(g(1) == [1, 0] and g(2) == [2, 0])");
+    starlark_ok!(F, "(chain(1) == [1, 2, 3] and chain(1, c=0) == [1, 2, 0])");
+    starlark_ok!(F, "(chain(2, 1) == [2, 1, 3])");
+    starlark_ok!(F, "(compr([1, 2, 3]) == [2, 6])");
+    // Only earlier parameters are visible, other names are resolved when defining `later`
+    starlark_ok!(F, "(later() == (10, 1))");
+    // A default cannot refer to an earlier parameter shadowing a variable
+    starlark_fail!(
+        "x = 1\ndef f(x, y=x + 1): pass",
+        AMBIGUOUS_DEFAULT_VALUE_ERROR_CODE
+    );
+    // Variables set after the definition are not ambiguous
+    starlark_ok!("def f(x, y=x + 1): return y\nx = 10\n(f(1) == 2)");
+}
+
+#[test]
+fn nested_loop_control_test() {
+    const F: &str = "
+def continue_inner():
+  r = []
+  for i in [1, 2, 3]:
+    for j in [1, 2, 3]:
+      if j == 2:
+        continue
+      r += [(i, j)]
+    r += [i]
+  return r
+
+def break_inner():
+  r = []
+  for i in [1, 2]:
+    for j in [1, 2, 3]:
+      if j == 2:
+        break
+      r += [(i, j)]
+    r += [i]
+  return r
+
+def break_outer_with_comprehension():
+  r = []
+  for i in [1, 2, 3, 4]:
+    sq = [x * x for x in [0, 1, 2, 3][:i] if x != 1]
+    if i == 3:
+      break
+    r += [sq]
+  return r
+";
+    starlark_ok!(
+        F,
+        "(continue_inner() == [(1, 1), (1, 3), 1, (2, 1), (2, 3), 2, (3, 1), (3, 3), 3])"
+    );
+    starlark_ok!(F, "(break_inner() == [(1, 1), 1, (2, 1), 2])");
+    starlark_ok!(F, "(break_outer_with_comprehension() == [[0], [0]])");
+    // Comprehensions are expressions, loop control cannot appear inside them
+    starlark_fail!("def f(): return [break for x in []]");
+}
+
 #[test]
 fn sets_disabled() {
     let (mut env, type_values) = crate::stdlib::global_environment();
@@ -143,6 +426,420 @@ fn sets() {
     .unwrap());
 }
 
+#[test]
+fn shadowing_builtins() {
+    let (global, type_values) = crate::stdlib::global_environment();
+    let global = global.freeze();
+
+    // By default module globals shadow builtins
+    let mut env = global.child("shadowing");
+    assert!(starlark_no_diagnostic(
+        &mut env,
+        "list = [1, 2]\nlen = 0\nlist == [1, 2] and len == 0",
+        &type_values,
+    )
+    .unwrap());
+    // Shadowing does not leak into other modules
+    assert!(starlark_no_diagnostic(
+        &mut global.child("other"),
+        "list((1,)) == [1]",
+        &type_values,
+    )
+    .unwrap());
+
+    let mut env = global.child("strict");
+    env.forbid_shadowing();
+    assert!(starlark_no_diagnostic(&mut env, "x = list((1,))\nx == [1]", &type_values).unwrap());
+    let err = starlark_no_diagnostic(&mut env, "list = [1, 2]", &type_values).unwrap_err();
+    assert_eq!(Some("CM04".to_owned()), err.code);
+    let err = starlark_no_diagnostic(&mut env, "def len(x): return 0", &type_values).unwrap_err();
+    assert_eq!(Some("CM04".to_owned()), err.code);
+}
+
+#[test]
+fn disabled_builtins() {
+    let (global, type_values) = crate::stdlib::global_environment();
+    let global = global.freeze();
+
+    let mut env = global.child("deny");
+    env.deny_builtins(&["repr", "len"]);
+    assert!(starlark_no_diagnostic(&mut env, "list((1,)) == [1]", &type_values).unwrap());
+    let err = starlark_no_diagnostic(&mut env, "repr(1)", &type_values).unwrap_err();
+    assert_eq!(Some("CM06".to_owned()), err.code);
+    assert_eq!("'repr' is disabled in this context", err.message);
+    let err = starlark_no_diagnostic(&mut env, "def f(x): return len(x)\nf([])", &type_values)
+        .unwrap_err();
+    assert_eq!(Some("CM06".to_owned()), err.code);
+    // The module can define its own variable with a disabled name
+    assert!(starlark_no_diagnostic(&mut env, "len = 3\nlen == 3", &type_values).unwrap());
+    // Unknown names are still reported as such
+    let err = starlark_no_diagnostic(&mut env, "nope", &type_values).unwrap_err();
+    assert_eq!(Some("CM01".to_owned()), err.code);
+
+    let mut env = global.child("allow");
+    env.allow_only_builtins(&["len", "str"]);
+    assert!(starlark_no_diagnostic(&mut env, "len(str(12)) == 2", &type_values).unwrap());
+    let err = starlark_no_diagnostic(&mut env, "list((1,))", &type_values).unwrap_err();
+    assert_eq!(Some("CM06".to_owned()), err.code);
+    assert_eq!("'list' is disabled in this context", err.message);
+
+    // Other environments sharing the same parent are not affected
+    assert!(starlark_no_diagnostic(
+        &mut global.child("other"),
+        "repr(len(list((1,)))) == '1'",
+        &type_values
+    )
+    .unwrap());
+}
+
+#[test]
+fn predeclared_names() {
+    let (global, type_values) = crate::stdlib::global_environment();
+    let global = global.freeze();
+
+    let mut env = global.child("predeclared");
+    env.predeclare("PLATFORM", Value::from("linux")).unwrap();
+    env.predeclare("len", Value::new(3)).unwrap();
+    env.predeclare("TARGETS", Value::from(vec!["a"])).unwrap();
+    assert!(starlark_no_diagnostic(
+        &mut env,
+        "def platform(): return PLATFORM
+x = platform() + str(len)
+x == 'linux3'",
+        &type_values,
+    )
+    .unwrap());
+    // A local variable can still have a predeclared name
+    assert!(starlark_no_diagnostic(
+        &mut env,
+        "def f():\n  PLATFORM = 1\n  return PLATFORM\nf() == 1",
+        &type_values,
+    )
+    .unwrap());
+    for code in &[
+        "PLATFORM = 'mac'",
+        "y, PLATFORM = 1, 2",
+        "def PLATFORM(): pass",
+    ] {
+        let err = starlark_no_diagnostic(&mut env, code, &type_values).unwrap_err();
+        assert_eq!(Some("CM05".to_owned()), err.code, "{}", code);
+    }
+    // Predeclared values are frozen
+    let err = starlark_no_diagnostic(&mut env, "TARGETS.append('b')", &type_values).unwrap_err();
+    assert_eq!(Some("CV01".to_owned()), err.code);
+    assert_eq!(vec!["f", "platform", "x", "y"], env.names());
+    assert_eq!("linux", env.get("PLATFORM").unwrap().to_str());
+
+    // Predeclared names are not visible from other modules
+    let err =
+        starlark_no_diagnostic(&mut global.child("other"), "PLATFORM", &type_values).unwrap_err();
+    assert_eq!(Some("CM01".to_owned()), err.code);
+}
+
+#[test]
+fn reuse_call_stack() {
+    let (global, type_values) = crate::stdlib::global_environment();
+    let global = global.freeze();
+    let mut call_stack = CallStack::default();
+
+    let program = "def f(x): return [x, len(str(x))]\nf(f(12))";
+    let mut results = Vec::new();
+    for _ in 0..2 {
+        results.push(
+            eval_with_call_stack(
+                &Arc::new(Mutex::new(CodeMap::new())),
+                "reuse.bzl",
+                program,
+                Dialect::Bzl,
+                &mut global.child("reuse"),
+                &type_values,
+                noload::NoLoadFileLoader,
+                &mut call_stack,
+            )
+            .unwrap()
+            .to_repr(),
+        );
+        assert!(call_stack.is_empty());
+    }
+    assert_eq!("[[12, 2], 7]", results[0]);
+    assert_eq!(results[0], results[1]);
+
+    // Frames are still recorded for error reporting
+    let err = eval_with_call_stack(
+        &Arc::new(Mutex::new(CodeMap::new())),
+        "reuse.bzl",
+        "def f(): return g()\ndef g(): fail('x')\nf()",
+        Dialect::Bzl,
+        &mut global.child("reuse"),
+        &type_values,
+        noload::NoLoadFileLoader,
+        &mut call_stack,
+    )
+    .unwrap_err();
+    assert_eq!(3, err.message.matches("call to").count());
+    // Stack is reset before each evaluation, even after an error
+    assert_eq!(
+        results[0],
+        eval_with_call_stack(
+            &Arc::new(Mutex::new(CodeMap::new())),
+            "reuse.bzl",
+            program,
+            Dialect::Bzl,
+            &mut global.child("reuse"),
+            &type_values,
+            noload::NoLoadFileLoader,
+            &mut call_stack,
+        )
+        .unwrap()
+        .to_repr()
+    );
+    call_stack.clear();
+    assert_eq!(0, call_stack.len());
+}
+
+#[test]
+fn eval_result_accessors() {
+    let (global, type_values) = crate::stdlib::global_environment();
+    let mut env = global.freeze().child("result");
+    let value = eval(
+        &Arc::new(Mutex::new(CodeMap::new())),
+        "result.bzl",
+        "n = 3\nname = 'lib'\nsrcs = ['a.cc', 'b.cc']\nsizes = [1, 'x']\nok = True\nn + 1",
+        Dialect::Bzl,
+        &mut env,
+        &type_values,
+        noload::NoLoadFileLoader,
+    )
+    .unwrap();
+    let result = EvaluatedModule::new(value, env);
+    fn message(err: ValueError) -> String {
+        match err {
+            ValueError::Runtime(e) => e.message,
+            e => panic!("unexpected error: {:?}", e),
+        }
+    }
+
+    assert_eq!(Value::new(4), *result.value());
+    assert_eq!(3, result.get_int("n").unwrap());
+    assert_eq!("lib", result.get_string("name").unwrap());
+    assert!(result.get_bool("ok").unwrap());
+    assert_eq!(
+        vec!["a.cc".to_owned(), "b.cc".to_owned()],
+        result.get_list::<String>("srcs").unwrap()
+    );
+    assert_eq!(3u32, result.get::<u32>("n").unwrap());
+
+    // Missing global
+    let err = result.get_int("missing").unwrap_err();
+    assert_eq!("Variable 'missing' not found", message(err));
+
+    // Wrong type
+    let err = result.get_int("name").unwrap_err();
+    assert_eq!(
+        "Global 'name' has type 'string', expected 'int'",
+        message(err)
+    );
+    assert!(result.get_list::<String>("name").is_err());
+    let err = result.get_list::<String>("sizes").unwrap_err();
+    assert_eq!(
+        "Global 'sizes' of type 'list' has an unexpected value",
+        message(err)
+    );
+}
+
+#[test]
+fn eval_result_globals() {
+    #[derive(Clone)]
+    struct LibFileLoader {}
+
+    impl FileLoader for LibFileLoader {
+        fn load(&self, path: &str, type_values: &TypeValues) -> Result<Environment, EvalException> {
+            let mut env = Environment::new("lib");
+            noload::eval(
+                &Arc::new(Mutex::new(CodeMap::new())),
+                path,
+                "def double(x): return 2 * x\nVERSION = 3\nOTHER = 4",
+                Dialect::Bzl,
+                &mut env,
+                type_values,
+            )
+            .unwrap();
+            env.freeze();
+            Ok(env)
+        }
+    }
+
+    let (global, type_values) = crate::stdlib::global_environment();
+    let mut env = global.freeze().child("config");
+    env.predeclare("PLATFORM", Value::from("linux")).unwrap();
+    let program = "
+load('lib.bzl', 'double', 'OTHER', v = 'VERSION')
+size = double(v)
+_cache = [size]
+OTHER = len(_cache)
+def rule(): pass
+PLATFORM + ''
+";
+    let value = eval(
+        &Arc::new(Mutex::new(CodeMap::new())),
+        "config.bzl",
+        program,
+        Dialect::Bzl,
+        &mut env,
+        &type_values,
+        LibFileLoader {},
+    )
+    .unwrap();
+    let result = EvaluatedModule::new(value, env);
+    assert_eq!(
+        r#"{"OTHER": 1, "rule": <function rule from config>(), "size": 6}"#,
+        result.globals(false).to_repr()
+    );
+    assert_eq!(
+        r#"{"OTHER": 1, "_cache": [6], "rule": <function rule from config>(), "size": 6}"#,
+        result.globals(true).to_repr()
+    );
+}
+
+#[test]
+fn constant_folding() {
+    let (global, type_values) = crate::stdlib::global_environment();
+    let global = global.freeze();
+    let map = Arc::new(Mutex::new(CodeMap::new()));
+
+    let program = "
+calls = []
+def f(x):
+  calls.append(x)
+  return x
+def g(n, m=2 * 3):
+  return [n + 1 if 1 < 2 else n, m, 'a' + 'b', f(1) and f(2)]
+r = g(1) + [0 or f(3), '' and f(4), True and f(5)]
+(r, calls)
+";
+    let mut module = parse(&map, "fold.bzl", program, Dialect::Bzl).unwrap();
+    let expected = eval_parsed_module(
+        &map,
+        &module,
+        &mut global.child("unfolded"),
+        &type_values,
+        noload::NoLoadFileLoader,
+    )
+    .unwrap()
+    .to_repr();
+    assert_eq!("([2, 6, \"ab\", 2, 3, \"\", 5], [1, 2, 3, 5])", expected);
+    module.fold_constants();
+    let folded = eval_parsed_module(
+        &map,
+        &module,
+        &mut global.child("folded"),
+        &type_values,
+        noload::NoLoadFileLoader,
+    )
+    .unwrap()
+    .to_repr();
+    assert_eq!(expected, folded);
+
+    // Errors are still reported when evaluating
+    let mut module = parse(&map, "fold.bzl", "x = 1 // 0", Dialect::Bzl).unwrap();
+    module.fold_constants();
+    let err = eval_parsed_module(
+        &map,
+        &module,
+        &mut global.child("error"),
+        &type_values,
+        noload::NoLoadFileLoader,
+    )
+    .unwrap_err();
+    assert_eq!(Some("CV08".to_owned()), err.code);
+}
+
+#[test]
+fn test_fold_injected_constants() {
+    let (global, type_values) = crate::stdlib::global_environment();
+    let global = global.freeze();
+    let map = Arc::new(Mutex::new(CodeMap::new()));
+    let program = "
+def f():
+  if DEBUG:
+    return 'debug'
+  return 'release'
+f()
+";
+    let mut constants = HashMap::new();
+    constants.insert("DEBUG".to_owned(), Value::new(false));
+    let mut module = parse(&map, "debug.bzl", program, Dialect::Bzl).unwrap();
+    module.fold_constants_with(&constants);
+    let mut env = global.child("debug");
+    for (name, value) in &constants {
+        env.predeclare(name, value.clone()).unwrap();
+    }
+    let result = eval_parsed_module(
+        &map,
+        &module,
+        &mut env,
+        &type_values,
+        noload::NoLoadFileLoader,
+    )
+    .unwrap();
+    assert_eq!("release", result.to_str());
+
+    // The module cannot assign an injected constant
+    let mut module = parse(&map, "assign.bzl", "DEBUG = True", Dialect::Bzl).unwrap();
+    module.fold_constants_with(&constants);
+    assert!(eval_parsed_module(
+        &map,
+        &module,
+        &mut env,
+        &type_values,
+        noload::NoLoadFileLoader,
+    )
+    .is_err());
+}
+
+#[test]
+fn test_module_cache() {
+    let (global, type_values) = crate::stdlib::global_environment();
+    let global = global.freeze();
+    let map = Arc::new(Mutex::new(CodeMap::new()));
+    let mut cache = ModuleCache::default();
+    let program = "x = []\nx.append(len(x))\nx";
+
+    let first = cache
+        .get_or_parse(&map, "cached.bzl", program, Dialect::Bzl)
+        .unwrap() as *const Module;
+    for i in 0..2 {
+        let module = cache
+            .get_or_parse(&map, "cached.bzl", program, Dialect::Bzl)
+            .unwrap();
+        assert!(std::ptr::eq(first, module));
+        let mut env = global.child(&format!("cached{}", i));
+        let result = eval_parsed_module(
+            &map,
+            module,
+            &mut env,
+            &type_values,
+            noload::NoLoadFileLoader,
+        )
+        .unwrap();
+        // Each evaluation starts from a fresh environment.
+        assert_eq!("[0]", result.to_repr());
+        assert_eq!("[0]", env.get("x").unwrap().to_repr());
+    }
+    assert_eq!(1, cache.len());
+
+    // A different source or path is parsed separately, parse errors are not cached.
+    cache
+        .get_or_parse(&map, "other.bzl", program, Dialect::Bzl)
+        .unwrap();
+    assert!(cache
+        .get_or_parse(&map, "cached.bzl", "x = (", Dialect::Bzl)
+        .is_err());
+    assert_eq!(2, cache.len());
+    cache.clear();
+    assert!(cache.is_empty());
+}
+
 #[test]
 fn test_context_captured() {
     #[derive(Clone)]
@@ -189,11 +886,393 @@ def f(): return x
     );
 }
 
+#[test]
+fn test_module_name() {
+    #[derive(Clone)]
+    struct LibFileLoader {}
+
+    impl FileLoader for LibFileLoader {
+        fn load(&self, path: &str, type_values: &TypeValues) -> Result<Environment, EvalException> {
+            let (global, _) = crate::stdlib::global_environment();
+            let mut env = global.freeze().child("//lib:defs.bzl");
+            let lib = "def where(): return module_name()\nloaded_name = module_name()";
+            noload::eval(
+                &Arc::new(Mutex::new(CodeMap::new())),
+                path,
+                lib,
+                Dialect::Bzl,
+                &mut env,
+                type_values,
+            )
+            .unwrap();
+            env.freeze();
+            Ok(env)
+        }
+    }
+
+    let (global, type_values) = crate::stdlib::global_environment();
+    let global = global.freeze();
+    let program = "load('defs.bzl', 'where', 'loaded_name')\n[module_name(), where(), loaded_name]";
+    for name in &["//pkg:BUILD", "//other:BUILD"] {
+        let result = eval(
+            &Arc::new(Mutex::new(CodeMap::new())),
+            "BUILD",
+            program,
+            Dialect::Build,
+            &mut global.child(name),
+            &type_values,
+            LibFileLoader {},
+        )
+        .unwrap();
+        assert_eq!(
+            format!("[\"{0}\", \"{0}\", \"//lib:defs.bzl\"]", name),
+            result.to_repr()
+        );
+    }
+    assert_eq!(None, CallStack::default().module_name());
+}
+
+#[test]
+fn test_tracer() {
+    let events = Rc::new(RefCell::new(Vec::new()));
+    let global = Environment::new("global");
+    let recorded = events.clone();
+    global.with_tracer(Box::new(move |event| {
+        recorded.borrow_mut().push(match event {
+            TraceEvent::Statement { span, description } => (Some(*span), description.to_string()),
+            TraceEvent::Result { value, .. } => (None, format!("-> {}", value.to_repr())),
+        })
+    }));
+    // The tracer is inherited by child environments
+    let mut env = global.child("my.bzl");
+    let map = Arc::new(Mutex::new(CodeMap::new()));
+    noload::eval(
+        &map,
+        "my.bzl",
+        "def f(x):\n  return x + 1\ny = 1\nf(y)\n",
+        Dialect::Bzl,
+        &mut env,
+        &TypeValues::default(),
+    )
+    .unwrap();
+    let map = map.lock().unwrap();
+    let trace: Vec<String> = events
+        .borrow()
+        .iter()
+        .map(|(span, description)| match span {
+            Some(span) => format!(
+                "{}: {}",
+                map.look_up_span(*span).begin.line + 1,
+                description
+            ),
+            None => description.clone(),
+        })
+        .collect();
+    assert_eq!(
+        vec![
+            "1: def f",
+            "-> <function f from my.bzl>(x)",
+            "3: assign",
+            "-> None",
+            "4: expression",
+            "2: return",
+            "-> 2",
+        ],
+        trace
+    );
+}
+
+#[test]
+fn test_print_handler() {
+    let printed = Rc::new(RefCell::new(Vec::new()));
+    let (global, type_values) = crate::stdlib::global_environment();
+    let recorded = printed.clone();
+    global.with_print_handler(Box::new(move |message| {
+        recorded.borrow_mut().push(message.to_owned())
+    }));
+    // The print handler is inherited by child environments
+    let mut env = global.child("my.bzl");
+    noload::eval(
+        &Arc::new(Mutex::new(CodeMap::new())),
+        "my.bzl",
+        "def f(x):\n  print('f', x, sep=': ')\nprint()\nf([1])\n",
+        Dialect::Bzl,
+        &mut env,
+        &type_values,
+    )
+    .unwrap();
+    assert_eq!(vec!["", "f: [1]"], *printed.borrow());
+}
+
+#[cfg(feature = "provenance")]
+#[test]
+fn test_provenance_recorder() {
+    use crate::eval::provenance::ProvenanceRecorder;
+    use codemap::Span;
+
+    let recorder = ProvenanceRecorder::new();
+    let (global, type_values) = crate::stdlib::global_environment();
+    global.with_provenance_recorder(recorder.clone());
+    // The recorder is inherited by child environments
+    let mut env = global.child("my.bzl");
+    let map = Arc::new(Mutex::new(CodeMap::new()));
+    noload::eval(
+        &map,
+        "my.bzl",
+        "def add(l, x):\n  l.append(x)\nshared = []\nshared.append(1)\nadd(shared, 2)\n\
+         copy = list(shared)\nshared[0] = 0\n",
+        Dialect::Bzl,
+        &mut env,
+        &type_values,
+    )
+    .unwrap();
+    let map = map.lock().unwrap();
+    let source = |span: Span| map.find_file(span.low()).source_slice(span).to_owned();
+
+    let shared = recorder.provenance(&env.get("shared").unwrap()).unwrap();
+    assert_eq!("[]", source(shared.created));
+    let mutations: Vec<String> = shared.mutations.into_iter().map(source).collect();
+    assert_eq!(
+        vec!["shared.append(1)", "l.append(x)", "shared[0] = 0"],
+        mutations
+    );
+
+    let copy = recorder.provenance(&env.get("copy").unwrap()).unwrap();
+    assert_eq!("list(shared)", source(copy.created));
+    assert!(copy.mutations.is_empty());
+
+    assert_eq!(None, recorder.provenance(&Value::from(vec![1])));
+    assert_eq!(None, recorder.provenance(&Value::new(1)));
+}
+
+#[test]
+fn test_metrics_recorder() {
+    use crate::eval::metrics::MetricsRecorder;
+
+    let recorder = MetricsRecorder::new();
+    let (global, type_values) = crate::stdlib::global_environment();
+    global.with_metrics_recorder(recorder.clone());
+    // The recorder is inherited by child environments
+    let mut env = global.child("my.bzl");
+    let map = Arc::new(Mutex::new(CodeMap::new()));
+    let program = "
+def sum(n):
+  total = 0
+  for i in range(n):
+    total += i
+  return total
+x = sum(3)
+";
+    noload::eval(
+        &map,
+        "my.bzl",
+        program,
+        Dialect::Bzl,
+        &mut env,
+        &type_values,
+    )
+    .unwrap();
+    let metrics = recorder.metrics();
+    // 2 statements in the module, 3 in the body of `sum` and 3 iterations of the loop
+    assert_eq!(8, metrics.statements);
+    // `range` is called from `sum`
+    assert_eq!(2, metrics.peak_call_depth);
+    assert!(metrics.values_allocated > 0);
+
+    // Nothing is counted once the evaluation is over
+    let allocated = recorder.metrics().values_allocated;
+    assert_eq!("[1]", Value::from(vec![1]).to_repr());
+    assert_eq!(allocated, recorder.metrics().values_allocated);
+
+    recorder.reset();
+    noload::eval(
+        &map,
+        "my.bzl",
+        "y = [x]",
+        Dialect::Bzl,
+        &mut env,
+        &type_values,
+    )
+    .unwrap();
+    assert_eq!(1, recorder.metrics().statements);
+    assert_eq!(0, recorder.metrics().peak_call_depth);
+}
+
+#[test]
+fn test_eval_capturing_prints() {
+    let (global, type_values) = crate::stdlib::global_environment();
+    let mut env = global.child("my.bzl");
+    let captured = eval_capturing_prints(
+        &Arc::new(Mutex::new(CodeMap::new())),
+        "my.bzl",
+        "def check(x):\n  print('checking', x)\n  if x > 1:\n    fail('too big: %d' % x)\n\
+         [check(x) for x in range(5)]\n",
+        Dialect::Bzl,
+        &mut env,
+        &type_values,
+        noload::NoLoadFileLoader,
+    );
+    assert_eq!(
+        vec!["checking 0", "checking 1", "checking 2"],
+        captured.prints
+    );
+    let err = captured.result.unwrap_err();
+    assert_eq!(Some("CR99".to_owned()), err.code);
+    assert!(err.message.contains("too big: 2"), "{}", err.message);
+
+    let captured = eval_capturing_prints(
+        &Arc::new(Mutex::new(CodeMap::new())),
+        "my.bzl",
+        "print(1)\n2",
+        Dialect::Bzl,
+        &mut env,
+        &type_values,
+        noload::NoLoadFileLoader,
+    );
+    assert_eq!(vec!["1"], captured.prints);
+    assert_eq!(2, captured.result.unwrap().to_int().unwrap());
+    // The previous print handler of the environment is restored
+    assert!(env.print_handler().is_none());
+}
+
+#[test]
+fn test_debug_hook() {
+    let steps = Rc::new(RefCell::new(Vec::new()));
+    let global = Environment::new("global");
+    let recorded = steps.clone();
+    global.with_debug_hook(Box::new(move |frame| {
+        let locals: Vec<String> = frame
+            .locals()
+            .iter()
+            .map(|(name, value)| format!("{}={}", name, value.to_repr()))
+            .collect();
+        recorded.borrow_mut().push(format!(
+            "{} local={} [{}]",
+            frame.description(),
+            frame.is_local(),
+            locals.join(", ")
+        ));
+        if frame.description() == "def g" {
+            DebugAction::Abort
+        } else {
+            DebugAction::Continue
+        }
+    }));
+    let mut env = global.child("my.bzl");
+    let err = noload::eval(
+        &Arc::new(Mutex::new(CodeMap::new())),
+        "my.bzl",
+        "def f(x):\n  y = x * 2\n  return y\nz = f(1)\ndef g(): pass\nz = 3\n",
+        Dialect::Bzl,
+        &mut env,
+        &TypeValues::default(),
+    )
+    .unwrap_err();
+    assert_eq!(Some(DEBUGGER_ABORT_ERROR_CODE.to_owned()), err.code);
+    assert_eq!(
+        vec![
+            "def f local=false []",
+            "assign local=false []",
+            "assign local=true [x=1]",
+            "return local=true [x=1, y=2]",
+            "def g local=false []",
+        ],
+        *steps.borrow()
+    );
+    // The statement where the evaluation was aborted is not executed
+    assert_eq!(2, env.get("z").unwrap().to_int().unwrap());
+    assert!(env.get("g").is_err());
+}
+
+#[test]
+fn test_debug_hook_locals() {
+    let seen = Rc::new(RefCell::new(Vec::new()));
+    let global = Environment::new("global");
+    let recorded = seen.clone();
+    global.with_debug_hook(Box::new(move |frame| {
+        // Breakpoint on the `return` statement of `f`
+        if frame.description() == "return" {
+            let mut seen = recorded.borrow_mut();
+            seen.push(frame.local_names().join(","));
+            seen.push(frame.get_local("y").unwrap().to_repr());
+            assert!(frame.get_local("z").is_err());
+            // Unknown locals and values of another type cannot be set
+            assert!(frame.set_local("z", Value::new(1)).is_err());
+            assert!(frame.set_local("y", Value::from("a")).is_err());
+            frame.set_local("y", Value::new(10)).unwrap();
+        } else {
+            assert!(frame.local_names().is_empty() || frame.is_local());
+        }
+        DebugAction::Continue
+    }));
+    let mut env = global.child("my.bzl");
+    let result = noload::eval(
+        &Arc::new(Mutex::new(CodeMap::new())),
+        "my.bzl",
+        "def f(x):\n  y = x + 1\n  return y\nf(1)",
+        Dialect::Bzl,
+        &mut env,
+        &TypeValues::default(),
+    )
+    .unwrap();
+    assert_eq!(vec!["x,y", "2"], *seen.borrow());
+    // The value set while paused is used when the evaluation resumes
+    assert_eq!(10, result.to_int().unwrap());
+}
+
+#[test]
+fn test_load_path_rewriter() {
+    struct VendoredFileLoader {}
+
+    impl FileLoader for VendoredFileLoader {
+        fn load(&self, path: &str, type_values: &TypeValues) -> Result<Environment, EvalException> {
+            assert_eq!("third_party/rules_foo/defs.bzl", path);
+            let mut env = Environment::new(path);
+            noload::eval(
+                &Arc::new(Mutex::new(CodeMap::new())),
+                path,
+                "def foo(): return 'vendored'",
+                Dialect::Bzl,
+                &mut env,
+                type_values,
+            )?;
+            Ok(env)
+        }
+    }
+
+    let global = Environment::new("global");
+    global.with_load_path_rewriter(Box::new(|path| {
+        // `@repo//package:file` -> `third_party/repo/package/file`
+        if path.starts_with('@') {
+            let (repo, target) = path[1..].split_at(path.find("//").unwrap() - 1);
+            let target = target[2..].trim_start_matches(':').replace(':', "/");
+            format!("third_party/{}/{}", repo, target)
+        } else {
+            path.to_owned()
+        }
+    }));
+    // The rewriter is inherited by child environments
+    let mut env = global.child("my.bzl");
+    let result = eval(
+        &Arc::new(Mutex::new(CodeMap::new())),
+        "my.bzl",
+        "load('@rules_foo//:defs.bzl', 'foo')\nfoo()",
+        Dialect::Bzl,
+        &mut env,
+        &TypeValues::default(),
+        VendoredFileLoader {},
+    )
+    .unwrap();
+    assert_eq!("vendored", result.to_str());
+}
+
 #[test]
 fn test_type_values_are_imported_from_caller() {
     use crate::starlark_fun;
     use crate::starlark_module;
+    use crate::starlark_native_function;
     use crate::starlark_parse_param_type;
+    use crate::starlark_return_type;
     use crate::starlark_signature;
     use crate::starlark_signature_extraction;
     use crate::starlark_signatures;
@@ -245,3 +1324,73 @@ fn test_type_values_are_imported_from_caller() {
 
     assert_eq!("[\"ab\", \"de\"]", result.to_str());
 }
+
+#[test]
+fn eval_expr_with_locals_test() {
+    let (global, type_values) = crate::stdlib::global_environment();
+    let env = global.child("expr");
+    env.set("b", Value::new(100)).unwrap();
+    env.set("c", Value::new(1000)).unwrap();
+    let mut locals = HashMap::new();
+    locals.insert("a".to_owned(), Value::new(1));
+    locals.insert("b".to_owned(), Value::new(2));
+    let map = Arc::new(Mutex::new(CodeMap::new()));
+    let eval =
+        |content: &str| eval_expr_with_locals(&map, "expr", content, &locals, &env, &type_values);
+
+    assert_eq!("3", eval("a + b").unwrap().to_repr());
+    // Globals and builtins are still visible
+    assert_eq!("1002", eval("a + c + len([b])").unwrap().to_repr());
+    assert_eq!("[2, 3]", eval("[x + a for x in [a, b]]").unwrap().to_repr());
+    // The environment is not modified
+    assert_eq!(Value::new(100), env.get("b").unwrap());
+    assert!(env.get("a").is_err());
+
+    assert!(eval("d").is_err());
+    assert_eq!(
+        Some(NOT_AN_EXPRESSION_ERROR_CODE.to_owned()),
+        eval("x = a").unwrap_err().code
+    );
+    assert_eq!(
+        Some(NOT_AN_EXPRESSION_ERROR_CODE.to_owned()),
+        eval("a\nb").unwrap_err().code
+    );
+}
+
+#[test]
+fn test_freeze_observer() {
+    let events = Rc::new(RefCell::new(Vec::new()));
+    let global = Environment::new("global");
+    let recorded = events.clone();
+    global.with_freeze_observer(Box::new(move |event| {
+        recorded.borrow_mut().push(event.clone())
+    }));
+    let mut env = global.child("my.bzl");
+    // Freezing the global environment to create a child has nothing to observe
+    assert!(events.borrow().is_empty());
+    noload::eval(
+        &Arc::new(Mutex::new(CodeMap::new())),
+        "my.bzl",
+        "l = [1, [2, 3]]\nd = {'a': [], 'b': 'x'}\ns = 'str'\ni = 1\n",
+        Dialect::Bzl,
+        &mut env,
+        &TypeValues::default(),
+    )
+    .unwrap();
+    env.freeze();
+    let mut events: Vec<(&str, Option<i64>)> = events
+        .borrow()
+        .iter()
+        .map(|e| (e.value_type, e.length))
+        .collect();
+    events.sort();
+    assert_eq!(
+        vec![
+            ("dict", Some(2)),
+            ("list", Some(0)),
+            ("list", Some(2)),
+            ("list", Some(2)),
+        ],
+        events
+    );
+}
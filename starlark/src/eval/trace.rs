@@ -0,0 +1,58 @@
+// Copyright 2019 The Starlark in Rust Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tracing of evaluated statements, see
+//! [`Environment::with_tracer`](crate::environment::Environment::with_tracer).
+
+use crate::eval::stmt::{AstStatementCompiled, StatementCompiled};
+use crate::values::Value;
+use codemap::Span;
+
+/// An event reported to the tracer of an environment.
+#[derive(Debug)]
+pub enum TraceEvent<'a> {
+    /// A statement is about to be executed.
+    Statement {
+        /// The span of the statement, which can be resolved with the evaluation's `CodeMap`.
+        span: Span,
+        /// A short description of the statement, e.g. `assign` or `def f`.
+        description: &'a str,
+    },
+    /// A statement finished executing without error.
+    ///
+    /// The value is the value of the statement, e.g. the value of an expression statement or
+    /// `None` for most other statements.
+    Result {
+        /// The span of the statement.
+        span: Span,
+        /// The value of the statement.
+        value: &'a Value,
+    },
+}
+
+/// A short description of a statement for tracing.
+pub(crate) fn describe(stmt: &AstStatementCompiled) -> String {
+    match stmt.node {
+        StatementCompiled::Break => "break".to_owned(),
+        StatementCompiled::Continue => "continue".to_owned(),
+        StatementCompiled::Return(..) => "return".to_owned(),
+        StatementCompiled::Expression(..) => "expression".to_owned(),
+        StatementCompiled::Assign(..) => "assign".to_owned(),
+        StatementCompiled::AugmentedAssign(..) => "augmented assign".to_owned(),
+        StatementCompiled::IfElse(..) => "if".to_owned(),
+        StatementCompiled::For(..) => "for".to_owned(),
+        StatementCompiled::Def(ref def) => format!("def {}", def.name.node),
+        StatementCompiled::Load(ref path, ..) => format!("load {:?}", path.node),
+    }
+}
@@ -197,11 +197,11 @@ impl TypedValue for Set {
     ) -> ValueResult {
         let (start, stop, stride) =
             Value::convert_slice_indices(self.length()?, start, stop, stride)?;
-        Ok(Value::from(tuple::slice_vector(
+        Ok(Value::from(slice_items(
             start,
             stop,
             stride,
-            self.content.iter().map(HashedValue::get_value),
+            self.content.iter().map(|v| v.get_value().clone()),
         )))
     }
 
@@ -313,4 +313,13 @@ mod tests {
         assert!(!v1.is_descendant_value(&v2));
         assert!(!v1.is_descendant_value(&v3));
     }
+
+    #[test]
+    fn test_freeze() {
+        let mut v = Set::from(vec![1, 2]).unwrap();
+        let alias = v.clone();
+        v.freeze();
+        assert!(alias.downcast_mut::<Set>().is_err());
+        assert_eq!(alias.to_str(), "{1, 2}");
+    }
 }
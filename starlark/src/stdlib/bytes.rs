@@ -0,0 +1,337 @@
+// Copyright 2019 The Starlark in Rust Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Constructors and methods for the `bytes` and `bytearray` types.
+
+use crate::environment::{Environment, TypeValues};
+use crate::eval::call_stack::CallStack;
+use crate::values::bytes::{bytes_content, to_byte, ByteArray, Bytes};
+use crate::values::error::{check_requested_length, RuntimeError, ValueError};
+use crate::values::none::NoneType;
+use crate::values::*;
+use linked_hash_map::LinkedHashMap;
+use std::convert::TryFrom;
+use std::fmt::Write as _;
+use std::iter;
+
+// Errors -- UF = User Failure -- Failure that should be expected by the user (e.g. from a fail()).
+pub const BYTES_INVALID_HEX_ERROR_CODE: &str = "UF41";
+pub const BYTES_DECODE_FAILED_ERROR_CODE: &str = "UF42";
+
+starlark_module! { bytes_functions =>
+    /// bytes: construct an immutable sequence of bytes.
+    ///
+    /// `bytes(x)` returns the UTF-8 encoding of `x` if it is a string, `x` zero bytes if it is
+    /// a non-negative int, and otherwise the elements of the iterable `x`, which must be ints
+    /// in `range(256)`. With no argument, `bytes()` returns empty bytes.
+    ///
+    /// `bytes.fromhex(s)` decodes a string of hexadecimal digit pairs, optionally separated by
+    /// spaces.
+    ///
+    /// Examples:
+    ///
+    /// ```
+    /// # use starlark::stdlib::starlark_default;
+    /// # assert!(starlark_default(r#"(
+    /// len(bytes("é")) == 2
+    /// # and
+    /// list(bytes([0, 255])) == [0, 255]
+    /// # and
+    /// bytes("ab") == bytes([97, 98])
+    /// # and
+    /// bytes.fromhex("00 ff") == bytes([0, 255])
+    /// # )"#).unwrap());
+    /// ```
+    bytes(?x, /) {
+        Ok(Value::new(Bytes::new(new_content(x)?)))
+    }
+
+    /// bytearray: construct a mutable sequence of bytes.
+    ///
+    /// `bytearray(x)` accepts the same arguments as `bytes(x)`.
+    ///
+    /// Examples:
+    ///
+    /// ```
+    /// # use starlark::stdlib::starlark_default;
+    /// # assert!(starlark_default(r#"
+    /// b = bytearray("abc")
+    /// b[0] = 65
+    /// b.append(100)
+    /// # (
+    /// b.decode() == "Abcd"
+    /// # )"#).unwrap());
+    /// ```
+    bytearray(?x, /) {
+        Ok(Value::new(ByteArray::new(new_content(x)?)))
+    }
+
+    fromhex(s: String, /) {
+        let digits: Vec<char> = s.chars().filter(|c| *c != ' ').collect();
+        let mut content = Vec::with_capacity(digits.len() / 2);
+        for pair in digits.chunks(2) {
+            let byte = match pair {
+                [h, l] => h.to_digit(16).and_then(|h| l.to_digit(16).map(|l| h * 16 + l)),
+                _ => None,
+            };
+            match byte {
+                Some(b) => content.push(b as u8),
+                None => starlark_err!(
+                    BYTES_INVALID_HEX_ERROR_CODE,
+                    format!("Invalid hexadecimal string {:?}", s),
+                    "invalid hex".to_owned()
+                ),
+            }
+        }
+        Ok(Value::new(Bytes::new(content)))
+    }
+
+    /// bytes.hex: hexadecimal representation of the bytes.
+    ///
+    /// `b.hex()` returns a string with two lowercase hexadecimal digits per byte.
+    ///
+    /// Examples:
+    ///
+    /// ```
+    /// # use starlark::stdlib::starlark_default;
+    /// # assert!(starlark_default(r#"(
+    /// bytes([0, 15, 255]).hex() == "000fff"
+    /// # )"#).unwrap());
+    /// ```
+    bytes.hex(this, /) {
+        let this = this.downcast_ref::<Bytes>().unwrap();
+        Ok(Value::new(to_hex(this.as_slice())))
+    }
+
+    /// bytes.decode: decode the bytes into a string.
+    ///
+    /// `b.decode(encoding="utf-8")` returns the string that `b` encodes. Supported encodings
+    /// are `"utf-8"`, `"ascii"` and `"latin-1"`. It is an error if `b` is not valid in the
+    /// encoding.
+    ///
+    /// Examples:
+    ///
+    /// ```
+    /// # use starlark::stdlib::starlark_default;
+    /// # assert!(starlark_default(r#"(
+    /// bytes([104, 195, 169]).decode() == "hé"
+    /// # and
+    /// bytes([104, 233]).decode("latin-1") == "hé"
+    /// # )"#).unwrap());
+    /// ```
+    bytes.decode(this, encoding: String = "utf-8".to_owned()) {
+        let this = this.downcast_ref::<Bytes>().unwrap();
+        Ok(Value::new(decode_bytes(this.as_slice(), &encoding)?))
+    }
+
+    /// bytearray.append: append a byte to a bytearray.
+    ///
+    /// `b.append(x)` appends the int `x`, which must be in `range(256)`, and returns `None`.
+    bytearray.append(this, x, /) {
+        this.downcast_mut::<ByteArray>()?.unwrap().push(x)?;
+        Ok(Value::new(NoneType::None))
+    }
+
+    /// bytearray.hex: hexadecimal representation of the bytes, like `bytes.hex`.
+    // The trailing `_` avoids a collision with the `bytes.hex` implementation.
+    bytearray.hex_(this, /) {
+        let this = this.downcast_ref::<ByteArray>().unwrap();
+        Ok(Value::new(to_hex(this.as_slice())))
+    }
+
+    /// bytearray.decode: decode the bytes into a string, like `bytes.decode`.
+    bytearray.decode_(this, encoding: String = "utf-8".to_owned()) {
+        let this = this.downcast_ref::<ByteArray>().unwrap();
+        Ok(Value::new(decode_bytes(this.as_slice(), &encoding)?))
+    }
+}
+
+/// The `bytes` global: calling it constructs bytes, and it has a `fromhex` attribute.
+struct BytesType {
+    constructor: Value,
+    fromhex: Value,
+}
+
+impl TypedValue for BytesType {
+    type Holder = Immutable<BytesType>;
+    const TYPE: &'static str = "function";
+
+    fn values_for_descendant_check_and_freeze<'a>(
+        &'a self,
+    ) -> Box<dyn Iterator<Item = Value> + 'a> {
+        Box::new(iter::empty())
+    }
+
+    fn to_repr_impl(&self, buf: &mut String) -> std::fmt::Result {
+        self.constructor.to_repr_impl(buf)
+    }
+
+    fn call(
+        &self,
+        call_stack: &mut CallStack,
+        type_values: &TypeValues,
+        positional: Vec<Value>,
+        named: LinkedHashMap<String, Value>,
+        args: Option<Value>,
+        kwargs: Option<Value>,
+    ) -> ValueResult {
+        self.constructor
+            .call(call_stack, type_values, positional, named, args, kwargs)
+    }
+
+    fn get_attr(&self, attribute: &str) -> Result<Value, ValueError> {
+        match attribute {
+            "fromhex" => Ok(self.fromhex.clone()),
+            _ => Err(ValueError::OperationNotSupported {
+                op: attribute.to_owned(),
+                left: "bytes".to_owned(),
+                right: None,
+            }),
+        }
+    }
+
+    fn has_attr(&self, attribute: &str) -> Result<bool, ValueError> {
+        Ok(attribute == "fromhex")
+    }
+
+    fn dir_attr(&self) -> Result<Vec<String>, ValueError> {
+        Ok(vec!["fromhex".to_owned()])
+    }
+}
+
+/// Register the `bytes` and `bytearray` constructors and the methods of their types.
+pub fn global(env: &mut Environment, type_values: &mut TypeValues) {
+    let mut functions = Environment::new("bytes");
+    bytes_functions(&mut functions, type_values);
+    let bytes = BytesType {
+        constructor: functions.get("bytes").unwrap(),
+        fromhex: functions.get("fromhex").unwrap(),
+    };
+    env.set("bytes", Value::new(bytes)).unwrap();
+    env.set("bytearray", functions.get("bytearray").unwrap())
+        .unwrap();
+}
+
+fn new_content(x: Option<Value>) -> Result<Vec<u8>, ValueError> {
+    let x = match x {
+        Some(x) => x,
+        None => return Ok(Vec::new()),
+    };
+    if let Some(content) = bytes_content(&x) {
+        return Ok(content);
+    }
+    match x.get_type() {
+        "string" => Ok(x.to_str().into_bytes()),
+        "int" => {
+            let len = x.to_int()?;
+            if len < 0 {
+                return Err(RuntimeError {
+                    code: super::NEGATIVE_LENGTH_ERROR_CODE,
+                    message: format!("Negative count {} of zero bytes", len),
+                    label: "Negative count".to_owned(),
+                }
+                .into());
+            }
+            let len = usize::try_from(len).unwrap_or(usize::MAX);
+            Ok(vec![0; check_requested_length(len, "bytes()")?])
+        }
+        _ => x.iter()?.iter().map(|v| to_byte(&v)).collect(),
+    }
+}
+
+fn to_hex(content: &[u8]) -> String {
+    let mut s = String::with_capacity(content.len() * 2);
+    for b in content {
+        write!(s, "{:02x}", b).unwrap();
+    }
+    s
+}
+
+fn decode_bytes(content: &[u8], encoding: &str) -> Result<String, ValueError> {
+    let decoded = match encoding.to_lowercase().replace('_', "-").as_str() {
+        "utf-8" | "utf8" => String::from_utf8(content.to_vec()).ok(),
+        "ascii" => {
+            if content.is_ascii() {
+                Some(content.iter().map(|b| *b as char).collect())
+            } else {
+                None
+            }
+        }
+        "latin-1" | "latin1" | "iso-8859-1" => Some(content.iter().map(|b| *b as char).collect()),
+        _ => {
+            return Err(RuntimeError {
+                code: BYTES_DECODE_FAILED_ERROR_CODE,
+                message: format!("Unknown encoding {:?}", encoding),
+                label: "unknown encoding".to_owned(),
+            }
+            .into())
+        }
+    };
+    decoded.ok_or_else(|| {
+        RuntimeError {
+            code: BYTES_DECODE_FAILED_ERROR_CODE,
+            message: format!("Bytes are not valid {}", encoding),
+            label: "cannot decode".to_owned(),
+        }
+        .into()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::starlark_default;
+    use super::super::tests::starlark_default_fail;
+    use super::{BYTES_DECODE_FAILED_ERROR_CODE, BYTES_INVALID_HEX_ERROR_CODE};
+    use crate::values::bytes::BYTE_OUT_OF_RANGE_ERROR_CODE;
+    use crate::values::error::VALUE_TOO_LARGE_ERROR_CODE;
+
+    macro_rules! starlark_ok {
+        ($($t:expr),+) => (starlark_ok_fn!(starlark_default, $($t),+))
+    }
+
+    macro_rules! starlark_fail {
+        ($($t:expr),+) => (starlark_fail_fn!(starlark_default_fail, $($t),+))
+    }
+
+    #[test]
+    fn test_bytes() {
+        starlark_ok!(r#"(bytes() == bytes("") and len(bytes(3)) == 3)"#);
+        starlark_ok!(r#"(bytes("\x00ab")[1:] == bytes("ab") and bytes("ab")[0] == 97)"#);
+        starlark_ok!(r#"(bytes("ab") + bytes("c") == bytes("abc"))"#);
+        starlark_ok!(r#"(bytes("ab") + bytearray("c") == bytes("abc"))"#);
+        starlark_ok!(r#"(bytearray("ab") + bytes("c") == bytearray("abc"))"#);
+        starlark_ok!(r#"(bytes.fromhex("DEAD beef").hex() == "deadbeef")"#);
+        starlark_ok!(r#"(type(bytes("a")) == "bytes" and {bytes("a"): 1}[bytes("a")] == 1)"#);
+        starlark_fail!(r#"bytes([256])"#, BYTE_OUT_OF_RANGE_ERROR_CODE);
+        starlark_fail!(r#"bytes(-1)"#, super::super::NEGATIVE_LENGTH_ERROR_CODE);
+        starlark_fail!(r#"bytes(10 ** 12)"#, VALUE_TOO_LARGE_ERROR_CODE);
+        starlark_fail!(r#"bytes.fromhex("abc")"#, BYTES_INVALID_HEX_ERROR_CODE);
+        starlark_fail!(r#"bytes([255]).decode()"#, BYTES_DECODE_FAILED_ERROR_CODE);
+        starlark_fail!(
+            r#"bytes([200]).decode("ascii")"#,
+            BYTES_DECODE_FAILED_ERROR_CODE
+        );
+    }
+
+    #[test]
+    fn test_bytearray() {
+        starlark_ok!(r#"(bytearray(bytes([1, 2])) == bytearray([1, 2]))"#);
+        starlark_ok!(r#"(list(bytearray("ab")) == [97, 98] and type(bytearray()) == "bytearray")"#);
+        starlark_fail!(
+            r#"b = bytearray("a"); b[0] = -1"#,
+            BYTE_OUT_OF_RANGE_ERROR_CODE
+        );
+        starlark_fail!(r#"{bytearray("a"): 1}"#);
+    }
+}
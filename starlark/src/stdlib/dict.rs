@@ -91,6 +91,8 @@ starlark_module! {global =>
     ///
     /// `D.items()` returns a new list of key/value pairs, one per element in
     /// dictionary D, in the same order as they would be returned by a `for` loop.
+    /// The list is a snapshot: it can be iterated several times and indexed, and
+    /// later updates of D do not change it.
     ///
     /// Examples:
     ///
@@ -100,6 +102,8 @@ starlark_module! {global =>
     /// x = {"one": 1, "two": 2}
     /// # (
     /// x.items() == [("one", 1), ("two", 2)]
+    /// # and
+    /// x.items()[1] == ("two", 2)
     /// # )"#).unwrap());
     /// ```
     dict.items(this) {
@@ -152,6 +156,8 @@ starlark_module! {global =>
     /// x == {"two": 2}
     /// # and
     /// x.pop("three", 0) == 0
+    /// # and
+    /// x.pop("three", None) == None
     /// # )"#).unwrap());
     /// ```
     ///
@@ -160,19 +166,22 @@ starlark_module! {global =>
     /// ```python
     /// x.pop("four")  # error: missing key
     /// ```
-    dict.pop(this, key, default = NoneType::None, /) {
+    dict.pop(this, key, ?default, /) {
         let mut this = this.downcast_mut::<Dictionary>()?.unwrap();
-        match this.remove(&key)? {
-            Some(x) => Ok(x),
-            None => if default.get_type() == "NoneType" {
-                let key_error = format!("Key '{}' not found in '{}'", key.to_repr(), this.to_repr());
+        match (this.remove(&key)?, default) {
+            (Some(x), _) => Ok(x),
+            (None, Some(default)) => Ok(default),
+            (None, None) => {
+                let key_error = format!(
+                    "Key '{}' not found in '{}'",
+                    key.to_error_repr(),
+                    error_repr(&*this)
+                );
                 starlark_err!(
                     DICT_KEY_NOT_FOUND_ERROR_CODE,
                     key_error,
                     "not found".to_owned()
                 );
-            } else {
-                Ok(default.clone())
             }
         }
     }
@@ -182,6 +191,8 @@ starlark_module! {global =>
     /// ): returns and removes the first key/value pair of a dictionary.
     ///
     /// `D.popitem()` returns the first key/value pair, removing it from the dictionary.
+    /// Unlike in Python, where the last inserted pair is returned, the pairs are popped in
+    /// insertion order, so repeated calls consume the dictionary like a `for` loop would.
     ///
     /// `popitem` fails if the dictionary is empty, frozen, or has active iterators.
     ///
@@ -270,6 +281,9 @@ starlark_module! {global =>
     /// string and used as the key for an insertion into D, with its corresponding
     /// value being `value`.
     ///
+    /// Keys already present keep their position and take the new value, new keys are added
+    /// at the end in order.
+    ///
     /// `update` fails if the dictionary is frozen.
     ///
     /// Examples:
@@ -286,38 +300,52 @@ starlark_module! {global =>
     /// # )"#).unwrap());
     /// ```
     dict.update(this, ?pairs, /, **kwargs) {
-        if let Some(pairs) = pairs {
-            match pairs.get_type() {
-                "list" => for v in &pairs.iter()? {
+        // Collect the pairs before inserting them, so that a dictionary can be updated with
+        // itself.
+        let mut items = Vec::new();
+        match pairs {
+            None => {}
+            Some(ref pairs) if pairs.get_type() == "NoneType" => {}
+            Some(ref pairs) if pairs.get_type() == "dict" => {
+                items = pairs.downcast_ref::<Dictionary>().unwrap().items();
+            }
+            Some(pairs) => {
+                let iter = match pairs.iter() {
+                    Ok(iter) => iter,
+                    Err(..) => starlark_err!(
+                        INCORRECT_PARAMETER_TYPE_ERROR_CODE,
+                        format!(
+                            concat!(
+                                "dict.update expect an iterable of pairs or a dictionary as ",
+                                "first argument, got a value of type {}."
+                            ),
+                            pairs.get_type()
+                        ),
+                        format!(
+                            "type {} while expected iterable or dict",
+                            pairs.get_type()
+                        )
+                    ),
+                };
+                for v in &iter {
                     if v.length()? != 2 {
                         starlark_err!(
                             INCORRECT_PARAMETER_TYPE_ERROR_CODE,
                             concat!(
-                                "dict.update expect a list of pairsor a dictionary as first ",
-                                "argument, got a list of non-pairs."
+                                "dict.update expect an iterable of pairs or a dictionary as ",
+                                "first argument, got an iterable of non-pairs."
                             ).to_owned(),
-                            "list of non-pairs".to_owned()
+                            "iterable of non-pairs".to_owned()
                         )
                     }
-                    this.set_at(v.at(Value::new(0))?, v.at(Value::new(1))?)?;
-                },
-                "dict" => for k in &pairs.iter()? {
-                    this.set_at(k.clone(), pairs.at(k)?)?
-                },
-                x => starlark_err!(
-                    INCORRECT_PARAMETER_TYPE_ERROR_CODE,
-                    format!(
-                        concat!(
-                            "dict.update expect a list or a dictionary as first argument, ",
-                            "got a value of type {}."
-                        ),
-                        x
-                    ),
-                    format!("type {} while expected list or dict", x)
-                )
+                    items.push((v.at(Value::new(0))?, v.at(Value::new(1))?));
+                }
             }
         }
 
+        for (k, v) in items {
+            this.set_at(k, v)?;
+        }
         for (k, v) in kwargs {
             this.set_at(k.into(), v)?;
         }
@@ -351,7 +379,7 @@ starlark_module! {global =>
 #[cfg(test)]
 mod tests {
     use super::super::starlark_default;
-    use super::super::tests::starlark_default_fail;
+    use super::super::tests::{starlark_default_fail, starlark_default_frozen};
     use super::*;
 
     macro_rules! starlark_ok {
@@ -365,6 +393,10 @@ mod tests {
     #[test]
     fn test_clear() {
         starlark_ok!(r#"x = {"one": 1, "two": 2}; x.clear(); (x == {})"#);
+        starlark_ok!(r#"x = {"one": 1, "two": 2}; (x.clear() == None)"#);
+        let err = starlark_default_frozen("x = {'one': 1}", "x.clear()").unwrap_err();
+        assert_eq!(Some(BORROW_MUT_ERROR_CODE.to_owned()), err.code);
+        assert!(starlark_default_frozen("x = {'one': 1}", "(x == {'one': 1})").unwrap());
     }
 
     #[test]
@@ -372,11 +404,36 @@ mod tests {
         starlark_ok!(r#"x = {"one": 1, "two": 2}; (x.get("one") == 1)"#);
         starlark_ok!(r#"x = {"one": 1, "two": 2}; (x.get("three") == None)"#);
         starlark_ok!(r#"x = {"one": 1, "two": 2}; (x.get("three", 0) == 0)"#);
+        starlark_ok!(r#"x = {"one": 1, "two": 2}; (x.get("one", 0) == 1)"#);
     }
 
     #[test]
     fn test_items() {
         starlark_ok!(r#"x = {"one": 1, "two": 2}; (x.items() == [("one", 1), ("two", 2)])"#);
+        starlark_ok!(
+            r#"
+x = {"b": 2, "a": 1}
+items = x.items()
+first = [k for k, v in items]
+second = [v for k, v in items]
+(first == ["b", "a"] and second == [2, 1] and items[0] == ("b", 2) and items[-1][1] == 1)"#
+        );
+        starlark_ok!(
+            r#"
+x = {"one": 1}
+items = x.items()
+x["two"] = 2
+items.append(("three", 3))
+(items == [("one", 1), ("three", 3)] and x == {"one": 1, "two": 2})"#
+        );
+        starlark_ok!(
+            r#"
+x = {"one": 1, "two": 2}
+keys = x.keys()
+values = x.values()
+x.pop("one")
+(keys == ["one", "two"] and values == [1, 2] and keys[1] == "two" and values[0] == 1)"#
+        );
     }
 
     #[test]
@@ -390,10 +447,16 @@ mod tests {
             r#"x = {"one": 1, "two": 2}; (
             x.pop("one") == 1 and x == {"two": 2} and x.pop("three", 0) == 0)"#
         );
+        starlark_ok!(r#"x = {"one": 1}; (x.pop("one", 0) == 1 and x == {})"#);
+        starlark_ok!(r#"x = {"one": 1}; (x.pop("two", None) == None and x == {"one": 1})"#);
         starlark_fail!(
             r#"x = {"one": 1}; x.pop("four")"#,
             DICT_KEY_NOT_FOUND_ERROR_CODE
         );
+        for snippet in &["x.pop('one')", "x.pop('two', 0)"] {
+            let err = starlark_default_frozen("x = {'one': 1}", snippet).unwrap_err();
+            assert_eq!(Some(BORROW_MUT_ERROR_CODE.to_owned()), err.code);
+        }
     }
 
     #[test]
@@ -402,7 +465,16 @@ mod tests {
             r#"x = {"one": 1, "two": 2}; (
             x.popitem() == ("one", 1) and x.popitem() == ("two", 2))"#
         );
+        starlark_ok!(
+            r#"
+x = {"b": 2, "a": 1}
+x["c"] = 3
+items = [x.popitem() for _ in range(len(x))]
+(items == [("b", 2), ("a", 1), ("c", 3)] and x == {})"#
+        );
         starlark_fail!(r#"x = {}; x.popitem()"#, POP_ON_EMPTY_DICT_ERROR_CODE);
+        let err = starlark_default_frozen("x = {'one': 1}", "x.popitem()").unwrap_err();
+        assert_eq!(Some(BORROW_MUT_ERROR_CODE.to_owned()), err.code);
     }
 
     #[test]
@@ -415,6 +487,17 @@ mod tests {
             x.setdefault("four") == None and
             x == {"one": 1, "two": 2, "three": 0, "four": None })"#
         );
+        starlark_ok!(
+            r#"
+x = {}
+x.setdefault("a", {})["b"] = 1
+x.setdefault("a", {})["c"] = 2
+(x == {"a": {"b": 1, "c": 2}})"#
+        );
+        for snippet in &["x.setdefault('one')", "x.setdefault('two', 2)"] {
+            let err = starlark_default_frozen("x = {'one': 1}", snippet).unwrap_err();
+            assert_eq!(Some(BORROW_MUT_ERROR_CODE.to_owned()), err.code);
+        }
     }
 
     #[test]
@@ -427,6 +510,27 @@ x.update({"d": 4})
 x.update(e=5)
 (x == {"a": 1, "b": 2, "c": 3, "d": 4, "e": 5})"#
         );
+        starlark_ok!(
+            r#"
+x = {"a": 1, "b": 2}
+x.update({"c": 3, "a": 4}, b=5)
+(x == {"a": 4, "b": 5, "c": 3} and x.keys() == ["a", "b", "c"])"#
+        );
+        starlark_ok!(
+            r#"
+x = {"a": 1}
+x.update(None)
+x.update((("b", 2), ["c", 3]))
+x.update(x)
+(x == {"a": 1, "b": 2, "c": 3})"#
+        );
+        starlark_fail!("{}.update(1)", INCORRECT_PARAMETER_TYPE_ERROR_CODE);
+        starlark_fail!(
+            "{}.update([(1, 2, 3)])",
+            INCORRECT_PARAMETER_TYPE_ERROR_CODE
+        );
+        let err = starlark_default_frozen("x = {'one': 1}", "x.update(two=2)").unwrap_err();
+        assert_eq!(Some(BORROW_MUT_ERROR_CODE.to_owned()), err.code);
     }
 
     #[test]
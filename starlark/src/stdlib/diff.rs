@@ -0,0 +1,189 @@
+// Copyright 2019 The Starlark in Rust Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Implementation of `dict_diff`, the structured difference between two dictionaries.
+
+use crate::environment::TypeValues;
+use crate::eval::call_stack::CallStack;
+use crate::stdlib::sort_values;
+use crate::stdlib::structs::StructBuilder;
+use crate::values::dict::Dictionary;
+use crate::values::error::*;
+use crate::values::*;
+
+starlark_module! { global =>
+    /// dict_diff: compare two dictionaries
+    ///
+    /// `dict_diff(old, new)` returns a struct describing how to go from the dictionary `old`
+    /// to the dictionary `new`, with the fields:
+    ///
+    /// * `added`: a dict of the keys only in `new`, with their value in `new`.
+    /// * `removed`: a dict of the keys only in `old`, with their value in `old`.
+    /// * `changed`: a dict of the keys whose value differs, with a `struct(old=..., new=...)`
+    ///   of their values, unless both values are dictionaries.
+    /// * `nested`: a dict of the keys whose value is a different dictionary on both sides,
+    ///   with the `dict_diff` of these dictionaries.
+    ///
+    /// Keys with an equal value on both sides are omitted, so `old == new` if and only if the
+    /// four dicts are empty. The keys of each dict are sorted, and `dict_diff` fails if the
+    /// keys cannot be compared with each other.
+    ///
+    /// Examples:
+    ///
+    /// ```
+    /// # use starlark::stdlib::starlark_default;
+    /// # assert!(starlark_default(r#"
+    /// d = dict_diff(
+    ///     {"name": "a", "size": 1, "opts": {"debug": True, "x": 1}},
+    ///     {"name": "a", "size": 2, "opts": {"debug": False, "y": 2}, "tag": "t"},
+    /// )
+    /// # (
+    /// d.added == {"tag": "t"}
+    /// # and
+    /// d.removed == {}
+    /// # and
+    /// d.changed == {"size": struct(old=1, new=2)}
+    /// # and
+    /// d.nested["opts"].changed == {"debug": struct(old=True, new=False)}
+    /// # and
+    /// d.nested["opts"].added == {"y": 2} and d.nested["opts"].removed == {"x": 1}
+    /// # )"#).unwrap());
+    /// ```
+    dict_diff(call_stack cs, env e, old, new, /) {
+        for (name, v) in &[("old", &old), ("new", &new)] {
+            if v.get_type() != "dict" {
+                starlark_err!(
+                    INCORRECT_PARAMETER_TYPE_ERROR_CODE,
+                    format!(
+                        "dict_diff() expect a dict as {} parameter while got a value of type {}.",
+                        name,
+                        v.get_type()
+                    ),
+                    format!("type {} while expected dict", v.get_type())
+                )
+            }
+        }
+        diff(cs, e, &old, &new)
+    }
+}
+
+/// The `dict_diff` of the dictionaries `old` and `new`.
+fn diff(call_stack: &mut CallStack, env: &TypeValues, old: &Value, new: &Value) -> ValueResult {
+    let mut keys: Vec<Value> = old.iter()?.iter().collect();
+    for k in &new.iter()? {
+        if !old.is_in(&k)? {
+            keys.push(k);
+        }
+    }
+    let keys = sort_values(call_stack, env, keys, None, false)?;
+
+    let mut added = Dictionary::new();
+    let mut removed = Dictionary::new();
+    let mut changed = Dictionary::new();
+    let mut nested = Dictionary::new();
+    for k in keys {
+        match (old.is_in(&k)?, new.is_in(&k)?) {
+            (true, false) => removed.set_at(k.clone(), old.at(k)?)?,
+            (false, true) => added.set_at(k.clone(), new.at(k)?)?,
+            _ => {
+                let (o, n) = (old.at(k.clone())?, new.at(k.clone())?);
+                if o.equals(&n)? {
+                    continue;
+                }
+                if o.get_type() == "dict" && n.get_type() == "dict" {
+                    nested.set_at(k, diff(call_stack, env, &o, &n)?)?;
+                } else {
+                    let values: StructBuilder = vec![("old", o), ("new", n)].into_iter().collect();
+                    changed.set_at(k, values.build())?;
+                }
+            }
+        }
+    }
+
+    let result: StructBuilder = vec![
+        ("added", added),
+        ("removed", removed),
+        ("changed", changed),
+        ("nested", nested),
+    ]
+    .into_iter()
+    .collect();
+    Ok(result.build())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::starlark_default;
+    use crate::values::error::INCORRECT_PARAMETER_TYPE_ERROR_CODE;
+
+    macro_rules! starlark_ok {
+        ($($t:expr),+) => (starlark_ok_fn!(starlark_default, $($t),+))
+    }
+
+    #[test]
+    fn test_dict_diff() {
+        starlark_ok!(
+            r#"
+d = dict_diff({"a": 1, "b": {"c": 2}}, {"b": {"c": 2}, "a": 1})
+(d == struct(added={}, removed={}, changed={}, nested={}))"#
+        );
+        starlark_ok!(
+            r#"
+d = dict_diff({"z": 1, "b": 2, "m": 3}, {"m": 4, "y": 5, "a": 6})
+(d.added == {"a": 6, "y": 5} and d.added.keys() == ["a", "y"] and
+ d.removed.keys() == ["b", "z"] and d.removed == {"b": 2, "z": 1} and
+ d.changed == {"m": struct(old=3, new=4)})"#
+        );
+    }
+
+    #[test]
+    fn test_dict_diff_nested() {
+        starlark_ok!(
+            r#"
+old = {"deps": {"a": {"version": 1}, "b": {"version": 1}}, "name": "x"}
+new = {"deps": {"a": {"version": 2}, "c": {"version": 1}}, "name": "x"}
+d = dict_diff(old, new)
+deps = d.nested["deps"]
+(d.changed == {} and d.nested.keys() == ["deps"] and
+ deps.added == {"c": {"version": 1}} and deps.removed == {"b": {"version": 1}} and
+ deps.nested["a"].changed == {"version": struct(old=1, new=2)})"#
+        );
+    }
+
+    #[test]
+    fn test_dict_diff_type_change() {
+        starlark_ok!(
+            r#"
+d = dict_diff({"a": {"x": 1}, "b": 1, "c": [1]}, {"a": [1], "b": "1", "c": {"x": 1}})
+(d.nested == {} and d.changed == {
+    "a": struct(old={"x": 1}, new=[1]),
+    "b": struct(old=1, new="1"),
+    "c": struct(old=[1], new={"x": 1}),
+})"#
+        );
+    }
+
+    #[test]
+    fn test_dict_diff_errors() {
+        for snippet in &["dict_diff([], {})", "dict_diff({}, None)"] {
+            let err = starlark_default(snippet).unwrap_err();
+            assert_eq!(
+                Some(INCORRECT_PARAMETER_TYPE_ERROR_CODE.to_owned()),
+                err.code
+            );
+        }
+        // Keys of different types cannot be sorted
+        assert!(starlark_default("dict_diff({1: 1}, {'a': 1})").is_err());
+    }
+}
@@ -0,0 +1,161 @@
+// Copyright 2019 The Starlark in Rust Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Implementation of the `fmt` module, a struct with the functions `fmt.int` and `fmt.float`
+//! formatting numbers for display.
+
+use crate::environment::{Environment, TypeValues};
+use crate::stdlib::structs::StructBuilder;
+use crate::values::error::{
+    check_requested_length, RuntimeError, INCORRECT_PARAMETER_TYPE_ERROR_CODE,
+};
+use crate::values::*;
+use std::convert::TryFrom;
+
+use super::FMT_INVALID_ARGUMENT_ERROR_CODE;
+
+starlark_module! { fmt_functions =>
+    /// fmt.int: format an integer with grouped thousands
+    ///
+    /// `fmt.int(x, thousands_sep=",")` returns the decimal representation of the integer `x`
+    /// with `thousands_sep` inserted between each group of three digits, from the right.
+    ///
+    /// Examples:
+    ///
+    /// ```
+    /// # use starlark::stdlib::starlark_default;
+    /// # assert!(starlark_default(r#"(
+    /// fmt.int(1234567) == "1,234,567"
+    /// # and
+    /// fmt.int(-1000, thousands_sep="_") == "-1_000"
+    /// # )"#).unwrap());
+    /// ```
+    int(x, /, thousands_sep: String = ",".to_owned()) {
+        check_type!(x, "fmt.int", int);
+        Ok(Value::new(group_thousands(&x.to_str(), &thousands_sep)))
+    }
+
+    /// fmt.float: format a number with a fixed number of decimals
+    ///
+    /// `fmt.float(x, precision=2)` returns the decimal representation of the number `x` with
+    /// exactly `precision` digits after the decimal point, and no decimal point if
+    /// `precision` is 0. It is an error if `precision` is negative. Integers are the only
+    /// numbers of this implementation, so their decimals are always zeros.
+    ///
+    /// Examples:
+    ///
+    /// ```
+    /// # use starlark::stdlib::starlark_default;
+    /// # assert!(starlark_default(r#"(
+    /// fmt.float(3) == "3.00"
+    /// # and
+    /// fmt.float(-12, precision=1) == "-12.0"
+    /// # )"#).unwrap());
+    /// ```
+    float(x, /, precision: i64 = 2) {
+        check_type!(x, "fmt.float", int);
+        if precision < 0 {
+            return Err(RuntimeError {
+                code: FMT_INVALID_ARGUMENT_ERROR_CODE,
+                message: format!("fmt.float() precision must be non-negative, got {}", precision),
+                label: "Negative precision".to_owned(),
+            }
+            .into());
+        }
+        let precision = usize::try_from(precision).unwrap_or(usize::MAX);
+        let precision = check_requested_length(precision, "fmt.float() precision")?;
+        let mut result = x.to_str();
+        if precision > 0 {
+            result.push('.');
+            result.push_str(&"0".repeat(precision));
+        }
+        Ok(Value::new(result))
+    }
+}
+
+/// Add the `fmt` struct to `env`.
+pub fn global(env: &mut Environment, type_values: &mut TypeValues) {
+    let mut functions = Environment::new("fmt");
+    fmt_functions(&mut functions, type_values);
+    let mut fmt = StructBuilder::default();
+    for name in &["int", "float"] {
+        fmt.add(name, functions.get(name).unwrap());
+    }
+    env.set("fmt", fmt.build()).unwrap();
+}
+
+/// Insert `sep` between the groups of three digits of the decimal integer `x`.
+fn group_thousands(x: &str, sep: &str) -> String {
+    let mut result = String::new();
+    let digits = match x.strip_prefix('-') {
+        Some(digits) => {
+            result.push('-');
+            digits
+        }
+        None => x,
+    };
+    for (i, c) in digits.chars().enumerate() {
+        if i != 0 && (digits.len() - i) % 3 == 0 {
+            result.push_str(sep);
+        }
+        result.push(c);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::starlark_default;
+    use super::FMT_INVALID_ARGUMENT_ERROR_CODE;
+    use crate::values::error::VALUE_TOO_LARGE_ERROR_CODE;
+
+    macro_rules! starlark_ok {
+        ($($t:expr),+) => (starlark_ok_fn!(starlark_default, $($t),+))
+    }
+
+    #[test]
+    fn test_int() {
+        starlark_ok!(
+            r#"(fmt.int(0) == "0" and fmt.int(999) == "999" and fmt.int(-999) == "-999")"#
+        );
+        starlark_ok!(r#"(fmt.int(1000) == "1,000" and fmt.int(-1000) == "-1,000")"#);
+        starlark_ok!(r#"(fmt.int(123456) == "123,456" and fmt.int(1234567) == "1,234,567")"#);
+        starlark_ok!(r#"(fmt.int(-9223372036854775807 - 1) == "-9,223,372,036,854,775,808")"#);
+        starlark_ok!(r#"(fmt.int(1048576, thousands_sep=" ") == "1 048 576")"#);
+        starlark_ok!(r#"(fmt.int(1048576, thousands_sep="") == "1048576")"#);
+        starlark_ok!(r#"(fmt.int(10000, "'") == "10'000")"#);
+        starlark_ok!(r#"(fmt.int(-2 ** 64) == "-18,446,744,073,709,551,616")"#);
+        assert!(starlark_default(r#"fmt.int("1000")"#).is_err());
+        assert!(starlark_default(r#"fmt.int(1000, thousands_sep=1)"#).is_err());
+    }
+
+    #[test]
+    fn test_float() {
+        starlark_ok!(r#"(fmt.float(0) == "0.00" and fmt.float(5) == "5.00")"#);
+        starlark_ok!(r#"(fmt.float(-5, precision=3) == "-5.000")"#);
+        starlark_ok!(r#"(fmt.float(42, precision=0) == "42")"#);
+        starlark_ok!(r#"(fmt.float(2 ** 64, precision=1) == "18446744073709551616.0")"#);
+        assert!(starlark_default(r#"fmt.float("1")"#).is_err());
+        assert_eq!(
+            Some(FMT_INVALID_ARGUMENT_ERROR_CODE.to_owned()),
+            starlark_default("fmt.float(1, precision=-1)")
+                .unwrap_err()
+                .code
+        );
+        assert_eq!(
+            Some(VALUE_TOO_LARGE_ERROR_CODE.to_owned()),
+            starlark_default("fmt.float(1, 10 ** 12)").unwrap_err().code
+        );
+    }
+}
@@ -0,0 +1,90 @@
+// Copyright 2019 The Starlark in Rust Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Methods for the `int` type.
+
+use crate::values::bigint::BigInt;
+use crate::values::*;
+
+starlark_module! {global =>
+    /// int.bit_length: number of bits needed to represent an integer.
+    ///
+    /// `x.bit_length()` returns the number of bits of the absolute value of `x` in binary,
+    /// excluding the sign and leading zeros. It is `0` for `0`.
+    ///
+    /// Examples:
+    ///
+    /// ```
+    /// # use starlark::stdlib::starlark_default;
+    /// # assert!(starlark_default(r#"(
+    /// (0).bit_length() == 0
+    /// # and
+    /// (5).bit_length() == 3
+    /// # and
+    /// (-256).bit_length() == 9
+    /// # )"#).unwrap());
+    /// ```
+    int.bit_length(this, /) {
+        Ok(Value::new(BigInt::from_value(&this).unwrap().bit_length() as i64))
+    }
+
+    /// int.bit_count: number of ones in the binary representation of an integer.
+    ///
+    /// `x.bit_count()` returns the number of ones in the binary representation of the
+    /// absolute value of `x`, also known as the population count.
+    ///
+    /// Examples:
+    ///
+    /// ```
+    /// # use starlark::stdlib::starlark_default;
+    /// # assert!(starlark_default(r#"(
+    /// (0).bit_count() == 0
+    /// # and
+    /// (5).bit_count() == 2
+    /// # and
+    /// (-7).bit_count() == 3
+    /// # )"#).unwrap());
+    /// ```
+    int.bit_count(this, /) {
+        Ok(Value::new(BigInt::from_value(&this).unwrap().bit_count() as i64))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::starlark_default;
+
+    macro_rules! starlark_ok {
+        ($($t:expr),+) => (starlark_ok_fn!(starlark_default, $($t),+))
+    }
+
+    #[test]
+    fn test_bit_length() {
+        starlark_ok!("((0).bit_length() == 0 and (1).bit_length() == 1)");
+        starlark_ok!("((255).bit_length() == 8 and (256).bit_length() == 9)");
+        starlark_ok!("([(2 ** i).bit_length() for i in range(63)] == list(range(1, 64)))");
+        starlark_ok!("((-1).bit_length() == 1 and (-256).bit_length() == 9)");
+        starlark_ok!("((-9223372036854775807 - 1).bit_length() == 64)");
+        starlark_ok!("((2 ** 70).bit_length() == 71 and (-(2 ** 70)).bit_length() == 71)");
+    }
+
+    #[test]
+    fn test_bit_count() {
+        starlark_ok!("((0).bit_count() == 0 and (1).bit_count() == 1)");
+        starlark_ok!("([(2 ** i).bit_count() for i in range(63)] == [1] * 63)");
+        starlark_ok!("((255).bit_count() == 8 and (-255).bit_count() == 8)");
+        starlark_ok!("((-9223372036854775807 - 1).bit_count() == 1)");
+        starlark_ok!("((2 ** 70 - 1).bit_count() == 70 and (-(2 ** 70)).bit_count() == 1)");
+    }
+}
@@ -0,0 +1,216 @@
+// Copyright 2019 The Starlark in Rust Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Implementation of the `json` module, a struct with the functions `json.encode` and
+//! `json.encode_canonical`.
+
+use crate::environment::{Environment, TypeValues};
+use crate::eval::json_error::write_string;
+use crate::stdlib::structs::{StarlarkStruct, StructBuilder};
+use crate::values::dict::Dictionary;
+use crate::values::error::{RuntimeError, ValueError};
+use crate::values::*;
+use std::fmt::Write;
+
+use super::JSON_ENCODE_ERROR_CODE;
+
+starlark_module! { json_functions =>
+    /// json.encode: encode a value as JSON
+    ///
+    /// `json.encode(x)` returns the JSON encoding of `x`, without any whitespace. `None`,
+    /// booleans, integers and strings are encoded as JSON values, lists and tuples as arrays,
+    /// and dictionaries and structs as objects whose members are in iteration order. It is
+    /// an error if `x` contains a value of another type, or a dictionary with a key which is
+    /// not a string.
+    ///
+    /// Examples:
+    ///
+    /// ```
+    /// # use starlark::stdlib::starlark_default;
+    /// # assert!(starlark_default(r#"(
+    /// json.encode({"b": [1, None], "a": (True, "x\ny")}) == '{"b":[1,null],"a":[true,"x\\ny"]}'
+    /// # )"#).unwrap());
+    /// ```
+    encode(x, /) {
+        let mut out = String::new();
+        encode_value(&x, false, &mut out)?;
+        Ok(Value::new(out))
+    }
+
+    /// json.encode_canonical: encode a value as canonical JSON
+    ///
+    /// `json.encode_canonical(x)` is like `json.encode(x)` except that the members of objects
+    /// are sorted by name, so equal values always have the same encoding regardless of the
+    /// insertion order of their dictionaries, e.g. to hash or diff them. Integers are encoded
+    /// in decimal without exponent or leading zeros.
+    ///
+    /// Examples:
+    ///
+    /// ```
+    /// # use starlark::stdlib::starlark_default;
+    /// # assert!(starlark_default(r#"(
+    /// json.encode_canonical({"b": 1, "a": {"d": 2, "c": 3}}) == '{"a":{"c":3,"d":2},"b":1}'
+    /// # )"#).unwrap());
+    /// ```
+    encode_canonical(x, /) {
+        let mut out = String::new();
+        encode_value(&x, true, &mut out)?;
+        Ok(Value::new(out))
+    }
+}
+
+/// Add the `json` struct to `env`.
+pub fn global(env: &mut Environment, type_values: &mut TypeValues) {
+    let mut functions = Environment::new("json");
+    json_functions(&mut functions, type_values);
+    let mut json = StructBuilder::default();
+    for name in &["encode", "encode_canonical"] {
+        json.add(name, functions.get(name).unwrap());
+    }
+    env.set("json", json.build()).unwrap();
+}
+
+fn encode_value(value: &Value, canonical: bool, out: &mut String) -> Result<(), ValueError> {
+    match value.get_type() {
+        "NoneType" => out.push_str("null"),
+        "bool" => out.push_str(if value.to_bool() { "true" } else { "false" }),
+        "int" => out.push_str(&value.to_str()),
+        "string" => write_string(out, &value.to_str()),
+        "list" | "tuple" => {
+            out.push('[');
+            for (i, v) in value.iter()?.iter().enumerate() {
+                if i != 0 {
+                    out.push(',');
+                }
+                encode_value(&v, canonical, out)?;
+            }
+            out.push(']');
+        }
+        "dict" => {
+            let mut members = Vec::new();
+            for (k, v) in value.downcast_ref::<Dictionary>().unwrap().items() {
+                if k.get_type() != "string" {
+                    return Err(encode_error(format!(
+                        "Cannot encode dictionary key {} of type '{}' as JSON, keys must be strings",
+                        k.to_repr(),
+                        k.get_type()
+                    )));
+                }
+                members.push((k.to_str(), v));
+            }
+            encode_object(members, canonical, out)?;
+        }
+        "struct" => {
+            let fields = value
+                .downcast_ref::<StarlarkStruct>()
+                .unwrap()
+                .fields()
+                .clone();
+            encode_object(fields.into_iter().collect(), canonical, out)?;
+        }
+        t => {
+            return Err(encode_error(format!(
+                "Cannot encode value {} of type '{}' as JSON",
+                value.to_repr(),
+                t
+            )))
+        }
+    }
+    Ok(())
+}
+
+fn encode_object(
+    mut members: Vec<(String, Value)>,
+    canonical: bool,
+    out: &mut String,
+) -> Result<(), ValueError> {
+    if canonical {
+        members.sort_by(|(a, _), (b, _)| a.cmp(b));
+    }
+    out.push('{');
+    for (i, (k, v)) in members.iter().enumerate() {
+        if i != 0 {
+            out.push(',');
+        }
+        write_string(out, k);
+        out.write_char(':').unwrap();
+        encode_value(v, canonical, out)?;
+    }
+    out.push('}');
+    Ok(())
+}
+
+fn encode_error(message: String) -> ValueError {
+    RuntimeError {
+        code: JSON_ENCODE_ERROR_CODE,
+        message,
+        label: "Not encodable as JSON".to_owned(),
+    }
+    .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::starlark_default;
+    use super::JSON_ENCODE_ERROR_CODE;
+
+    macro_rules! starlark_ok {
+        ($($t:expr),+) => (starlark_ok_fn!(starlark_default, $($t),+))
+    }
+
+    #[test]
+    fn test_encode() {
+        starlark_ok!(r#"(json.encode(None) == "null")"#);
+        starlark_ok!(r#"(json.encode([True, False, -12]) == "[true,false,-12]")"#);
+        starlark_ok!(r#"(json.encode(("a\"b\\", "\t\x01é")) == '["a\\"b\\\\","\\t\\u0001é"]')"#);
+        starlark_ok!(r#"(json.encode({}) == "{}" and json.encode([]) == "[]")"#);
+        starlark_ok!(r#"(json.encode({"b": 1, "a": 2}) == '{"b":1,"a":2}')"#);
+        starlark_ok!(
+            r#"(json.encode(struct(y=[1], x={"k": None})) == '{"y":[1],"x":{"k":null}}')"#
+        );
+    }
+
+    #[test]
+    fn test_encode_canonical() {
+        starlark_ok!(r#"(json.encode_canonical({"b": 1, "a": 2}) == '{"a":2,"b":1}')"#);
+        starlark_ok!(
+            r#"a = {"x": 1, "y": [{"q": 1, "p": 2}], "z": struct(n=1, m=2)}
+b = {}
+b["z"] = struct(m=2, n=1)
+b["y"] = [{"p": 2, "q": 1}]
+b["x"] = 1
+"#,
+            r#"(json.encode(a) != json.encode(b) and
+                json.encode_canonical(a) == json.encode_canonical(b) and
+                json.encode_canonical(a) == '{"x":1,"y":[{"p":2,"q":1}],"z":{"m":2,"n":1}}')"#
+        );
+    }
+
+    #[test]
+    fn test_encode_errors() {
+        for code in &[
+            "json.encode({1: 2})",
+            "json.encode_canonical([{'a': {None: 1}}])",
+            "json.encode(len)",
+            "json.encode_canonical(range(2))",
+        ] {
+            assert_eq!(
+                Some(JSON_ENCODE_ERROR_CODE.to_owned()),
+                starlark_default(code).unwrap_err().code,
+                "{}",
+                code
+            );
+        }
+    }
+}
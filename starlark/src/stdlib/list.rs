@@ -14,6 +14,8 @@
 
 //! Methods for the `list` type.
 
+use crate::stdlib::sort_values;
+use crate::values::bigint::BigInt;
 use crate::values::list::List;
 use crate::values::none::NoneType;
 use crate::values::*;
@@ -77,6 +79,28 @@ starlark_module! {global =>
         Ok(Value::new(NoneType::None))
     }
 
+    /// list.count: count the occurrences of an element in a list.
+    ///
+    /// `L.count(x)` returns the number of elements of the list L which are equal to `x`.
+    ///
+    /// Examples:
+    ///
+    /// ```
+    /// # use starlark::stdlib::starlark_default;
+    /// # assert!(starlark_default(r#"
+    /// x = ["b", "a", "n", "a", "n", "a"]
+    /// # (
+    /// x.count("a") == 3
+    /// # and
+    /// x.count("c") == 0
+    /// # )"#).unwrap());
+    /// ```
+    list.count(this, needle, /) {
+        let it = this.iter()?;
+        let count = it.iter().filter(|x| *x == needle).count();
+        Ok(Value::new(count as i64))
+    }
+
     /// [list.extend](
     /// https://github.com/google/skylark/blob/3705afa472e466b8b061cce44b47c9ddc6db696d/doc/spec.md#list·extend
     /// ): extend a list with another iterable's content.
@@ -106,6 +130,45 @@ starlark_module! {global =>
         Ok(Value::new(NoneType::None))
     }
 
+    /// list.get: get an element of the list, or a default value.
+    ///
+    /// `L.get(i[, default])` returns the element of the list L at index `i`, like `L[i]`,
+    /// or `default` if `i` is out of range instead of failing. `default` is `None` if not
+    /// specified. As usual, a negative index counts from the end of the list.
+    ///
+    /// `get` fails if `i` is not an `int`.
+    ///
+    /// Examples:
+    ///
+    /// ```
+    /// # use starlark::stdlib::starlark_default;
+    /// # assert!(starlark_default(r#"
+    /// x = ["a", "b", "c"]
+    /// # (
+    /// x.get(1) == "b"
+    /// # and
+    /// x.get(-1) == "c"
+    /// # and
+    /// x.get(3) == None
+    /// # and
+    /// x.get(-4, "z") == "z"
+    /// # )"#).unwrap());
+    /// ```
+    list.get(this, index, default = NoneType::None, /) {
+        if index.downcast_ref::<BigInt>().is_some() {
+            // Out of the `i64` range, so out of the list
+            return Ok(default);
+        }
+        let len = this.length()?;
+        let i = index.to_int()?;
+        let i = if i < 0 { i + len } else { i };
+        if i < 0 || i >= len {
+            Ok(default)
+        } else {
+            this.at(Value::new(i))
+        }
+    }
+
     /// [list.index](
     /// https://github.com/google/skylark/blob/3705afa472e466b8b061cce44b47c9ddc6db696d/doc/spec.md#list·index
     /// ): get the index of an element in the list.
@@ -247,13 +310,56 @@ starlark_module! {global =>
         this.remove(needle)?;
         Ok(Value::new(NoneType::None))
     }
+
+    /// list.sort: sort a list in place.
+    ///
+    /// `L.sort(key=None, reverse=False)` sorts the elements of the list L and returns `None`.
+    /// The sort is stable.
+    ///
+    /// The optional `key` is a function of one argument, called once for each element, whose
+    /// result is used as the sort key instead of the element. If `reverse` is true, the
+    /// elements are sorted in descending order, equal elements keeping their relative order.
+    ///
+    /// `sort` fails if the elements (or keys) cannot be compared, if `key` fails, or if the
+    /// list is frozen or has active iterators.
+    ///
+    /// Examples:
+    ///
+    /// ```
+    /// # use starlark::stdlib::starlark_default;
+    /// # assert!(starlark_default(r#"
+    /// x = [3, 1, 2]
+    /// x.sort()
+    /// # t = (
+    /// x == [1, 2, 3]
+    /// # )
+    /// def second(p):
+    ///     return p[1]
+    /// y = [("a", 2), ("b", 3), ("c", 2)]
+    /// y.sort(key=second, reverse=True)
+    /// # (t and (
+    /// y == [("b", 3), ("a", 2), ("c", 2)]
+    /// # ))"#).unwrap());
+    /// ```
+    list.sort(call_stack cs, env e, this, /, ?key, reverse = false) {
+        // The key function may access the list, so it is not borrowed while sorting.
+        let values = this.iter()?.to_vec();
+        let sorted = sort_values(cs, e, values, key, reverse.to_bool())?;
+        let mut this = this.downcast_mut::<List>()?.unwrap();
+        this.clear();
+        for v in sorted {
+            this.push(v)?;
+        }
+        Ok(Value::new(NoneType::None))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::super::starlark_default;
-    use super::super::tests::starlark_default_fail;
+    use super::super::tests::{starlark_default_fail, starlark_default_frozen};
     use super::LIST_REMOVE_ELEMENT_NOT_FOUND_ERROR_CODE;
+    use crate::values::error::BORROW_MUT_ERROR_CODE;
 
     macro_rules! starlark_ok {
         ($($t:expr),+) => (starlark_ok_fn!(starlark_default, $($t),+))
@@ -271,6 +377,18 @@ mod tests {
     #[test]
     fn test_clear() {
         starlark_ok!(r#"x = [1, 2, 3]; x.clear(); (x == [])"#);
+        starlark_ok!(r#"x = [1, 2, 3]; (x.clear() == None)"#);
+        let err = starlark_default_frozen("x = [1, 2, 3]", "x.clear()").unwrap_err();
+        assert_eq!(Some(BORROW_MUT_ERROR_CODE.to_owned()), err.code);
+        assert!(starlark_default_frozen("x = [1, 2, 3]", "(x == [1, 2, 3])").unwrap());
+    }
+
+    #[test]
+    fn test_count() {
+        starlark_ok!(r#"x = [1, 2, 1, [1], 1]; (x.count(1) == 3)"#);
+        starlark_ok!(r#"x = [1, 2, 1, [1], 1]; (x.count([1]) == 1)"#);
+        starlark_ok!(r#"x = [1, 2, 1, [1], 1]; (x.count(3) == 0)"#);
+        starlark_ok!(r#"([].count(None) == 0)"#);
     }
 
     #[test]
@@ -278,6 +396,17 @@ mod tests {
         starlark_ok!(r#"x = []; x.extend([1, 2, 3]); x.extend(["foo"]); (x == [1, 2, 3, "foo"])"#);
     }
 
+    #[test]
+    fn test_get() {
+        starlark_ok!(r#"x = [1, 2, 3]; (x.get(0) == 1 and x.get(2) == 3)"#);
+        starlark_ok!(r#"x = [1, 2, 3]; (x.get(-1) == 3 and x.get(-3) == 1)"#);
+        starlark_ok!(r#"x = [1, 2, 3]; (x.get(3) == None and x.get(-4) == None)"#);
+        starlark_ok!(r#"x = [1, 2, 3]; (x.get(100, "d") == "d" and x.get(-100, "d") == "d")"#);
+        starlark_ok!(r#"x = [None]; (x.get(0, 1) == None and [].get(0, 1) == 1)"#);
+        starlark_ok!(r#"([1].get(2 ** 70) == None and [1].get(-2 ** 70, 0) == 0)"#);
+        starlark_fail!(r#"[1].get("0")"#);
+    }
+
     #[test]
     fn test_index() {
         starlark_ok!(
@@ -313,4 +442,37 @@ x.remove(2); t2 = x == [1, 3]
             LIST_REMOVE_ELEMENT_NOT_FOUND_ERROR_CODE
         );
     }
+
+    #[test]
+    fn test_sort() {
+        starlark_ok!(r#"x = [3, 1, 2]; (x.sort() == None and x == [1, 2, 3])"#);
+        starlark_ok!(r#"x = []; x.sort(reverse=True); (x == [])"#);
+        starlark_ok!(r#"x = ["b", "c", "a"]; x.sort(reverse=True); (x == ["c", "b", "a"])"#);
+        starlark_ok!(
+            r#"x = ["bb", "a", "cc", "d"]; x.sort(key=len); (x == ["a", "d", "bb", "cc"])"#
+        );
+        starlark_ok!(
+            r#"x = ["bb", "a", "cc", "d"]; x.sort(key=len, reverse=True); (
+            x == ["bb", "cc", "a", "d"])"#
+        );
+        // The key is computed once per element
+        starlark_ok!(
+            r#"
+calls = []
+def key(p):
+    calls.append(p)
+    return p[1]
+x = [("a", 2), ("b", 3), ("c", 1), ("d", 2)]
+x.sort(key=key)
+(x == [("c", 1), ("a", 2), ("d", 2), ("b", 3)] and len(calls) == 4)"#
+        );
+        starlark_fail!(r#"x = [1, "a"]; x.sort()"#);
+        starlark_fail!(
+            r#"def f(x): fail("no key")
+[2, 1].sort(key=f)"#,
+            super::super::USER_FAILURE_ERROR_CODE
+        );
+        let err = starlark_default_frozen("x = [2, 1]", "x.sort()").unwrap_err();
+        assert_eq!(Some(BORROW_MUT_ERROR_CODE.to_owned()), err.code);
+    }
 }
@@ -31,10 +31,37 @@ macro_rules! starlark_signature {
         $( starlark_signature!($signature $($rest)+) )?
     };
     ($signature:ident call_stack $e:ident $(,$($rest:tt)+)?) => {
-        $( starlark_signature!($signature $($rest)+) )?;
+        $( starlark_signature!($signature $($rest)+); )?
     };
     ($signature:ident env $e:ident $(,$($rest:tt)+)?) => {
-        $( starlark_signature!($signature $($rest)+) )?;
+        $( starlark_signature!($signature $($rest)+); )?
+    };
+    // after a bare `*`, only keyword-only parameters and `**kwargs` may follow
+    ($signature:ident * $(,$($rest:tt)+)?) => {
+        $signature.push_star();
+        $( starlark_signature!(@keyword_only $signature $($rest)+) )?
+    };
+    (@keyword_only $signature:ident ** $t:ident $(: $pt:ty)?) => {
+        $signature.push_kwargs(stringify!($t));
+    };
+    (@keyword_only $signature:ident ? $t:ident $(: $pt:ty)? $(,$($rest:tt)+)?) => {
+        $signature.push_optional(stringify!($t));
+        $( starlark_signature!(@keyword_only $signature $($rest)+) )?
+    };
+    (@keyword_only $signature:ident $t:ident : $pt:ty = $e:expr $(,$($rest:tt)+)?) => {
+        $signature.push_with_default_value::<starlark_parse_param_type!(1 : $pt)>(
+            stringify!($t),
+            $e,
+        );
+        $( starlark_signature!(@keyword_only $signature $($rest)+) )?
+    };
+    (@keyword_only $signature:ident $t:ident = $e:expr $(,$($rest:tt)+)?) => {
+        $signature.push_with_default_value(stringify!($t), $e);
+        $( starlark_signature!(@keyword_only $signature $($rest)+) )?
+    };
+    (@keyword_only $signature:ident $t:ident $(: $pt:ty)? $(,$($rest:tt)+)?) => {
+        $signature.push_normal(stringify!($t));
+        $( starlark_signature!(@keyword_only $signature $($rest)+) )?
     };
     ($signature:ident * $t:ident $(: $pt:ty)? $(,$($rest:tt)+)?) => {
         $signature.push_args(stringify!($t));
@@ -45,6 +72,20 @@ macro_rules! starlark_signature {
         $( starlark_signature!($signature $($rest)+) )?
     };
 
+    // handle params with a Starlark type, which must come before Rust types
+    ($signature:ident $t:ident : $expected:literal $(,$($rest:tt)+)?) => {
+        $signature.push_typed(stringify!($t), $expected);
+        $( starlark_signature!($signature $($rest)+) )?
+    };
+    ($signature:ident ? $t:ident : $expected:literal $(,$($rest:tt)+)?) => {
+        $signature.push_typed_optional(stringify!($t), $expected);
+        $( starlark_signature!($signature $($rest)+) )?
+    };
+    ($signature:ident $t:ident : $expected:literal = $e:expr $(,$($rest:tt)+)?) => {
+        $signature.push_typed_with_default_value(stringify!($t), $expected, $e);
+        $( starlark_signature!($signature $($rest)+) )?
+    };
+
     // handle params without default value (both named and unnamed)
     ($signature:ident $t:ident $(: $pt:ty)? $(,$($rest:tt)+)?) => {
         $signature.push_normal(stringify!($t));
@@ -114,11 +155,14 @@ macro_rules! starlark_signature_extraction {
     };
     ($ctx:ident call_stack $e:ident $(,$($rest:tt)+)?) => {
         let $e = $ctx.call_stack;
-        $( starlark_signature_extraction!($ctx $($rest)+) )?;
+        $( starlark_signature_extraction!($ctx $($rest)+); )?
     };
     ($ctx:ident env $e:ident $(,$($rest:tt)+)?) => {
         let $e = $ctx.env;
-        $( starlark_signature_extraction!($ctx $($rest)+) )?;
+        $( starlark_signature_extraction!($ctx $($rest)+); )?
+    };
+    ($ctx:ident * $(,$($rest:tt)+)?) => {
+        $( starlark_signature_extraction!($ctx $($rest)+) )?
     };
     ($ctx:ident * $t:ident $(: $pt:ty)? $(,$($rest:tt)+)?) => {
         #[allow(unused_mut)]
@@ -133,6 +177,18 @@ macro_rules! starlark_signature_extraction {
         $( starlark_signature_extraction!($ctx $($rest)+) )?
     };
 
+    ($ctx:ident ? $t:ident : $expected:literal $(,$($rest:tt)+)?) => {
+        #[allow(unused_mut)]
+        let mut $t: starlark_parse_param_type!(?) =
+            $ctx.args.next_arg()?.into_optional(stringify!($t))?;
+        $( starlark_signature_extraction!($ctx $($rest)+) )?
+    };
+    ($ctx:ident $t:ident : $expected:literal $(= $e:expr)? $(,$($rest:tt)+)?) => {
+        #[allow(unused_mut)]
+        let mut $t: starlark_parse_param_type!(1) =
+            $ctx.args.next_arg()?.into_normal(stringify!($t))?;
+        $( starlark_signature_extraction!($ctx $($rest)+) )?
+    };
     ($ctx:ident ? $t:ident $(: $pt:ty)? $(,$($rest:tt)+)?) => {
         #[allow(unused_mut)]
         let mut $t: starlark_parse_param_type!(? $(: $pt)?) =
@@ -147,16 +203,44 @@ macro_rules! starlark_signature_extraction {
     };
 }
 
+#[doc(hidden)]
+#[macro_export]
+macro_rules! starlark_return_type {
+    () => {
+        $crate::values::Value
+    };
+    ($ret:ty) => {
+        $ret
+    };
+}
+
+/// Convert the result of a function declared by `starlark_fun` to a `ValueResult` when it is
+/// called.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! starlark_native_function {
+    ($fn:ident, $name:expr, $signature:expr) => {
+        $crate::values::function::NativeFunction::new(
+            $name,
+            |call_stack, env, args| {
+                $crate::values::IntoValueResult::into_value_result($fn(call_stack, env, args))
+            },
+            $signature,
+        )
+    };
+}
+
 #[doc(hidden)]
 #[macro_export]
 macro_rules! starlark_fun {
-    ($(#[$attr:meta])* $fn:ident ( $($signature:tt)* ) { $($content:tt)* } $($($rest:tt)+)?) => {
+    ($(#[$attr:meta])* $fn:ident ( $($signature:tt)* ) $(-> $ret:ty)? { $($content:tt)* }
+            $($($rest:tt)+)?) => {
         $(#[$attr])*
         fn $fn(
             call_stack: &mut $crate::eval::call_stack::CallStack,
             env: &$crate::environment::TypeValues,
             args: $crate::values::function::ParameterParser,
-        ) -> $crate::values::ValueResult {
+        ) -> Result<starlark_return_type!($($ret)?), $crate::values::error::ValueError> {
             let mut ctx = $crate::stdlib::macros::SignatureExtractionContext {
                 call_stack,
                 env,
@@ -170,14 +254,14 @@ macro_rules! starlark_fun {
             $($rest)+
         })?
     };
-    ($(#[$attr:meta])* $ty:ident . $fn:ident ( $($signature:tt)* ) { $($content:tt)* }
-            $($($rest:tt)+)?) => {
+    ($(#[$attr:meta])* $ty:ident . $fn:ident ( $($signature:tt)* ) $(-> $ret:ty)?
+            { $($content:tt)* } $($($rest:tt)+)?) => {
         $(#[$attr])*
         fn $fn(
             call_stack: &mut $crate::eval::call_stack::CallStack,
             env: &$crate::environment::TypeValues,
             args: $crate::values::function::ParameterParser,
-        ) -> $crate::values::ValueResult {
+        ) -> Result<starlark_return_type!($($ret)?), $crate::values::error::ValueError> {
             let mut ctx = $crate::stdlib::macros::SignatureExtractionContext {
                 call_stack,
                 env,
@@ -196,27 +280,27 @@ macro_rules! starlark_fun {
 #[doc(hidden)]
 #[macro_export]
 macro_rules! starlark_signatures {
-    ($env:expr, $type_values:expr, $(#[$attr:meta])* $name:ident ( $($signature:tt)* ) { $($content:tt)* }
-            $($($rest:tt)+)?) => {
+    ($env:expr, $type_values:expr, $(#[$attr:meta])* $name:ident ( $($signature:tt)* )
+            $(-> $ret:ty)? { $($content:tt)* } $($($rest:tt)+)?) => {
         {
             let name = stringify!($name).trim_matches('_');
             #[allow(unused_mut)]
             let mut signature = $crate::stdlib::macros::signature::SignatureBuilder::default();
             starlark_signature!(signature $($signature)*);
-            $env.set(name, $crate::values::function::NativeFunction::new(name.to_owned(), $name, signature.build())).unwrap();
+            $env.set(name, starlark_native_function!($name, name.to_owned(), signature.build())).unwrap();
         }
         $(starlark_signatures!{ $env, $type_values,
             $($rest)+
         })?
     };
-    ($env:expr, $type_values:expr, $(#[$attr:meta])* $ty:ident . $name:ident ( $($signature:tt)* ) { $($content:tt)* }
-            $($($rest:tt)+)?) => {
+    ($env:expr, $type_values:expr, $(#[$attr:meta])* $ty:ident . $name:ident ( $($signature:tt)* )
+            $(-> $ret:ty)? { $($content:tt)* } $($($rest:tt)+)?) => {
         {
             let name = stringify!($name).trim_matches('_');
             let mut signature = $crate::stdlib::macros::signature::SignatureBuilder::default();
             starlark_signature!(signature $($signature)*);
             $type_values.add_type_value(stringify!($ty), name,
-                $crate::values::function::NativeFunction::new(name.to_owned(), $name, signature.build()));
+                starlark_native_function!($name, name.to_owned(), signature.build()));
         }
         $(starlark_signatures!{ $env, $type_values,
             $($rest)+
@@ -259,6 +343,13 @@ macro_rules! starlark_signatures {
 /// # Ok(Value::new(true))
 ///     }
 ///
+///     // Parameters after a bare `*` are keyword-only: they can only be passed by name,
+///     // e.g. `my_fmt(1, sep = "-")`.
+///     my_fmt(x, *, sep = ",", ?width) {
+///       // ...
+/// # Ok(Value::new(true))
+///     }
+///
 ///     // Functions can optionally specify parameter types after colon.
 ///     // Parameter can be any type which implements `TryParamConvertFromValue`.
 ///     // When parameter type is not specified, it is defaulted to `Value`
@@ -268,6 +359,14 @@ macro_rules! starlark_signatures {
 ///         Ok(Value::new(x * x))
 ///     }
 ///
+///     // A string literal after the colon is instead the Starlark type of the parameter, as
+///     // returned by `type()`: the parameter is a `Value`, checked when the function is called.
+///     // Typed parameters can be optional or have a default value, but not be keyword-only.
+///     repeat(s: "string", ?sep: "string", n: "int" = 2) {
+///       // ...
+/// # Ok(Value::new(true))
+///     }
+///
 ///     // It is also possible to capture the call stack with
 ///     // `call_stack name` (type `Vec<String>`). For example a `dbg` function that print the
 ///     // the call stack:
@@ -293,6 +392,14 @@ macro_rules! starlark_signatures {
 /// The module would declare a function `my_starlark_module` that can be called to add the
 /// corresponding functions to an environment.
 ///
+/// A leading `_` is dropped from a parameter name followed by a keyword, so a parameter can be
+/// named after a Rust keyword (`_type` declares `type`) or a Starlark keyword (`_in` declares
+/// `in`).
+/// A parameter named after a Starlark keyword cannot be passed as `f(in = 1)`, which is a
+/// syntax error, but can be passed with a dictionary splat: `f(**{"in": 1})`. Likewise, the
+/// `**kwargs` dictionary receives the keys of a splatted dictionary untouched, even the keys
+/// which are keywords or not identifiers at all, and they can be read from it by string.
+///
 /// ```
 /// # #[macro_use] extern crate starlark;
 /// # use starlark::values::*;
@@ -311,6 +418,32 @@ macro_rules! starlark_signatures {
 /// # }
 /// ```
 ///
+/// A function returns a [`ValueResult`](crate::values::ValueResult) unless it declares another
+/// result type after an arrow: a pair or a triple, which is returned to Starlark as a tuple (see
+/// [`IntoValueResult`](crate::values::IntoValueResult)):
+///
+/// ```
+/// # #[macro_use] extern crate starlark;
+/// # use starlark::values::*;
+/// # use starlark::values::error::ValueError;
+/// # use starlark::environment::Environment;
+/// # use starlark::environment::TypeValues;
+/// starlark_module!{ my_starlark_module =>
+///     divmod(a: i64, b: i64) -> (i64, i64) {
+///         if b <= 0 {
+///             return Err(ValueError::DivisionByZero);
+///         }
+///         Ok((a / b, a % b))
+///     }
+/// }
+/// # fn main() {
+/// #    let mut env = Environment::new("test");
+/// #    let mut type_values = TypeValues::default();
+/// #    my_starlark_module(&mut env, &mut type_values);
+/// #    assert_eq!(env.get("divmod").unwrap().get_type(), "function");
+/// # }
+/// ```
+///
 /// Additionally function might be declared for a type by prefixing them by `type.`, e.g the
 /// definition of a `hello` function for the `string` type would look like:
 ///
@@ -469,8 +602,13 @@ macro_rules! convert_indices {
 mod tests {
     use crate::environment::Environment;
     use crate::environment::TypeValues;
+    use crate::eval::noload;
+    use crate::syntax::dialect::Dialect;
+    use crate::values::error::ValueError;
     use crate::values::none::NoneType;
     use crate::values::Value;
+    use codemap::CodeMap;
+    use std::sync::{Arc, Mutex};
 
     #[test]
     fn no_arg() {
@@ -484,4 +622,199 @@ mod tests {
         global(&mut env, &mut TypeValues::default());
         env.get("nop").unwrap();
     }
+
+    #[test]
+    fn keyword_named_parameters() {
+        starlark_module! { global =>
+            option(key: String, **kwargs) {
+                Ok(kwargs.get(&key).cloned().unwrap_or_else(|| Value::new(NoneType::None)))
+            }
+
+            contains(_in, _not = 0) {
+                Ok(Value::new(_in.to_int()? - _not.to_int()?))
+            }
+        }
+
+        let mut env = Environment::new("root");
+        let mut type_values = TypeValues::default();
+        global(&mut env, &mut type_values);
+        let mut env = env.freeze().child("test");
+        let mut eval = |code| {
+            noload::eval(
+                &Arc::new(Mutex::new(CodeMap::new())),
+                "test.bzl",
+                code,
+                Dialect::Bzl,
+                &mut env,
+                &type_values,
+            )
+            .unwrap()
+            .to_repr()
+        };
+        assert_eq!("1", eval("option('in', **{'in': 1, 'not': 2})"));
+        assert_eq!("2", eval("option('not', **{'in': 1, 'not': 2})"));
+        assert_eq!("3", eval("option('a-b', **{'a-b': 3})"));
+        assert_eq!("5", eval("contains(**{'in': 7, 'not': 2})"));
+        assert_eq!("7", eval("contains(7)"));
+    }
+
+    #[test]
+    fn typed_kwargs_errors_name_the_key() {
+        starlark_module! { global =>
+            style(**kwargs: linked_hash_map::LinkedHashMap<String, String>) {
+                Ok(Value::new(kwargs.len() as i64))
+            }
+        }
+
+        let mut env = Environment::new("root");
+        let mut type_values = TypeValues::default();
+        global(&mut env, &mut type_values);
+        let mut env = env.freeze().child("test");
+        let mut eval = |code| {
+            noload::eval(
+                &Arc::new(Mutex::new(CodeMap::new())),
+                "test.bzl",
+                code,
+                Dialect::Bzl,
+                &mut env,
+                &type_values,
+            )
+        };
+        assert_eq!("2", eval("style(color='red', size='1')").unwrap().to_repr());
+        // The first incorrect entry in argument order is reported
+        let err = eval("style(size='1', color=1, shade=[])").unwrap_err();
+        assert_eq!(
+            "Type of parameters kwargs['color'] mismatch, got int",
+            err.message
+        );
+        let err = eval("style(**{'shade': [], 'color': 1})").unwrap_err();
+        assert_eq!(
+            "Type of parameters kwargs['shade'] mismatch, got list",
+            err.message
+        );
+    }
+
+    #[test]
+    fn tuple_results() {
+        starlark_module! { global =>
+            split_at(s: String, i: i64) -> (String, String) {
+                let (head, tail) = s.split_at(i as usize);
+                Ok((head.to_owned(), tail.to_owned()))
+            }
+
+            triple(x: i64) -> (i64, i64, i64) {
+                if x < 0 {
+                    return Err(ValueError::IncorrectParameterType);
+                }
+                Ok((x, x + 1, x + 2))
+            }
+        }
+
+        let mut env = Environment::new("root");
+        let mut type_values = TypeValues::default();
+        global(&mut env, &mut type_values);
+        let mut env = env.freeze().child("test");
+        let mut eval = |code| {
+            noload::eval(
+                &Arc::new(Mutex::new(CodeMap::new())),
+                "test.bzl",
+                code,
+                Dialect::Bzl,
+                &mut env,
+                &type_values,
+            )
+        };
+        assert_eq!(
+            "(\"ab\", \"cd\")",
+            eval("split_at('abcd', 2)").unwrap().to_repr()
+        );
+        assert_eq!(
+            "(\"cd\", \"ab\")",
+            eval("head, tail = split_at('abcd', 2)\n(tail, head)")
+                .unwrap()
+                .to_repr()
+        );
+        assert_eq!(
+            "True",
+            eval("a, b, c = triple(1)\n[a, b, c] == [1, 2, 3]")
+                .unwrap()
+                .to_repr()
+        );
+        assert!(eval("triple(-1)").is_err());
+    }
+
+    #[test]
+    fn keyword_only_parameters() {
+        starlark_module! { global =>
+            join(
+                a: String, *, sep: String = ",".to_owned(), ?suffix: Option<String>, end: String
+            ) {
+                Ok(Value::new(format!("{}{}{}{}", a, sep, suffix.unwrap_or_default(), end)))
+            }
+        }
+
+        let mut env = Environment::new("root");
+        let mut type_values = TypeValues::default();
+        global(&mut env, &mut type_values);
+        let mut env = env.freeze().child("test");
+        let mut eval = |code| {
+            noload::eval(
+                &Arc::new(Mutex::new(CodeMap::new())),
+                "test.bzl",
+                code,
+                Dialect::Bzl,
+                &mut env,
+                &type_values,
+            )
+        };
+        assert_eq!("\"a,!\"", eval("join('a', end='!')").unwrap().to_repr());
+        assert_eq!(
+            "\"a-x!\"",
+            eval("join(end='!', suffix='x', sep='-', a='a')")
+                .unwrap()
+                .to_repr()
+        );
+        assert_eq!(
+            "<native function join>(a, *, sep = \",\", ?suffix, end)",
+            eval("join").unwrap().to_repr()
+        );
+        let err = eval("join('a', '-', end='!')").unwrap_err();
+        assert_eq!(Some("CF06".to_owned()), err.code);
+        assert!(err.message.contains("parameter sep is keyword-only"));
+        assert_eq!(Some("CF00".to_owned()), eval("join('a')").unwrap_err().code);
+    }
+
+    #[test]
+    fn typed_parameters() {
+        starlark_module! { global =>
+            repeat(s: "string", ?sep: "string", n: "int" = 2) {
+                let sep = sep.map(|s| s.to_str()).unwrap_or_default();
+                Ok(Value::new(vec![s.to_str(); n.to_int()? as usize].join(&sep)))
+            }
+        }
+
+        let mut env = Environment::new("root");
+        let mut type_values = TypeValues::default();
+        global(&mut env, &mut type_values);
+        let mut env = env.freeze().child("test");
+        let mut eval = |code| {
+            noload::eval(
+                &Arc::new(Mutex::new(CodeMap::new())),
+                "test.bzl",
+                code,
+                Dialect::Bzl,
+                &mut env,
+                &type_values,
+            )
+        };
+        assert_eq!("\"aa\"", eval("repeat('a')").unwrap().to_repr());
+        assert_eq!("\"a-a-a\"", eval("repeat('a', '-', 3)").unwrap().to_repr());
+        assert_eq!(
+            "<native function repeat>(s: string, ?sep: string, n: int = 2)",
+            eval("repeat").unwrap().to_repr()
+        );
+        for code in &["repeat(1)", "repeat('a', sep=1)", "repeat('a', n='3')"] {
+            assert_eq!(Some("CF09".to_owned()), eval(code).unwrap_err().code);
+        }
+    }
 }
@@ -20,44 +20,106 @@ use crate::values::function::FunctionParameter;
 use crate::values::function::FunctionSignature;
 use crate::values::Value;
 
+/// Keywords which cannot be used as a Rust identifier or a Starlark parameter name.
+const KEYWORDS: &[&str] = &[
+    "and", "as", "assert", "async", "await", "break", "class", "const", "continue", "crate", "def",
+    "del", "dyn", "elif", "else", "enum", "except", "extern", "false", "finally", "fn", "for",
+    "from", "global", "if", "impl", "import", "in", "is", "lambda", "let", "load", "loop", "match",
+    "mod", "move", "mut", "nonlocal", "not", "or", "pass", "pub", "raise", "ref", "return", "self",
+    "static", "struct", "super", "trait", "true", "try", "type", "unsafe", "use", "where", "while",
+    "with", "yield",
+];
+
+/// The Starlark name of the parameter declared as `name` in a macro: a leading `_` is dropped
+/// before a keyword, so that `_fn` declares the parameter `fn` and `_in` the parameter `in`.
+fn param_name(name: &str) -> String {
+    match name.strip_prefix('_') {
+        Some(keyword) if KEYWORDS.contains(&keyword) => keyword.to_owned(),
+        _ => name.to_owned(),
+    }
+}
+
 /// Signature builder utility used in macros. Do not use directly.
 #[derive(Default)]
 pub struct SignatureBuilder {
     params: Vec<FunctionParameter>,
     positional_count: Option<usize>,
+    /// Set after a bare `*`: the following parameters are keyword-only
+    keyword_only: bool,
 }
 
 impl SignatureBuilder {
     pub fn push_normal(&mut self, name: &str) {
-        self.params.push(FunctionParameter::Normal(name.to_owned()));
+        self.params.push(if self.keyword_only {
+            FunctionParameter::KWOnlyNormal(param_name(name))
+        } else {
+            FunctionParameter::Normal(param_name(name))
+        });
     }
 
     pub fn push_optional(&mut self, name: &str) {
-        self.params
-            .push(FunctionParameter::Optional(name.to_owned()));
+        self.params.push(if self.keyword_only {
+            FunctionParameter::KWOnlyOptional(param_name(name))
+        } else {
+            FunctionParameter::Optional(param_name(name))
+        });
     }
 
     pub fn push_with_default_value<V: Into<Value>>(&mut self, name: &str, default_value: V) {
-        self.params.push(FunctionParameter::WithDefaultValue(
-            name.to_owned(),
+        self.params.push(if self.keyword_only {
+            FunctionParameter::KWOnlyWithDefaultValue(param_name(name), default_value.into())
+        } else {
+            FunctionParameter::WithDefaultValue(param_name(name), default_value.into())
+        });
+    }
+
+    /// Typed parameters are never keyword-only: the macros do not accept them after a `*`.
+    pub fn push_typed(&mut self, name: &str, expected: &str) {
+        self.params.push(FunctionParameter::Typed(
+            param_name(name),
+            expected.to_owned(),
+        ));
+    }
+
+    pub fn push_typed_optional(&mut self, name: &str, expected: &str) {
+        self.params.push(FunctionParameter::TypedOptional(
+            param_name(name),
+            expected.to_owned(),
+        ));
+    }
+
+    pub fn push_typed_with_default_value<V: Into<Value>>(
+        &mut self,
+        name: &str,
+        expected: &str,
+        default_value: V,
+    ) {
+        self.params.push(FunctionParameter::TypedWithDefaultValue(
+            param_name(name),
+            expected.to_owned(),
             default_value.into(),
         ));
     }
 
     pub fn push_kwargs(&mut self, name: &str) {
         self.params
-            .push(FunctionParameter::KWArgsDict(name.to_owned()));
+            .push(FunctionParameter::KWArgsDict(param_name(name)));
     }
 
     pub fn push_args(&mut self, name: &str) {
         self.params
-            .push(FunctionParameter::ArgsArray(name.to_owned()));
+            .push(FunctionParameter::ArgsArray(param_name(name)));
     }
     pub fn push_slash(&mut self) {
         assert!(self.positional_count.is_none());
         self.positional_count = Some(self.params.len());
     }
 
+    /// The following parameters are keyword-only.
+    pub fn push_star(&mut self) {
+        self.keyword_only = true;
+    }
+
     pub fn build(self) -> FunctionSignature {
         FunctionSignature::new(self.params, self.positional_count.unwrap_or(0))
     }
@@ -16,20 +16,34 @@
 use codemap::CodeMap;
 use codemap_diagnostic::{ColorConfig, Diagnostic, Emitter};
 use linked_hash_map::LinkedHashMap;
+use sha2::{Digest, Sha256};
 use std;
 use std::cmp::Ordering;
+use std::convert::TryFrom;
 use std::error::Error;
 use std::num::NonZeroI64;
 use std::sync;
 
 use crate::environment::{Environment, TypeValues};
+use crate::eval::call_stack::CallStack;
 use crate::eval::noload::eval;
+use crate::eval::{DEBUGGER_ABORT_ERROR_CODE, RECURSION_ERROR_CODE};
 use crate::linked_hash_set;
+use crate::linked_hash_set::set_impl::LinkedHashSet;
 use crate::syntax::dialect::Dialect;
+use crate::syntax::errors::SyntaxError;
+use crate::values::bigint::BigInt;
 use crate::values::dict::Dictionary;
+use crate::values::error::{
+    check_requested_length, RuntimeError, ValueError, INCORRECT_PARAMETER_TYPE_ERROR_CODE,
+    TOO_MANY_RECURSION_LEVEL_ERROR_CODE,
+};
 use crate::values::function::WrappedMethod;
+use crate::values::hashed_value::HashedValue;
+use crate::values::lazy_seq::LazySeq;
 use crate::values::none::NoneType;
 use crate::values::range::Range;
+use crate::values::tuple::NamedTupleType;
 use crate::values::*;
 
 // Errors -- CR = Critical Runtime
@@ -39,11 +53,29 @@ const INT_CONVERSION_FAILED_ERROR_CODE: &str = "CR03";
 const ORD_EXPECT_ONE_CHAR_ERROR_CODE: &str = "CR04";
 const EMPTY_ITERABLE_ERROR_CODE: &str = "CR05";
 const NUL_RANGE_STEP_ERROR_CODE: &str = "CR06";
+const GROUP_BY_KEY_NOT_HASHABLE_ERROR_CODE: &str = "CR07";
+const NAMEDTUPLE_INVALID_NAME_ERROR_CODE: &str = "CR08";
+const ZIP_DICT_LENGTH_MISMATCH_ERROR_CODE: &str = "CR09";
+const DEEP_MERGE_UNKNOWN_LIST_POLICY_ERROR_CODE: &str = "CR10";
+const JSON_ENCODE_ERROR_CODE: &str = "CR11";
+const UNIQUE_ELEMENT_NOT_HASHABLE_ERROR_CODE: &str = "CR12";
+const NEGATIVE_LENGTH_ERROR_CODE: &str = "CR13";
+const FMT_INVALID_ARGUMENT_ERROR_CODE: &str = "CR14";
+const SCHEMA_MISMATCH_ERROR_CODE: &str = "CR15";
+const INVALID_SIZE_ERROR_CODE: &str = "CR16";
+const FLATTEN_DICT_KEY_COLLISION_ERROR_CODE: &str = "CR17";
+const FLATTEN_DICT_INVALID_OPTION_ERROR_CODE: &str = "CR18";
+const ZIP_LENGTH_MISMATCH_ERROR_CODE: &str = "CR19";
 const USER_FAILURE_ERROR_CODE: &str = "CR99";
 
 #[macro_use]
 pub mod macros;
+pub mod bytes;
 pub mod dict;
+pub mod diff;
+pub mod fmt;
+pub mod int;
+pub mod json;
 pub mod list;
 pub mod string;
 pub mod structs;
@@ -67,6 +99,47 @@ starlark_module! {global_functions =>
         )
     }
 
+    /// accumulate: running results of a binary function
+    ///
+    /// `accumulate(x)` returns a list of the running sums of the elements of the iterable `x`.
+    /// The optional parameter `fn` replaces the addition by a function of two arguments, the
+    /// accumulated value and the next element. If `initial` is provided, it is used as the first
+    /// accumulated value and the result starts with it.
+    ///
+    /// Examples:
+    ///
+    /// ```
+    /// # use starlark::stdlib::starlark_default;
+    /// # assert!(starlark_default("(
+    /// accumulate([1, 2, 3, 4]) == [1, 3, 6, 10]
+    /// # )").unwrap());
+    /// # assert!(starlark_default("(
+    /// accumulate([1, 2, 3], initial=10) == [10, 11, 13, 16]
+    /// # )").unwrap());
+    /// # assert!(starlark_default("(
+    /// accumulate([3, 1, 4], fn=max) == [3, 3, 4]
+    /// # )").unwrap());
+    /// ```
+    accumulate(call_stack cs, env e, x, /, ?_fn, ?initial) {
+        let mut result = Vec::new();
+        let mut acc = initial;
+        if let Some(ref v) = acc {
+            result.push(v.clone());
+        }
+        for el in &x.iter()? {
+            let next = match acc {
+                None => el,
+                Some(prev) => match _fn {
+                    None => prev.add(el)?,
+                    Some(ref f) => f.call(cs, e, vec![prev, el], LinkedHashMap::new(), None, None)?,
+                },
+            };
+            result.push(next.clone());
+            acc = Some(next);
+        }
+        Ok(Value::from(result))
+    }
+
     /// [any](
     /// https://github.com/google/skylark/blob/a0e5de7e63b47e716cca7226662a4c95d47bf873/doc/spec.md#any
     /// ): returns true if any value in the iterable object have a truth value of true.
@@ -133,6 +206,125 @@ starlark_module! {global_functions =>
         Ok(Value::new(true))
     }
 
+    /// approx_equal: structural equality with a tolerance on numbers
+    ///
+    /// `approx_equal(a, b, tol=0)` returns `True` if `a` and `b` have the same structure: lists
+    /// and tuples of the same length whose elements are approximately equal, and dictionaries
+    /// with the same keys, in any order, whose values are approximately equal. Numbers are
+    /// approximately equal if they differ by at most `tol`, all other values must be equal.
+    /// Integers are the only numbers of this implementation. It is an error if `tol` is
+    /// negative.
+    ///
+    /// Examples:
+    ///
+    /// ```
+    /// # use starlark::stdlib::starlark_default;
+    /// # assert!(starlark_default(r#"(
+    /// approx_equal({"a": [100, (5, "x")]}, {"a": [101, (4, "x")]}, tol=1)
+    /// # and
+    /// not approx_equal([100], [102], tol=1)
+    /// # )"#).unwrap());
+    /// ```
+    approx_equal(a, b, /, tol: i64 = 0) {
+        if tol < 0 {
+            starlark_err!(
+                INCORRECT_PARAMETER_TYPE_ERROR_CODE,
+                format!("approx_equal() tolerance must be non-negative, got {}", tol),
+                "Negative tolerance".to_owned()
+            );
+        }
+        Ok(Value::new(approx_equal_values(&a, &b, tol)?))
+    }
+
+    /// argmax: index of the maximum of an iterable
+    ///
+    /// `argmax(x)` returns the index of the greatest element of the iterable `x`. If `key` is
+    /// given, it is called once per element and elements are compared by the results. Ties are
+    /// broken by the first occurrence. It is an error if `x` is empty.
+    ///
+    /// Examples:
+    ///
+    /// ```
+    /// # use starlark::stdlib::starlark_default;
+    /// # assert!(starlark_default(r#"(
+    /// argmax([3, 1, 4, 1, 4]) == 2
+    /// # and
+    /// argmax(["bb", "a", "cc"], key=len) == 0
+    /// # )"#).unwrap());
+    /// ```
+    argmax(call_stack cs, env e, x, /, ?key) {
+        let (i, _) = find_extremum(cs, e, "argmax", &x, key, Ordering::Greater)?;
+        Ok(Value::new(i))
+    }
+
+    /// argmin: index of the minimum of an iterable
+    ///
+    /// `argmin(x)` returns the index of the least element of the iterable `x`. If `key` is
+    /// given, it is called once per element and elements are compared by the results. Ties are
+    /// broken by the first occurrence. It is an error if `x` is empty.
+    ///
+    /// Examples:
+    ///
+    /// ```
+    /// # use starlark::stdlib::starlark_default;
+    /// # assert!(starlark_default(r#"(
+    /// argmin([3, 1, 4, 1, 4]) == 1
+    /// # and
+    /// argmin(["bb", "a", "c"], key=len) == 1
+    /// # )"#).unwrap());
+    /// ```
+    argmin(call_stack cs, env e, x, /, ?key) {
+        let (i, _) = find_extremum(cs, e, "argmin", &x, key, Ordering::Less)?;
+        Ok(Value::new(i))
+    }
+
+    /// attrdict: create a dictionary whose string keys are also attributes
+    ///
+    /// `attrdict(d)` returns a new dictionary with the items of the dictionary `d` (or no
+    /// items if `d` is omitted) and of the keyword arguments, where `x.name` reads
+    /// `x["name"]` and `x.name = value` sets it, if `x` is not frozen. Reading an attribute
+    /// which is not a key fails as for any other missing attribute. Methods of dictionaries
+    /// take precedence over keys of the same name, e.g. `x.keys` is always the method.
+    ///
+    /// Otherwise the result is a regular dictionary: `type(x) == "dict"` and it is equal to
+    /// dictionaries with the same items. Nested dictionaries are not converted, and
+    /// operations returning new dictionaries, like `dict(x)`, return regular dictionaries.
+    ///
+    /// Examples:
+    ///
+    /// ```
+    /// # use starlark::stdlib::starlark_default;
+    /// # assert!(starlark_default(r#"
+    /// config = attrdict({"name": "app"}, port=80)
+    /// config.port = 8080
+    /// (config.name == "app" and config["port"] == 8080 and config == {"name": "app", "port": 8080})
+    /// # "#).unwrap());
+    /// ```
+    attrdict(?d, /, **kwargs) {
+        let mut map = Dictionary::new_with_attribute_access();
+        if let Some(d) = d {
+            match d.downcast_ref::<Dictionary>() {
+                Some(d) => {
+                    for (k, v) in d.items() {
+                        map.insert(k, v)?;
+                    }
+                }
+                None => starlark_err!(
+                    INCORRECT_PARAMETER_TYPE_ERROR_CODE,
+                    format!(
+                        "attrdict() argument must be a dict, got a value of type '{}'",
+                        d.get_type()
+                    ),
+                    "Not a dict".to_owned()
+                ),
+            }
+        }
+        for (k, v) in kwargs {
+            map.insert(Value::from(k), v)?;
+        }
+        Ok(Value::new(map))
+    }
+
     /// [bool](
     /// https://github.com/google/skylark/blob/a0e5de7e63b47e716cca7226662a4c95d47bf873/doc/spec.md#bool
     /// ): returns the truth value of any starlark value.
@@ -167,6 +359,80 @@ starlark_module! {global_functions =>
         Ok(Value::new(x.to_bool()))
     }
 
+    /// catch: call a function, capturing its failure
+    ///
+    /// `catch(f)` calls `f()` and returns `(True, result)` with its result, or
+    /// `(False, message)` if the call failed, e.g. with `fail()` or a type error, where
+    /// `message` is the first line of the error message. Errors aborting the evaluation,
+    /// i.e. recursion and debugger aborts, are not captured.
+    ///
+    /// Examples:
+    ///
+    /// ```
+    /// # use starlark::stdlib::starlark_default;
+    /// # assert!(starlark_default(r#"
+    /// def ok(): return 1
+    /// def ko(): fail("bad value")
+    /// (catch(ok) == (True, 1) and catch(ko) == (False, "fail(): bad value"))
+    /// # "#).unwrap());
+    /// ```
+    catch(call_stack cs, env e, f, /) {
+        let depth = cs.len();
+        match f.call(cs, e, Vec::new(), LinkedHashMap::new(), None, None) {
+            Ok(v) => Ok(Value::from((true, v))),
+            Err(err) => {
+                cs.truncate(depth);
+                let aborts = match err {
+                    ValueError::TooManyRecursionLevel => true,
+                    ValueError::DiagnosedError(ref d) => match d.code {
+                        Some(ref c) => [
+                            RECURSION_ERROR_CODE,
+                            DEBUGGER_ABORT_ERROR_CODE,
+                            TOO_MANY_RECURSION_LEVEL_ERROR_CODE,
+                        ]
+                        .contains(&c.as_str()),
+                        None => false,
+                    },
+                    _ => false,
+                };
+                // The span of the call to `catch` is irrelevant, only the message is kept. There
+                // is none if `catch` is called from Rust rather than Starlark, the error is then
+                // returned unchanged.
+                let span = match cs.call_span() {
+                    Some(span) if !aborts => span,
+                    _ => return Err(err),
+                };
+                let message = err.to_diagnostic(span).message;
+                let first_line = message.lines().next().unwrap_or_default().to_owned();
+                Ok(Value::from((false, first_line)))
+            }
+        }
+    }
+
+    /// chain: concatenate iterables
+    ///
+    /// `chain(*iterables)` returns a new list containing the elements of each of its arguments
+    /// in order.
+    ///
+    /// Examples:
+    ///
+    /// ```
+    /// # use starlark::stdlib::starlark_default;
+    /// # assert!(starlark_default("(
+    /// chain([1, 2], (3,), range(4, 6)) == [1, 2, 3, 4, 5]
+    /// # )").unwrap());
+    /// # assert!(starlark_default("(
+    /// chain() == []
+    /// # )").unwrap());
+    /// ```
+    chain(*args) {
+        let mut result = Vec::new();
+        for arg in args {
+            result.extend(&arg.iter()?);
+        }
+        Ok(Value::from(result))
+    }
+
     /// [chr](
     /// https://github.com/google/skylark/blob/a0e5de7e63b47e716cca7226662a4c95d47bf873/doc/spec.md#bool
     /// ): returns a string encoding a codepoint.
@@ -202,6 +468,114 @@ starlark_module! {global_functions =>
         }
     }
 
+    /// check_schema: check that a value has the expected shape
+    ///
+    /// `check_schema(value, schema)` returns `None` if `value` matches `schema` and fails with
+    /// the path of the first mismatch otherwise. A schema is either:
+    ///
+    /// * a type name as returned by `type()`, e.g. `"int"`, which matches any value of that
+    ///   type, or `"any"` which matches any value;
+    /// * a dictionary, which matches a dictionary with exactly the same keys whose values
+    ///   match the values of the schema;
+    /// * a list of one schema, which matches a list whose elements all match that schema.
+    ///
+    /// Examples:
+    ///
+    /// ```
+    /// # use starlark::stdlib::starlark_default;
+    /// # assert!(starlark_default(r#"(
+    /// check_schema({"name": "x", "deps": ["a"]}, {"name": "string", "deps": ["string"]}) == None
+    /// # )"#).unwrap());
+    /// ```
+    ///
+    /// `check_schema({"deps": ["a", "b", 3]}, {"deps": ["string"]})` fails with
+    /// `deps[2]: expected string, got int`.
+    check_schema(value, schema, /) {
+        check_schema_value(&value, &schema, "")?;
+        Ok(Value::new(NoneType::None))
+    }
+
+    /// checksum: returns a stable digest of a value.
+    ///
+    /// `checksum(x)` returns the hex-encoded SHA-256 digest of the canonical serialization of
+    /// `x`. Values which compare equal have the same digest, across runs and regardless of the
+    /// insertion order of dictionaries. `x` may only contain `None`, booleans, integers, strings,
+    /// lists, tuples and dictionaries; other values (e.g. functions) are rejected.
+    ///
+    /// Examples:
+    ///
+    /// ```
+    /// # use starlark::stdlib::starlark_default;
+    /// # assert!(starlark_default("(
+    /// checksum({'a': 1, 'b': [2]}) == checksum({'b': [2], 'a': 1})
+    /// # )").unwrap());
+    /// # assert!(starlark_default("(
+    /// checksum([1, 2]) != checksum((1, 2))
+    /// # )").unwrap());
+    /// # assert!(starlark_default("(
+    /// len(checksum('')) == 64
+    /// # )").unwrap());
+    /// ```
+    checksum(a, /) {
+        let digest = Sha256::digest(&canonical::canonical_bytes(&a)?);
+        let hex: String = digest.iter().map(|b| format!("{:02x}", b)).collect();
+        Ok(Value::new(hex))
+    }
+
+    /// deep_merge: recursively merge two dictionaries
+    ///
+    /// `deep_merge(base, override)` returns a new dictionary with the keys of `base` and
+    /// `override`. When a key is in both and both values are dictionaries, they are merged
+    /// recursively, otherwise the value of `override` wins, including when only one of the
+    /// values is a dictionary.
+    ///
+    /// The `lists` parameter selects how two lists are merged: `"replace"` (the default) keeps
+    /// the list of `override` and `"concat"` concatenates the list of `base` with the list of
+    /// `override`.
+    ///
+    /// Neither argument is modified: the dictionaries of the result, including the nested
+    /// ones, are new dictionaries.
+    ///
+    /// Examples:
+    ///
+    /// ```
+    /// # use starlark::stdlib::starlark_default;
+    /// # assert!(starlark_default(r#"(
+    /// deep_merge({"a": {"x": 1, "y": 2}, "b": 3}, {"a": {"y": 4}}) == {"a": {"x": 1, "y": 4}, "b": 3}
+    /// # and
+    /// deep_merge({"a": [1], "b": {"x": 1}}, {"a": [2], "b": 5}) == {"a": [2], "b": 5}
+    /// # and
+    /// deep_merge({"a": [1]}, {"a": [2]}, lists = "concat") == {"a": [1, 2]}
+    /// # )"#).unwrap());
+    /// ```
+    deep_merge(base, override_, /, lists: String = "replace".to_owned()) {
+        let concat_lists = match lists.as_str() {
+            "replace" => false,
+            "concat" => true,
+            _ => starlark_err!(
+                DEEP_MERGE_UNKNOWN_LIST_POLICY_ERROR_CODE,
+                format!(
+                    "deep_merge() lists must be \"replace\" or \"concat\", got {:?}",
+                    lists
+                ),
+                "Unknown list merge policy".to_owned()
+            ),
+        };
+        for (name, value) in &[("base", &base), ("override", &override_)] {
+            if value.get_type() != "dict" {
+                starlark_err!(
+                    INCORRECT_PARAMETER_TYPE_ERROR_CODE,
+                    format!(
+                        "deep_merge() {} must be a dict, got a value of type '{}'",
+                        name,
+                        value.get_type()
+                    ),
+                    "Not a dict".to_owned()
+                );
+            }
+        }
+        deep_merge_values(Some(base), override_, concat_lists)
+    }
 
     /// [dict](
     /// https://github.com/google/skylark/blob/a0e5de7e63b47e716cca7226662a4c95d47bf873/doc/spec.md#dict
@@ -283,6 +657,34 @@ starlark_module! {global_functions =>
        Ok(map)
     }
 
+    /// defaultdict: create a dictionary with default values for missing keys
+    ///
+    /// `defaultdict(factory)` returns a new empty dictionary. When a key which is not in the
+    /// dictionary is accessed with `d[key]`, including in an augmented assignment like
+    /// `d[key] += 1`, `factory()` is called and its result is inserted for `key` and returned.
+    ///
+    /// Other operations behave as for any dictionary, e.g. `d.get(key)` and `key in d` do
+    /// not insert missing keys.
+    ///
+    /// Examples:
+    ///
+    /// ```
+    /// # use starlark::stdlib::starlark_default;
+    /// # assert!(starlark_default(r#"
+    /// def zero(): return 0
+    /// counts = defaultdict(zero)
+    /// counts["a"] += 1
+    /// counts["b"] += 1
+    /// counts["a"] += 1
+    /// groups = defaultdict(list)
+    /// groups["x"].append(1)
+    /// (counts == {"a": 2, "b": 1} and groups == {"x": [1]})
+    /// # "#).unwrap());
+    /// ```
+    defaultdict(factory, /) {
+        Ok(Value::new(Dictionary::new_with_default_factory(factory)))
+    }
+
     /// [dir](
     /// https://github.com/google/skylark/blob/a0e5de7e63b47e716cca7226662a4c95d47bf873/doc/spec.md#dir
     /// ): list attributes of a value.
@@ -307,6 +709,31 @@ starlark_module! {global_functions =>
         Ok(Value::from(result))
     }
 
+    /// divmod: quotient and remainder of a division
+    ///
+    /// `divmod(x, y)` returns the named tuple `(x // y, x % y)`, whose elements can also be
+    /// accessed as the `quotient` and `remainder` attributes.
+    ///
+    /// Examples:
+    ///
+    /// ```
+    /// # use starlark::stdlib::starlark_default;
+    /// # assert!(starlark_default(r#"
+    /// q, r = divmod(7, 2)
+    /// (q == 3 and r == 1
+    ///  and divmod(7, 2).quotient == 3
+    ///  and divmod(-7, 2).remainder == 1)
+    /// # "#).unwrap());
+    /// ```
+    divmod(x, y, /) {
+        let quotient = x.floor_div(y.clone())?;
+        let remainder = x.percent(y)?;
+        let result_type = NamedTupleType::new(
+            "divmod_result",
+            vec!["quotient".to_owned(), "remainder".to_owned()],
+        );
+        Ok(result_type.instance(vec![quotient, remainder]))
+    }
 
     /// [enumerate](
     /// https://github.com/google/skylark/blob/a0e5de7e63b47e716cca7226662a4c95d47bf873/doc/spec.md#enumerate
@@ -316,6 +743,7 @@ starlark_module! {global_functions =>
     /// the iterable sequence and the index of the value within the sequence.
     ///
     /// The optional second parameter, `start`, specifies an integer value to add to each index.
+    /// It can be passed positionally or by name.
     ///
     /// Examples:
     ///
@@ -327,18 +755,98 @@ starlark_module! {global_functions =>
     /// # assert!(starlark_default(r#"(
     /// enumerate(["one", "two"], 1) == [(1, "one"), (2, "two")]
     /// # )"#).unwrap());
+    /// # assert!(starlark_default(r#"(
+    /// enumerate(["one", "two"], start=1) == [(1, "one"), (2, "two")]
+    /// # )"#).unwrap());
     /// ```
-    enumerate(it, offset: i64 = 0, /) {
-        let v : Vec<Value> =
-            it
-            .iter()?
-            .iter()
-            .enumerate()
-            .map(|(k, v)| Value::from((Value::new(k as i64 + offset), v)))
-            .collect();
+    enumerate(it, /, start: i64 = 0) {
+        let mut v = Vec::new();
+        let mut index = Value::new(start);
+        for el in &it.iter()? {
+            // Indices past `i64::MAX` are promoted to big integers
+            let next = index.add(Value::new(1))?;
+            v.push(Value::from((index, el)));
+            index = next;
+        }
         Ok(Value::from(v))
     }
 
+    /// flatten_dict: flatten nested dictionaries into a dictionary with joined keys
+    ///
+    /// `flatten_dict(d, sep=".", lists="keep")` returns a new dictionary where every value of
+    /// `d` which is a non-empty dictionary is replaced by its own flattened entries, with keys
+    /// joined by `sep`: `{"a": {"b": 1}}` becomes `{"a.b": 1}`. Keys are converted with `str`.
+    ///
+    /// The `lists` parameter selects how lists are handled: `"keep"` (the default) keeps them
+    /// as values and `"index"` flattens their elements with their index as key.
+    ///
+    /// It is an error if two entries get the same flattened key, as in
+    /// `{"a.b": 1, "a": {"b": 2}}`, or if `sep` is empty.
+    ///
+    /// Examples:
+    ///
+    /// ```
+    /// # use starlark::stdlib::starlark_default;
+    /// # assert!(starlark_default(r#"(
+    /// flatten_dict({"a": {"b": 1, "c": {"d": 2}}, "e": 3}) == {"a.b": 1, "a.c.d": 2, "e": 3}
+    /// # and
+    /// flatten_dict({"a": {"b": [1, 2]}}, sep="_") == {"a_b": [1, 2]}
+    /// # and
+    /// flatten_dict({"a": [1, {"b": 2}]}, lists="index") == {"a.0": 1, "a.1.b": 2}
+    /// # )"#).unwrap());
+    /// ```
+    flatten_dict(d, /, sep: String = ".".to_owned(), lists: String = "keep".to_owned()) {
+        check_flatten_options("flatten_dict", &sep, &lists)?;
+        if d.get_type() != "dict" {
+            starlark_err!(
+                INCORRECT_PARAMETER_TYPE_ERROR_CODE,
+                format!(
+                    "flatten_dict() argument must be a dict, got a value of type '{}'",
+                    d.get_type()
+                ),
+                "Not a dict".to_owned()
+            );
+        }
+        let mut result = Dictionary::new_typed();
+        for (k, v) in d.downcast_ref::<Dictionary>().unwrap().items() {
+            flatten_value(&mut result, k.to_str(), v, &sep, lists == "index")?;
+        }
+        Ok(Value::new(result))
+    }
+
+    /// format_size: human-readable representation of a number of bytes
+    ///
+    /// `format_size(bytes, binary=True)` returns `bytes` in the largest unit it reaches, with
+    /// at most two decimals: `B`, `KiB`, `MiB`, `GiB` and `TiB` (powers of 1024), or `B`, `KB`,
+    /// `MB`, `GB` and `TB` (powers of 1000) if `binary` is false. Sizes which are a whole
+    /// number of hundredths of their unit are parsed back by `parse_size`. It is an error if
+    /// `bytes` is negative.
+    ///
+    /// Examples:
+    ///
+    /// ```
+    /// # use starlark::stdlib::starlark_default;
+    /// # assert!(starlark_default(r#"(
+    /// format_size(10485760) == "10MiB"
+    /// # and
+    /// format_size(1536) == "1.5KiB"
+    /// # and
+    /// format_size(2500000, binary=False) == "2.5MB"
+    /// # and
+    /// format_size(100) == "100B"
+    /// # )"#).unwrap());
+    /// ```
+    format_size(bytes: i64, /, binary = true) {
+        if bytes < 0 {
+            starlark_err!(
+                INVALID_SIZE_ERROR_CODE,
+                format!("format_size() expects a non-negative size, got {}", bytes),
+                "Negative size".to_owned()
+            );
+        }
+        Ok(Value::new(format_size_str(bytes, binary.to_bool())))
+    }
+
     /// [getattr](
     /// https://github.com/google/skylark/blob/a0e5de7e63b47e716cca7226662a4c95d47bf873/doc/spec.md#getattr
     /// ): returns the value of an attribute
@@ -369,6 +877,50 @@ starlark_module! {global_functions =>
         }
     }
 
+    /// group_by: group the elements of an iterable by a key function
+    ///
+    /// `group_by(x, key)` calls `key` on each element of the iterable `x` and returns a dict
+    /// mapping each distinct result to the list of elements for which `key` returned it.
+    /// Keys appear in the order they are first seen, and elements keep their order within
+    /// each group. It is an error if `key` returns an unhashable value.
+    ///
+    /// Examples:
+    ///
+    /// ```
+    /// # use starlark::stdlib::starlark_default;
+    /// # assert!(starlark_default(r#"(
+    /// group_by(["bb", "a", "cc", "d"], len) == {2: ["bb", "cc"], 1: ["a", "d"]}
+    /// # )"#).unwrap());
+    /// # assert!(starlark_default(r#"
+    /// def parity(n): return n % 2
+    /// group_by([1, 2, 3, 4], key=parity) == {1: [1, 3], 0: [2, 4]}
+    /// # "#).unwrap());
+    /// ```
+    group_by(call_stack cs, env e, x, /, key) {
+        let mut groups: LinkedHashMap<HashedValue, Vec<Value>> = LinkedHashMap::new();
+        for el in &x.iter()? {
+            let k = key.call(cs, e, vec![el.clone()], LinkedHashMap::new(), None, None)?;
+            let k = match HashedValue::new(k.clone()) {
+                Ok(k) => k,
+                Err(..) => starlark_err!(
+                    GROUP_BY_KEY_NOT_HASHABLE_ERROR_CODE,
+                    format!(
+                        "group_by() key function returned unhashable value {} of type '{}'",
+                        k.to_repr(),
+                        k.get_type()
+                    ),
+                    "key is not hashable".to_owned()
+                ),
+            };
+            groups.entry(k).or_default().push(el);
+        }
+        let mut result = Dictionary::new_typed();
+        for (k, v) in groups {
+            result.insert(k.get_value().clone(), Value::from(v))?;
+        }
+        Ok(Value::new(result))
+    }
+
     /// [hasattr](
     /// https://github.com/google/skylark/blob/a0e5de7e63b47e716cca7226662a4c95d47bf873/doc/spec.md#hasattr
     /// ): test if an object has an attribute
@@ -467,31 +1019,89 @@ starlark_module! {global_functions =>
             };
             match i64::from_str_radix(&s, base) {
                 Ok(i) => Ok(Value::new(sign * i)),
-                Err(x) => starlark_err!(
-                    INT_CONVERSION_FAILED_ERROR_CODE,
-                    format!(
-                        "{} is not a valid number in base {}: {}",
-                        a.to_repr(),
-                        base,
-                        x.description(),
-                    ),
-                    format!("Not a base {} integer", base)
-                ),
-            }
-        } else {
-            match base {
-                Some(base) => {
+                Err(x) => if let Some(big) = BigInt::parse(&s, base)? {
+                    // Out of the `i64` range
+                    Ok(if sign < 0 { big.neg() } else { big }.into_value())
+                } else {
+                    starlark_err!(
+                        INT_CONVERSION_FAILED_ERROR_CODE,
+                        format!(
+                            "{} is not a valid number in base {}: {}",
+                            a.to_repr(),
+                            base,
+                            x.description(),
+                        ),
+                        format!("Not a base {} integer", base)
+                    )
+                },
+            }
+        } else {
+            match base {
+                Some(base) => {
                     starlark_err!(
                         INT_CONVERSION_FAILED_ERROR_CODE,
                         "int() cannot convert non-string with explicit base".to_owned(),
                         format!("Explict base '{}' provided with non-string", base.to_repr())
                     )
                 }
-                None => Ok(Value::new(a.to_int()?)),
+                None => match a.downcast_ref::<BigInt>() {
+                    Some(_) => Ok(a.clone()),
+                    None => Ok(Value::new(a.to_int()?)),
+                },
             }
         }
     }
 
+    /// lazy_seq: a sequence whose elements are computed on demand
+    ///
+    /// `lazy_seq(f, n)` returns a sequence of length `n` whose element `i` is `f(i)`. `f` is
+    /// only called the first time element `i` is needed, by indexing or iterating the
+    /// sequence, and its result is cached and frozen. A `for` loop or a comprehension calls `f`
+    /// for each element it reaches, while other iterations, e.g. `list(s)`, compute all the
+    /// elements before returning the first one. It is an error if `n` is negative, or if `f`
+    /// needs the element it is computing.
+    ///
+    /// Examples:
+    ///
+    /// ```
+    /// # use starlark::stdlib::starlark_default;
+    /// # assert!(starlark_default(r#"
+    /// def square(i):
+    ///     return i * i
+    /// squares = lazy_seq(square, 1000000)
+    /// # (
+    /// squares[12] == 144
+    /// # and
+    /// len(squares) == 1000000
+    /// # and
+    /// list(lazy_seq(str, 3)) == ["0", "1", "2"]
+    /// # )"#).unwrap());
+    /// ```
+    lazy_seq(call_stack cs, env e, f, n: i64, /) {
+        if n < 0 {
+            starlark_err!(
+                NEGATIVE_LENGTH_ERROR_CODE,
+                format!("lazy_seq() length must be non-negative, got {}", n),
+                "Negative length".to_owned()
+            );
+        }
+        let type_values = e.clone();
+        let compute = move |call_stack: &mut CallStack, i: i64| {
+            f.call(
+                call_stack,
+                &type_values,
+                vec![Value::new(i)],
+                LinkedHashMap::new(),
+                None,
+                None,
+            )
+        };
+        // Accesses without a caller only keep the module of the creation
+        let mut call_stack = cs.clone();
+        call_stack.clear();
+        Ok(Value::new(LazySeq::new(n, Box::new(compute), call_stack)))
+    }
+
     /// [len](
     /// https://github.com/google/skylark/blob/a0e5de7e63b47e716cca7226662a4c95d47bf873/doc/spec.md#len
     /// ): get the length of a sequence
@@ -529,7 +1139,11 @@ starlark_module! {global_functions =>
     /// or if the sequence is empty.
     ///
     /// The optional named parameter `key` specifies a function to be applied
-    /// to each element prior to comparison.
+    /// to each element prior to comparison. It is called once per element. If several
+    /// elements are maximal, the first one is returned.
+    ///
+    /// The optional named parameter `default` is returned if the sequence is empty, instead
+    /// of failing. It can only be used with a single positional argument.
     ///
     /// ```
     /// # use starlark::stdlib::starlark_default;
@@ -539,44 +1153,40 @@ starlark_module! {global_functions =>
     /// max("two", "three", "four")           == "two"    # the lexicographically greatest
     /// # and
     /// max("two", "three", "four", key=len)  == "three"  # the longest
+    /// # and
+    /// max("one", "two", key=len)            == "one"    # the first of the longest
+    /// # and
+    /// max([], default=0)                    == 0
     /// # )"#).unwrap());
     /// ```
-    max(call_stack cs, env e, *args, ?key) {
-        let args = if args.len() == 1 {
-            args.swap_remove(0)
-        } else {
-            Value::from(args)
-        };
-        let it = args.iter()?;
-        let mut it = it.iter();
-        let mut max = match it.next() {
-            Some(x) => x,
-            None => starlark_err!(
-                EMPTY_ITERABLE_ERROR_CODE,
-                "Argument is an empty iterable, max() expect a non empty iterable".to_owned(),
-                "Empty".to_owned()
-            )
-        };
-        match key {
-            None => {
-                for i in it {
-                    if max.compare(&i)? == Ordering::Less {
-                        max = i;
-                    }
-                }
-            }
-            Some(key) => {
-                let mut cached = key.call(cs, e, vec![max.clone()], LinkedHashMap::new(), None, None)?;
-                for i in it {
-                    let keyi = key.call(cs, e, vec![i.clone()], LinkedHashMap::new(), None, None)?;
-                    if cached.compare(&keyi)? == Ordering::Less {
-                        max = i;
-                        cached = keyi;
-                    }
-                }
-            }
-        };
-        Ok(max)
+    max(call_stack cs, env e, *args, ?key, ?default) {
+        let args = extremum_args("max", args, &default)?;
+        match find_extremum_opt(cs, e, &args, key, Ordering::Greater)? {
+            Some((_, el)) => Ok(el),
+            None => match default {
+                Some(default) => Ok(default),
+                None => Err(empty_iterable_error("max")),
+            },
+        }
+    }
+
+    /// max_by: the element of an iterable with the greatest key
+    ///
+    /// `max_by(x, key)` returns the element of the iterable `x` for which `key` returns the
+    /// greatest value. `key` is called exactly once per element, and ties are broken by the
+    /// first occurrence. It is an error if `x` is empty.
+    ///
+    /// Examples:
+    ///
+    /// ```
+    /// # use starlark::stdlib::starlark_default;
+    /// # assert!(starlark_default(r#"(
+    /// max_by(["a", "ccc", "bb", "ddd"], len) == "ccc"
+    /// # )"#).unwrap());
+    /// ```
+    max_by(call_stack cs, env e, x, /, key) {
+        let (_, el) = find_extremum(cs, e, "max_by", &x, Some(key), Ordering::Greater)?;
+        Ok(el)
     }
 
     /// [min](
@@ -588,6 +1198,13 @@ starlark_module! {global_functions =>
     /// It is an error if any element does not support ordered comparison,
     /// or if the sequence is empty.
     ///
+    /// The optional named parameter `key` specifies a function to be applied
+    /// to each element prior to comparison. It is called once per element. If several
+    /// elements are minimal, the first one is returned.
+    ///
+    /// The optional named parameter `default` is returned if the sequence is empty, instead
+    /// of failing. It can only be used with a single positional argument.
+    ///
     /// ```
     /// # use starlark::stdlib::starlark_default;
     /// # assert!(starlark_default(r#"(
@@ -596,44 +1213,106 @@ starlark_module! {global_functions =>
     /// min("two", "three", "four")             == "four"  # the lexicographically least
     /// # and
     /// min("two", "three", "four", key=len)    == "two"   # the shortest
+    /// # and
+    /// min("one", "two", key=len)              == "one"   # the first of the shortest
+    /// # and
+    /// min([], default=None)                   == None
     /// # )"#).unwrap());
     /// ```
-    min(call_stack cs, env e, *args, ?key) {
-        let args = if args.len() == 1 {
-            args.swap_remove(0)
-        } else {
-            Value::from(args)
-        };
-        let it = args.iter()?;
-        let mut it = it.iter();
-        let mut min = match it.next() {
-            Some(x) => x,
-            None => starlark_err!(
-                EMPTY_ITERABLE_ERROR_CODE,
-                "Argument is an empty iterable, min() expect a non empty iterable".to_owned(),
-                "Empty".to_owned()
-            )
-        };
-        match key {
-            None => {
-                for i in it {
-                    if min.compare(&i)? == Ordering::Greater {
-                        min = i;
-                    }
-                }
+    min(call_stack cs, env e, *args, ?key, ?default) {
+        let args = extremum_args("min", args, &default)?;
+        match find_extremum_opt(cs, e, &args, key, Ordering::Less)? {
+            Some((_, el)) => Ok(el),
+            None => match default {
+                Some(default) => Ok(default),
+                None => Err(empty_iterable_error("min")),
+            },
+        }
+    }
+
+    /// min_by: the element of an iterable with the least key
+    ///
+    /// `min_by(x, key)` returns the element of the iterable `x` for which `key` returns the
+    /// least value. `key` is called exactly once per element, and ties are broken by the
+    /// first occurrence. It is an error if `x` is empty.
+    ///
+    /// Examples:
+    ///
+    /// ```
+    /// # use starlark::stdlib::starlark_default;
+    /// # assert!(starlark_default(r#"(
+    /// min_by(["ccc", "a", "bb", "d"], len) == "a"
+    /// # )"#).unwrap());
+    /// ```
+    min_by(call_stack cs, env e, x, /, key) {
+        let (_, el) = find_extremum(cs, e, "min_by", &x, Some(key), Ordering::Less)?;
+        Ok(el)
+    }
+
+    /// module_name: the name of the module being evaluated
+    ///
+    /// `module_name()` returns the name given by the host to the environment of the module
+    /// being evaluated, e.g. to build labels relative to the current package. When called
+    /// from a function defined in a loaded module, it still returns the name of the module
+    /// being evaluated. It returns `None` outside of the evaluation of a module.
+    ///
+    /// Examples:
+    ///
+    /// ```
+    /// # use starlark::stdlib::starlark_default;
+    /// # assert!(starlark_default(r#"(
+    /// module_name() == "test"
+    /// # )"#).unwrap());
+    /// ```
+    module_name(call_stack cs) {
+        match cs.module_name() {
+            Some(name) => Ok(Value::new(name.to_owned())),
+            None => Ok(Value::new(NoneType::None)),
+        }
+    }
+
+    /// namedtuple: create a tuple type with named fields
+    ///
+    /// `namedtuple(typename, fields)` returns a new type whose instances are tuples with the
+    /// elements also accessible as attributes named by `fields`, a list of strings.
+    /// Calling the type with one argument per field, positionally or by name, creates an
+    /// instance. Instances can be indexed and unpacked like any other tuple.
+    ///
+    /// Examples:
+    ///
+    /// ```
+    /// # use starlark::stdlib::starlark_default;
+    /// # assert!(starlark_default(r#"
+    /// Point = namedtuple("Point", ["x", "y"])
+    /// p = Point(1, y = 2)
+    /// x, y = p
+    /// (p.x == 1 and p[1] == 2 and x == 1 and y == 2 and p == (1, 2)
+    ///  and repr(p) == "Point(x=1, y=2)")
+    /// # "#).unwrap());
+    /// ```
+    namedtuple(typename: String, fields, /) {
+        check_namedtuple_name(&typename)?;
+        let mut names: Vec<String> = Vec::new();
+        for field in &fields.iter()? {
+            if field.get_type() != "string" {
+                starlark_err!(
+                    NAMEDTUPLE_INVALID_NAME_ERROR_CODE,
+                    format!("namedtuple field names must be strings, got '{}'", field.get_type()),
+                    "Not a string".to_owned()
+                );
             }
-            Some(key) => {
-                let mut cached = key.call(cs, e, vec![min.clone()], LinkedHashMap::new(), None, None)?;
-                for i in it {
-                    let keyi = key.call(cs, e, vec![i.clone()], LinkedHashMap::new(), None, None)?;
-                    if cached.compare(&keyi)? == Ordering::Greater {
-                        min = i;
-                        cached = keyi;
-                    }
-                }
+            let name = field.to_str();
+            check_namedtuple_name(&name)?;
+            if names.contains(&name) {
+                starlark_err!(
+                    NAMEDTUPLE_INVALID_NAME_ERROR_CODE,
+                    format!("Duplicate namedtuple field name '{}'", name),
+                    "Duplicate field".to_owned()
+                );
             }
-        };
-        Ok(min)
+            names.push(name);
+        }
+        Ok(Value::new(NamedTupleType::new(&typename, names)))
     }
 
     /// [ord](
@@ -675,6 +1354,91 @@ starlark_module! {global_functions =>
         }
     }
 
+    /// partition: split the elements of an iterable by a predicate
+    ///
+    /// `partition(x, pred)` calls `pred` once on each element of the iterable `x` and returns
+    /// a tuple of two lists: the elements for which `pred` returned a true value, and the
+    /// other elements, both in the order of `x`.
+    ///
+    /// Examples:
+    ///
+    /// ```
+    /// # use starlark::stdlib::starlark_default;
+    /// # assert!(starlark_default(r#"
+    /// def is_odd(n): return n % 2 == 1
+    /// partition([1, 2, 3, 4, 5], is_odd) == ([1, 3, 5], [2, 4])
+    /// # "#).unwrap());
+    /// ```
+    partition(call_stack cs, env e, x, /, pred) {
+        let mut matches = Vec::new();
+        let mut rest = Vec::new();
+        for el in &x.iter()? {
+            if pred.call(cs, e, vec![el.clone()], LinkedHashMap::new(), None, None)?.to_bool() {
+                matches.push(el);
+            } else {
+                rest.push(el);
+            }
+        }
+        Ok(Value::from((Value::from(matches), Value::from(rest))))
+    }
+
+    /// parse_size: parse a human-readable number of bytes
+    ///
+    /// `parse_size(s)` returns the number of bytes described by the string `s`: a
+    /// non-negative number, possibly with decimals, optionally followed by a unit, `B`, `KiB`,
+    /// `MiB`, `GiB` and `TiB` for powers of 1024 or `KB`, `MB`, `GB` and `TB` for powers of
+    /// 1000. Whitespace is allowed between the number and the unit. It is an error if the unit
+    /// is unknown or if the size is not a whole number of bytes.
+    ///
+    /// Examples:
+    ///
+    /// ```
+    /// # use starlark::stdlib::starlark_default;
+    /// # assert!(starlark_default(r#"(
+    /// parse_size("10MiB") == 10485760
+    /// # and
+    /// parse_size("10 MB") == 10000000
+    /// # and
+    /// parse_size("1.5KiB") == 1536
+    /// # and
+    /// parse_size("42") == 42
+    /// # )"#).unwrap());
+    /// ```
+    parse_size(s: String, /) {
+        match parse_size_str(&s) {
+            Some(bytes) => Ok(Value::new(bytes)),
+            None => starlark_err!(
+                INVALID_SIZE_ERROR_CODE,
+                format!(
+                    "Invalid size \"{}\", expected a number of bytes like \"10MiB\" or \"2GB\"",
+                    s
+                ),
+                "Invalid size".to_owned()
+            ),
+        }
+    }
+
+    /// print: output the string representation of values
+    ///
+    /// `print(*args, sep=" ")` joins the `str()` of its arguments with `sep` and outputs the
+    /// result as a line, then returns `None`. The line is passed to the print handler of the
+    /// environment being evaluated if there is one, see `Environment::with_print_handler`,
+    /// and written to stderr otherwise.
+    ///
+    /// Examples:
+    ///
+    /// ```
+    /// # use starlark::stdlib::starlark_default;
+    /// # assert!(starlark_default(r#"(
+    /// print("some message", 1) == None  # prints "some message 1"
+    /// # )"#).unwrap());
+    /// ```
+    print(call_stack cs, *args, sep: String = " ".to_owned()) {
+        let message: Vec<String> = args.iter().map(Value::to_str).collect();
+        cs.print(&message.join(&sep));
+        Ok(Value::new(NoneType::None))
+    }
+
     /// [range](
     /// https://github.com/google/skylark/blob/a0e5de7e63b47e716cca7226662a4c95d47bf873/doc/spec.md#range
     /// ): return a range of integers
@@ -734,6 +1498,52 @@ starlark_module! {global_functions =>
         Ok(Value::new(Range::new(start, stop, step)))
     }
 
+    /// repeat_to: cycle through an iterable up to a length
+    ///
+    /// `repeat_to(x, n)` returns a list of `n` elements made of the elements of the iterable
+    /// `x` in order, starting again from the first element of `x` after its last one. Only
+    /// the first `n` elements of `x` are iterated. It is an error if `n` is negative or too
+    /// large, or if `x` is empty and `n` is not zero.
+    ///
+    /// Examples:
+    ///
+    /// ```
+    /// # use starlark::stdlib::starlark_default;
+    /// # assert!(starlark_default(r#"(
+    /// repeat_to(["a", "b"], 5) == ["a", "b", "a", "b", "a"]
+    /// # and
+    /// repeat_to(range(10), 3) == [0, 1, 2]
+    /// # )"#).unwrap());
+    /// ```
+    repeat_to(x, n: i64, /) {
+        if n < 0 {
+            starlark_err!(
+                NEGATIVE_LENGTH_ERROR_CODE,
+                format!("repeat_to() length must be non-negative, got {}", n),
+                "Negative length".to_owned()
+            );
+        }
+        let n = usize::try_from(n).unwrap_or(usize::MAX);
+        let n = check_requested_length(n, "repeat_to()")?;
+        let mut result: Vec<Value> = x.iter()?.iter().take(n).collect();
+        if result.len() < n {
+            if result.is_empty() {
+                starlark_err!(
+                    EMPTY_ITERABLE_ERROR_CODE,
+                    "Argument is an empty iterable, repeat_to() expect a non empty iterable"
+                        .to_owned(),
+                    "Empty".to_owned()
+                );
+            }
+            let cycle = result.len();
+            for i in cycle..n {
+                let el = result[i % cycle].clone();
+                result.push(el);
+            }
+        }
+        Ok(Value::from(result))
+    }
+
     /// [repr](
     /// https://github.com/google/skylark/blob/a0e5de7e63b47e716cca7226662a4c95d47bf873/doc/spec.md#repr
     /// ): formats its argument as a string.
@@ -761,6 +1571,9 @@ starlark_module! {global_functions =>
     /// `reversed(x)` returns a new list containing the elements of the iterable sequence x in
     /// reverse order.
     ///
+    /// Dictionaries and sets are not sequences and cannot be reversed: reverse the list of
+    /// their keys or elements instead.
+    ///
     /// ```
     /// # use starlark::stdlib::starlark_default;
     /// # assert!(starlark_default(r#"(
@@ -772,8 +1585,19 @@ starlark_module! {global_functions =>
     /// # )"#).unwrap());
     /// ```
     reversed(a, /) {
-        let v: Vec<Value> = a.to_vec()?;
-        let v: Vec<Value> = v.into_iter().rev().collect();
+        if a.get_type() == "dict" || a.get_type() == "set" {
+            starlark_err!(
+                INCORRECT_PARAMETER_TYPE_ERROR_CODE,
+                format!(
+                    "reversed() argument must be a sequence, got a value of type '{}'",
+                    a.get_type()
+                ),
+                "Not a sequence".to_owned()
+            );
+        }
+        // The result is the only copy of the elements.
+        let mut v: Vec<Value> = a.to_vec()?;
+        v.reverse();
         Ok(Value::from(v))
     }
 
@@ -805,50 +1629,101 @@ starlark_module! {global_functions =>
     /// # )"#).unwrap());
     /// ```
     sorted(call_stack cs, env e, x, /, ?key, reverse = false) {
-        let it = x.iter()?;
-        let x = it.iter();
-        let mut it = match key {
-            None => {
-                x.map(|x| (x.clone(), x)).collect()
-            }
-            Some(key) => {
-                let mut v = Vec::new();
-                for el in x {
-                    v.push((
-                        el.clone(),
-                        key.call(cs, e, vec![el], LinkedHashMap::new(), None, None)?
-                    ));
-                }
-                v
-            }
+        let x = x.iter()?.to_vec();
+        Ok(Value::from(sort_values(cs, e, x, key, reverse.to_bool())?))
+    }
+
+    /// sorted_items: the items of a dictionary sorted by value or by key
+    ///
+    /// `sorted_items(d, by="value", reverse=False)` returns a new list of the `(key, value)`
+    /// tuples of the dictionary `d`, sorted by value if `by` is `"value"` or by key if `by` is
+    /// `"key"`. The sort is stable: items with equal values keep their insertion order, also
+    /// when `reverse` is true. It is an error if the values (or keys) cannot be compared.
+    ///
+    /// ```
+    /// # use starlark::stdlib::starlark_default;
+    /// # assert!(starlark_default(r#"(
+    /// sorted_items({"a": 2, "b": 1, "c": 2})                == [("b", 1), ("a", 2), ("c", 2)]
+    /// # and
+    /// sorted_items({"a": 2, "b": 1, "c": 2}, reverse=True)  == [("a", 2), ("c", 2), ("b", 1)]
+    /// # and
+    /// sorted_items({"b": 1, "a": 2}, by="key")              == [("a", 2), ("b", 1)]
+    /// # )"#).unwrap());
+    /// ```
+    sorted_items(d, /, by: String = "value".to_owned(), reverse = false) {
+        if d.get_type() != "dict" {
+            starlark_err!(
+                INCORRECT_PARAMETER_TYPE_ERROR_CODE,
+                format!("sorted_items() expects a dict, got {}", d.get_type()),
+                "Not a dict".to_owned()
+            );
+        }
+        let by_value = match by.as_str() {
+            "value" => true,
+            "key" => false,
+            _ => starlark_err!(
+                INCORRECT_PARAMETER_TYPE_ERROR_CODE,
+                format!("sorted_items() by must be \"value\" or \"key\", got \"{}\"", by),
+                "Invalid sort order".to_owned()
+            ),
         };
+        let mut items = Vec::new();
+        for k in &d.iter()? {
+            let v = d.at(k.clone())?;
+            items.push((k, v));
+        }
 
         let mut compare_ok = Ok(());
-
         let reverse = reverse.to_bool();
-        it.sort_by(
-            |x : &(Value, Value), y : &(Value, Value)| {
-                let ord_or_err = if reverse {
-                    x.1.compare(&y.1).map(Ordering::reverse)
-                } else {
-                    x.1.compare(&y.1)
-                };
-                match ord_or_err {
-                    Ok(r) => r,
-                    Err(e) => {
-                        compare_ok = Err(e);
-                        Ordering::Equal // does not matter
-                    }
+        items.sort_by(|x: &(Value, Value), y: &(Value, Value)| {
+            let ord_or_err = if by_value { x.1.compare(&y.1) } else { x.0.compare(&y.0) };
+            match ord_or_err {
+                Ok(r) if reverse => r.reverse(),
+                Ok(r) => r,
+                Err(e) => {
+                    compare_ok = Err(e);
+                    Ordering::Equal // does not matter
                 }
             }
-        );
-
+        });
         compare_ok?;
 
-        let result : Vec<Value> = it.into_iter().map(|x| x.0).collect();
+        let result: Vec<Value> = items
+            .into_iter()
+            .map(|(k, v)| Value::new(tuple::Tuple::new(vec![k, v])))
+            .collect();
         Ok(Value::from(result))
     }
 
+    /// stable_hash: a hash of a value which is stable across versions
+    ///
+    /// `stable_hash(x)` returns a 64-bit integer hash of the hashable value `x`. Unlike
+    /// `hash(x)`, whose algorithm may change between versions of this implementation, the
+    /// algorithm of `stable_hash` is part of its contract, so its results can be persisted,
+    /// e.g. as cache keys: the hash is the first 8 bytes, read as a big-endian signed integer,
+    /// of the SHA-256 digest of the [canonical serialization](crate::values::canonical) of `x`,
+    /// where integers outside of the 64-bit range are length-prefixed. Values which compare
+    /// equal have the same hash. It is an error if `x` is not hashable,
+    /// e.g. a list or a function, or if it cannot be serialized.
+    ///
+    /// Examples:
+    ///
+    /// ```
+    /// # use starlark::stdlib::starlark_default;
+    /// # assert!(starlark_default(r#"(
+    /// stable_hash("starlark") == stable_hash("star" + "lark")
+    /// # and
+    /// stable_hash((1, "a")) != stable_hash(("a", 1))
+    /// # )"#).unwrap());
+    /// ```
+    stable_hash(a, /) {
+        a.get_hash()?;
+        let digest = Sha256::digest(&canonical::canonical_bytes(&a)?);
+        let mut prefix = [0; 8];
+        prefix.copy_from_slice(&digest[..8]);
+        Ok(Value::new(i64::from_be_bytes(prefix)))
+    }
+
     /// [str](
     /// https://github.com/google/skylark/blob/a0e5de7e63b47e716cca7226662a4c95d47bf873/doc/spec.md#str
     /// ): formats its argument as a string.
@@ -870,75 +1745,877 @@ starlark_module! {global_functions =>
         Ok(Value::new(a.to_str()))
     }
 
-    /// [tuple](
-    /// https://github.com/google/skylark/blob/a0e5de7e63b47e716cca7226662a4c95d47bf873/doc/spec.md#tuple
-    /// ): returns a tuple containing the elements of the iterable x.
+    /// transitive_closure: the nodes reachable in a graph
     ///
-    /// With no arguments, `tuple()` returns the empty tuple.
-    tuple(?a, /) {
-        if let Some(a) = a {
-            Ok(Value::new(tuple::Tuple::new(a.to_vec()?)))
-        } else {
-            Ok(Value::new(tuple::Tuple::new(Vec::new())))
-        }
-    }
-
-    /// [type](
-    /// https://github.com/google/skylark/blob/a0e5de7e63b47e716cca7226662a4c95d47bf873/doc/spec.md#type
-    /// ): returns a string describing the type of its operand.
+    /// `transitive_closure(graph, roots)` returns the list of the nodes reachable from the
+    /// iterable `roots` in `graph`, a dictionary mapping each node to an iterable of its
+    /// successors. Nodes which are not keys of `graph` have no successors.
     ///
-    /// ```
-    /// # use starlark::stdlib::starlark_default;
-    /// # assert!(starlark_default(r#"(
-    /// type(None)              == "NoneType"
-    /// # and
-    /// type(0)                 == "int"
-    /// # )"#).unwrap());
-    /// ```
-    _type(a, /) {
-        Ok(Value::new(a.get_type().to_owned()))
-    }
-
-    /// [zip](
-    /// https://github.com/google/skylark/blob/a0e5de7e63b47e716cca7226662a4c95d47bf873/doc/spec.md#zip
-    /// ): zip several iterables together
+    /// The result starts with the roots and lists each node once, in breadth-first order:
+    /// successors are visited in the order of their iterable. Cycles are allowed. Nodes must be
+    /// hashable.
     ///
-    /// `zip()` returns a new list of n-tuples formed from corresponding
-    /// elements of each of the n iterable sequences provided as arguments to
-    /// `zip`.  That is, the first tuple contains the first element of each of
-    /// the sequences, the second element contains the second element of each
-    /// of the sequences, and so on.  The result list is only as long as the
-    /// shortest of the input sequences.
+    /// Examples:
     ///
     /// ```
     /// # use starlark::stdlib::starlark_default;
     /// # assert!(starlark_default(r#"(
-    /// zip()                                   == []
+    /// transitive_closure({"a": ["b", "c"], "b": ["d"], "c": ["d"]}, ["a"]) == ["a", "b", "c", "d"]
     /// # and
-    /// zip(range(5))                           == [(0,), (1,), (2,), (3,), (4,)]
+    /// transitive_closure({"a": ["b"], "b": ["a"]}, ["b"]) == ["b", "a"]
     /// # and
-    /// zip(range(5), "abc".split_codepoints()) == [(0, "a"), (1, "b"), (2, "c")]
+    /// transitive_closure({"a": ["b"]}, ["x"]) == ["x"]
     /// # )"#).unwrap());
     /// ```
-    zip(*args) {
-        let mut v = Vec::new();
+    transitive_closure(graph, roots, /) {
+        let graph = match graph.downcast_ref::<Dictionary>() {
+            Some(graph) => graph,
+            None => starlark_err!(
+                INCORRECT_PARAMETER_TYPE_ERROR_CODE,
+                format!(
+                    "transitive_closure() graph must be a dict, got a value of type '{}'",
+                    graph.get_type()
+                ),
+                "Not a dict".to_owned()
+            ),
+        };
+        let mut seen = LinkedHashSet::new();
+        let mut result = Vec::new();
+        for root in &roots.iter()? {
+            let root = HashedValue::new(root)?;
+            if !seen.contains(&root) {
+                result.push(root.get_value().clone());
+                seen.insert(root);
+            }
+        }
+        // `result` doubles as the queue of the breadth-first search.
+        let mut next = 0;
+        while next < result.len() {
+            let node = HashedValue::new(result[next].clone())?;
+            next += 1;
+            if let Some(successors) = graph.get_hashed(&node) {
+                for successor in &successors.iter()? {
+                    let successor = HashedValue::new(successor)?;
+                    if !seen.contains(&successor) {
+                        result.push(successor.get_value().clone());
+                        seen.insert(successor);
+                    }
+                }
+            }
+        }
+        Ok(Value::from(result))
+    }
+
+    /// try_int: parse an integer, returning `None` on failure
+    ///
+    /// `try_int(x)` behaves like `int(x)` but returns `None` instead of failing when `x` cannot
+    /// be converted. Leading and trailing whitespace of a string is ignored, and a string which
+    /// is empty or only contains whitespace gives `None`. As with `int(x)`, a string may have a
+    /// sign and a `0b`, `0o` or `0x` prefix.
+    ///
+    /// Examples:
+    ///
+    /// ```
+    /// # use starlark::stdlib::starlark_default;
+    /// # assert!(starlark_default(r#"(
+    /// try_int("42") == 42
+    /// # and
+    /// try_int(" -0x10\n") == -16
+    /// # and
+    /// try_int("4.2") == None
+    /// # and
+    /// try_int("") == None
+    /// # )"#).unwrap());
+    /// ```
+    try_int(x, /) {
+        if x.get_type() != "string" {
+            if let Some(big) = BigInt::from_value(&x) {
+                return Ok(big.into_value());
+            }
+            return Ok(match x.to_int() {
+                Ok(i) => Value::new(i),
+                Err(..) => Value::new(NoneType::None),
+            });
+        }
+        Ok(match parse_int_literal(x.to_str().trim()) {
+            Some(i) => i.into_value(),
+            None => Value::new(NoneType::None),
+        })
+    }
+
+    /// [tuple](
+    /// https://github.com/google/skylark/blob/a0e5de7e63b47e716cca7226662a4c95d47bf873/doc/spec.md#tuple
+    /// ): returns a tuple containing the elements of the iterable x.
+    ///
+    /// With no arguments, `tuple()` returns the empty tuple.
+    tuple(?a, /) {
+        if let Some(a) = a {
+            Ok(Value::new(tuple::Tuple::new(a.to_vec()?)))
+        } else {
+            Ok(Value::new(tuple::Tuple::new(Vec::new())))
+        }
+    }
+
+    /// [type](
+    /// https://github.com/google/skylark/blob/a0e5de7e63b47e716cca7226662a4c95d47bf873/doc/spec.md#type
+    /// ): returns a string describing the type of its operand.
+    ///
+    /// ```
+    /// # use starlark::stdlib::starlark_default;
+    /// # assert!(starlark_default(r#"(
+    /// type(None)              == "NoneType"
+    /// # and
+    /// type(0)                 == "int"
+    /// # )"#).unwrap());
+    /// ```
+    _type(a, /) {
+        Ok(Value::new(a.get_type().to_owned()))
+    }
+
+    /// unflatten_dict: inverse of `flatten_dict`
+    ///
+    /// `unflatten_dict(d, sep=".", lists="keep")` returns a new dictionary where every key of
+    /// `d` is split on `sep` and its value is stored in nested dictionaries:
+    /// `{"a.b": 1}` becomes `{"a": {"b": 1}}`. All keys of `d` must be strings.
+    ///
+    /// With `lists="index"`, nested dictionaries whose keys are `"0"`, `"1"`, ... in order
+    /// become lists, which reverts `flatten_dict(d, lists="index")`.
+    ///
+    /// It is an error if a key is both a value and a prefix of another key, as in
+    /// `{"a": 1, "a.b": 2}`, or if `sep` is empty.
+    ///
+    /// Examples:
+    ///
+    /// ```
+    /// # use starlark::stdlib::starlark_default;
+    /// # assert!(starlark_default(r#"(
+    /// unflatten_dict({"a.b": 1, "a.c.d": 2, "e": 3}) == {"a": {"b": 1, "c": {"d": 2}}, "e": 3}
+    /// # and
+    /// unflatten_dict({"a.0": 1, "a.1.b": 2}, lists="index") == {"a": [1, {"b": 2}]}
+    /// # )"#).unwrap());
+    /// ```
+    unflatten_dict(d, /, sep: String = ".".to_owned(), lists: String = "keep".to_owned()) {
+        check_flatten_options("unflatten_dict", &sep, &lists)?;
+        if d.get_type() != "dict" {
+            starlark_err!(
+                INCORRECT_PARAMETER_TYPE_ERROR_CODE,
+                format!(
+                    "unflatten_dict() argument must be a dict, got a value of type '{}'",
+                    d.get_type()
+                ),
+                "Not a dict".to_owned()
+            );
+        }
+        let mut root = LinkedHashMap::new();
+        for (k, v) in d.downcast_ref::<Dictionary>().unwrap().items() {
+            if k.get_type() != "string" {
+                starlark_err!(
+                    INCORRECT_PARAMETER_TYPE_ERROR_CODE,
+                    format!(
+                        "unflatten_dict() keys must be strings, got a key of type '{}'",
+                        k.get_type()
+                    ),
+                    "Not a string".to_owned()
+                );
+            }
+            let key = k.to_str();
+            let parts: Vec<&str> = key.split(sep.as_str()).collect();
+            unflatten_insert(&mut root, &parts, v, &key)?;
+        }
+        let mut result = Dictionary::new_typed();
+        for (k, v) in root {
+            result.insert(Value::new(k), unflattened_to_value(v, lists == "index")?)?;
+        }
+        Ok(Value::new(result))
+    }
+
+    /// unique: remove the duplicate elements of an iterable
+    ///
+    /// `unique(x)` returns a new list with the elements of the iterable `x` in order,
+    /// keeping only the first occurrence of equal elements. It is an error if an element is
+    /// not hashable, unless the named parameter `by_equality` is `True`, in which case the
+    /// unhashable elements are compared for equality with each of the previously kept
+    /// unhashable elements, which is quadratic in their number.
+    ///
+    /// Examples:
+    ///
+    /// ```
+    /// # use starlark::stdlib::starlark_default;
+    /// # assert!(starlark_default(r#"(
+    /// unique([3, 1, 3, 2, 1]) == [3, 1, 2]
+    /// # and
+    /// unique([[1], 2, [1], 2], by_equality=True) == [[1], 2]
+    /// # )"#).unwrap());
+    /// ```
+    unique(x, /, by_equality = false) {
+        let by_equality = by_equality.to_bool();
+        let mut result = Vec::new();
+        let mut seen = LinkedHashSet::new();
+        let mut unhashable: Vec<Value> = Vec::new();
+        for el in &x.iter()? {
+            match HashedValue::new(el.clone()) {
+                Ok(k) => {
+                    if !seen.contains(&k) {
+                        seen.insert(k);
+                        result.push(el);
+                    }
+                }
+                Err(..) if by_equality => {
+                    let mut duplicate = false;
+                    for u in &unhashable {
+                        if u.equals(&el)? {
+                            duplicate = true;
+                            break;
+                        }
+                    }
+                    if !duplicate {
+                        unhashable.push(el.clone());
+                        result.push(el);
+                    }
+                }
+                Err(..) => starlark_err!(
+                    UNIQUE_ELEMENT_NOT_HASHABLE_ERROR_CODE,
+                    format!(
+                        "unique() got unhashable element {} of type '{}'",
+                        el.to_repr(),
+                        el.get_type()
+                    ),
+                    "element is not hashable".to_owned()
+                ),
+            }
+        }
+        Ok(Value::from(result))
+    }
+
+    /// [zip](
+    /// https://github.com/google/skylark/blob/a0e5de7e63b47e716cca7226662a4c95d47bf873/doc/spec.md#zip
+    /// ): zip several iterables together
+    ///
+    /// `zip()` returns a new list of n-tuples formed from corresponding
+    /// elements of each of the n iterable sequences provided as arguments to
+    /// `zip`.  That is, the first tuple contains the first element of each of
+    /// the sequences, the second element contains the second element of each
+    /// of the sequences, and so on.  The result list is only as long as the
+    /// shortest of the input sequences.
+    ///
+    /// With `strict = True`, it is an error if the sequences do not all have
+    /// the same length, which catches mismatched inputs instead of silently
+    /// dropping the extra elements.
+    ///
+    /// ```
+    /// # use starlark::stdlib::starlark_default;
+    /// # assert!(starlark_default(r#"(
+    /// zip()                                   == []
+    /// # and
+    /// zip(range(5))                           == [(0,), (1,), (2,), (3,), (4,)]
+    /// # and
+    /// zip(range(5), "abc".split_codepoints()) == [(0, "a"), (1, "b"), (2, "c")]
+    /// # and
+    /// zip([1, 2], [3, 4], [5, 6], strict=True) == [(1, 3, 5), (2, 4, 6)]
+    /// # )"#).unwrap());
+    /// ```
+    zip(*args, strict = false) {
+        let mut columns = Vec::new();
+        for arg in args {
+            columns.push(arg.to_vec()?);
+        }
+        let len = columns.iter().map(Vec::len).min().unwrap_or(0);
+        if strict.to_bool() {
+            if let Some(i) = columns.iter().position(|c| c.len() != len) {
+                starlark_err!(
+                    ZIP_LENGTH_MISMATCH_ERROR_CODE,
+                    format!(
+                        "zip() argument {} has length {} but argument {} has length {}",
+                        i + 1,
+                        columns[i].len(),
+                        columns.iter().position(|c| c.len() == len).unwrap() + 1,
+                        len
+                    ),
+                    "Length mismatch".to_owned()
+                );
+            }
+        }
+        let mut v = Vec::with_capacity(len);
+        for idx in 0..len {
+            let row: Vec<Value> = columns.iter().map(|c| c[idx].clone()).collect();
+            v.push(Value::new(tuple::Tuple::new(row)));
+        }
+        Ok(Value::from(v))
+    }
+
+    /// zip_dict: build a dictionary from an iterable of keys and an iterable of values
+    ///
+    /// `zip_dict(keys, values)` returns a dictionary mapping each element of `keys` to the
+    /// element of `values` at the same position, i.e. it is equivalent to
+    /// `dict(zip(keys, values))`. Unlike `zip`, it is an error if `keys` and `values` do not
+    /// have the same length. If a key appears several times, the last value wins. It is an
+    /// error if a key is not hashable.
+    ///
+    /// Examples:
+    ///
+    /// ```
+    /// # use starlark::stdlib::starlark_default;
+    /// # assert!(starlark_default(r#"(
+    /// zip_dict(["a", "b"], [1, 2]) == {"a": 1, "b": 2}
+    /// # and
+    /// zip_dict(["a", "b", "a"], [1, 2, 3]) == {"a": 3, "b": 2}
+    /// # )"#).unwrap());
+    /// ```
+    zip_dict(keys, values, /) {
+        let keys = keys.to_vec()?;
+        let values = values.to_vec()?;
+        if keys.len() != values.len() {
+            starlark_err!(
+                ZIP_DICT_LENGTH_MISMATCH_ERROR_CODE,
+                format!(
+                    "zip_dict() got {} keys but {} values",
+                    keys.len(),
+                    values.len()
+                ),
+                "Length mismatch".to_owned()
+            );
+        }
+        let mut result = Dictionary::new_typed();
+        for (k, v) in keys.into_iter().zip(values) {
+            result.insert(k, v)?;
+        }
+        Ok(Value::new(result))
+    }
+
+    /// zip_longest: zip several iterables together, padding the shorter ones
+    ///
+    /// `zip_longest()` is like `zip()` except that the result list is as long
+    /// as the longest of the input sequences. Elements of the exhausted
+    /// sequences are replaced by the named parameter `fillvalue`, which
+    /// defaults to `None`.
+    ///
+    /// ```
+    /// # use starlark::stdlib::starlark_default;
+    /// # assert!(starlark_default(r#"(
+    /// zip_longest()                                   == []
+    /// # and
+    /// zip_longest(range(3), "a".split_codepoints())   == [(0, "a"), (1, None), (2, None)]
+    /// # and
+    /// zip_longest([1], [2, 3], fillvalue=0)           == [(1, 2), (0, 3)]
+    /// # )"#).unwrap());
+    /// ```
+    zip_longest(*args, fillvalue = NoneType::None) {
+        let mut columns = Vec::new();
+        for arg in args {
+            columns.push(arg.to_vec()?);
+        }
+        let len = columns.iter().map(Vec::len).max().unwrap_or(0);
+        let mut v = Vec::with_capacity(len);
+        for idx in 0..len {
+            let row: Vec<Value> = columns
+                .iter()
+                .map(|c| c.get(idx).cloned().unwrap_or_else(|| fillvalue.clone()))
+                .collect();
+            v.push(Value::new(tuple::Tuple::new(row)));
+        }
+        Ok(Value::from(v))
+    }
+}
+
+/// Stable sort of `values` for `sorted()` and `list.sort()`, by the result of `key` if it is
+/// set. `key` is called once per element, before sorting.
+pub(crate) fn sort_values(
+    call_stack: &mut CallStack,
+    env: &TypeValues,
+    values: Vec<Value>,
+    key: Option<Value>,
+    reverse: bool,
+) -> Result<Vec<Value>, ValueError> {
+    let mut keyed = Vec::with_capacity(values.len());
+    for el in values {
+        let k = match key {
+            None => el.clone(),
+            Some(ref key) => key.call(
+                call_stack,
+                env,
+                vec![el.clone()],
+                LinkedHashMap::new(),
+                None,
+                None,
+            )?,
+        };
+        keyed.push((el, k));
+    }
+
+    let mut compare_ok = Ok(());
+    keyed.sort_by(|x: &(Value, Value), y: &(Value, Value)| {
+        let ord_or_err = if reverse {
+            x.1.compare(&y.1).map(Ordering::reverse)
+        } else {
+            x.1.compare(&y.1)
+        };
+        match ord_or_err {
+            Ok(r) => r,
+            Err(e) => {
+                compare_ok = Err(e);
+                Ordering::Equal // does not matter
+            }
+        }
+    });
+    compare_ok?;
+
+    Ok(keyed.into_iter().map(|x| x.0).collect())
+}
+
+/// Find the first element of the iterable `x` whose key compares as `wanted` against all other
+/// keys, where the key is the element itself or the result of calling `key` on it.
+/// Return the index of the element and the element.
+fn find_extremum(
+    call_stack: &mut CallStack,
+    env: &TypeValues,
+    name: &str,
+    x: &Value,
+    key: Option<Value>,
+    wanted: Ordering,
+) -> Result<(i64, Value), ValueError> {
+    find_extremum_opt(call_stack, env, x, key, wanted)?.ok_or_else(|| empty_iterable_error(name))
+}
+
+/// Find the first element of `x` whose key is the greatest (if `wanted` is `Greater`) or the
+/// least (if `wanted` is `Less`), with its index. Return `None` if `x` is empty.
+fn find_extremum_opt(
+    call_stack: &mut CallStack,
+    env: &TypeValues,
+    x: &Value,
+    key: Option<Value>,
+    wanted: Ordering,
+) -> Result<Option<(i64, Value)>, ValueError> {
+    let mut best: Option<(i64, Value, Value)> = None;
+    for (i, el) in x.iter()?.iter().enumerate() {
+        let k = match key {
+            None => el.clone(),
+            Some(ref key) => key.call(
+                call_stack,
+                env,
+                vec![el.clone()],
+                LinkedHashMap::new(),
+                None,
+                None,
+            )?,
+        };
+        let better = match best {
+            None => true,
+            Some((_, _, ref best_key)) => k.compare(best_key)? == wanted,
+        };
+        if better {
+            best = Some((i as i64, el, k));
+        }
+    }
+    Ok(best.map(|(i, el, _)| (i, el)))
+}
+
+/// The iterable to search for `min()` and `max()`: the single positional argument, or the
+/// tuple of all of them.
+fn extremum_args(
+    name: &str,
+    mut args: Vec<Value>,
+    default: &Option<Value>,
+) -> Result<Value, ValueError> {
+    if args.len() == 1 {
+        return Ok(args.swap_remove(0));
+    }
+    if default.is_some() {
+        starlark_err!(
+            INCORRECT_PARAMETER_TYPE_ERROR_CODE,
+            format!(
+                "{}() default can only be used with a single positional argument",
+                name
+            ),
+            "Multiple positional arguments".to_owned()
+        );
+    }
+    Ok(Value::from(args))
+}
+
+fn empty_iterable_error(name: &str) -> ValueError {
+    RuntimeError {
+        code: EMPTY_ITERABLE_ERROR_CODE,
+        message: format!(
+            "Argument is an empty iterable, {}() expect a non empty iterable",
+            name
+        ),
+        label: "Empty".to_owned(),
+    }
+    .into()
+}
+
+/// Merge `over` into `base` for `deep_merge()`, copying the dictionaries of both.
+fn deep_merge_values(base: Option<Value>, over: Value, concat_lists: bool) -> ValueResult {
+    let base_type = base.as_ref().map(Value::get_type);
+    if over.get_type() == "dict" && (base_type.is_none() || base_type == Some("dict")) {
+        let over = over.downcast_ref::<Dictionary>().unwrap();
+        let mut result = Dictionary::new_typed();
+        if let Some(base) = base {
+            for (k, v) in base.downcast_ref::<Dictionary>().unwrap().items() {
+                let merged = match over.get(&k)? {
+                    Some(o) => deep_merge_values(Some(v), o.clone(), concat_lists)?,
+                    None => deep_merge_values(None, v, concat_lists)?,
+                };
+                result.insert(k, merged)?;
+            }
+        }
+        for (k, v) in over.items() {
+            if result.get(&k)?.is_none() {
+                result.insert(k, deep_merge_values(None, v, concat_lists)?)?;
+            }
+        }
+        Ok(Value::new(result))
+    } else if concat_lists && over.get_type() == "list" && base_type == Some("list") {
+        base.unwrap().add(over)
+    } else {
+        Ok(over)
+    }
+}
+
+/// Check the `sep` and `lists` parameters of `flatten_dict()` and `unflatten_dict()`.
+fn check_flatten_options(name: &str, sep: &str, lists: &str) -> Result<(), ValueError> {
+    if sep.is_empty() {
+        starlark_err!(
+            FLATTEN_DICT_INVALID_OPTION_ERROR_CODE,
+            format!("{}() sep must not be empty", name),
+            "Empty separator".to_owned()
+        );
+    }
+    if lists != "keep" && lists != "index" {
+        starlark_err!(
+            FLATTEN_DICT_INVALID_OPTION_ERROR_CODE,
+            format!(
+                "{}() lists must be \"keep\" or \"index\", got {:?}",
+                name, lists
+            ),
+            "Unknown list policy".to_owned()
+        );
+    }
+    Ok(())
+}
+
+/// Insert `value` into `result` under `key` for `flatten_dict()`, flattening it first if it
+/// is a non-empty dictionary, or a non-empty list when `index_lists` is set.
+fn flatten_value(
+    result: &mut Dictionary,
+    key: String,
+    value: Value,
+    sep: &str,
+    index_lists: bool,
+) -> Result<(), ValueError> {
+    let children: Vec<(String, Value)> = match value.get_type() {
+        "dict" => value
+            .downcast_ref::<Dictionary>()
+            .unwrap()
+            .items()
+            .into_iter()
+            .map(|(k, v)| (k.to_str(), v))
+            .collect(),
+        "list" if index_lists => value
+            .iter()?
+            .iter()
+            .enumerate()
+            .map(|(i, v)| (i.to_string(), v))
+            .collect(),
+        _ => Vec::new(),
+    };
+    if children.is_empty() {
+        let key = Value::new(key);
+        if result.get(&key)?.is_some() {
+            starlark_err!(
+                FLATTEN_DICT_KEY_COLLISION_ERROR_CODE,
+                format!("flatten_dict() produced the key {} twice", key.to_repr()),
+                "Key collision".to_owned()
+            );
+        }
+        return result.insert(key, value);
+    }
+    for (k, v) in children {
+        flatten_value(result, format!("{}{}{}", key, sep, k), v, sep, index_lists)?;
+    }
+    Ok(())
+}
+
+/// Nested dictionaries being built by `unflatten_dict()`.
+enum UnflattenedNode {
+    Leaf(Value),
+    Branch(LinkedHashMap<String, UnflattenedNode>),
+}
+
+/// Store `value` in `node` under the path `parts` for `unflatten_dict()`. `key` is the
+/// flattened key, for error messages.
+fn unflatten_insert(
+    node: &mut LinkedHashMap<String, UnflattenedNode>,
+    parts: &[&str],
+    value: Value,
+    key: &str,
+) -> Result<(), ValueError> {
+    let collision = || -> Result<(), ValueError> {
+        Err(RuntimeError {
+            code: FLATTEN_DICT_KEY_COLLISION_ERROR_CODE,
+            message: format!("unflatten_dict() key {:?} conflicts with another key", key),
+            label: "Key collision".to_owned(),
+        }
+        .into())
+    };
+    let (first, rest) = parts.split_first().unwrap();
+    if rest.is_empty() {
+        if node.contains_key(*first) {
+            return collision();
+        }
+        node.insert((*first).to_owned(), UnflattenedNode::Leaf(value));
+        return Ok(());
+    }
+    let child = node
+        .entry((*first).to_owned())
+        .or_insert_with(|| UnflattenedNode::Branch(LinkedHashMap::new()));
+    match child {
+        UnflattenedNode::Branch(child) => unflatten_insert(child, rest, value, key),
+        UnflattenedNode::Leaf(..) => collision(),
+    }
+}
+
+/// Convert the result of `unflatten_dict()` to a value, turning branches whose keys are
+/// `"0"`, `"1"`, ... into lists if `index_lists` is set.
+fn unflattened_to_value(node: UnflattenedNode, index_lists: bool) -> ValueResult {
+    let children = match node {
+        UnflattenedNode::Leaf(value) => return Ok(value),
+        UnflattenedNode::Branch(children) => children,
+    };
+    if index_lists
+        && children
+            .keys()
+            .enumerate()
+            .all(|(i, k)| *k == i.to_string())
+    {
+        let items: Result<Vec<Value>, ValueError> = children
+            .into_iter()
+            .map(|(_, v)| unflattened_to_value(v, index_lists))
+            .collect();
+        return Ok(Value::from(items?));
+    }
+    let mut result = Dictionary::new_typed();
+    for (k, v) in children {
+        result.insert(Value::new(k), unflattened_to_value(v, index_lists)?)?;
+    }
+    Ok(Value::new(result))
+}
+
+/// Compare `a` and `b` for `approx_equal()`.
+fn approx_equal_values(a: &Value, b: &Value, tol: i64) -> Result<bool, ValueError> {
+    if a.get_type() != b.get_type() {
+        return Ok(false);
+    }
+    match a.get_type() {
+        "int" => {
+            let (x, y) = (
+                BigInt::from_value(a).unwrap(),
+                BigInt::from_value(b).unwrap(),
+            );
+            // A difference too large to be represented exceeds any tolerance
+            Ok(match x.sub(&y) {
+                Ok(d) => d.abs() <= BigInt::from(tol),
+                Err(..) => false,
+            })
+        }
+        "list" | "tuple" => {
+            if a.length()? != b.length()? {
+                return Ok(false);
+            }
+            for (x, y) in a.iter()?.iter().zip(b.iter()?.iter()) {
+                if !approx_equal_values(&x, &y, tol)? {
+                    return Ok(false);
+                }
+            }
+            Ok(true)
+        }
+        "dict" => {
+            if a.length()? != b.length()? {
+                return Ok(false);
+            }
+            for k in &a.iter()? {
+                if !b.is_in(&k)? || !approx_equal_values(&a.at(k.clone())?, &b.at(k)?, tol)? {
+                    return Ok(false);
+                }
+            }
+            Ok(true)
+        }
+        _ => a.equals(b),
+    }
+}
+
+/// Check that `value` matches `schema` for `check_schema()`, `path` locating `value` in the
+/// checked value.
+fn check_schema_value(value: &Value, schema: &Value, path: &str) -> Result<(), ValueError> {
+    let mismatch = |message: String| -> Result<(), ValueError> {
+        let message = if path.is_empty() {
+            message
+        } else {
+            format!("{}: {}", path, message)
+        };
+        starlark_err!(
+            SCHEMA_MISMATCH_ERROR_CODE,
+            message,
+            "Schema mismatch".to_owned()
+        )
+    };
+    let expected = match schema.get_type() {
+        "string" => schema.to_str(),
+        "dict" | "list" => schema.get_type().to_owned(),
+        t => {
+            return Err(RuntimeError {
+                code: INCORRECT_PARAMETER_TYPE_ERROR_CODE,
+                message: format!("Invalid schema of type '{}' at '{}'", t, path),
+                label: "Invalid schema".to_owned(),
+            }
+            .into())
+        }
+    };
+    if expected != "any" && expected != value.get_type() {
+        return mismatch(format!("expected {}, got {}", expected, value.get_type()));
+    }
+    match schema.get_type() {
+        "dict" => {
+            for k in &value.iter()? {
+                if !schema.is_in(&k)? {
+                    return mismatch(format!("unexpected key {}", k.to_repr()));
+                }
+            }
+            for k in &schema.iter()? {
+                if !value.is_in(&k)? {
+                    return mismatch(format!("missing key {}", k.to_repr()));
+                }
+                let child = if k.get_type() != "string" {
+                    format!("{}[{}]", path, k.to_repr())
+                } else if path.is_empty() {
+                    k.to_str()
+                } else {
+                    format!("{}.{}", path, k.to_str())
+                };
+                check_schema_value(&value.at(k.clone())?, &schema.at(k)?, &child)?;
+            }
+        }
+        "list" => {
+            if schema.length()? != 1 {
+                return Err(RuntimeError {
+                    code: INCORRECT_PARAMETER_TYPE_ERROR_CODE,
+                    message: format!("Invalid schema at '{}', lists must have one element", path),
+                    label: "Invalid schema".to_owned(),
+                }
+                .into());
+            }
+            let element = schema.at(Value::new(0))?;
+            for (i, v) in value.iter()?.iter().enumerate() {
+                check_schema_value(&v, &element, &format!("{}[{}]", path, i))?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Check that `name` is a valid type or field name for `namedtuple`.
+fn check_namedtuple_name(name: &str) -> Result<(), ValueError> {
+    let valid = match name.chars().next() {
+        Some(c) => c.is_alphabetic() || c == '_',
+        None => false,
+    } && name.chars().all(|c| c.is_alphanumeric() || c == '_');
+    if !valid {
+        starlark_err!(
+            NAMEDTUPLE_INVALID_NAME_ERROR_CODE,
+            format!("'{}' is not a valid namedtuple type or field name", name),
+            "Invalid name".to_owned()
+        );
+    }
+    Ok(())
+}
+
+/// Parse an integer with an optional sign and base prefix, as accepted by `int(x)`.
+fn parse_int_literal(s: &str) -> Option<BigInt> {
+    let (negative, s) = match s.chars().next()? {
+        '+' => (false, &s[1..]),
+        '-' => (true, &s[1..]),
+        _ => (false, s),
+    };
+    let (base, digits) = match s.get(0..2) {
+        Some("0b") | Some("0B") => (2, &s[2..]),
+        Some("0o") | Some("0O") => (8, &s[2..]),
+        Some("0x") | Some("0X") => (16, &s[2..]),
+        _ => (10, s),
+    };
+    // `BigInt::parse` only accepts digits, so a second sign is rejected, as are values too
+    // large to be represented.
+    match BigInt::parse(digits, base) {
+        Ok(Some(i)) if negative => Some(i.neg()),
+        Ok(Some(i)) => Some(i),
+        _ => None,
+    }
+}
 
-        for arg in args {
-            let first = v.is_empty();
-            let mut idx = 0;
-            for e in &arg.iter()? {
-                if first {
-                    v.push(Value::from((e.clone(),)));
-                    idx += 1;
-                } else if idx < v.len() {
-                    v[idx] = v[idx].add(Value::from((e.clone(),)))?;
-                    idx += 1;
-                }
-            }
-            v.truncate(idx);
-        }
-        Ok(Value::from(v))
+/// Units of `parse_size()` and `format_size()`, by increasing size.
+const BINARY_SIZE_UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+const DECIMAL_SIZE_UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+
+/// Parse a size for `parse_size()`, `None` if it is invalid.
+fn parse_size_str(s: &str) -> Option<i64> {
+    let s = s.trim();
+    let number_len = s
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(s.len());
+    let (number, unit) = s.split_at(number_len);
+    let unit = unit.trim_start();
+    let multiplier: i128 = if unit.is_empty() {
+        1
+    } else if let Some(i) = BINARY_SIZE_UNITS.iter().position(|u| *u == unit) {
+        1024i128.pow(i as u32)
+    } else if let Some(i) = DECIMAL_SIZE_UNITS.iter().position(|u| *u == unit) {
+        1000i128.pow(i as u32)
+    } else {
+        return None;
+    };
+    let (whole, fraction) = match number.find('.') {
+        Some(i) => (&number[..i], &number[i + 1..]),
+        None => (number, ""),
+    };
+    if whole.is_empty() || fraction.contains('.') || number.ends_with('.') {
+        return None;
+    }
+    // Avoid overflows of the exact computation below, such sizes are too large anyway
+    if whole.len() + fraction.len() > 24 {
+        return None;
+    }
+    let digits: i128 = format!("{}{}", whole, fraction).parse().ok()?;
+    let scale = 10i128.pow(fraction.len() as u32);
+    let bytes = digits * multiplier;
+    if bytes % scale != 0 {
+        return None;
     }
+    i64::try_from(bytes / scale).ok()
+}
+
+/// Format a size for `format_size()`.
+fn format_size_str(bytes: i64, binary: bool) -> String {
+    let (base, units) = if binary {
+        (1024i128, &BINARY_SIZE_UNITS)
+    } else {
+        (1000i128, &DECIMAL_SIZE_UNITS)
+    };
+    let bytes = i128::from(bytes);
+    let mut unit = 0;
+    while unit + 1 < units.len() && bytes >= base.pow(unit as u32 + 1) {
+        unit += 1;
+    }
+    let unit_size = base.pow(unit as u32);
+    // Round to the nearest hundredth of the unit
+    let hundredths = (bytes * 100 + unit_size / 2) / unit_size;
+    let mut result = (hundredths / 100).to_string();
+    match hundredths % 100 {
+        0 => {}
+        d if d % 10 == 0 => result.push_str(&format!(".{}", d / 10)),
+        d => result.push_str(&format!(".{:02}", d)),
+    }
+    result.push_str(units[unit]);
+    result
 }
 
 /// Return the default global environment, it is not yet frozen so that a caller can refine it.
@@ -955,13 +2632,19 @@ pub fn global_environment() -> (Environment, TypeValues) {
     string::global(&mut env, &mut type_values);
     list::global(&mut env, &mut type_values);
     dict::global(&mut env, &mut type_values);
+    bytes::global(&mut env, &mut type_values);
+    int::global(&mut env, &mut type_values);
     (env, type_values)
 }
 
-/// Default global environment with added non-standard `struct` and `set` extensions.
+/// Default global environment with added non-standard `struct`, `set`, `json`, `fmt` and
+/// `dict_diff` extensions.
 pub fn global_environment_with_extensions() -> (Environment, TypeValues) {
     let (mut env, mut type_values) = global_environment();
     structs::global(&mut env, &mut type_values);
+    diff::global(&mut env, &mut type_values);
+    json::global(&mut env, &mut type_values);
+    fmt::global(&mut env, &mut type_values);
     linked_hash_set::global(&mut env, &mut type_values);
     (env, type_values)
 }
@@ -995,6 +2678,7 @@ pub mod tests {
     use super::starlark_default;
     use super::Dialect;
     use crate::eval::noload::eval;
+    use crate::values::error::{BORROW_MUT_ERROR_CODE, NOT_SUPPORTED_ERROR_CODE};
     use codemap::CodeMap;
     use codemap_diagnostic::Diagnostic;
     use std::sync;
@@ -1016,6 +2700,31 @@ pub mod tests {
         }
     }
 
+    /// Execute `globals` in the global environment, freeze it, then execute `snippet` in a
+    /// child environment, e.g. to test that the values defined by `globals` are immutable.
+    pub fn starlark_default_frozen(globals: &str, snippet: &str) -> Result<bool, Diagnostic> {
+        let map = sync::Arc::new(sync::Mutex::new(CodeMap::new()));
+        let (mut env, type_values) = global_environment();
+        eval(
+            &map,
+            "<globals>",
+            globals,
+            Dialect::Bzl,
+            &mut env,
+            &type_values,
+        )?;
+        let mut env = env.freeze().child("test");
+        eval(
+            &map,
+            "<test>",
+            snippet,
+            Dialect::Bzl,
+            &mut env,
+            &type_values,
+        )
+        .map(|v| v.to_bool())
+    }
+
     /// A simple macro to execute a Starlark snippet and fails if the last statement is false.
     macro_rules! starlark_ok {
         ($($t:expr),+) => (starlark_ok_fn!(starlark_default, $($t),+))
@@ -1033,6 +2742,50 @@ pub mod tests {
         starlark_ok!("True");
     }
 
+    #[test]
+    fn test_accumulate() {
+        starlark_ok!("(accumulate([]) == [])");
+        starlark_ok!("(accumulate([1, 2, 3, 4]) == [1, 3, 6, 10])");
+        starlark_ok!("(accumulate(['a', 'b', 'c']) == ['a', 'ab', 'abc'])");
+        starlark_ok!("(accumulate([1, 2, 3], initial=10) == [10, 11, 13, 16])");
+        starlark_ok!("(accumulate([], initial=0) == [0])");
+        starlark_ok!(
+            "def mul(a, b): return a * b\n(accumulate([1, 2, 3, 4], fn=mul) == [1, 2, 6, 24])"
+        );
+        starlark_ok!("(accumulate([3, 1, 4, 1, 5], max) == [3, 3, 4, 4, 5])");
+        starlark_fail!("accumulate([1, 'a'])");
+        starlark_fail!("def f(a, b): fail('oops')\naccumulate([1, 2], fn=f)");
+    }
+
+    #[test]
+    fn test_catch() {
+        let defs = "def ok(): return [1]\ndef ko(): fail('oops')\ndef div(): return 1 // 0\n";
+        starlark_ok!(defs, "(catch(ok) == (True, [1]))");
+        starlark_ok!(defs, "(catch(ko) == (False, 'fail(): oops'))");
+        starlark_ok!(defs, "(catch(div) == (False, 'Cannot divide by zero'))");
+        starlark_ok!("(catch(list) == (True, []))");
+        starlark_ok!("(not catch(len)[0] and catch(len)[1].startswith('Missing parameter'))");
+        // The evaluation continues after a captured failure
+        starlark_ok!(
+            defs,
+            "def both(): return [catch(ko), catch(ok)]\n\
+             (both() == [(False, 'fail(): oops'), (True, [1])])"
+        );
+        // Recursion aborts the evaluation
+        starlark_fail!(
+            "def rec(): return rec()\ncatch(rec)",
+            crate::eval::RECURSION_ERROR_CODE
+        );
+    }
+
+    #[test]
+    fn test_chain() {
+        starlark_ok!("(chain() == [])");
+        starlark_ok!("(chain([1, 2], (3,), range(4, 6)) == [1, 2, 3, 4, 5])");
+        starlark_ok!("(chain('ab'.split_codepoints(), {'c': 1}) == ['a', 'b', 'c'])");
+        starlark_fail!("chain([1], 2)");
+    }
+
     #[test]
     fn test_any() {
         starlark_ok!("any([0, True])");
@@ -1042,6 +2795,37 @@ pub mod tests {
         starlark_ok!("(not any([0, False]))");
     }
 
+    #[test]
+    fn test_approx_equal() {
+        starlark_ok!(
+            "(approx_equal(1, 1) and not approx_equal(1, 2) and approx_equal(1, 2, tol=1))"
+        );
+        starlark_ok!("(approx_equal('a', 'a') and not approx_equal('a', 'b', tol=10))");
+        starlark_ok!("(not approx_equal(1, '1') and not approx_equal([1], (1,)))");
+        starlark_ok!("(not approx_equal([1, 2], [1]) and not approx_equal({'a': 1}, {'b': 1}))");
+        // Nested structures differing by a number within the tolerance
+        starlark_ok!(
+            "(approx_equal({'a': [1000, {'b': (7, None)}], 'c': 'x'}, \
+                           {'c': 'x', 'a': [1003, {'b': (5, None)}]}, tol=3))"
+        );
+        // Outside the tolerance
+        starlark_ok!(
+            "(not approx_equal({'a': [1000, {'b': (7, None)}], 'c': 'x'}, \
+                               {'c': 'x', 'a': [1003, {'b': (3, None)}]}, tol=3))"
+        );
+        starlark_ok!(
+            "(approx_equal(-9223372036854775807 - 1, 9223372036854775807, tol=0) == False)"
+        );
+        starlark_ok!(
+            "(approx_equal(2 ** 70, 2 ** 70 + 1, tol=1) and not approx_equal(2 ** 70, 1, tol=1) \
+              and approx_equal(-2 ** 64, 5 - 2 ** 64, tol=5))"
+        );
+        starlark_fail!(
+            "approx_equal(1, 1, tol=-1)",
+            super::INCORRECT_PARAMETER_TYPE_ERROR_CODE
+        );
+    }
+
     #[test]
     fn test_all() {
         starlark_ok!("all([True, True])");
@@ -1085,6 +2869,47 @@ pub mod tests {
         starlark_fail!("chr(0x110000)", super::CHR_NOT_UTF8_CODEPOINT_ERROR_CODE);
     }
 
+    #[test]
+    fn test_checksum() {
+        starlark_ok!("(checksum({'a': 1, 'b': (2, 'x')}) == checksum({'b': (2, 'x'), 'a': 1}))");
+        starlark_ok!("(checksum({'a': 1, 'b': (2, 'x')}) != checksum({'a': 1, 'b': (3, 'x')}))");
+        starlark_ok!("(checksum([None, True, 1, '1']) == checksum([None, True, 1, '1']))");
+        starlark_ok!("(checksum(1) != checksum('1'))");
+        starlark_fail!("checksum(len)");
+        starlark_fail!("checksum([len])");
+    }
+
+    #[test]
+    fn test_stable_hash() {
+        // These values are part of the contract of `stable_hash` and must never change
+        starlark_ok!("(stable_hash(None) == -8293261706438822254)");
+        starlark_ok!("(stable_hash(True) == -1859222445340495060)");
+        starlark_ok!("(stable_hash(0) == 4275427143010778709)");
+        starlark_ok!("(stable_hash(-1) == 9161689689776059611)");
+        starlark_ok!("(stable_hash('') == 8559766167582867106)");
+        starlark_ok!("(stable_hash('starlark') == 5239727842688380884)");
+        starlark_ok!("(stable_hash((1, 'a', None)) == 3956735175012157930)");
+        starlark_ok!("(stable_hash(()) == 1838192291662010842)");
+        starlark_ok!("(stable_hash(2 ** 64) == -3384875867443270230)");
+        starlark_ok!("(stable_hash(-2 ** 64) == 8058405347733706690)");
+
+        starlark_ok!("(stable_hash((1, 'a')) == stable_hash(tuple([1, 'a'])))");
+        starlark_ok!("(stable_hash(1) != stable_hash('1'))");
+        starlark_fail!(
+            "stable_hash([1])",
+            crate::values::error::NOT_HASHABLE_VALUE_ERROR_CODE
+        );
+        starlark_fail!(
+            "stable_hash({})",
+            crate::values::error::NOT_HASHABLE_VALUE_ERROR_CODE
+        );
+        starlark_fail!(
+            "stable_hash((1, [2]))",
+            crate::values::error::NOT_HASHABLE_VALUE_ERROR_CODE
+        );
+        starlark_fail!("stable_hash(len)");
+    }
+
     #[test]
     fn test_ord() {
         starlark_ok!("(65 == ord('A'))");
@@ -1092,6 +2917,121 @@ pub mod tests {
         starlark_ok!("(0x1F63F == ord('😿'))");
     }
 
+    #[test]
+    fn test_partition() {
+        starlark_ok!("(partition([], len) == ([], []))");
+        starlark_ok!("(partition(['', 'b', '', 'a'], len) == (['b', 'a'], ['', '']))");
+        starlark_ok!(
+            "def is_odd(n): return n % 2\n",
+            "(partition(range(7), pred=is_odd) == ([1, 3, 5], [0, 2, 4, 6]))"
+        );
+        // The predicate is called once per element, in order
+        starlark_ok!(
+            "calls = []\ndef pred(v):\n  calls.append(v)\n  return v > 2\n",
+            "(partition((3, 1, 4, 1, 5), pred) == ([3, 4, 5], [1, 1]) and calls == [3, 1, 4, 1, 5])"
+        );
+        starlark_fail!("def f(v): fail('oops')\npartition([1], f)");
+        starlark_fail!("partition([1], len)");
+    }
+
+    #[test]
+    fn test_check_schema() {
+        starlark_ok!("(check_schema(1, 'int') == None and check_schema([1, 'a'], 'any') == None)");
+        starlark_ok!("(check_schema([], ['string']) == None)");
+        let schema = "schema = {'name': 'string', 'srcs': {'main': ['string'], 'extra': 'any'}, \
+                      'opts': {1: 'bool'}}\n";
+        starlark_ok!(
+            schema,
+            "(check_schema({'name': 'x', 'srcs': {'main': ['a', 'b'], 'extra': None}, \
+              'opts': {1: True}}, schema) == None)"
+        );
+        let fails = [
+            ("1", "expected dict, got int"),
+            (
+                "{'name': 'x', 'srcs': {'main': ['a', 'b', 3], 'extra': 1}, 'opts': {1: True}}",
+                "srcs.main[2]: expected string, got int",
+            ),
+            (
+                "{'name': 'x', 'srcs': {'main': (), 'extra': 1}, 'opts': {1: True}}",
+                "srcs.main: expected list, got tuple",
+            ),
+            (
+                "{'name': 'x', 'srcs': {'main': []}, 'opts': {1: True}}",
+                "srcs: missing key \"extra\"",
+            ),
+            (
+                "{'name': 'x', 'srcs': {'main': [], 'extra': 1}, 'opts': {1: 2}}",
+                "opts[1]: expected bool, got int",
+            ),
+            (
+                "{'name': 'x', 'srcs': {'main': [], 'extra': 1}, 'opts': {}, 'z': 1}",
+                "unexpected key \"z\"",
+            ),
+        ];
+        for (value, message) in fails.iter() {
+            let err = starlark_default_fail(&format!("{}check_schema({}, schema)", schema, value))
+                .unwrap_err();
+            assert_eq!(Some(super::SCHEMA_MISMATCH_ERROR_CODE.to_owned()), err.code);
+            assert_eq!(*message, err.message);
+        }
+        starlark_fail!(
+            "check_schema(1, 2)",
+            super::INCORRECT_PARAMETER_TYPE_ERROR_CODE
+        );
+        starlark_fail!(
+            "check_schema([1], ['int', 'int'])",
+            super::INCORRECT_PARAMETER_TYPE_ERROR_CODE
+        );
+    }
+
+    #[test]
+    fn test_deep_merge() {
+        starlark_ok!("(deep_merge({}, {}) == {})");
+        starlark_ok!(
+            "(deep_merge({'a': {'x': 1, 'y': {'p': 1}}, 'b': 2}, {'a': {'y': {'q': 2}, 'z': 3}}) \
+              == {'a': {'x': 1, 'y': {'p': 1, 'q': 2}, 'z': 3}, 'b': 2})"
+        );
+        // Keys of the base come first
+        starlark_ok!("(deep_merge({'a': 1, 'b': 2}, {'c': 3, 'a': 4}).keys() == ['a', 'b', 'c'])");
+        // Lists
+        starlark_ok!("(deep_merge({'a': [1]}, {'a': [2]}) == {'a': [2]})");
+        starlark_ok!("(deep_merge({'a': [1]}, {'a': [2]}, lists='replace') == {'a': [2]})");
+        starlark_ok!(
+            "(deep_merge({'a': {'l': [1]}}, {'a': {'l': [2]}}, lists='concat') \
+              == {'a': {'l': [1, 2]}})"
+        );
+        starlark_ok!("(deep_merge({'a': (1,)}, {'a': [2]}, lists='concat') == {'a': [2]})");
+        // Type conflicts
+        starlark_ok!("(deep_merge({'a': {'x': 1}}, {'a': 1}) == {'a': 1})");
+        starlark_ok!("(deep_merge({'a': 1}, {'a': {'x': 1}}) == {'a': {'x': 1}})");
+        starlark_ok!(
+            "(deep_merge({'a': [1]}, {'a': {'x': 1}}, lists='concat') == {'a': {'x': 1}})"
+        );
+        // Inputs are not modified, and the result does not share dictionaries with them
+        starlark_ok!(
+            "base = {'a': {'x': 1}, 'l': [1]}\n\
+             over = {'a': {'y': 2}, 'b': {'z': 3}, 'l': [2]}\n\
+             r = deep_merge(base, over, lists='concat')\n\
+             r['a']['x'] = 0\n\
+             r['b']['z'] = 0\n\
+             r['l'].append(3)\n",
+            "(base == {'a': {'x': 1}, 'l': [1]} and \
+              over == {'a': {'y': 2}, 'b': {'z': 3}, 'l': [2]})"
+        );
+        starlark_fail!(
+            "deep_merge({}, {}, lists='append')",
+            super::DEEP_MERGE_UNKNOWN_LIST_POLICY_ERROR_CODE
+        );
+        starlark_fail!(
+            "deep_merge([], {})",
+            super::INCORRECT_PARAMETER_TYPE_ERROR_CODE
+        );
+        starlark_fail!(
+            "deep_merge({}, 1)",
+            super::INCORRECT_PARAMETER_TYPE_ERROR_CODE
+        );
+    }
+
     #[test]
     fn test_dict() {
         starlark_ok!("(dict() == {})");
@@ -1107,6 +3047,33 @@ pub mod tests {
             "(enumerate(['zero', 'one', 'two']) == [(0, 'zero'), (1, 'one'), (2, 'two')])"
         );
         starlark_ok!("(enumerate(['one', 'two'], 1) == [(1, 'one'), (2, 'two')])");
+        starlark_ok!("(enumerate(['one', 'two'], start=1) == [(1, 'one'), (2, 'two')])");
+        starlark_ok!("(enumerate('ab'.split_codepoints(), start=-1) == [(-1, 'a'), (0, 'b')])");
+        starlark_ok!("(enumerate({'a': 1, 'b': 2}, start=10) == [(10, 'a'), (11, 'b')])");
+        starlark_ok!("(enumerate([]) == [] and enumerate((), start=5) == [])");
+        starlark_ok!(
+            "(enumerate([1, 2], start=9223372036854775807) == \
+              [(9223372036854775807, 1), (2 ** 63, 2)])"
+        );
+        starlark_fail!("enumerate([1], start='a')");
+        starlark_fail!("enumerate(1)");
+    }
+
+    #[test]
+    fn test_group_by() {
+        starlark_ok!("(group_by([], len) == {})");
+        starlark_ok!("(group_by(['bb', 'a', 'cc', 'd'], len) == {2: ['bb', 'cc'], 1: ['a', 'd']})");
+        starlark_ok!("(list(group_by(['bb', 'a', 'cc', 'd'], key=len).keys()) == [2, 1])");
+        starlark_ok!(
+            "def first(p): return p[0]\n",
+            "(group_by([('x', 1), ('y', 2), ('x', 3)], first) == \
+              {'x': [('x', 1), ('x', 3)], 'y': [('y', 2)]})"
+        );
+        starlark_fail!(
+            "def wrap(v): return [v]\n",
+            "group_by([1, 2], wrap)",
+            super::GROUP_BY_KEY_NOT_HASHABLE_ERROR_CODE
+        );
     }
 
     #[test]
@@ -1143,6 +3110,81 @@ pub mod tests {
         starlark_ok!("(list((1,2,3)) == [1, 2, 3])");
     }
 
+    #[test]
+    fn test_lazy_seq() {
+        let counting = "calls = []\n\
+                        def f(i):\n  calls.append(i)\n  return [i]\n\
+                        s = lazy_seq(f, 3)\n";
+        starlark_ok!(counting, "(len(s) == 3 and calls == [])");
+        starlark_ok!(counting, "(s[1] == [1] and s[-2] == [1] and calls == [1])");
+        starlark_ok!(
+            counting,
+            "([x[0] for x in s] == [0, 1, 2] and list(s) == [[0], [1], [2]])"
+        );
+        starlark_ok!(
+            counting,
+            "(s[2] == [2] and list(s) == [[0], [1], [2]] and calls == [2, 0, 1])"
+        );
+        starlark_fail!(&format!("{}s[1].append(2)", counting));
+        starlark_fail!(
+            &format!("{}s[3]", counting),
+            crate::values::error::OUT_OF_BOUND_ERROR_CODE
+        );
+        // Loops stopped early only compute the elements they reached
+        let first_over = format!(
+            "{}def first_over(n):\n  for x in s:\n    if x[0] > n:\n      return x\n",
+            counting
+        );
+        starlark_ok!(
+            &first_over,
+            "(first_over(0) == [1] and calls == [0, 1] and first_over(1) == [2] and calls == [0, 1, 2])"
+        );
+        starlark_ok!(
+            "calls = []\n\
+             def f(i):\n  calls.append(i)\n  return i\n\
+             def first(s):\n  for x in s:\n    return x\n",
+            "(first(lazy_seq(f, 1000000000)) == 0 and calls == [0])"
+        );
+        starlark_fail!(
+            "def f(i):\n  if i == 2:\n    fail('no element')\n  return i\n\
+             def g():\n  return [x for x in lazy_seq(f, 3)]\n\
+             g()",
+            crate::stdlib::USER_FAILURE_ERROR_CODE
+        );
+        starlark_fail!("lazy_seq(str, -1)", super::NEGATIVE_LENGTH_ERROR_CODE);
+        // Elements needing themselves
+        starlark_fail!(
+            "def f(i): return s[i]\ns = lazy_seq(f, 3)\ns[0]",
+            crate::values::error::RECURSIVE_LAZY_SEQ_ERROR_CODE
+        );
+        // Indexing calls `f` with the call stack of the caller
+        let err = starlark_default_fail(
+            "def f(i): fail('boom')\ns = lazy_seq(f, 3)\ndef g(): return s[0]\ng()",
+        )
+        .unwrap_err();
+        assert!(err.message.contains("call to g()"), "{}", err.message);
+        starlark_fail!(
+            "def f(i): return list(s)\ns = lazy_seq(f, 3)\ns[0]",
+            crate::values::error::RECURSIVE_LAZY_SEQ_ERROR_CODE
+        );
+    }
+
+    #[test]
+    fn test_repeat_to() {
+        starlark_ok!("(repeat_to([1, 2, 3], 2) == [1, 2])");
+        starlark_ok!("(repeat_to([1, 2, 3], 3) == [1, 2, 3])");
+        starlark_ok!("(repeat_to((1, 2, 3), 7) == [1, 2, 3, 1, 2, 3, 1])");
+        starlark_ok!("(repeat_to({'a': 1, 'b': 2}, 3) == ['a', 'b', 'a'])");
+        starlark_ok!("(repeat_to(range(1000000000), 2) == [0, 1])");
+        starlark_ok!("(repeat_to([1], 0) == [] and repeat_to([], 0) == [])");
+        starlark_fail!("repeat_to([], 1)", super::EMPTY_ITERABLE_ERROR_CODE);
+        starlark_fail!("repeat_to([1], -1)", super::NEGATIVE_LENGTH_ERROR_CODE);
+        starlark_fail!(
+            "repeat_to([1], 10 ** 12)",
+            crate::values::error::VALUE_TOO_LARGE_ERROR_CODE
+        );
+    }
+
     #[test]
     fn test_repr() {
         starlark_ok!("(repr(1) == '1')");
@@ -1176,6 +3218,16 @@ pub mod tests {
         starlark_ok!("(min([3, 1, 4, 1, 5, 9]) == 1)");
         starlark_ok!("(min('two', 'three', 'four') == 'four')");
         starlark_ok!("(min('two', 'three', 'four', key=len) == 'two')");
+        // Ties are broken by the first occurrence
+        starlark_ok!("(min(['bb', 'a', 'c'], key=len) == 'a')");
+        starlark_ok!("(min([(1, 'b'), (1, 'a')], key=len) == (1, 'b'))");
+        starlark_ok!("(min([], default=None) == None and min([2, 1], default=0) == 1)");
+        starlark_ok!("(min((), key=len, default='none') == 'none')");
+        starlark_fail!("min([])", super::EMPTY_ITERABLE_ERROR_CODE);
+        starlark_fail!(
+            "min(1, 2, default=0)",
+            super::INCORRECT_PARAMETER_TYPE_ERROR_CODE
+        );
     }
 
     #[test]
@@ -1183,6 +3235,209 @@ pub mod tests {
         starlark_ok!("(max([3, 1, 4, 1, 5, 9]) == 9)");
         starlark_ok!("(max('two', 'three', 'four') == 'two')");
         starlark_ok!("(max('two', 'three', 'four', key=len) == 'three')");
+        // Ties are broken by the first occurrence
+        starlark_ok!("(max(['a', 'bb', 'cc'], key=len) == 'bb')");
+        starlark_ok!("(max([(1, 'b'), (1, 'a')], key=len) == (1, 'b'))");
+        starlark_ok!("(max([], default=0) == 0 and max([1, 2], default=0) == 2)");
+        starlark_ok!("(max({}, key=len, default=[]) == [])");
+        // The key function is called once per element
+        starlark_ok!(
+            "calls = []\ndef k(x):\n  calls.append(x)\n  return -x\n",
+            "(max([1, 2, 3], key=k) == 1 and calls == [1, 2, 3])"
+        );
+        starlark_fail!("max([])", super::EMPTY_ITERABLE_ERROR_CODE);
+        starlark_fail!(
+            "max(1, 2, default=0)",
+            super::INCORRECT_PARAMETER_TYPE_ERROR_CODE
+        );
+    }
+
+    #[test]
+    fn test_max_by_min_by() {
+        starlark_ok!("(max_by(['a', 'ccc', 'bb'], len) == 'ccc')");
+        starlark_ok!("(min_by(['ccc', 'a', 'bb'], key=len) == 'a')");
+        // Ties are broken by the first occurrence
+        starlark_ok!("(max_by([(1, 'x'), (2, 'y'), (2, 'z')], len) == (1, 'x'))");
+        starlark_ok!("(max_by(['aa', 'b', 'cc'], len) == 'aa')");
+        starlark_ok!("(min_by(['aa', 'b', 'c'], len) == 'b')");
+        // The key function is called once per element
+        starlark_ok!(
+            "calls = []\ndef k(x):\n  calls.append(x)\n  return -x\n",
+            "(max_by([1, 2, 3], k) == 1 and calls == [1, 2, 3])"
+        );
+        starlark_fail!("max_by([], len)", super::EMPTY_ITERABLE_ERROR_CODE);
+        starlark_fail!("min_by([], len)", super::EMPTY_ITERABLE_ERROR_CODE);
+    }
+
+    #[test]
+    fn test_argmax_argmin() {
+        starlark_ok!("(argmax([3, 1, 4, 1, 5]) == 4)");
+        starlark_ok!("(argmin([3, 1, 4, 1, 5]) == 1)");
+        starlark_ok!("(argmax([5, 1, 5]) == 0 and argmin([1, 0, 0]) == 1)");
+        starlark_ok!("(argmax(['bb', 'a', 'cc'], key=len) == 0)");
+        starlark_ok!("(argmin(['bb', 'a', 'c'], key=len) == 1)");
+        starlark_fail!("argmax([])", super::EMPTY_ITERABLE_ERROR_CODE);
+        starlark_fail!("argmin(())", super::EMPTY_ITERABLE_ERROR_CODE);
+    }
+
+    #[test]
+    fn test_attrdict() {
+        starlark_ok!("(attrdict() == {} and type(attrdict()) == 'dict')");
+        starlark_ok!(
+            "d = attrdict({'a': 1}, b=2); (d.a == 1 and d.b == 2 and d == {'a': 1, 'b': 2})"
+        );
+        starlark_ok!("d = attrdict(); d.a = 1; d.a += 1; (d == {'a': 2} and d['a'] == d.a)");
+        starlark_ok!("d = attrdict({'a': 1}); d['b'] = 2; (d.b == 2)");
+        // Methods take precedence over keys
+        starlark_ok!("d = attrdict({'keys': 1}); (d.keys() == ['keys'] and d['keys'] == 1)");
+        starlark_ok!("d = attrdict({'a': 1, 2: 3}); (dir(d) == sorted(dir({}) + ['a']))");
+        starlark_ok!(
+            "d = attrdict({'a': 1}); (hasattr(d, 'a') and not hasattr(d, 'b') and getattr(d, 'b', 0) == 0)"
+        );
+        // The argument is copied, and nested dictionaries are not converted
+        starlark_ok!("x = {'a': {'b': 1}}; d = attrdict(x); d.c = 2; (x == {'a': {'b': 1}} and not hasattr(d.a, 'b'))");
+        starlark_fail!("attrdict({'a': 1}).b", NOT_SUPPORTED_ERROR_CODE);
+        starlark_fail!("{'a': 1}.a", NOT_SUPPORTED_ERROR_CODE);
+        starlark_fail!("d = {}; d.a = 1", NOT_SUPPORTED_ERROR_CODE);
+        starlark_fail!("attrdict([])", super::INCORRECT_PARAMETER_TYPE_ERROR_CODE);
+        let err = starlark_default_frozen("d = attrdict()", "d.a = 1").unwrap_err();
+        assert_eq!(Some(BORROW_MUT_ERROR_CODE.to_owned()), err.code);
+    }
+
+    #[test]
+    fn test_divmod() {
+        starlark_ok!("(divmod(7, 2) == (3, 1))");
+        starlark_ok!("q, r = divmod(-7, 2)\n(q == -4 and r == 1)");
+        starlark_ok!("(divmod(7, 2).quotient == 3 and divmod(7, 2).remainder == 1)");
+        starlark_ok!("(divmod(7, 2)[0] == 3 and divmod(7, 2)[-1] == 1)");
+        starlark_ok!("(repr(divmod(7, 2)) == 'divmod_result(quotient=3, remainder=1)')");
+        starlark_fail!("divmod(1, 0)");
+    }
+
+    #[test]
+    fn test_namedtuple() {
+        let point = "Point = namedtuple('Point', ['x', 'y'])\n";
+        starlark_ok!(point, "p = Point(1, 2)\n(p.x == 1 and p.y == 2)");
+        starlark_ok!(point, "p = Point(y = 2, x = 1)\n(p[0] == 1 and p[1] == 2)");
+        starlark_ok!(point, "x, y = Point(1, 2)\n(x == 1 and y == 2)");
+        starlark_ok!(point, "(Point(1, 2) == (1, 2) and len(Point(1, 2)) == 2)");
+        starlark_ok!(
+            point,
+            "(type(Point(1, 2)) == 'tuple' and hasattr(Point(1, 2), 'x'))"
+        );
+        starlark_ok!(point, "(repr(Point(1, 'a')) == 'Point(x=1, y=\"a\")')");
+        starlark_ok!(point, "(Point(1, 2)[:1] == (1,))");
+        starlark_fail!(&format!("{}Point(1)", point));
+        starlark_fail!(&format!("{}Point(1, 2, 3)", point));
+        starlark_fail!(&format!("{}Point(1, 2).z", point));
+        starlark_fail!(
+            "namedtuple('T', ['a', 'a'])",
+            super::NAMEDTUPLE_INVALID_NAME_ERROR_CODE
+        );
+        starlark_fail!(
+            "namedtuple('T', ['a b'])",
+            super::NAMEDTUPLE_INVALID_NAME_ERROR_CODE
+        );
+        starlark_fail!(
+            "namedtuple('T', [1])",
+            super::NAMEDTUPLE_INVALID_NAME_ERROR_CODE
+        );
+    }
+
+    #[test]
+    fn test_transitive_closure() {
+        // DAG with a diamond
+        starlark_ok!(
+            "(transitive_closure({'a': ['b', 'c'], 'b': ['d'], 'c': ['d', 'e'], 'd': []}, ['a']) \
+              == ['a', 'b', 'c', 'd', 'e'])"
+        );
+        starlark_ok!(
+            "(transitive_closure({'a': ['b', 'c'], 'b': ['d'], 'c': ['d']}, ['b']) == ['b', 'd'])"
+        );
+        // Cycles, including self loops
+        starlark_ok!(
+            "(transitive_closure({'a': ['b'], 'b': ['c'], 'c': ['a']}, ['b']) == ['b', 'c', 'a'])"
+        );
+        starlark_ok!("(transitive_closure({1: [1, 2], 2: (1,)}, [1]) == [1, 2])");
+        // Disconnected and repeated roots
+        starlark_ok!(
+            "(transitive_closure({'a': ['b'], 'x': ['y'], 'y': ['a']}, ['a', 'x', 'a']) \
+              == ['a', 'x', 'b', 'y'])"
+        );
+        starlark_ok!("(transitive_closure({'a': ['b']}, ['z']) == ['z'])");
+        starlark_ok!("(transitive_closure({'a': ['b']}, []) == [])");
+        starlark_fail!(
+            "transitive_closure([], ['a'])",
+            super::INCORRECT_PARAMETER_TYPE_ERROR_CODE
+        );
+        starlark_fail!("transitive_closure({'a': [[1]]}, ['a'])");
+        starlark_fail!("transitive_closure({'a': 1}, ['a'])");
+    }
+
+    #[test]
+    fn test_try_int() {
+        starlark_ok!("(try_int('42') == 42 and try_int('-7') == -7 and try_int('+3') == 3)");
+        starlark_ok!("(try_int('0x1f') == 31 and try_int('0b101') == 5 and try_int('0o17') == 15)");
+        starlark_ok!("(try_int(12) == 12 and try_int(True) == 1)");
+        // Whitespace is ignored
+        starlark_ok!("(try_int('  42 ') == 42 and try_int('\\t-5\\n') == -5)");
+        // Invalid inputs give None
+        starlark_ok!("(try_int('') == None and try_int('   ') == None)");
+        starlark_ok!("(try_int('abc') == None and try_int('4.2') == None)");
+        starlark_ok!("(try_int('1 2') == None and try_int('--1') == None)");
+        starlark_ok!(
+            "(try_int('0x') == None and try_int('99999999999999999999') == int('99999999999999999999'))"
+        );
+        starlark_ok!("(try_int('18446744073709551616') == 2**64 and try_int(2**64) == 2**64)");
+        starlark_ok!("(try_int('-0x10000000000000000') == -2**64)");
+        starlark_ok!("(try_int([1]) == None and try_int(None) == None)");
+        starlark_ok!("(try_int('-9223372036854775808') == -9223372036854775807 - 1)");
+    }
+
+    #[test]
+    fn test_zip_dict() {
+        starlark_ok!("(zip_dict(['a', 'b'], [1, 2]) == {'a': 1, 'b': 2})");
+        starlark_ok!("(zip_dict((), []) == {})");
+        starlark_ok!("(zip_dict(range(3), ['x', 'y', 'z']) == {0: 'x', 1: 'y', 2: 'z'})");
+        starlark_ok!("(zip_dict(['b', 'a'], [1, 2]).keys() == ['b', 'a'])");
+        // The last value of a duplicate key wins
+        starlark_ok!("(zip_dict(['a', 'b', 'a'], [1, 2, 3]) == {'a': 3, 'b': 2})");
+        starlark_fail!(
+            "zip_dict(['a', 'b'], [1])",
+            super::ZIP_DICT_LENGTH_MISMATCH_ERROR_CODE
+        );
+        starlark_fail!(
+            "zip_dict(['a'], [1, 2])",
+            super::ZIP_DICT_LENGTH_MISMATCH_ERROR_CODE
+        );
+        starlark_fail!("zip_dict([[1]], [1])");
+    }
+
+    #[test]
+    fn test_defaultdict() {
+        let zero = "def zero(): return 0\n";
+        // Missing keys are inserted with the value of the factory
+        starlark_ok!("d = defaultdict(list)\nd['a'].append(1)\n(d == {'a': [1]})");
+        starlark_ok!(
+            zero,
+            "d = defaultdict(zero)\nd['a'] += 2\nd['b'] += 1\n(d == {'a': 2, 'b': 1})"
+        );
+        starlark_ok!(
+            zero,
+            "d = defaultdict(zero)\n(d['x'] == 0 and 'x' in d and len(d) == 1)"
+        );
+        starlark_ok!(
+            "def f(): return 'default'\n",
+            "d = defaultdict(f)\n(d[1] == 'default' and d.keys() == [1])"
+        );
+        // Existing keys are returned as for a dict
+        starlark_ok!("d = defaultdict(list)\nd['a'] = 5\n(d['a'] == 5 and d == {'a': 5})");
+        starlark_ok!("d = defaultdict(list)\nd.update({'b': 2})\n(d['b'] == 2)");
+        // Only `[]` inserts missing keys
+        starlark_ok!("d = defaultdict(list)\n(d.get('a') == None and 'a' not in d and d == {})");
+        starlark_ok!("(type(defaultdict(list)) == 'dict')");
+        starlark_fail!("d = defaultdict(list)\nd[[1]]");
+        starlark_fail!("d = {}\nd['a']");
     }
 
     #[test]
@@ -1190,6 +3445,16 @@ pub mod tests {
         starlark_ok!("(reversed(['a', 'b', 'c']) == ['c', 'b', 'a'])");
         starlark_ok!("(reversed(range(5)) == [4, 3, 2, 1, 0])");
         starlark_ok!("(reversed({'one': 1, 'two': 2}.keys()) == ['two', 'one'])");
+        starlark_ok!("(reversed([]) == [] and reversed(range(0)) == [])");
+        starlark_ok!("(reversed((1, 2)) == [2, 1] and reversed(range(1, 10, 3)) == [7, 4, 1])");
+        starlark_ok!("(reversed(range(10, 0, -4)) == [2, 6, 10])");
+        starlark_ok!("([x * 2 for x in reversed(range(4)) if x != 1] == [6, 4, 0])");
+        starlark_ok!("x = [1, 2, 3]; y = reversed(x); y.append(0); (x == [1, 2, 3])");
+        starlark_fail!(
+            "reversed({'a': 1})",
+            super::INCORRECT_PARAMETER_TYPE_ERROR_CODE
+        );
+        starlark_fail!("reversed(1)", NOT_SUPPORTED_ERROR_CODE);
     }
 
     #[test]
@@ -1209,6 +3474,194 @@ pub mod tests {
         starlark_ok!(
             "(sorted(['two', 'three', 'four'], key=len, reverse=True) == ['three', 'four', 'two'])"
         );
+        starlark_ok!("(sorted([[2], [1, 5], [1], []]) == [[], [1], [1, 5], [2]])");
+        starlark_ok!("(sorted([(1, 'b'), (0, 'z'), (1, 'a')]) == [(0, 'z'), (1, 'a'), (1, 'b')])");
+        // The sort is stable, also in reverse
+        starlark_ok!("(sorted(['b', 'a', 'cc', 'd'], key=len) == ['b', 'a', 'd', 'cc'])");
+        starlark_ok!(
+            "(sorted(['b', 'a', 'cc', 'd'], key=len, reverse=True) == ['cc', 'b', 'a', 'd'])"
+        );
+    }
+
+    #[test]
+    fn test_sorted_items() {
+        starlark_ok!("(sorted_items({}) == [])");
+        starlark_ok!("(sorted_items({'a': 3, 'b': 1, 'c': 2}) == [('b', 1), ('c', 2), ('a', 3)])");
+        starlark_ok!(
+            "(sorted_items({'a': 3, 'b': 1, 'c': 2}, reverse=True) == [('a', 3), ('c', 2), ('b', 1)])"
+        );
+        starlark_ok!(
+            "(sorted_items({'b': 1, 'c': 2, 'a': 3}, by='key') == [('a', 3), ('b', 1), ('c', 2)])"
+        );
+        starlark_ok!(
+            "(sorted_items({'b': 1, 'c': 2, 'a': 3}, by='key', reverse=True) == [('c', 2), ('b', 1), ('a', 3)])"
+        );
+        // Ties keep the insertion order
+        starlark_ok!(
+            "(sorted_items({'z': 1, 'a': 0, 'm': 1, 'b': 0}) == [('a', 0), ('b', 0), ('z', 1), ('m', 1)])"
+        );
+        starlark_ok!(
+            "(sorted_items({'z': 1, 'a': 0, 'm': 1, 'b': 0}, reverse=True) == [('z', 1), ('m', 1), ('a', 0), ('b', 0)])"
+        );
+        starlark_fail!(
+            "sorted_items({'a': 1, 'b': 'x'})",
+            crate::values::error::NOT_SUPPORTED_ERROR_CODE
+        );
+        starlark_fail!(
+            "sorted_items({'a': 1}, by='size')",
+            super::INCORRECT_PARAMETER_TYPE_ERROR_CODE
+        );
+        starlark_fail!(
+            "sorted_items([1, 2])",
+            super::INCORRECT_PARAMETER_TYPE_ERROR_CODE
+        );
+    }
+
+    #[test]
+    fn test_parse_size() {
+        starlark_ok!("(parse_size('0') == 0 and parse_size('42') == 42 and parse_size('7B') == 7)");
+        starlark_ok!("(parse_size('1KiB') == 1024 and parse_size('1KB') == 1000)");
+        starlark_ok!("(parse_size('10MiB') == 10485760 and parse_size('10MB') == 10000000)");
+        starlark_ok!("(parse_size('2GiB') == 2147483648 and parse_size('2GB') == 2000000000)");
+        starlark_ok!(
+            "(parse_size('1TiB') == 1099511627776 and parse_size('1TB') == 10 * 1000000 * 100000)"
+        );
+        starlark_ok!("(parse_size(' 1.5 KiB ') == 1536 and parse_size('0.25MB') == 250000)");
+        for invalid in &[
+            "parse_size('10XB')",
+            "parse_size('10kib')",
+            "parse_size('MiB')",
+            "parse_size('')",
+            "parse_size('-1KB')",
+            "parse_size('1.2.3KB')",
+            "parse_size('1.KB')",
+            "parse_size('1.0001KB')",
+            "parse_size('9000000TiB')",
+        ] {
+            starlark_fail!(invalid, super::INVALID_SIZE_ERROR_CODE);
+        }
+    }
+
+    #[test]
+    fn test_flatten_dict() {
+        starlark_ok!("(flatten_dict({}) == {})");
+        starlark_ok!(
+            "(flatten_dict({'a': {'b': 1, 'c': {'d': 2}}, 'e': 3}) == {'a.b': 1, 'a.c.d': 2, 'e': 3})"
+        );
+        starlark_ok!("(flatten_dict({'a': {'b': 1}, 'c': 2}).keys() == ['a.b', 'c'])");
+        starlark_ok!("(flatten_dict({'a': {'b': 1}}, sep='__') == {'a__b': 1})");
+        // Empty dictionaries and lists are kept as values
+        starlark_ok!("(flatten_dict({'a': {}, 'b': []}, lists='index') == {'a': {}, 'b': []})");
+        starlark_ok!("(flatten_dict({'a': {1: [1, 2]}}) == {'a.1': [1, 2]})");
+        starlark_ok!(
+            "(flatten_dict({'a': [1, [2], {'b': 3}]}, lists='index') \
+              == {'a.0': 1, 'a.1.0': 2, 'a.2.b': 3})"
+        );
+        starlark_fail!(
+            "flatten_dict({'a.b': 1, 'a': {'b': 2}})",
+            super::FLATTEN_DICT_KEY_COLLISION_ERROR_CODE
+        );
+        starlark_fail!(
+            "flatten_dict({'a_0': 0, 'a': [1]}, sep='_', lists='index')",
+            super::FLATTEN_DICT_KEY_COLLISION_ERROR_CODE
+        );
+        starlark_fail!(
+            "flatten_dict({}, sep='')",
+            super::FLATTEN_DICT_INVALID_OPTION_ERROR_CODE
+        );
+        starlark_fail!(
+            "flatten_dict({}, lists='concat')",
+            super::FLATTEN_DICT_INVALID_OPTION_ERROR_CODE
+        );
+        starlark_fail!(
+            "flatten_dict([])",
+            super::INCORRECT_PARAMETER_TYPE_ERROR_CODE
+        );
+    }
+
+    #[test]
+    fn test_unflatten_dict() {
+        starlark_ok!("(unflatten_dict({}) == {})");
+        starlark_ok!(
+            "(unflatten_dict({'a.b': 1, 'a.c.d': 2, 'e': 3}) == {'a': {'b': 1, 'c': {'d': 2}}, 'e': 3})"
+        );
+        starlark_ok!("(unflatten_dict({'a.0': 1, 'a.1': 2}) == {'a': {'0': 1, '1': 2}})");
+        starlark_ok!("(unflatten_dict({'a.0': 1, 'a.1': 2}, lists='index') == {'a': [1, 2]})");
+        // Only keys which are consecutive indices starting at 0 become lists
+        starlark_ok!("(unflatten_dict({'a.1': 1}, lists='index') == {'a': {'1': 1}})");
+        starlark_ok!("(unflatten_dict({'0': 1}, lists='index') == {'0': 1})");
+        starlark_ok!(
+            "(unflatten_dict({'a__b': 1, 'a.c': 2}, sep='__') == {'a': {'b': 1}, 'a.c': 2})"
+        );
+        // Round trips
+        for d in &[
+            "{'a': {'b': 1, 'c': {'d': [1, 2]}}, 'e': {}}",
+            "{'a': [1, {'b': [2, 3]}], 'c': 'x'}",
+        ] {
+            starlark_ok!(&format!("(unflatten_dict(flatten_dict({0})) == {0})", d));
+            starlark_ok!(&format!(
+                "(unflatten_dict(flatten_dict({0}, lists='index'), lists='index') == {0})",
+                d
+            ));
+        }
+        starlark_fail!(
+            "unflatten_dict({'a': 1, 'a.b': 2})",
+            super::FLATTEN_DICT_KEY_COLLISION_ERROR_CODE
+        );
+        starlark_fail!(
+            "unflatten_dict({'a.b': 1, 'a': 2})",
+            super::FLATTEN_DICT_KEY_COLLISION_ERROR_CODE
+        );
+        starlark_fail!(
+            "unflatten_dict({1: 2})",
+            super::INCORRECT_PARAMETER_TYPE_ERROR_CODE
+        );
+        starlark_fail!(
+            "unflatten_dict({}, sep='')",
+            super::FLATTEN_DICT_INVALID_OPTION_ERROR_CODE
+        );
+    }
+
+    #[test]
+    fn test_format_size() {
+        starlark_ok!("(format_size(0) == '0B' and format_size(1023) == '1023B')");
+        starlark_ok!("(format_size(1024) == '1KiB' and format_size(1000, binary=False) == '1KB')");
+        starlark_ok!(
+            "(format_size(1000) == '1000B' and format_size(1024, binary=False) == '1.02KB')"
+        );
+        starlark_ok!("(format_size(10485760) == '10MiB' and format_size(1536) == '1.5KiB')");
+        starlark_ok!("(format_size(1099511627776 * 2048) == '2048TiB')");
+        starlark_ok!("(format_size(1234567, binary=False) == '1.23MB')");
+        starlark_fail!("format_size(-1)", super::INVALID_SIZE_ERROR_CODE);
+        // Sizes with at most two decimals in their unit round-trip
+        for size in &["0B", "512B", "1KiB", "1.5KiB", "10MiB", "1.25GiB", "3TiB"] {
+            starlark_ok!(&format!("(format_size(parse_size('{0}')) == '{0}')", size));
+        }
+        for size in &["999B", "1KB", "2.5MB", "1.01GB", "7TB"] {
+            starlark_ok!(&format!(
+                "(format_size(parse_size('{0}'), binary=False) == '{0}')",
+                size
+            ));
+        }
+    }
+
+    #[test]
+    fn test_unique() {
+        starlark_ok!("(unique([]) == [])");
+        starlark_ok!("(unique([3, 1, 3, 2, 1]) == [3, 1, 2])");
+        starlark_ok!("(unique(('b', 'a', 'b', 'a')) == ['b', 'a'])");
+        starlark_ok!("(unique(range(3)) == [0, 1, 2])");
+        // Values of different types are distinct
+        starlark_ok!("(unique([1, True, (1, 2), (1, 2)]) == [1, True, (1, 2)])");
+        starlark_ok!("(unique([[1], 2, [1], [2], 2], by_equality=True) == [[1], 2, [2]])");
+        starlark_fail!(
+            "unique([1, [2], 1])",
+            super::UNIQUE_ELEMENT_NOT_HASHABLE_ERROR_CODE
+        );
+        starlark_fail!(
+            "unique([{}], by_equality=False)",
+            super::UNIQUE_ELEMENT_NOT_HASHABLE_ERROR_CODE
+        );
     }
 
     #[test]
@@ -1216,5 +3669,29 @@ pub mod tests {
         starlark_ok!("(zip() == [])");
         starlark_ok!("(zip(range(5)) == [(0,), (1,), (2,), (3,), (4,)])");
         starlark_ok!("(zip(range(5), 'abc'.split_codepoints()) == [(0, 'a'), (1, 'b'), (2, 'c')])");
+        starlark_ok!("(zip([1, 2, 3], 'ab'.split_codepoints(), (True, False, None, 0)) == [(1, 'a', True), (2, 'b', False)])");
+        starlark_ok!("(zip([1, 2], [], [3]) == [])");
+        starlark_ok!("(zip([1, 2], (3, 4), strict=True) == [(1, 3), (2, 4)])");
+        starlark_ok!("(zip(strict=True) == [] and zip([], [], strict=True) == [])");
+        starlark_fail!(
+            "zip([1, 2], [3], strict=True)",
+            super::ZIP_LENGTH_MISMATCH_ERROR_CODE
+        );
+        starlark_fail!(
+            "zip([1], [2], [3, 4], strict=True)",
+            super::ZIP_LENGTH_MISMATCH_ERROR_CODE
+        );
+    }
+
+    #[test]
+    fn test_zip_longest() {
+        starlark_ok!("(zip_longest() == [])");
+        starlark_ok!("(zip_longest([1, 2]) == [(1,), (2,)])");
+        starlark_ok!("(zip_longest([1, 2, 3], ['a']) == [(1, 'a'), (2, None), (3, None)])");
+        starlark_ok!(
+            "(zip_longest([1], [2, 3], [4, 5, 6]) == [(1, 2, 4), (None, 3, 5), (None, None, 6)])"
+        );
+        starlark_ok!("(zip_longest([1], [2, 3], fillvalue='-') == [(1, 2), ('-', 3)])");
+        starlark_ok!("(zip_longest([], [], fillvalue=0) == [])");
     }
 }
@@ -14,6 +14,7 @@
 
 //! Methods for the `string` type.
 
+use crate::values::bigint::BigInt;
 use crate::values::error::*;
 use crate::values::none::NoneType;
 use crate::values::*;
@@ -26,6 +27,8 @@ pub const FORMAT_STRING_UNMATCHED_BRACKET_ERROR_CODE: &str = "UF01";
 pub const FORMAT_STRING_ORDER_INDEX_MIX_ERROR_CODE: &str = "UF02";
 pub const FORMAT_STRING_INVALID_SPECIFIER_ERROR_CODE: &str = "UF03";
 pub const FORMAT_STRING_INVALID_CHARACTER_ERROR_CODE: &str = "UF04";
+pub const TEMPLATE_KEY_NOT_FOUND_ERROR_CODE: &str = "UF05";
+pub const TEMPLATE_INVALID_PLACEHOLDER_ERROR_CODE: &str = "UF06";
 
 macro_rules! ok {
     ($e:expr) => {
@@ -47,19 +50,10 @@ fn format_capture<T: Iterator<Item = Value>>(
     args: &Value,
     kwargs: &Value,
 ) -> Result<String, ValueError> {
-    let (n, conv) = {
-        if let Some(x) = capture.find('!') {
-            (capture.get(1..x).unwrap(), capture.get(x + 1..).unwrap())
-        } else {
-            (capture.get(1..).unwrap(), "s")
-        }
-    };
-    let conv_s = |x: Value| x.to_str();
-    let conv_r = |x: Value| x.to_repr();
-    let conv: &dyn Fn(Value) -> String = match conv {
-        "s" => &conv_s,
-        "r" => &conv_r,
-        c => starlark_err!(
+    let (field, conv, spec) = split_replacement_field(capture.get(1..).unwrap());
+    match conv {
+        None | Some("s") | Some("r") => {}
+        Some(c) => starlark_err!(
             FORMAT_STRING_INVALID_SPECIFIER_ERROR_CODE,
             format!(
                 concat!(
@@ -71,7 +65,12 @@ fn format_capture<T: Iterator<Item = Value>>(
             "Invalid format string specifier".to_owned()
         ),
     };
-    if n.is_empty() {
+    let n_end = field.find(&['.', '['][..]).unwrap_or(field.len());
+    let (n, accessors) = field.split_at(n_end);
+    if let Some(x) = n.chars().find(|c| [',', ']'].contains(c)) {
+        return Err(invalid_replacement_field_character(x));
+    }
+    let value = if n.is_empty() {
         if *captured_by_index {
             starlark_err!(
                 FORMAT_STRING_ORDER_INDEX_MIX_ERROR_CODE,
@@ -85,7 +84,7 @@ fn format_capture<T: Iterator<Item = Value>>(
         } else {
             *captured_by_order = true;
             if let Some(x) = it.next() {
-                return Ok(conv(x));
+                x
             } else {
                 starlark_err!(
                     OUT_OF_BOUND_ERROR_CODE,
@@ -107,20 +106,368 @@ fn format_capture<T: Iterator<Item = Value>>(
             )
         } else {
             *captured_by_index = true;
-            Ok(conv(args.at(Value::from(i64::from_str(n).unwrap()))?))
+            args.at(Value::from(i64::from_str(n).unwrap()))?
         }
     } else {
-        if let Some(x) = n.chars().find(|c| match c {
-            '.' | ',' | '[' | ']' => true,
-            _ => false,
-        }) {
-            starlark_err!(
-                FORMAT_STRING_INVALID_CHARACTER_ERROR_CODE,
-                format!("Invalid character '{}' inside replacement field", x),
-                format!("Invalid character '{}'", x)
-            )
+        kwargs.at(Value::from(n))?
+    };
+    let value = access_field(value, accessors)?;
+    let value = match conv {
+        Some("r") => Value::from(value.to_repr()),
+        Some(..) => Value::from(value.to_str()),
+        None => value,
+    };
+    if spec.is_empty() {
+        Ok(value.to_str())
+    } else {
+        FormatSpec::parse(spec)?.format(&value)
+    }
+}
+
+/// Split the content of a replacement field into its field name, conversion and format
+/// specifier, e.g. `name[0]!r:>10` into `name[0]`, `r` and `>10`.
+fn split_replacement_field(field: &str) -> (&str, Option<&str>, &str) {
+    let mut in_brackets = false;
+    let end = field.find(|c| {
+        match c {
+            '[' => in_brackets = true,
+            ']' => in_brackets = false,
+            _ => {}
+        }
+        !in_brackets && (c == '!' || c == ':')
+    });
+    let (name, rest) = match end {
+        Some(end) => field.split_at(end),
+        None => return (field, None, ""),
+    };
+    if let Some(spec) = rest.strip_prefix(':') {
+        return (name, None, spec);
+    }
+    match rest.find(':') {
+        Some(colon) => (name, Some(&rest[1..colon]), &rest[colon + 1..]),
+        None => (name, Some(&rest[1..]), ""),
+    }
+}
+
+fn invalid_replacement_field_character(c: char) -> ValueError {
+    RuntimeError {
+        code: FORMAT_STRING_INVALID_CHARACTER_ERROR_CODE,
+        message: format!("Invalid character '{}' inside replacement field", c),
+        label: format!("Invalid character '{}'", c),
+    }
+    .into()
+}
+
+/// Apply the `.attribute` and `[index]` accessors following a field name to `value`.
+///
+/// An index made of digits is an int, anything else is a string key.
+fn access_field(mut value: Value, mut accessors: &str) -> Result<Value, ValueError> {
+    while !accessors.is_empty() {
+        let end = accessors[1..]
+            .find(&['.', '['][..])
+            .map_or(accessors.len(), |i| i + 1);
+        let (accessor, rest) = accessors.split_at(end);
+        accessors = rest;
+        if let Some(attr) = accessor.strip_prefix('.') {
+            if !is_identifier(attr) {
+                return Err(invalid_replacement_field_character('.'));
+            }
+            value = value.get_attr(attr)?;
+        } else {
+            let key = match accessor.find(']') {
+                Some(close) if close == accessor.len() - 1 && close > 1 => &accessor[1..close],
+                _ => return Err(invalid_replacement_field_character('[')),
+            };
+            value = if key.chars().all(|c| c.is_ascii_digit()) {
+                value.at(Value::from(i64::from_str(key).unwrap()))?
+            } else {
+                value.at(Value::from(key))?
+            };
+        }
+    }
+    Ok(value)
+}
+
+fn is_identifier(s: &str) -> bool {
+    let mut chars = s.chars();
+    match chars.next() {
+        Some(c) if c == '_' || c.is_alphabetic() => chars.all(|c| c == '_' || c.is_alphanumeric()),
+        _ => false,
+    }
+}
+
+/// A format specifier of `string.format`, following the Python syntax
+/// `[[fill]align][sign][#][0][width][grouping][.precision][type]`.
+#[derive(Default)]
+struct FormatSpec {
+    fill: Option<char>,
+    align: Option<char>,
+    sign: Option<char>,
+    alternate: bool,
+    width: usize,
+    grouping: Option<char>,
+    precision: Option<usize>,
+    format_type: Option<char>,
+}
+
+fn invalid_format_spec(message: String) -> ValueError {
+    RuntimeError {
+        code: FORMAT_STRING_INVALID_SPECIFIER_ERROR_CODE,
+        message,
+        label: "Invalid format specifier".to_owned(),
+    }
+    .into()
+}
+
+impl FormatSpec {
+    fn parse(spec: &str) -> Result<FormatSpec, ValueError> {
+        let chars: Vec<char> = spec.chars().collect();
+        let is_align = |c: &char| "<>=^".contains(*c);
+        let mut result = FormatSpec::default();
+        let mut i = 0;
+        if chars.len() >= 2 && is_align(&chars[1]) {
+            result.fill = Some(chars[0]);
+            result.align = Some(chars[1]);
+            i = 2;
+        } else if !chars.is_empty() && is_align(&chars[0]) {
+            result.align = Some(chars[0]);
+            i = 1;
+        }
+        if let Some(c) = chars.get(i).filter(|c| "+- ".contains(**c)) {
+            result.sign = Some(*c);
+            i += 1;
+        }
+        if chars.get(i) == Some(&'#') {
+            result.alternate = true;
+            i += 1;
+        }
+        if chars.get(i) == Some(&'0') {
+            if result.align.is_none() {
+                result.fill = Some('0');
+                result.align = Some('=');
+            }
+            i += 1;
+        }
+        // The number at `i`, if any, which must not exceed `MAX_REQUESTED_LENGTH`
+        let digits = |i: &mut usize, what: &str| -> Result<Option<usize>, ValueError> {
+            let start = *i;
+            while *i < chars.len() && chars[*i].is_ascii_digit() {
+                *i += 1;
+            }
+            if start == *i {
+                return Ok(None);
+            }
+            let n = chars[start..*i]
+                .iter()
+                .collect::<String>()
+                .parse::<usize>()
+                .unwrap_or(usize::MAX);
+            check_requested_length(n, what).map(Some)
+        };
+        result.width = digits(&mut i, "Format specifier width")?.unwrap_or(0);
+        if let Some(c) = chars.get(i).filter(|c| **c == ',' || **c == '_') {
+            result.grouping = Some(*c);
+            i += 1;
+        }
+        if chars.get(i) == Some(&'.') {
+            i += 1;
+            match digits(&mut i, "Format specifier precision")? {
+                Some(precision) => result.precision = Some(precision),
+                None => {
+                    return Err(invalid_format_spec(
+                        "Format specifier missing precision".to_owned(),
+                    ))
+                }
+            }
+        }
+        result.format_type = chars.get(i).cloned();
+        if chars.len() > i + 1 {
+            return Err(invalid_format_spec(format!(
+                "Invalid format specifier '{}'",
+                spec
+            )));
+        }
+        Ok(result)
+    }
+
+    fn format(&self, value: &Value) -> Result<String, ValueError> {
+        if value.get_type() == "int" {
+            self.format_int(value)
+        } else {
+            self.format_str(value.to_str())
+        }
+    }
+
+    fn format_str(&self, s: String) -> Result<String, ValueError> {
+        match self.format_type {
+            None | Some('s') => {}
+            Some(t) => {
+                return Err(invalid_format_spec(format!(
+                    "Unknown format code '{}' for a string",
+                    t
+                )))
+            }
+        }
+        if self.sign.is_some() || self.alternate || self.grouping.is_some() {
+            return Err(invalid_format_spec(
+                "Sign, '#' and grouping are not allowed in string format specifier".to_owned(),
+            ));
+        }
+        if self.align == Some('=') {
+            return Err(invalid_format_spec(
+                "'=' alignment not allowed in string format specifier".to_owned(),
+            ));
+        }
+        let s = match self.precision {
+            Some(precision) => s.chars().take(precision).collect(),
+            None => s,
+        };
+        Ok(self.pad("", &s, '<'))
+    }
+
+    fn format_int(&self, value: &Value) -> Result<String, ValueError> {
+        if self.precision.is_some() {
+            return Err(invalid_format_spec(
+                "Precision not allowed in integer format specifier".to_owned(),
+            ));
+        }
+        let repr = value.to_str();
+        let negative = repr.starts_with('-');
+        let (digits, prefix, group_size) = match self.format_type {
+            None | Some('d') => (repr.trim_start_matches('-').to_owned(), "", 3),
+            Some(t @ 'b') | Some(t @ 'o') | Some(t @ 'x') | Some(t @ 'X') => {
+                if self.grouping == Some(',') {
+                    return Err(invalid_format_spec(format!(
+                        "Cannot specify ',' with '{}'",
+                        t
+                    )));
+                }
+                let abs = BigInt::from_value(value).unwrap().abs();
+                match t {
+                    'b' => (format!("{:b}", abs), "0b", 4),
+                    'o' => (format!("{:o}", abs), "0o", 4),
+                    'x' => (format!("{:x}", abs), "0x", 4),
+                    _ => (format!("{:X}", abs), "0X", 4),
+                }
+            }
+            Some(t) => {
+                return Err(invalid_format_spec(format!(
+                    "Unknown format code '{}' for an int",
+                    t
+                )))
+            }
+        };
+        let digits = match self.grouping {
+            Some(sep) => {
+                let chars: Vec<char> = digits.chars().collect();
+                let groups: Vec<String> = chars
+                    .rchunks(group_size)
+                    .rev()
+                    .map(|g| g.iter().collect())
+                    .collect();
+                groups.join(&sep.to_string())
+            }
+            None => digits,
+        };
+        let sign = match (negative, self.sign) {
+            (true, _) => "-",
+            (false, Some('+')) => "+",
+            (false, Some(' ')) => " ",
+            _ => "",
+        };
+        let prefix = if self.alternate { prefix } else { "" };
+        Ok(self.pad(&format!("{}{}", sign, prefix), &digits, '>'))
+    }
+
+    /// Pad `prefix` followed by `body` to the width, `=` alignment padding between them.
+    fn pad(&self, prefix: &str, body: &str, default_align: char) -> String {
+        let len = prefix.chars().count() + body.chars().count();
+        let padding = self.width.saturating_sub(len);
+        let fill = |n: usize| self.fill.unwrap_or(' ').to_string().repeat(n);
+        match self.align.unwrap_or(default_align) {
+            '<' => format!("{}{}{}", prefix, body, fill(padding)),
+            '^' => format!(
+                "{}{}{}{}",
+                fill(padding / 2),
+                prefix,
+                body,
+                fill(padding - padding / 2)
+            ),
+            '=' => format!("{}{}{}", prefix, fill(padding), body),
+            _ => format!("{}{}{}", fill(padding), prefix, body),
+        }
+    }
+}
+
+/// The fill character of `center`, `ljust` and `rjust`, which must be a single character.
+fn fill_char(fillchar: &str, function: &str) -> Result<char, ValueError> {
+    let mut chars = fillchar.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Ok(c),
+        _ => Err(RuntimeError {
+            code: INCORRECT_PARAMETER_TYPE_ERROR_CODE,
+            message: format!(
+                "{}() expect a fill character of length 1 while got {:?}",
+                function, fillchar
+            ),
+            label: "fill character must be exactly one character".to_owned(),
+        }
+        .into()),
+    }
+}
+
+/// Pad `s` with `left` and `right` times `fill`.
+fn pad(s: &str, left: usize, right: usize, fill: char) -> String {
+    let fill = fill.to_string();
+    format!("{}{}{}", fill.repeat(left), s, fill.repeat(right))
+}
+
+/// The number of fill characters needed to make `s` `width` characters long, `width` being
+/// at most `MAX_REQUESTED_LENGTH`.
+fn padding(s: &str, width: i64, function: &str) -> Result<usize, ValueError> {
+    let width = usize::try_from(width.max(0)).unwrap_or(usize::MAX);
+    Ok(check_requested_length(width, function)?.saturating_sub(s.chars().count()))
+}
+
+/// The part of `s` from the char index `start` to the char index `end`, as computed by
+/// `convert_indices!`, or `None` if `start` is after `end`.
+fn char_slice(s: &str, start: usize, end: usize) -> Option<&str> {
+    if start > end {
+        return None;
+    }
+    let byte_offset = |i: usize| {
+        s.char_indices()
+            .nth(i)
+            .map_or(s.len(), |(offset, _)| offset)
+    };
+    Some(&s[byte_offset(start)..byte_offset(end)])
+}
+
+/// The candidate affixes of `startswith` and `endswith`: a string, or a tuple or list of
+/// strings.
+fn affix_candidates(affixes: &Value, function: &str) -> Result<Vec<String>, ValueError> {
+    let invalid = |ty: &str| -> ValueError {
+        RuntimeError {
+            code: INCORRECT_PARAMETER_TYPE_ERROR_CODE,
+            message: format!(
+                "{}() expect a string or a tuple of strings while got a value of type {}",
+                function, ty
+            ),
+            label: format!("type {} while expected string", ty),
         }
-        Ok(conv(kwargs.at(Value::from(n))?))
+        .into()
+    };
+    match affixes.get_type() {
+        "string" => Ok(vec![affixes.to_str()]),
+        "tuple" | "list" => affixes
+            .iter()?
+            .iter()
+            .map(|a| match a.get_type() {
+                "string" => Ok(a.to_str()),
+                ty => Err(invalid(ty)),
+            })
+            .collect(),
+        ty => Err(invalid(ty)),
     }
 }
 
@@ -241,6 +588,36 @@ starlark_module! {global =>
         ok!(result)
     }
 
+    /// string.center: center a string in a given width.
+    ///
+    /// `S.center(width[, fillchar])` returns S centered in a string of `width`
+    /// characters, padded on both sides with `fillchar`, a space by default.
+    /// When the padding cannot be split evenly, the extra character goes to
+    /// the right, as in Python. S is returned unchanged if it is already at
+    /// least `width` characters long.
+    ///
+    /// `center` fails if `fillchar` is not exactly one character.
+    ///
+    /// Examples:
+    ///
+    /// ```
+    /// # use starlark::stdlib::starlark_default;
+    /// # assert!(starlark_default(r#"(
+    /// "ab".center(6) == "  ab  "
+    /// # and
+    /// "ab".center(5, "*") == "**ab*"
+    /// # and
+    /// "abc".center(2) == "abc"
+    /// # )"#).unwrap());
+    /// ```
+    string.center(this: String, width: i64, fillchar: String = " ".to_owned(), /) {
+        let fill = fill_char(&fillchar, "string.center")?;
+        let total = padding(&this, width, "string.center()")?;
+        // Same rounding as Python, which depends on the parity of `width`.
+        let left = total / 2 + (total & width as usize & 1);
+        ok!(pad(&this, left, total - left, fill))
+    }
+
     /// [string.codepoints](
     /// https://github.com/google/skylark/blob/3705afa472e466b8b061cce44b47c9ddc6db696d/doc/spec.md#string·codepoints
     /// ): returns an iterable of the unicode codepoint of a string.
@@ -318,7 +695,12 @@ starlark_module! {global =>
     /// https://github.com/google/skylark/blob/3705afa472e466b8b061cce44b47c9ddc6db696d/doc/spec.md#string·endswith
     /// ): determine if a string ends with a given suffix.
     ///
-    /// `S.endswith(suffix)` reports whether the string S has the specified suffix.
+    /// `S.endswith(suffix[, start[, end]])` reports whether the string S has the specified
+    /// suffix. `suffix` may also be a tuple or a list of strings, in which case `endswith`
+    /// reports whether S ends with any of them.
+    ///
+    /// If either or both of `start` or `end` are specified, they specify a subrange of S
+    /// to test instead of the whole string, interpreted like in `S.find`.
     ///
     /// Examples:
     ///
@@ -326,10 +708,22 @@ starlark_module! {global =>
     /// # use starlark::stdlib::starlark_default;
     /// # assert!(starlark_default(r#"(
     /// "filename.sky".endswith(".sky") == True
+    /// # and
+    /// "filename.bzl".endswith((".sky", ".bzl")) == True
+    /// # and
+    /// "filename.sky".endswith("name", 0, 8) == True
     /// # )"#).unwrap());
     /// ```
-    string.endswith(this: String, suffix: String, /) {
-        ok!(this.ends_with(suffix.as_str()))
+    string.endswith(this: String, suffix, start = 0, end = NoneType::None, /) {
+        let suffixes = affix_candidates(&suffix, "string.endswith")?;
+        if start.get_type() != "NoneType" && start.to_int()? == 0 && end.get_type() == "NoneType" {
+            ok!(suffixes.iter().any(|suffix| this.as_str().ends_with(suffix.as_str())));
+        }
+        convert_indices!(this, start, end);
+        ok!(match char_slice(this.as_str(), start, end) {
+            Some(s) => suffixes.iter().any(|suffix| s.ends_with(suffix.as_str())),
+            None => false,
+        })
     }
 
     /// [string.find](
@@ -400,6 +794,9 @@ starlark_module! {global =>
     /// If all the numeric field names form the sequence 0, 1, 2, and so on,
     /// they may be omitted and those values will be implied; however,
     /// the explicit and implicit forms may not be mixed.
+    /// The field name may be followed by any number of `.attr` attribute accesses
+    /// and `[key]` indexing operations, where a key made of digits is an int and any
+    /// other key a string, e.g. `{0[1].name}` or `{d[key]}`.
     ///
     /// The *conversion* specifies how to convert an argument value `x` to a
     /// string. It may be either `!r`, which converts the value using
@@ -407,8 +804,22 @@ starlark_module! {global =>
     /// the default.
     ///
     /// The *format specifier*, after a colon, specifies field width,
-    /// alignment, padding, and numeric precision.
-    /// Currently it must be empty, but it is reserved for future use.
+    /// alignment, padding, and numeric precision, following the Python syntax
+    /// `[[fill]align][sign][#][0][width][grouping][.precision][type]`:
+    ///
+    /// * `align` is `<` (left, the default for strings), `>` (right, the default
+    ///   for ints), `^` (centered) or `=` (padding after the sign of an int), and
+    ///   `fill` is the padding character, a space by default.
+    /// * `sign` is `+`, `-` or a space, to print a sign for all ints, only for
+    ///   negative ints, or a space before non-negative ints.
+    /// * `#` adds a `0b`, `0o` or `0x` prefix to binary, octal or hexadecimal ints.
+    /// * `0` pads ints with zeros after their sign.
+    /// * `width` is the minimum number of characters of the field.
+    /// * `grouping` is `,` or `_` to separate thousands, or groups of four digits
+    ///   with `_` for the non-decimal types.
+    /// * `.precision` truncates strings to that many characters.
+    /// * `type` is `s` for strings, and `d`, `b`, `o`, `x` or `X` to print ints in
+    ///   decimal, binary, octal, or lower or upper case hexadecimal.
     ///
     /// Examples:
     ///
@@ -426,6 +837,15 @@ starlark_module! {global =>
     /// # assert!(starlark_default(r#"(
     /// "Is {0!r} {0!s}?".format("heterological") == "Is \"heterological\" heterological?"
     /// # )"#).unwrap());
+    /// # assert!(starlark_default(r#"(
+    /// "{0} {name:>4}|{name:*^5}|{1:<3}|".format(1, "a", name="x") == "1    x|**x**|a  |"
+    /// # )"#).unwrap());
+    /// # assert!(starlark_default(r#"(
+    /// "{:+05d} {:,} {:#x} {:.3}".format(42, 1234567, 255, "abcdef") == "+0042 1,234,567 0xff abc"
+    /// # )"#).unwrap());
+    /// # assert!(starlark_default(r#"(
+    /// "{0[1]} {1[k]}".format(["a", "b"], {"k": "v"}) == "b v"
+    /// # )"#).unwrap());
     /// ```
     string.format(this: String, *args, **kwargs) {
         let mut it = args.iter().cloned();
@@ -799,6 +1219,31 @@ starlark_module! {global =>
         ok!(r)
     }
 
+    /// string.ljust: left-justify a string in a given width.
+    ///
+    /// `S.ljust(width[, fillchar])` returns S padded on the right with
+    /// `fillchar`, a space by default, to `width` characters. S is returned
+    /// unchanged if it is already at least `width` characters long.
+    ///
+    /// `ljust` fails if `fillchar` is not exactly one character.
+    ///
+    /// Examples:
+    ///
+    /// ```
+    /// # use starlark::stdlib::starlark_default;
+    /// # assert!(starlark_default(r#"(
+    /// "ab".ljust(4) == "ab  "
+    /// # and
+    /// "ab".ljust(4, ".") == "ab.."
+    /// # and
+    /// "abc".ljust(2) == "abc"
+    /// # )"#).unwrap());
+    /// ```
+    string.ljust(this: String, width: i64, fillchar: String = " ".to_owned(), /) {
+        let fill = fill_char(&fillchar, "string.ljust")?;
+        ok!(pad(&this, 0, padding(&this, width, "string.ljust()")?, fill))
+    }
+
     /// [string.lower](
     /// https://github.com/google/skylark/blob/3705afa472e466b8b061cce44b47c9ddc6db696d/doc/spec.md#string·lower
     /// ): test if all letters of a string are lowercased.
@@ -876,6 +1321,58 @@ starlark_module! {global =>
         }
     }
 
+    /// string.removeprefix: remove a prefix from a string.
+    ///
+    /// `S.removeprefix(prefix)` returns a copy of string S without the leading
+    /// substring `prefix`, or S unchanged if it does not start with `prefix`.
+    /// Unlike `S.lstrip`, `prefix` is removed at most once, as a whole string
+    /// rather than as a set of characters.
+    ///
+    /// Examples:
+    ///
+    /// ```
+    /// # use starlark::stdlib::starlark_default;
+    /// # assert!(starlark_default(r#"(
+    /// "lib_foo".removeprefix("lib_") == "foo"
+    /// # and
+    /// "foo".removeprefix("lib_") == "foo"
+    /// # and
+    /// "aab".removeprefix("a") == "ab"
+    /// # )"#).unwrap());
+    /// ```
+    string.removeprefix(this: String, prefix: String, /) {
+        match this.strip_prefix(prefix.as_str()) {
+            Some(rest) => ok!(rest),
+            None => ok!(this),
+        }
+    }
+
+    /// string.removesuffix: remove a suffix from a string.
+    ///
+    /// `S.removesuffix(suffix)` returns a copy of string S without the trailing
+    /// substring `suffix`, or S unchanged if it does not end with `suffix`.
+    /// Unlike `S.rstrip`, `suffix` is removed at most once, as a whole string
+    /// rather than as a set of characters.
+    ///
+    /// Examples:
+    ///
+    /// ```
+    /// # use starlark::stdlib::starlark_default;
+    /// # assert!(starlark_default(r#"(
+    /// "foo.bzl".removesuffix(".bzl") == "foo"
+    /// # and
+    /// "foo".removesuffix(".bzl") == "foo"
+    /// # and
+    /// "abb".removesuffix("b") == "ab"
+    /// # )"#).unwrap());
+    /// ```
+    string.removesuffix(this: String, suffix: String, /) {
+        match this.strip_suffix(suffix.as_str()) {
+            Some(rest) => ok!(rest),
+            None => ok!(this),
+        }
+    }
+
     /// [string.replace](
     /// https://github.com/google/skylark/blob/3705afa472e466b8b061cce44b47c9ddc6db696d/doc/spec.md#string·replace
     /// ): replace all occurences of a subtring.
@@ -973,6 +1470,31 @@ starlark_module! {global =>
         );
     }
 
+    /// string.rjust: right-justify a string in a given width.
+    ///
+    /// `S.rjust(width[, fillchar])` returns S padded on the left with
+    /// `fillchar`, a space by default, to `width` characters. S is returned
+    /// unchanged if it is already at least `width` characters long.
+    ///
+    /// `rjust` fails if `fillchar` is not exactly one character.
+    ///
+    /// Examples:
+    ///
+    /// ```
+    /// # use starlark::stdlib::starlark_default;
+    /// # assert!(starlark_default(r#"(
+    /// "ab".rjust(4) == "  ab"
+    /// # and
+    /// "7".rjust(3, "0") == "007"
+    /// # and
+    /// "abc".rjust(2) == "abc"
+    /// # )"#).unwrap());
+    /// ```
+    string.rjust(this: String, width: i64, fillchar: String = " ".to_owned(), /) {
+        let fill = fill_char(&fillchar, "string.rjust")?;
+        ok!(pad(&this, padding(&this, width, "string.rjust()")?, 0, fill))
+    }
+
     /// [string.rpartition](
     /// https://github.com/google/skylark/blob/3705afa472e466b8b061cce44b47c9ddc6db696d/doc/spec.md#string·rpartition
     /// ): partition a string in 3 elements.
@@ -1191,6 +1713,8 @@ starlark_module! {global =>
     /// The optional argument, `keepends`, is interpreted as a Boolean.
     /// If true, line terminators are preserved in the result, though
     /// the final element does not necessarily end with a line terminator.
+    /// A line terminator at the end of S does not start an extra empty line,
+    /// and the empty string has no lines.
     ///
     /// Examples:
     ///
@@ -1202,6 +1726,9 @@ starlark_module! {global =>
     /// # assert!(starlark_default(r#"(
     /// "one\n\ntwo".splitlines(True) == ["one\n", "\n", "two"]
     /// # )"#).unwrap());
+    /// # assert!(starlark_default(r#"(
+    /// "a\r\nb\n".splitlines() == ["a", "b"]
+    /// # )"#).unwrap());
     /// ```
     string.splitlines(this: String, keepends = false, /) {
         check_type!(keepends, "string.splitlines", bool);
@@ -1238,7 +1765,12 @@ starlark_module! {global =>
     /// https://github.com/google/skylark/blob/3705afa472e466b8b061cce44b47c9ddc6db696d/doc/spec.md#string·startswith
     /// ): test wether a string starts with a given prefix.
     ///
-    /// `S.startswith(suffix)` reports whether the string S has the specified prefix.
+    /// `S.startswith(prefix[, start[, end]])` reports whether the string S has the specified
+    /// prefix. `prefix` may also be a tuple or a list of strings, in which case `startswith`
+    /// reports whether S starts with any of them.
+    ///
+    /// If either or both of `start` or `end` are specified, they specify a subrange of S
+    /// to test instead of the whole string, interpreted like in `S.find`.
     ///
     /// Examples:
     ///
@@ -1246,11 +1778,22 @@ starlark_module! {global =>
     /// # use starlark::stdlib::starlark_default;
     /// # assert!(starlark_default(r#"(
     /// "filename.sky".startswith("filename") == True
+    /// # and
+    /// "lib_foo".startswith(("bin_", "lib_")) == True
+    /// # and
+    /// "filename.sky".startswith("name", 4) == True
     /// # )"#).unwrap());
     /// ```
-    string.startswith(this: String, prefix, /) {
-        check_string!(prefix, startswith);
-        ok!(this.to_str().starts_with(prefix.to_str().as_str()))
+    string.startswith(this: String, prefix, start = 0, end = NoneType::None, /) {
+        let prefixes = affix_candidates(&prefix, "string.startswith")?;
+        if start.get_type() != "NoneType" && start.to_int()? == 0 && end.get_type() == "NoneType" {
+            ok!(prefixes.iter().any(|prefix| this.as_str().starts_with(prefix.as_str())));
+        }
+        convert_indices!(this, start, end);
+        ok!(match char_slice(this.as_str(), start, end) {
+            Some(s) => prefixes.iter().any(|prefix| s.starts_with(prefix.as_str())),
+            None => false,
+        })
     }
 
     /// [string.strip](
@@ -1271,6 +1814,70 @@ starlark_module! {global =>
         ok!(this.trim())
     }
 
+    /// string.template: substitute `${name}` placeholders.
+    ///
+    /// `S.template(mapping)` returns a copy of the string S where each `${name}` placeholder is
+    /// replaced by `str(mapping[name])`. `name` must be an identifier, and `$$` stands for a
+    /// single `$`. Any other `$` is kept as is. It is an error if `name` is not in `mapping`.
+    ///
+    /// Examples:
+    ///
+    /// ```
+    /// # use starlark::stdlib::starlark_default;
+    /// # assert!(starlark_default(r#"(
+    /// "${host}:${port}".template({"host": "localhost", "port": 80}) == "localhost:80"
+    /// # )"#).unwrap());
+    /// # assert!(starlark_default(r#"(
+    /// """cost: $$${n}
+    /// total: $1""".template({"n": 3}) == "cost: $3\ntotal: $1"
+    /// # )"#).unwrap());
+    /// ```
+    string.template(this: String, mapping, /) {
+        let mut result = String::new();
+        let mut chars = this.chars().peekable();
+        while let Some(c) = chars.next() {
+            match (c, chars.peek()) {
+                ('$', Some('$')) => {
+                    chars.next();
+                    result.push('$');
+                }
+                ('$', Some('{')) => {
+                    chars.next();
+                    let mut name = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) if c == '_' || c.is_alphanumeric() => name.push(c),
+                            _ => starlark_err!(
+                                TEMPLATE_INVALID_PLACEHOLDER_ERROR_CODE,
+                                format!("Invalid placeholder '${{{}' in template", name),
+                                "invalid placeholder".to_owned()
+                            ),
+                        }
+                    }
+                    if name.is_empty() || name.starts_with(|c: char| c.is_numeric()) {
+                        starlark_err!(
+                            TEMPLATE_INVALID_PLACEHOLDER_ERROR_CODE,
+                            format!("Invalid placeholder '${{{}}}' in template", name),
+                            "invalid placeholder".to_owned()
+                        );
+                    }
+                    let key = Value::new(name.clone());
+                    if !mapping.is_in(&key)? {
+                        starlark_err!(
+                            TEMPLATE_KEY_NOT_FOUND_ERROR_CODE,
+                            format!("Template placeholder '${{{}}}' has no value", name),
+                            format!("'{}' not found", name)
+                        );
+                    }
+                    result += &mapping.at(key)?.to_str();
+                }
+                (c, _) => result.push(c),
+            }
+        }
+        ok!(result);
+    }
+
     /// [string.title](
     /// https://github.com/google/skylark/blob/3705afa472e466b8b061cce44b47c9ddc6db696d/doc/spec.md#string·title
     /// ): convert a string to title case.
@@ -1329,6 +1936,34 @@ starlark_module! {global =>
     string.upper(this: String) {
         ok!(this.to_str().to_uppercase())
     }
+
+    /// string.zfill: pad a numeric string with zeros.
+    ///
+    /// `S.zfill(width)` returns S padded on the left with `0` to `width`
+    /// characters. A leading `+` or `-` sign stays in front of the zeros.
+    /// S is returned unchanged if it is already at least `width` characters
+    /// long.
+    ///
+    /// Examples:
+    ///
+    /// ```
+    /// # use starlark::stdlib::starlark_default;
+    /// # assert!(starlark_default(r#"(
+    /// "42".zfill(5) == "00042"
+    /// # and
+    /// "-5".zfill(4) == "-005"
+    /// # and
+    /// "12345".zfill(3) == "12345"
+    /// # )"#).unwrap());
+    /// ```
+    string.zfill(this: String, width: i64, /) {
+        let zeros = padding(&this, width, "string.zfill()")?;
+        let (sign, digits) = match this.chars().next() {
+            Some(c @ '+') | Some(c @ '-') => this.split_at(c.len_utf8()),
+            _ => ("", this.as_str()),
+        };
+        ok!(format!("{}{}", sign, pad(digits, zeros, 0, '0')))
+    }
 }
 
 #[cfg(test)]
@@ -1466,6 +2101,20 @@ mod tests {
         starlark_ok!(r#"("hello, world!".capitalize()	 == "Hello, World!")"#);
     }
 
+    #[test]
+    fn test_center() {
+        starlark_ok!(r#"("ab".center(6) == "  ab  " and "ab".center(5) == "  ab ")"#);
+        starlark_ok!(r#"("a".center(4) == " a  " and "abc".center(6, "-") == "-abc--")"#);
+        starlark_ok!(r#"("abc".center(2) == "abc" and "abc".center(-1) == "abc")"#);
+        starlark_ok!(r#"("é".center(3, "·") == "·é·")"#);
+        starlark_fail!(r#""a".center(3, "")"#, INCORRECT_PARAMETER_TYPE_ERROR_CODE);
+        starlark_fail!(r#""a".center(2 ** 40)"#, VALUE_TOO_LARGE_ERROR_CODE);
+        starlark_fail!(
+            r#""a".center(3, "ab")"#,
+            INCORRECT_PARAMETER_TYPE_ERROR_CODE
+        );
+    }
+
     #[test]
     fn test_codepoints() {
         starlark_ok!(
@@ -1484,6 +2133,21 @@ mod tests {
     #[test]
     fn test_endswith() {
         starlark_ok!(r#"("filename.sky".endswith(".sky") == True)"#);
+        starlark_ok!(
+            r#"("a.bzl".endswith((".sky", ".bzl")) and not "a.py".endswith((".sky", ".bzl")))"#
+        );
+        starlark_ok!(r#"("a.bzl".endswith([".bzl"]) and not "a".endswith(()))"#);
+        starlark_ok!(r#"("abcd".endswith("bc", 0, 3) and not "abcd".endswith("cd", 0, -1))"#);
+        starlark_ok!(r#"("abcd".endswith("cd", -2) and "abcd".endswith("", 4))"#);
+        starlark_ok!(r#"("é.sky".endswith(".sky") and "日本.bzl".endswith((".bzl", ".sky")))"#);
+        starlark_ok!(
+            r#"("日本.bzl".endswith("本", 0, 2) and not "日本.bzl".endswith("本", 0, 3))"#
+        );
+        starlark_fail!(
+            r#""a".endswith(("a", 1))"#,
+            INCORRECT_PARAMETER_TYPE_ERROR_CODE
+        );
+        starlark_fail!(r#""a".endswith(1)"#, INCORRECT_PARAMETER_TYPE_ERROR_CODE);
     }
 
     #[test]
@@ -1504,6 +2168,105 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_format_spec() {
+        // Examples from the Python documentation of the format specification mini-language.
+        starlark_ok!(r#"("{:<30}".format("left aligned") == "left aligned                  ")"#);
+        starlark_ok!(r#"("{:>30}".format("right aligned") == "                 right aligned")"#);
+        starlark_ok!(r#"("{:^30}".format("centered") == "           centered           ")"#);
+        starlark_ok!(r#"("{:*^30}".format("centered") == "***********centered***********")"#);
+        starlark_ok!(r#"("{:+d}; {:+d}".format(3, -3) == "+3; -3")"#);
+        starlark_ok!(r#"("{: d}; {: d}".format(3, -3) == " 3; -3")"#);
+        starlark_ok!(r#"("{:-d}; {:-d}".format(3, -3) == "3; -3")"#);
+        starlark_ok!(
+            r#"("int: {0:d};  hex: {0:x};  oct: {0:o};  bin: {0:b}".format(42) ==
+                    "int: 42;  hex: 2a;  oct: 52;  bin: 101010")"#
+        );
+        starlark_ok!(
+            r#"("int: {0:d};  hex: {0:#x};  oct: {0:#o};  bin: {0:#b}".format(42) ==
+                    "int: 42;  hex: 0x2a;  oct: 0o52;  bin: 0b101010")"#
+        );
+        starlark_ok!(r#"("{:,}".format(1234567890) == "1,234,567,890")"#);
+        starlark_ok!(r#"("{:_x}".format(0xdeadbeef) == "dead_beef")"#);
+        starlark_ok!(r#"("{:08d}|{:=+8}|{:x<4}".format(-42, 42, 7) == "-0000042|+     42|7xxx")"#);
+        starlark_ok!(
+            r#"("{:.3}|{:6.2}|{!r:>5}".format("abcdef", "xyz", "a") == "abc|xy    |  \"a\"")"#
+        );
+        starlark_ok!(r#"("{name:>10}".format(name="x") == "         x")"#);
+        starlark_ok!(r#"("{}".format(2 ** 64) == "18446744073709551616")"#);
+        starlark_ok!(r#"("{:,}".format(-2 ** 64) == "-18,446,744,073,709,551,616")"#);
+        starlark_ok!(
+            r#"("{:#x}|{:X}".format(2 ** 64, -2 ** 64) == "0x10000000000000000|-10000000000000000")"#
+        );
+        starlark_ok!(r#"("{:b}".format(2 ** 64) == "1" + "0" * 64)"#);
+        starlark_fail!(
+            r#""{:18446744073709551615}".format(1)"#,
+            VALUE_TOO_LARGE_ERROR_CODE
+        );
+        starlark_fail!(
+            r#""{:99999999999999}".format("a")"#,
+            VALUE_TOO_LARGE_ERROR_CODE
+        );
+        starlark_fail!(
+            r#""{:999999999999999999999}".format(1)"#,
+            VALUE_TOO_LARGE_ERROR_CODE
+        );
+        starlark_fail!(
+            r#""{:d}".format("a")"#,
+            FORMAT_STRING_INVALID_SPECIFIER_ERROR_CODE
+        );
+        starlark_fail!(
+            r#""{:.2}".format(1)"#,
+            FORMAT_STRING_INVALID_SPECIFIER_ERROR_CODE
+        );
+        starlark_fail!(
+            r#""{:=5}".format("a")"#,
+            FORMAT_STRING_INVALID_SPECIFIER_ERROR_CODE
+        );
+        starlark_fail!(
+            r#""{:,x}".format(1)"#,
+            FORMAT_STRING_INVALID_SPECIFIER_ERROR_CODE
+        );
+        starlark_fail!(
+            r#""{:5.}".format("a")"#,
+            FORMAT_STRING_INVALID_SPECIFIER_ERROR_CODE
+        );
+        starlark_fail!(
+            r#""{:ss}".format("a")"#,
+            FORMAT_STRING_INVALID_SPECIFIER_ERROR_CODE
+        );
+    }
+
+    #[test]
+    fn test_format_field_access() {
+        starlark_ok!(r#"("{0[1]} {1[k]}".format(["a", "b"], {"k": "v"}) == "b v")"#);
+        starlark_ok!(r#"("{[0]} {}".format(["a", "b"], "c") == "a c")"#);
+        starlark_ok!(r#"("{x[0][1]:>3}".format(x=[[1, 2]]) == "  2")"#);
+        starlark_ok!(r#"("{s.a}".format(s=struct(a=1)) == "1")"#);
+        starlark_ok!(r#"("{d[a:b]}".format(d={"a:b": 1}) == "1")"#);
+        starlark_fail!(r#""{0.foo}".format([])"#);
+        starlark_fail!(
+            r#""{0.}".format(1)"#,
+            FORMAT_STRING_INVALID_CHARACTER_ERROR_CODE
+        );
+        starlark_fail!(
+            r#""{0.1}".format(1)"#,
+            FORMAT_STRING_INVALID_CHARACTER_ERROR_CODE
+        );
+        starlark_fail!(
+            r#""{0[}".format(1)"#,
+            FORMAT_STRING_INVALID_CHARACTER_ERROR_CODE
+        );
+        starlark_fail!(
+            r#""{0[]}".format(1)"#,
+            FORMAT_STRING_INVALID_CHARACTER_ERROR_CODE
+        );
+        starlark_fail!(
+            r#""{0[0]x}".format([1])"#,
+            FORMAT_STRING_INVALID_CHARACTER_ERROR_CODE
+        );
+    }
+
     #[test]
     fn test_index() {
         starlark_ok!(r#"("bonbon".index("on") == 1)"#);
@@ -1569,6 +2332,15 @@ mod tests {
         starlark_ok!(r#"("a".join("ctmrn".split_codepoints()) == "catamaran")"#);
     }
 
+    #[test]
+    fn test_ljust() {
+        starlark_ok!(r#"("ab".ljust(4) == "ab  " and "ab".ljust(4, "*") == "ab**")"#);
+        starlark_ok!(r#"("abc".ljust(2) == "abc" and "abc".ljust(3) == "abc")"#);
+        starlark_ok!(r#"("".ljust(2) == "  " and "é".ljust(2, "é") == "éé")"#);
+        starlark_fail!(r#""a".ljust(3, "--")"#, INCORRECT_PARAMETER_TYPE_ERROR_CODE);
+        starlark_fail!(r#""a".ljust(2 ** 40)"#, VALUE_TOO_LARGE_ERROR_CODE);
+    }
+
     #[test]
     fn test_lower() {
         starlark_ok!(r#"("Hello, World!".lower() == "hello, world!")"#);
@@ -1584,6 +2356,29 @@ mod tests {
         starlark_ok!(r#"("one/two/three".partition("/")	 == ("one", "/", "two/three"))"#);
     }
 
+    #[test]
+    fn test_removeprefix() {
+        starlark_ok!(r#"("lib_foo".removeprefix("lib_") == "foo")"#);
+        starlark_ok!(r#"("foo".removeprefix("lib_") == "foo")"#);
+        starlark_ok!(r#"("foo".removeprefix("") == "foo" and "".removeprefix("a") == "")"#);
+        starlark_ok!(r#"("aaab".removeprefix("a") == "aab")"#);
+        starlark_ok!(r#"("abab".removeprefix("ab") == "ab")"#);
+        starlark_ok!(r#"("ba".removeprefix("ab") == "ba" and "ab".removeprefix("ab") == "")"#);
+        starlark_ok!(r#"("été".removeprefix("é") == "té")"#);
+        starlark_fail!(r#""a".removeprefix(1)"#);
+    }
+
+    #[test]
+    fn test_removesuffix() {
+        starlark_ok!(r#"("foo.bzl".removesuffix(".bzl") == "foo")"#);
+        starlark_ok!(r#"("foo".removesuffix(".bzl") == "foo")"#);
+        starlark_ok!(r#"("foo".removesuffix("") == "foo" and "".removesuffix("a") == "")"#);
+        starlark_ok!(r#"("abbb".removesuffix("b") == "abb")"#);
+        starlark_ok!(r#"("abab".removesuffix("ab") == "ab")"#);
+        starlark_ok!(r#"("ba".removesuffix("ab") == "ba" and "ab".removesuffix("ab") == "")"#);
+        starlark_fail!(r#""a".removesuffix(1)"#);
+    }
+
     #[test]
     fn test_replace() {
         starlark_ok!(r#"("banana".replace("a", "o")	 == "bonono")"#);
@@ -1607,6 +2402,14 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_rjust() {
+        starlark_ok!(r#"("ab".rjust(4) == "  ab" and "ab".rjust(4, "*") == "**ab")"#);
+        starlark_ok!(r#"("abc".rjust(2) == "abc" and "abc".rjust(0) == "abc")"#);
+        starlark_fail!(r#""a".rjust(3, "")"#, INCORRECT_PARAMETER_TYPE_ERROR_CODE);
+        starlark_fail!(r#""a".rjust(2 ** 40)"#, VALUE_TOO_LARGE_ERROR_CODE);
+    }
+
     #[test]
     fn test_rpartition() {
         starlark_ok!(r#"("one/two/three".rpartition("/") == ("one/two", "/", "three"))"#);
@@ -1645,11 +2448,34 @@ mod tests {
         starlark_ok!(r#"("one\n\ntwo".splitlines() == ["one", "", "two"])"#);
         starlark_ok!(r#"("one\n\ntwo".splitlines(True) == ["one\n", "\n", "two"])"#);
         starlark_ok!(r#"("a\nb".splitlines() == ["a", "b"])"#);
+        starlark_ok!(r#"("a\nb\n".splitlines() == ["a", "b"])"#);
+        starlark_ok!(r#"("a\nb\n".splitlines(True) == ["a\n", "b\n"])"#);
+        starlark_ok!(r#"("a\nb".splitlines(True) == ["a\n", "b"])"#);
+        starlark_ok!(r#"("a\r\nb\rc\n\rd".splitlines() == ["a", "b", "c", "", "d"])"#);
+        starlark_ok!(r#"("a\r\nb\rc\n".splitlines(True) == ["a\r\n", "b\r", "c\n"])"#);
+        starlark_ok!(r#"("".splitlines() == [] and "".splitlines(True) == [])"#);
+        starlark_ok!(r#"("\n".splitlines() == [""] and "\n\n".splitlines(True) == ["\n", "\n"])"#);
+        starlark_ok!(r#"(type("a".splitlines()) == "list")"#);
     }
 
     #[test]
     fn test_startswith() {
         starlark_ok!(r#"("filename.sky".startswith("filename") == True)"#);
+        starlark_ok!(
+            r#"("lib_a".startswith(("bin_", "lib_")) and not "a".startswith(("bin_", "lib_")))"#
+        );
+        starlark_ok!(r#"("lib_a".startswith(["lib_"]) and not "a".startswith(()))"#);
+        starlark_ok!(r#"("abcd".startswith("bc", 1) and not "abcd".startswith("bc", 1, 2))"#);
+        starlark_ok!(r#"("abcd".startswith("cd", -2) and not "abcd".startswith("", 3, 1))"#);
+        starlark_ok!(r#"("héllo".startswith("llo", 2) and "héllo".startswith("hé"))"#);
+        starlark_ok!(
+            r#"("日本.bzl".startswith(".b", 2, 4) and not "日本.bzl".startswith("本", -3))"#
+        );
+        starlark_fail!(
+            r#""a".startswith(("a", None))"#,
+            INCORRECT_PARAMETER_TYPE_ERROR_CODE
+        );
+        starlark_fail!(r#""a".startswith(1)"#, INCORRECT_PARAMETER_TYPE_ERROR_CODE);
     }
 
     #[test]
@@ -1657,6 +2483,36 @@ mod tests {
         starlark_ok!(r#"("  hello  ".strip() == "hello")"#);
     }
 
+    #[test]
+    fn test_template() {
+        starlark_ok!(r#"("".template({}) == "")"#);
+        starlark_ok!(r#"("no placeholder $ here".template({}) == "no placeholder $ here")"#);
+        starlark_ok!(r#"("${a}-${b_1}-${a}".template({"a": 1, "b_1": [2]}) == "1-[2]-1")"#);
+        starlark_ok!(r#"("$${a} $$ ${a}$".template({"a": "x"}) == "${a} $ x$")"#);
+        starlark_ok!(
+            r#"("""
+name = "${name}"
+deps = ${deps}
+""".template({"name": "lib", "deps": ["a", "b"]}) == '\nname = "lib"\ndeps = ["a", "b"]\n')"#
+        );
+        starlark_fail!(
+            r#""${a} ${b}".template({"a": 1})"#,
+            TEMPLATE_KEY_NOT_FOUND_ERROR_CODE
+        );
+        starlark_fail!(
+            r#""${a".template({"a": 1})"#,
+            TEMPLATE_INVALID_PLACEHOLDER_ERROR_CODE
+        );
+        starlark_fail!(
+            r#""${}".template({})"#,
+            TEMPLATE_INVALID_PLACEHOLDER_ERROR_CODE
+        );
+        starlark_fail!(
+            r#""${a b}".template({})"#,
+            TEMPLATE_INVALID_PLACEHOLDER_ERROR_CODE
+        );
+    }
+
     #[test]
     fn test_title() {
         starlark_ok!(r#"("hElLo, WoRlD!".title() == "Hello, World!")"#);
@@ -1666,4 +2522,13 @@ mod tests {
     fn test_upper() {
         starlark_ok!(r#"("Hello, World!".upper() == "HELLO, WORLD!")"#);
     }
+
+    #[test]
+    fn test_zfill() {
+        starlark_ok!(r#"("42".zfill(5) == "00042" and "42".zfill(2) == "42")"#);
+        starlark_ok!(r#"("-5".zfill(4) == "-005" and "+5".zfill(4) == "+005")"#);
+        starlark_ok!(r#"("-".zfill(3) == "-00" and "".zfill(2) == "00")"#);
+        starlark_ok!(r#"("-12345".zfill(3) == "-12345" and "a-1".zfill(4) == "0a-1")"#);
+        starlark_fail!(r#""1".zfill(2 ** 40)"#, VALUE_TOO_LARGE_ERROR_CODE);
+    }
 }
@@ -14,17 +14,70 @@
 
 //! Implementation of `struct` function.
 
-use crate::values::error::ValueError;
+use crate::values::error::{error_repr, ValueError};
 use crate::values::*;
 use linked_hash_map::LinkedHashMap;
 use std::fmt;
 use std::fmt::Write as _;
 
 /// `struct()` implementation.
+///
+/// Host code can create struct values with [`StructBuilder`].
 pub struct StarlarkStruct {
     fields: LinkedHashMap<String, Value>,
 }
 
+impl StarlarkStruct {
+    /// Create a struct with the given fields, in order.
+    pub fn new(fields: LinkedHashMap<String, Value>) -> StarlarkStruct {
+        StarlarkStruct { fields }
+    }
+
+    /// The fields of this struct, in order.
+    pub fn fields(&self) -> &LinkedHashMap<String, Value> {
+        &self.fields
+    }
+}
+
+/// Builder of struct values for host code, e.g.:
+///
+/// ```
+/// # use starlark::stdlib::structs::StructBuilder;
+/// # use starlark::values::Value;
+/// let mut builder = StructBuilder::default();
+/// builder.add("host", "localhost");
+/// builder.add("port", 80);
+/// let s: Value = builder.build();
+/// assert_eq!("struct(host=\"localhost\", port=80)", s.to_repr());
+/// ```
+#[derive(Default)]
+pub struct StructBuilder {
+    fields: LinkedHashMap<String, Value>,
+}
+
+impl StructBuilder {
+    /// Add a field, replacing the previous value if a field with that name already exists.
+    pub fn add<V: Into<Value>>(&mut self, name: &str, value: V) {
+        self.fields.insert(name.to_owned(), value.into());
+    }
+
+    /// Create the struct value.
+    pub fn build(self) -> Value {
+        Value::new(StarlarkStruct::new(self.fields))
+    }
+}
+
+impl<S: Into<String>, V: Into<Value>> std::iter::FromIterator<(S, V)> for StructBuilder {
+    fn from_iter<I: IntoIterator<Item = (S, V)>>(iter: I) -> StructBuilder {
+        StructBuilder {
+            fields: iter
+                .into_iter()
+                .map(|(k, v)| (k.into(), v.into()))
+                .collect(),
+        }
+    }
+}
+
 impl TypedValue for StarlarkStruct {
     type Holder = Immutable<StarlarkStruct>;
 
@@ -73,7 +126,7 @@ impl TypedValue for StarlarkStruct {
             Some(v) => Ok(v.clone()),
             None => Err(ValueError::OperationNotSupported {
                 op: attribute.to_owned(),
-                left: self.to_repr(),
+                left: error_repr(self),
                 right: None,
             }),
         }
@@ -109,8 +162,63 @@ starlark_module! { global =>
     /// # )").unwrap());
     /// ```
     struct_(**kwargs) {
-        Ok(Value::new(StarlarkStruct {
-            fields: kwargs
-        }))
+        Ok(Value::new(StarlarkStruct::new(kwargs)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_struct_builder() {
+        let mut builder = StructBuilder::default();
+        builder.add("name", "a");
+        builder.add("size", 3);
+        let a = builder.build();
+        let b = vec![("name", Value::from("a")), ("size", Value::new(3))]
+            .into_iter()
+            .collect::<StructBuilder>()
+            .build();
+        let c = vec![("name", "b")]
+            .into_iter()
+            .collect::<StructBuilder>()
+            .build();
+
+        assert_eq!("struct", a.get_type());
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+        assert_eq!("struct(name=\"a\", size=3)", a.to_repr());
+        assert_eq!(Value::new(3), a.get_attr("size").unwrap());
+        assert!(a.get_attr("missing").is_err());
+        assert_eq!(
+            vec!["name".to_owned(), "size".to_owned()],
+            a.dir_attr().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_struct_repr() {
+        use crate::stdlib::starlark_default;
+        use crate::values::error::UNSUPPORTED_RECURSIVE_DATA_STRUCTURE_ERROR_CODE;
+
+        // Fields are rendered in declaration order, nested values with their own repr
+        let code = r#"s = struct(b=[1, struct(y=None, x="a")], a=2)
+repr(s) == 'struct(b=[1, struct(y=None, x="a")], a=2)'"#;
+        assert!(starlark_default(code).unwrap());
+        // A struct cannot be made to contain itself through a mutable value, so the repr
+        // of a struct is always finite
+        for code in &[
+            "l = [1]\ns = struct(a=l)\nl.append(s)",
+            "l = [1]\ns = struct(a=l)\nl[0] = s",
+            "d = {}\ns = struct(a=[d])\nd['s'] = s",
+        ] {
+            assert_eq!(
+                Some(UNSUPPORTED_RECURSIVE_DATA_STRUCTURE_ERROR_CODE.to_owned()),
+                starlark_default(code).unwrap_err().code,
+                "{}",
+                code
+            );
+        }
     }
 }
@@ -347,6 +347,86 @@ impl Expr {
             }
         }
     }
+
+    /// Collect names of all identifiers referenced by this expression (including names
+    /// bound by comprehensions).
+    pub(crate) fn collect_identifiers<'a>(expr: &'a AstExpr, identifiers: &mut Vec<&'a str>) {
+        if let Expr::Identifier(ref ident) = expr.node {
+            identifiers.push(&ident.node);
+        }
+        let mut exprs = Vec::new();
+        Expr::collect_children(expr, &mut exprs);
+        for e in exprs {
+            Expr::collect_identifiers(e, identifiers);
+        }
+    }
+
+    /// Collect the direct subexpressions of this expression, including the ones in
+    /// comprehension clauses and their assignment targets.
+    pub(crate) fn collect_children<'a>(expr: &'a AstExpr, exprs: &mut Vec<&'a AstExpr>) {
+        match expr.node {
+            Expr::Identifier(..) | Expr::IntLiteral(..) | Expr::StringLiteral(..) => {}
+            Expr::Tuple(ref items) | Expr::List(ref items) | Expr::Set(ref items) => {
+                exprs.extend(items)
+            }
+            Expr::Dict(ref pairs) => {
+                for (k, v) in pairs {
+                    exprs.push(k);
+                    exprs.push(v);
+                }
+            }
+            Expr::Dot(ref e, _) | Expr::Not(ref e) | Expr::Plus(ref e) | Expr::Minus(ref e) => {
+                exprs.push(e)
+            }
+            Expr::ArrayIndirection(ref a, ref b)
+            | Expr::Op(_, ref a, ref b)
+            | Expr::And(ref a, ref b)
+            | Expr::Or(ref a, ref b) => {
+                exprs.push(a);
+                exprs.push(b);
+            }
+            Expr::Call(ref func, ref args, ref named, ref star, ref star_star) => {
+                exprs.push(func);
+                exprs.extend(args);
+                exprs.extend(named.iter().map(|(_, e)| e));
+                exprs.extend(star);
+                exprs.extend(star_star);
+            }
+            Expr::Slice(ref array, ref a, ref b, ref c) => {
+                exprs.push(array);
+                exprs.extend(a);
+                exprs.extend(b);
+                exprs.extend(c);
+            }
+            Expr::If(ref cond, ref then_expr, ref else_expr) => {
+                exprs.push(cond);
+                exprs.push(then_expr);
+                exprs.push(else_expr);
+            }
+            Expr::ListComprehension(ref e, ref clauses)
+            | Expr::SetComprehension(ref e, ref clauses) => {
+                exprs.push(e);
+                Self::collect_clause_exprs(clauses, exprs);
+            }
+            Expr::DictComprehension((ref k, ref v), ref clauses) => {
+                exprs.push(k);
+                exprs.push(v);
+                Self::collect_clause_exprs(clauses, exprs);
+            }
+        }
+    }
+
+    fn collect_clause_exprs<'a>(clauses: &'a [AstClause], exprs: &mut Vec<&'a AstExpr>) {
+        for clause in clauses {
+            match clause.node {
+                Clause::If(ref e) => exprs.push(e),
+                Clause::For(ref target, ref over) => {
+                    exprs.push(over);
+                    AssignTargetExpr::collect_exprs(target, exprs);
+                }
+            }
+        }
+    }
 }
 
 impl AssignTargetExpr {
@@ -383,6 +463,22 @@ impl AssignTargetExpr {
         })
     }
 
+    fn collect_exprs<'a>(expr: &'a AstAssignTargetExpr, exprs: &mut Vec<&'a AstExpr>) {
+        match expr.node {
+            AssignTargetExpr::Identifier(..) => {}
+            AssignTargetExpr::Dot(ref object, _) => exprs.push(object),
+            AssignTargetExpr::ArrayIndirection(ref array, ref index) => {
+                exprs.push(array);
+                exprs.push(index);
+            }
+            AssignTargetExpr::Subtargets(ref subtargets) => {
+                for s in subtargets {
+                    AssignTargetExpr::collect_exprs(s, exprs);
+                }
+            }
+        }
+    }
+
     pub(crate) fn collect_locals_from_assign_expr(
         expr: &AstAssignTargetExpr,
         locals_builder: &mut LocalsBuilder,
@@ -469,6 +565,7 @@ pub enum BinOp {
     Percent,
     Division,
     FloorDivision,
+    Power,
     Pipe,
 }
 
@@ -708,6 +805,7 @@ impl Display for BinOp {
             BinOp::Percent => f.write_str(" % "),
             BinOp::Division => f.write_str(" / "),
             BinOp::FloorDivision => f.write_str(" // "),
+            BinOp::Power => f.write_str(" ** "),
             BinOp::Pipe => f.write_str(" | "),
         }
     }
@@ -134,6 +134,29 @@ fn test_unary_op() {
     assert_eq!(unwrap_parse!("a = +a"), "a = +a\n");
 }
 
+#[test]
+fn test_not_in() {
+    assert_eq!(unwrap_parse!("a not in b"), "(a not in b)\n");
+    assert_eq!(unwrap_parse!("not a in b"), "(not (a in b))\n");
+    assert_eq!(unwrap_parse!("not a not in b"), "(not (a not in b))\n");
+    assert_eq!(
+        unwrap_parse!("a not in b and c not in d or e"),
+        "(((a not in b) and (c not in d)) or e)\n"
+    );
+    assert_eq!(unwrap_parse!("a not in b + c"), "(a not in (b + c))\n");
+    assert_eq!(unwrap_parse!("a not in in_b"), "(a not in in_b)\n");
+    assert_eq!(unwrap_parse!("not in_b"), "(not in_b)\n");
+}
+
+#[test]
+fn test_power_op() {
+    assert_eq!(unwrap_parse!("a ** b ** c"), "(a ** (b ** c))\n");
+    assert_eq!(unwrap_parse!("-a ** b"), "-(a ** b)\n");
+    assert_eq!(unwrap_parse!("a ** -b"), "(a ** -b)\n");
+    assert_eq!(unwrap_parse!("a * b ** c"), "(a * (b ** c))\n");
+    assert_eq!(unwrap_parse!("a.b ** c[0]"), "(a.b ** c[0])\n");
+}
+
 #[test]
 fn test_tuples() {
     assert_eq!(unwrap_parse!("a = (-1)"), "a = -1\n"); // Not a tuple
@@ -237,6 +237,8 @@ pub struct Lexer {
     indentation_stack: LinkedList<u32>,
     parentheses: i32,
     backlog: LinkedList<LexerItem>,
+    /// Reject unknown escape sequences instead of keeping them verbatim
+    strict_escapes: bool,
 }
 
 /// An iterator that buffer a Lexer in order to wait for end of block / parentheses.
@@ -316,9 +318,18 @@ impl Lexer {
             indentation_stack: LinkedList::new(),
             parentheses: 0,
             backlog: LinkedList::new(),
+            strict_escapes: false,
         }
     }
 
+    /// Reject unknown escape sequences in non-raw string literals (e.g. `"\\w"`).
+    ///
+    /// By default unknown escape sequences are kept verbatim, backslash included.
+    pub fn with_strict_escapes(mut self) -> Self {
+        self.strict_escapes = true;
+        self
+    }
+
     /// Mark this Lexer to process or not the end of iterator as end of file
     fn process_eof(&mut self, process: bool) {
         self.process_end_of_file = process
@@ -686,6 +697,31 @@ impl Lexer {
         }
     }
 
+    /// Consume exactly `count` digits in base `radix`
+    fn consume_fixed_digits(&mut self, count: usize, radix: u32) -> Option<u32> {
+        let mut r = 0;
+        for _ in 0..count {
+            let d = self.peek_char().to_digit(radix)?;
+            self.pop();
+            r = r * radix + d;
+        }
+        Some(r)
+    }
+
+    fn escaped_char(
+        &mut self,
+        pos: u64,
+        code_point: Option<u32>,
+    ) -> Result<Option<char>, LexerError> {
+        match code_point.and_then(char::from_u32) {
+            Some(c) => Ok(Some(c)),
+            None => {
+                let p = self.end_pos();
+                Err(LexerError::InvalidEscapeSequence(pos, p.1))
+            }
+        }
+    }
+
     fn consume_escape_sequence(&mut self, triple: bool) -> Result<Option<char>, LexerError> {
         if let Some((pos, c)) = self.pop() {
             assert_eq!(c, '\\');
@@ -703,29 +739,42 @@ impl Lexer {
                         self.pop();
                         Ok(Some('\t'))
                     }
-                    '0' => {
+                    'a' => {
                         self.pop();
-                        if self.peek_char().is_digit(8) {
-                            if let Ok(r) = self.consume_int_r(8) {
-                                Ok(Some(char::from_u32(r as u32).unwrap()))
-                            } else {
-                                let p = self.end_pos();
-                                Err(LexerError::InvalidEscapeSequence(pos, p.1))
+                        Ok(Some('\x07'))
+                    }
+                    'b' => {
+                        self.pop();
+                        Ok(Some('\x08'))
+                    }
+                    'f' => {
+                        self.pop();
+                        Ok(Some('\x0c'))
+                    }
+                    'v' => {
+                        self.pop();
+                        Ok(Some('\x0b'))
+                    }
+                    '0'..='7' => {
+                        // Up to 3 octal digits
+                        let mut r = 0;
+                        for _ in 0..3 {
+                            match self.peek_char().to_digit(8) {
+                                Some(d) => {
+                                    self.pop();
+                                    r = r * 8 + d;
+                                }
+                                None => break,
                             }
-                        } else {
-                            Ok(Some('\0'))
                         }
+                        self.escaped_char(pos, Some(r))
                     }
                     'x' => {
                         self.pop();
-                        if let Ok(r) = self.consume_int_r(16) {
-                            Ok(Some(char::from_u32(r as u32).unwrap()))
-                        } else {
-                            let p = self.end_pos();
-                            Err(LexerError::InvalidEscapeSequence(pos, p.1))
-                        }
+                        let r = self.consume_fixed_digits(2, 16);
+                        self.escaped_char(pos, r)
                     }
-                    '1'..='9' => {
+                    '8' | '9' => {
                         self.pop();
                         Err(LexerError::InvalidEscapeSequence(pos, pos2 + 1))
                     }
@@ -739,27 +788,33 @@ impl Lexer {
                     }
                     'u' => {
                         self.pop();
-                        let c = self.next_char();
-                        if c != '{' {
-                            let p = self.end_pos();
-                            Err(LexerError::InvalidEscapeSequence(pos, p.1))
-                        } else if let Ok(r) = self.consume_int_r(16) {
-                            let c = self.next_char();
-                            if c != '}' {
+                        if self.peek_char() == '{' {
+                            self.pop();
+                            let r = self.consume_int_r(16).ok();
+                            if self.next_char() != '}' {
                                 let p = self.end_pos();
                                 Err(LexerError::InvalidEscapeSequence(pos, p.1))
                             } else {
-                                Ok(Some(char::from_u32(r as u32).unwrap()))
+                                self.escaped_char(pos, r.map(|r| r as u32))
                             }
                         } else {
-                            let p = self.end_pos();
-                            Err(LexerError::InvalidEscapeSequence(pos, p.1))
+                            let r = self.consume_fixed_digits(4, 16);
+                            self.escaped_char(pos, r)
                         }
                     }
+                    'U' => {
+                        self.pop();
+                        let r = self.consume_fixed_digits(8, 16);
+                        self.escaped_char(pos, r)
+                    }
                     '"' | '\'' | '\\' => {
                         self.pop();
                         Ok(Some(c2))
                     }
+                    _ if self.strict_escapes => {
+                        self.pop();
+                        Err(LexerError::InvalidEscapeSequence(pos, pos2 + 1))
+                    }
                     _ => Ok(Some('\\')),
                 }
             } else {
@@ -1373,6 +1428,59 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_string_escapes() {
+        let r = collect_result(
+            r#"'\a\b\f\v' '\0' '\101\1010' '\x41\x410' 'é\u{e9}' '\U0001F63F' r'\x41\101'"#,
+        );
+        assert_eq!(
+            &[
+                Token::StringLiteral("\x07\x08\x0c\x0b".to_owned()),
+                Token::StringLiteral("\0".to_owned()),
+                Token::StringLiteral("AA0".to_owned()),
+                Token::StringLiteral("AA0".to_owned()),
+                Token::StringLiteral("éé".to_owned()),
+                Token::StringLiteral("😿".to_owned()),
+                Token::StringLiteral("\\x41\\101".to_owned()),
+                Token::Newline,
+            ],
+            &r[..]
+        );
+
+        fn first_error(input: &str) -> Option<super::LexerError> {
+            super::Lexer::new(input)
+                .with_strict_escapes()
+                .filter_map(Result::err)
+                .next()
+        }
+        // Invalid numeric escapes
+        for invalid in &[
+            r"'\x4'",
+            r"'\xg1'",
+            r"'\u00e'",
+            r"'\U0001F63'",
+            r"'\u{110000}'",
+            r"'\ud800'",
+            r"'\8'",
+            r"'\u{41'",
+        ] {
+            match first_error(invalid) {
+                Some(super::LexerError::InvalidEscapeSequence(..)) => {}
+                e => panic!("{}: expected invalid escape sequence, got {:?}", invalid, e),
+            }
+        }
+        // Unknown escapes are kept verbatim unless strict
+        assert_eq!(
+            &[Token::StringLiteral("\\w".to_owned()), Token::Newline],
+            &collect_result(r"'\w'")[..]
+        );
+        assert_eq!(
+            Some(super::LexerError::InvalidEscapeSequence(1, 3)),
+            first_error(r"'\w'")
+        );
+        assert_eq!(None, first_error(r"r'\w'"));
+    }
+
     #[test]
     fn test_simple_example() {
         let r = collect_result(
@@ -0,0 +1,182 @@
+// Copyright 2019 The Starlark in Rust Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Conversion between byte offsets and line/column positions in a source text.
+//!
+//! Lines and columns start at 0 and columns count characters, not bytes, as in the
+//! [`LineCol`] of the `codemap` crate used for diagnostics. Lines are terminated by `\n`, a
+//! `\r` preceding it is part of the line.
+//!
+//! ```
+//! # use starlark::syntax::line_map::LineMap;
+//! # use codemap::LineCol;
+//! let map = LineMap::new("x = 1\ny = \"é\" + x\n");
+//! assert_eq!(Some(LineCol { line: 1, column: 9 }), map.line_col(16));
+//! assert_eq!(Some(16), map.offset(LineCol { line: 1, column: 9 }));
+//! ```
+
+use codemap::LineCol;
+
+/// The line starts of a source text, to convert between byte offsets and line/column
+/// positions in `O(log(lines))` plus the length of the line.
+#[derive(Debug, Clone)]
+pub struct LineMap {
+    source: String,
+    /// Byte offset of the start of each line, the first one is always 0.
+    line_starts: Vec<usize>,
+}
+
+impl LineMap {
+    /// Build the line map of `source`.
+    pub fn new(source: &str) -> LineMap {
+        let line_starts = std::iter::once(0)
+            .chain(source.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        LineMap {
+            source: source.to_owned(),
+            line_starts,
+        }
+    }
+
+    /// The source text.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// The number of lines, a text ending with `\n` has an empty last line.
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+
+    /// The text of line `line`, without its `\n`, or `None` if there is no such line.
+    pub fn line(&self, line: usize) -> Option<&str> {
+        let start = *self.line_starts.get(line)?;
+        let end = match self.line_starts.get(line + 1) {
+            Some(next) => next - 1,
+            None => self.source.len(),
+        };
+        Some(&self.source[start..end])
+    }
+
+    /// The line and column of the byte offset `offset`.
+    ///
+    /// The offset of the end of the text is valid. Return `None` for an offset past the end
+    /// of the text or in the middle of a multi-byte character.
+    pub fn line_col(&self, offset: usize) -> Option<LineCol> {
+        if !self.source.is_char_boundary(offset) {
+            return None;
+        }
+        let line = match self.line_starts.binary_search(&offset) {
+            Ok(line) => line,
+            Err(next) => next - 1,
+        };
+        let column = self.source[self.line_starts[line]..offset].chars().count();
+        Some(LineCol { line, column })
+    }
+
+    /// The byte offset of the line and column `pos`.
+    ///
+    /// The column of the end of a line, i.e. of its `\n` or the end of the text, is valid.
+    /// Return `None` if there is no such line or if the column is past the end of the line.
+    pub fn offset(&self, pos: LineCol) -> Option<usize> {
+        let text = self.line(pos.line)?;
+        let start = self.line_starts[pos.line];
+        if pos.column == text.chars().count() {
+            return Some(start + text.len());
+        }
+        text.char_indices()
+            .nth(pos.column)
+            .map(|(index, _)| start + index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lc(line: usize, column: usize) -> LineCol {
+        LineCol { line, column }
+    }
+
+    #[test]
+    fn test_line_boundaries() {
+        let map = LineMap::new("ab\n\ncd\n");
+        assert_eq!(4, map.line_count());
+        let positions = [
+            (0, lc(0, 0)),
+            (2, lc(0, 2)),
+            (3, lc(1, 0)),
+            (4, lc(2, 0)),
+            (6, lc(2, 2)),
+            (7, lc(3, 0)),
+        ];
+        for &(offset, pos) in &positions {
+            assert_eq!(Some(pos), map.line_col(offset), "offset {}", offset);
+            assert_eq!(Some(offset), map.offset(pos), "position {:?}", pos);
+        }
+        assert_eq!(None, map.line_col(8));
+        assert_eq!(None, map.offset(lc(0, 3)));
+        assert_eq!(None, map.offset(lc(1, 1)));
+        assert_eq!(None, map.offset(lc(4, 0)));
+        assert_eq!(Some(""), map.line(3));
+        assert_eq!(None, map.line(4));
+    }
+
+    #[test]
+    fn test_empty_source() {
+        let map = LineMap::new("");
+        assert_eq!(1, map.line_count());
+        assert_eq!(Some(lc(0, 0)), map.line_col(0));
+        assert_eq!(Some(0), map.offset(lc(0, 0)));
+        assert_eq!(None, map.line_col(1));
+    }
+
+    #[test]
+    fn test_multibyte_characters() {
+        // 'é' is 2 bytes and '😀' is 4 bytes long.
+        let map = LineMap::new("é😀x\r\n😀\n");
+        let positions = [
+            (0, lc(0, 0)),
+            (2, lc(0, 1)),
+            (6, lc(0, 2)),
+            (7, lc(0, 3)),
+            (8, lc(0, 4)),
+            (9, lc(1, 0)),
+            (13, lc(1, 1)),
+            (14, lc(2, 0)),
+        ];
+        for &(offset, pos) in &positions {
+            assert_eq!(Some(pos), map.line_col(offset), "offset {}", offset);
+            assert_eq!(Some(offset), map.offset(pos), "position {:?}", pos);
+        }
+        // In the middle of a character
+        assert_eq!(None, map.line_col(1));
+        assert_eq!(None, map.line_col(3));
+        assert_eq!(None, map.line_col(10));
+        assert_eq!(None, map.offset(lc(1, 2)));
+        assert_eq!(Some("é😀x\r"), map.line(0));
+    }
+
+    #[test]
+    fn test_agrees_with_codemap() {
+        let source = "def f(x):\n  return \"ü\" + x\n\nf('😀')\n";
+        let mut codemap = codemap::CodeMap::new();
+        let file = codemap.add_file("a.bzl".to_owned(), source.to_owned());
+        let map = LineMap::new(source);
+        for (offset, _) in source.char_indices() {
+            let pos = file.span.subspan(offset as u64, offset as u64).low();
+            assert_eq!(Some(file.find_line_col(pos)), map.line_col(offset));
+        }
+    }
+}
@@ -26,6 +26,7 @@ pub mod ast;
 pub mod dialect;
 #[doc(hidden)]
 pub mod lexer;
+pub mod line_map;
 
 mod grammar {
     include!(concat!(env!("OUT_DIR"), "/syntax/grammar.rs"));
@@ -12,6 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use super::ast::AstStatement;
 use super::dialect::Dialect;
 use super::errors::SyntaxError;
 use super::grammar::{BuildFileParser, StarlarkParser};
@@ -172,6 +173,18 @@ pub fn parse_lexer<T1: Iterator<Item = LexerItem>, T2: LexerIntoIter<T1>>(
     dialect: Dialect,
     lexer: T2,
 ) -> Result<Module, Diagnostic> {
+    Module::compile(parse_ast(map, filename, content, dialect, lexer)?, dialect)
+}
+
+/// Same as [`parse_lexer`], but return the syntax tree without post-processing it into a
+/// [`Module`].
+pub(crate) fn parse_ast<T1: Iterator<Item = LexerItem>, T2: LexerIntoIter<T1>>(
+    map: &Arc<Mutex<CodeMap>>,
+    filename: &str,
+    content: &str,
+    dialect: Dialect,
+    lexer: T2,
+) -> Result<AstStatement, Diagnostic> {
     let filespan = {
         map.lock()
             .unwrap()
@@ -184,7 +197,7 @@ pub fn parse_lexer<T1: Iterator<Item = LexerItem>, T2: LexerIntoIter<T1>>(
             Dialect::Bzl => StarlarkParser::new().parse(content, filespan, lexer),
         }
     } {
-        Result::Ok(v) => Ok(Module::compile(v, dialect)?),
+        Result::Ok(v) => Ok(v),
         Result::Err(p) => Result::Err(p.to_diagnostic(filespan)),
     }
 }
@@ -0,0 +1,417 @@
+// Copyright 2019 The Starlark in Rust Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Arbitrary-precision integers for Starlark.
+//!
+//! Integers are stored as a plain `i64` whenever they fit, and only promoted to a
+//! [`BigInt`](struct.BigInt.html) when an operation overflows. A `BigInt` value is therefore
+//! always outside of the `i64` range: results which fit again are demoted back to `i64`, so
+//! both representations never hold the same number and the `int` type stays transparent.
+
+use crate::values::error::{RuntimeError, ValueError, NEGATIVE_EXPONENT_ERROR_CODE};
+use crate::values::*;
+use num_integer::Integer;
+use num_traits::{One, Pow, Signed, ToPrimitive, Zero};
+use std::cmp::Ordering;
+use std::collections::hash_map::DefaultHasher;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::iter;
+
+/// Largest number of bits of an integer: operations whose result would be larger fail with
+/// an overflow instead of exhausting time or memory.
+pub const MAX_INT_BITS: u64 = 1 << 20;
+
+/// A signed integer of arbitrary size, up to [`MAX_INT_BITS`] bits.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct BigInt(num_bigint::BigInt);
+
+impl From<i64> for BigInt {
+    fn from(v: i64) -> BigInt {
+        BigInt(v.into())
+    }
+}
+
+impl BigInt {
+    /// Fail if `v` is larger than [`MAX_INT_BITS`].
+    fn checked(v: num_bigint::BigInt) -> Result<BigInt, ValueError> {
+        if v.bits() > MAX_INT_BITS {
+            Err(ValueError::IntegerOverflow)
+        } else {
+            Ok(BigInt(v))
+        }
+    }
+
+    /// Parse the digits `s` in `radix`, without sign, prefix or separators.
+    ///
+    /// Returns `Ok(None)` if `s` is not a number and an error if the number is too large.
+    pub fn parse(s: &str, radix: u32) -> Result<Option<BigInt>, ValueError> {
+        if s.is_empty() || !s.chars().all(|c| c.is_digit(radix)) {
+            return Ok(None);
+        }
+        match num_bigint::BigInt::parse_bytes(s.as_bytes(), radix) {
+            Some(v) => BigInt::checked(v).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Convert to an `i64` if the number is in range.
+    pub fn to_i64(&self) -> Option<i64> {
+        self.0.to_i64()
+    }
+
+    /// The nearest `f64`, infinite if the number is out of the `f64` range.
+    pub fn to_f64(&self) -> f64 {
+        self.0.to_f64().unwrap_or_else(|| {
+            if self.0.is_negative() {
+                f64::NEG_INFINITY
+            } else {
+                f64::INFINITY
+            }
+        })
+    }
+
+    /// The two's complement representation, most significant byte first, in as few bytes
+    /// as possible.
+    pub fn to_signed_bytes_be(&self) -> Vec<u8> {
+        self.0.to_signed_bytes_be()
+    }
+
+    /// Read the two's complement representation written by
+    /// [`to_signed_bytes_be`](BigInt::to_signed_bytes_be).
+    pub fn from_signed_bytes_be(bytes: &[u8]) -> Result<BigInt, ValueError> {
+        BigInt::checked(num_bigint::BigInt::from_signed_bytes_be(bytes))
+    }
+
+    /// Convert into a Starlark value, using a plain `int` if the number fits.
+    pub fn into_value(self) -> Value {
+        match self.to_i64() {
+            Some(v) => Value::new(v),
+            None => Value::new(self),
+        }
+    }
+
+    /// Convert an `int` value, plain or big, to a `BigInt`.
+    pub fn from_value(value: &Value) -> Option<BigInt> {
+        match value.downcast_ref::<i64>() {
+            Some(v) => Some(BigInt::from(*v)),
+            None => value.downcast_ref::<BigInt>().map(|v| v.clone()),
+        }
+    }
+
+    /// Number of bits needed to represent the absolute value, i.e. without sign and leading
+    /// zeros. It is 0 for 0.
+    pub fn bit_length(&self) -> u64 {
+        self.0.bits()
+    }
+
+    /// Number of ones in the binary representation of the absolute value.
+    pub fn bit_count(&self) -> u64 {
+        self.0.magnitude().count_ones()
+    }
+
+    /// Return both operands as `BigInt` if they are integers and at least one of them is
+    /// not a plain `i64`.
+    pub(crate) fn operands(left: &Value, right: &Value) -> Option<(BigInt, BigInt)> {
+        let left_big = left.downcast_ref::<BigInt>().map(|v| v.clone());
+        let right_big = right.downcast_ref::<BigInt>().map(|v| v.clone());
+        match (left_big, right_big) {
+            (None, None) => None,
+            (Some(l), Some(r)) => Some((l, r)),
+            (Some(l), None) => right.downcast_ref::<i64>().map(|r| (l, BigInt::from(*r))),
+            (None, Some(r)) => left.downcast_ref::<i64>().map(|l| (BigInt::from(*l), r)),
+        }
+    }
+
+    pub fn neg(&self) -> BigInt {
+        BigInt(-&self.0)
+    }
+
+    pub fn abs(&self) -> BigInt {
+        BigInt(self.0.abs())
+    }
+
+    pub fn add(&self, other: &BigInt) -> Result<BigInt, ValueError> {
+        BigInt::checked(&self.0 + &other.0)
+    }
+
+    pub fn sub(&self, other: &BigInt) -> Result<BigInt, ValueError> {
+        BigInt::checked(&self.0 - &other.0)
+    }
+
+    pub fn mul(&self, other: &BigInt) -> Result<BigInt, ValueError> {
+        // The product has at most as many bits as both operands together.
+        if self.bit_length() + other.bit_length() > MAX_INT_BITS + 1 {
+            return Err(ValueError::IntegerOverflow);
+        }
+        BigInt::checked(&self.0 * &other.0)
+    }
+
+    /// Floored division and the matching remainder, which has the sign of `other`.
+    fn div_mod(&self, other: &BigInt) -> Result<(BigInt, BigInt), ValueError> {
+        if other.0.is_zero() {
+            return Err(ValueError::DivisionByZero);
+        }
+        let (q, r) = self.0.div_mod_floor(&other.0);
+        Ok((BigInt(q), BigInt(r)))
+    }
+
+    pub fn floor_div(&self, other: &BigInt) -> Result<BigInt, ValueError> {
+        Ok(self.div_mod(other)?.0)
+    }
+
+    pub fn percent(&self, other: &BigInt) -> Result<BigInt, ValueError> {
+        Ok(self.div_mod(other)?.1)
+    }
+
+    pub fn pow(&self, other: &BigInt) -> Result<BigInt, ValueError> {
+        if other.0.is_negative() {
+            // There is no float type to hold the result.
+            return Err(RuntimeError {
+                code: NEGATIVE_EXPONENT_ERROR_CODE,
+                message: format!("Negative exponent in {} ** {}", self, other),
+                label: "Negative exponent".to_owned(),
+            }
+            .into());
+        }
+        if self.0.is_zero() || self.0.magnitude().is_one() {
+            // 0, 1 and -1 may be raised to any power.
+            return Ok(if other.0.is_zero() {
+                BigInt(One::one())
+            } else if self.0.is_negative() && other.0.is_even() {
+                self.neg()
+            } else {
+                self.clone()
+            });
+        }
+        // The result has more than `exponent * (bits - 1)` bits.
+        let exponent = match other.0.to_u64() {
+            Some(e) if e <= MAX_INT_BITS / (self.bit_length() - 1).max(1) => e,
+            _ => return Err(ValueError::IntegerOverflow),
+        };
+        BigInt::checked(Pow::pow(&self.0, exponent))
+    }
+}
+
+impl fmt::Display for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+impl fmt::Binary for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Binary::fmt(&self.0, f)
+    }
+}
+
+impl fmt::Octal for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Octal::fmt(&self.0, f)
+    }
+}
+
+impl fmt::LowerHex for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::LowerHex::fmt(&self.0, f)
+    }
+}
+
+impl fmt::UpperHex for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::UpperHex::fmt(&self.0, f)
+    }
+}
+
+/// Define the int type for values outside of the `i64` range
+impl TypedValue for BigInt {
+    type Holder = Immutable<Self>;
+    const TYPE: &'static str = "int";
+
+    fn equals(&self, other: &BigInt) -> Result<bool, ValueError> {
+        Ok(self == other)
+    }
+    fn compare(&self, other: &BigInt) -> Result<Ordering, ValueError> {
+        Ok(self.cmp(other))
+    }
+    fn to_str_impl(&self, buf: &mut String) -> fmt::Result {
+        write!(buf, "{}", self)
+    }
+    fn to_repr_impl(&self, buf: &mut String) -> fmt::Result {
+        write!(buf, "{}", self)
+    }
+    fn to_int(&self) -> Result<i64, ValueError> {
+        Err(ValueError::IntegerOverflow)
+    }
+    fn to_bool(&self) -> bool {
+        !self.0.is_zero()
+    }
+    fn get_hash(&self) -> Result<u64, ValueError> {
+        let mut hasher = DefaultHasher::new();
+        self.hash(&mut hasher);
+        Ok(hasher.finish())
+    }
+    fn plus(&self) -> Result<BigInt, ValueError> {
+        Ok(self.clone())
+    }
+
+    fn values_for_descendant_check_and_freeze<'a>(
+        &'a self,
+    ) -> Box<dyn Iterator<Item = Value> + 'a> {
+        Box::new(iter::empty())
+    }
+}
+
+/// Apply an integer operation, promoting to a `BigInt` when needed.
+///
+/// Two plain `i64` go through `small` and are only retried with `big` if that overflows.
+/// Operands which are not integers are passed to `small` untouched.
+pub(crate) fn int_bin_op<F>(
+    left: &Value,
+    right: Value,
+    big: fn(&BigInt, &BigInt) -> Result<BigInt, ValueError>,
+    small: F,
+) -> ValueResult
+where
+    F: FnOnce(&Value, Value) -> ValueResult,
+{
+    if let (ValueInner::Int(l), ValueInner::Int(r)) = (&left.0, &right.0) {
+        let (l, r) = (*l, *r);
+        return match small(left, right) {
+            Err(ValueError::IntegerOverflow) => {
+                big(&BigInt::from(l), &BigInt::from(r)).map(BigInt::into_value)
+            }
+            res => res,
+        };
+    }
+    match BigInt::operands(left, &right) {
+        Some((l, r)) => big(&l, &r).map(BigInt::into_value),
+        None => small(left, right),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn big(s: &str) -> Value {
+        match s.strip_prefix('-') {
+            Some(digits) => BigInt::parse(digits, 10)
+                .unwrap()
+                .unwrap()
+                .neg()
+                .into_value(),
+            None => BigInt::parse(s, 10).unwrap().unwrap().into_value(),
+        }
+    }
+
+    #[test]
+    fn test_promotion_and_demotion() {
+        let max = Value::new(i64::MAX);
+        let sum = max.add(Value::new(1)).unwrap();
+        assert_eq!("int", sum.get_type());
+        assert_eq!("9223372036854775808", sum.to_str());
+        assert!(sum.downcast_ref::<BigInt>().is_some());
+        let back = sum.sub(Value::new(1)).unwrap();
+        assert_eq!(Some(i64::MAX), back.downcast_ref::<i64>().map(|v| *v));
+        assert_eq!("-9223372036854775808", Value::new(i64::MIN).to_str());
+        assert_eq!(
+            "9223372036854775808",
+            Value::new(i64::MIN).minus().unwrap().to_str()
+        );
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        let a = big("123456789012345678901234567890");
+        let b = big("-987654321098765432109876543210");
+        assert_eq!(
+            "-864197532086419753208641975320",
+            a.add(b.clone()).unwrap().to_str()
+        );
+        assert_eq!(
+            "1111111110111111111011111111100",
+            a.sub(b.clone()).unwrap().to_str()
+        );
+        assert_eq!(
+            "-121932631137021795226185032733622923332237463801111263526900",
+            a.mul(b.clone()).unwrap().to_str()
+        );
+        // Floored division and modulo follow the sign of the divisor.
+        assert_eq!("-9", b.floor_div(a.clone()).unwrap().to_str());
+        assert_eq!(
+            "123456780012345678001234567800",
+            b.percent(a.clone()).unwrap().to_str()
+        );
+        assert_eq!("8", b.floor_div(a.minus().unwrap()).unwrap().to_str());
+        assert_eq!(
+            "18446744073709551616",
+            Value::new(2).pow(Value::new(64)).unwrap().to_str()
+        );
+        assert_eq!(
+            "-1",
+            big("-18446744073709551616")
+                .floor_div(big("18446744073709551616"))
+                .unwrap()
+                .to_str()
+        );
+        match a.percent(Value::new(0)) {
+            Err(ValueError::DivisionByZero) => (),
+            r => panic!("expected division by zero, got {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_compare_and_hash() {
+        let a = big("18446744073709551616");
+        let b = Value::new(2).pow(Value::new(64)).unwrap();
+        assert!(a.equals(&b).unwrap());
+        assert_eq!(a.get_hash().unwrap(), b.get_hash().unwrap());
+        assert!(!a.equals(&Value::new(1)).unwrap());
+        assert_eq!(Ordering::Greater, a.compare(&Value::new(1)).unwrap());
+        assert_eq!(Ordering::Less, Value::new(1).compare(&a).unwrap());
+        assert_eq!(
+            Ordering::Less,
+            a.minus().unwrap().compare(&Value::new(i64::MIN)).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_size_limit() {
+        let two = Value::new(2);
+        let max = two.pow(Value::new(MAX_INT_BITS as i64 - 1)).unwrap();
+        assert_eq!(MAX_INT_BITS, BigInt::from_value(&max).unwrap().bit_length());
+        for r in &[
+            max.mul(two.clone()),
+            max.add(max.clone()),
+            max.mul(max.clone()),
+            two.pow(Value::new(MAX_INT_BITS as i64)),
+            Value::new(3).pow(Value::new(i64::MAX)),
+        ] {
+            match r {
+                Err(ValueError::IntegerOverflow) => (),
+                r => panic!("expected an overflow, got {:?}", r.as_ref().map(|_| ())),
+            }
+        }
+        // Large operands are still fast
+        let a = two.pow(Value::new(1_000_000)).unwrap();
+        let b = two
+            .pow(Value::new(500_000))
+            .unwrap()
+            .add(Value::new(1))
+            .unwrap();
+        let q = a.floor_div(b.clone()).unwrap();
+        assert!(q.mul(b).unwrap().compare(&a).unwrap() != Ordering::Greater);
+    }
+}
@@ -0,0 +1,345 @@
+// Copyright 2019 The Starlark in Rust Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Define the bytes and bytearray types for Starlark.
+//!
+//! Both hold a sequence of bytes, which can be any binary data, unlike strings which must be
+//! UTF-8. `bytes` is immutable and hashable, `bytearray` is its mutable counterpart. As in
+//! Python 3, indexing and iteration yield ints in `range(256)`.
+
+use crate::values::error::{RuntimeError, ValueError};
+use crate::values::iter::TypedIterable;
+use crate::values::*;
+use std::cmp::Ordering;
+use std::collections::hash_map::DefaultHasher;
+use std::convert::TryFrom;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::iter;
+
+// Errors -- UF = User Failure -- Failure that should be expected by the user (e.g. from a fail()).
+pub const BYTE_OUT_OF_RANGE_ERROR_CODE: &str = "UF40";
+
+/// An immutable sequence of bytes.
+#[derive(Clone, Default, Debug, PartialEq)]
+pub struct Bytes {
+    content: Vec<u8>,
+}
+
+/// A mutable sequence of bytes.
+#[derive(Clone, Default, Debug, PartialEq)]
+pub struct ByteArray {
+    content: Vec<u8>,
+}
+
+impl Bytes {
+    pub fn new(content: Vec<u8>) -> Bytes {
+        Bytes { content }
+    }
+
+    pub fn as_slice(&self) -> &[u8] {
+        &self.content
+    }
+}
+
+impl ByteArray {
+    pub fn new(content: Vec<u8>) -> ByteArray {
+        ByteArray { content }
+    }
+
+    pub fn as_slice(&self) -> &[u8] {
+        &self.content
+    }
+
+    pub fn push(&mut self, value: Value) -> Result<(), ValueError> {
+        self.content.push(to_byte(&value)?);
+        Ok(())
+    }
+}
+
+impl From<Vec<u8>> for Bytes {
+    fn from(content: Vec<u8>) -> Bytes {
+        Bytes::new(content)
+    }
+}
+
+impl From<&[u8]> for Value {
+    fn from(content: &[u8]) -> Value {
+        Value::new(Bytes::new(content.to_vec()))
+    }
+}
+
+/// Return the content of a `bytes` or `bytearray` value.
+pub fn bytes_content(value: &Value) -> Option<Vec<u8>> {
+    if let Some(b) = value.downcast_ref::<Bytes>() {
+        return Some(b.content.clone());
+    }
+    if let Some(b) = value.downcast_ref::<ByteArray>() {
+        return Some(b.content.clone());
+    }
+    None
+}
+
+/// Concatenate `bytes` and `bytearray` values of different types, the result having the type
+/// of `left` as in Python. `None` if the values are not of these two types.
+pub(crate) fn concat_mixed(left: &Value, right: &Value) -> Option<Value> {
+    match (left.get_type(), right.get_type()) {
+        ("bytes", "bytearray") | ("bytearray", "bytes") => {}
+        _ => return None,
+    }
+    let mut content = bytes_content(left).unwrap();
+    content.extend(bytes_content(right).unwrap());
+    Some(match left.get_type() {
+        "bytes" => Value::new(Bytes::new(content)),
+        _ => Value::new(ByteArray::new(content)),
+    })
+}
+
+/// Convert an int in `range(256)` to a byte.
+pub fn to_byte(value: &Value) -> Result<u8, ValueError> {
+    let i = value.to_int()?;
+    u8::try_from(i).map_err(|_| {
+        RuntimeError {
+            code: BYTE_OUT_OF_RANGE_ERROR_CODE,
+            message: format!("byte must be in range(0, 256), got {}", i),
+            label: "byte out of range".to_owned(),
+        }
+        .into()
+    })
+}
+
+/// Write `content` as a `b"..."` literal.
+fn write_bytes_repr(content: &[u8], buf: &mut String) -> fmt::Result {
+    write!(buf, "b\"")?;
+    for b in content {
+        match *b {
+            b'"' => write!(buf, "\\\"")?,
+            b'\\' => write!(buf, "\\\\")?,
+            b'\t' => write!(buf, "\\t")?,
+            b'\n' => write!(buf, "\\n")?,
+            b'\r' => write!(buf, "\\r")?,
+            b' '..=b'~' => buf.push(*b as char),
+            b => write!(buf, "\\x{:02x}", b)?,
+        }
+    }
+    write!(buf, "\"")
+}
+
+fn byte_at(content: &[u8], index: Value) -> ValueResult {
+    let i = index.convert_index(content.len() as i64)? as usize;
+    Ok(Value::new(i64::from(content[i])))
+}
+
+fn slice_bytes(
+    content: &[u8],
+    start: Option<Value>,
+    stop: Option<Value>,
+    stride: Option<Value>,
+) -> Result<Vec<u8>, ValueError> {
+    let (start, stop, stride) =
+        Value::convert_slice_indices(content.len() as i64, start, stop, stride)?;
+    Ok(slice_items(start, stop, stride, content.iter().cloned()))
+}
+
+/// `x in b` accepts a byte value as an int, or a subsequence as bytes or bytearray.
+fn contains_bytes(content: &[u8], other: &Value) -> Result<bool, ValueError> {
+    if let Some(needle) = bytes_content(other) {
+        return Ok(needle.is_empty() || content.windows(needle.len()).any(|w| w == &needle[..]));
+    }
+    if other.get_type() == "int" {
+        return Ok(content.contains(&to_byte(other)?));
+    }
+    Err(ValueError::IncorrectParameterType)
+}
+
+impl TypedValue for Bytes {
+    type Holder = Immutable<Bytes>;
+    const TYPE: &'static str = "bytes";
+
+    fn values_for_descendant_check_and_freeze<'a>(
+        &'a self,
+    ) -> Box<dyn Iterator<Item = Value> + 'a> {
+        Box::new(iter::empty())
+    }
+
+    fn to_repr_impl(&self, buf: &mut String) -> fmt::Result {
+        write_bytes_repr(&self.content, buf)
+    }
+    fn to_bool(&self) -> bool {
+        !self.content.is_empty()
+    }
+    fn get_hash(&self) -> Result<u64, ValueError> {
+        let mut s = DefaultHasher::new();
+        self.content.hash(&mut s);
+        Ok(s.finish())
+    }
+    fn equals(&self, other: &Bytes) -> Result<bool, ValueError> {
+        Ok(self.content == other.content)
+    }
+    fn compare(&self, other: &Bytes) -> Result<Ordering, ValueError> {
+        Ok(self.content.cmp(&other.content))
+    }
+    fn at(&self, index: Value) -> ValueResult {
+        byte_at(&self.content, index)
+    }
+    fn length(&self) -> Result<i64, ValueError> {
+        Ok(self.content.len() as i64)
+    }
+    fn is_in(&self, other: &Value) -> Result<bool, ValueError> {
+        contains_bytes(&self.content, other)
+    }
+    fn slice(
+        &self,
+        start: Option<Value>,
+        stop: Option<Value>,
+        stride: Option<Value>,
+    ) -> ValueResult {
+        Ok(Value::new(Bytes::new(slice_bytes(
+            &self.content,
+            start,
+            stop,
+            stride,
+        )?)))
+    }
+    fn iter(&self) -> Result<&dyn TypedIterable, ValueError> {
+        Ok(self)
+    }
+    fn add(&self, other: &Bytes) -> Result<Bytes, ValueError> {
+        let mut content = self.content.clone();
+        content.extend_from_slice(&other.content);
+        Ok(Bytes::new(content))
+    }
+}
+
+impl TypedIterable for Bytes {
+    fn to_iter<'a>(&'a self) -> Box<dyn Iterator<Item = Value> + 'a> {
+        Box::new(self.content.iter().map(|b| Value::new(i64::from(*b))))
+    }
+}
+
+impl TypedValue for ByteArray {
+    type Holder = Mutable<ByteArray>;
+    const TYPE: &'static str = "bytearray";
+
+    fn values_for_descendant_check_and_freeze<'a>(
+        &'a self,
+    ) -> Box<dyn Iterator<Item = Value> + 'a> {
+        Box::new(iter::empty())
+    }
+
+    fn to_repr_impl(&self, buf: &mut String) -> fmt::Result {
+        write!(buf, "bytearray(")?;
+        write_bytes_repr(&self.content, buf)?;
+        write!(buf, ")")
+    }
+    fn to_bool(&self) -> bool {
+        !self.content.is_empty()
+    }
+    fn equals(&self, other: &ByteArray) -> Result<bool, ValueError> {
+        Ok(self.content == other.content)
+    }
+    fn compare(&self, other: &ByteArray) -> Result<Ordering, ValueError> {
+        Ok(self.content.cmp(&other.content))
+    }
+    fn at(&self, index: Value) -> ValueResult {
+        byte_at(&self.content, index)
+    }
+    fn length(&self) -> Result<i64, ValueError> {
+        Ok(self.content.len() as i64)
+    }
+    fn is_in(&self, other: &Value) -> Result<bool, ValueError> {
+        contains_bytes(&self.content, other)
+    }
+    fn slice(
+        &self,
+        start: Option<Value>,
+        stop: Option<Value>,
+        stride: Option<Value>,
+    ) -> ValueResult {
+        Ok(Value::new(ByteArray::new(slice_bytes(
+            &self.content,
+            start,
+            stop,
+            stride,
+        )?)))
+    }
+    fn iter(&self) -> Result<&dyn TypedIterable, ValueError> {
+        Ok(self)
+    }
+    fn add(&self, other: &ByteArray) -> Result<ByteArray, ValueError> {
+        let mut content = self.content.clone();
+        content.extend_from_slice(&other.content);
+        Ok(ByteArray::new(content))
+    }
+    fn set_at(&mut self, index: Value, new_value: Value) -> Result<(), ValueError> {
+        let i = index.convert_index(self.content.len() as i64)? as usize;
+        self.content[i] = to_byte(&new_value)?;
+        Ok(())
+    }
+}
+
+impl TypedIterable for ByteArray {
+    fn to_iter<'a>(&'a self) -> Box<dyn Iterator<Item = Value> + 'a> {
+        Box::new(self.content.iter().map(|b| Value::new(i64::from(*b))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_repr() {
+        assert_eq!(
+            "b\"a\\\"\\\\\\n\\x00\\xff\"",
+            Value::from(&b"a\"\\\n\x00\xff"[..]).to_repr()
+        );
+        assert_eq!(
+            "bytearray(b\"ab\")",
+            Value::new(ByteArray::new(b"ab".to_vec())).to_repr()
+        );
+    }
+
+    #[test]
+    fn test_index_and_iterate() {
+        let b = Value::from(&b"\x00\x80\xff"[..]);
+        assert_eq!(3, b.length().unwrap());
+        assert_eq!(255, b.at(Value::new(-1)).unwrap().to_int().unwrap());
+        let items: Vec<i64> = b
+            .iter()
+            .unwrap()
+            .iter()
+            .map(|v| v.to_int().unwrap())
+            .collect();
+        assert_eq!(vec![0, 128, 255], items);
+        assert!(b.is_in(&Value::new(128)).unwrap());
+        assert!(b.is_in(&Value::from(&b"\x80\xff"[..])).unwrap());
+    }
+
+    #[test]
+    fn test_mutate_bytearray() {
+        let mut b = Value::new(ByteArray::new(b"abc".to_vec()));
+        b.set_at(Value::new(0), Value::new(0x41)).unwrap();
+        assert_eq!("bytearray(b\"Abc\")", b.to_repr());
+        assert!(b.set_at(Value::new(0), Value::new(256)).is_err());
+    }
+
+    #[test]
+    fn test_concat_mixed() {
+        let b = Value::from(&b"ab"[..]);
+        let a = Value::new(ByteArray::new(b"c".to_vec()));
+        assert_eq!("b\"abc\"", b.add(a.clone()).unwrap().to_repr());
+        assert_eq!("bytearray(b\"cab\")", a.add(b).unwrap().to_repr());
+    }
+}
@@ -0,0 +1,355 @@
+// Copyright 2019 The Starlark in Rust Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Canonical binary serialization of plain data values.
+//!
+//! Two values which compare equal serialize to the same bytes, independently of
+//! the insertion order of dictionaries. `None`, `bool`, `int`, `string` and
+//! `list`, `tuple` and `dict` of those are supported, as well as host types
+//! implementing [`TypedValue::serialize`](crate::values::TypedValue::serialize).
+//!
+//! The format is stable, as the `stable_hash` builtin hashes it. Each value starts with a
+//! one-byte tag; lengths and integers are 8-byte big-endian:
+//!
+//! * `None`, `True` and `False` are `N`, `T` and `F`.
+//! * An integer in the `i64` range is `i` followed by its value.
+//! * A larger integer is `I` followed by the length of its two's complement representation
+//!   and that representation, big-endian in as few bytes as possible.
+//! * A string is `s` followed by its length in bytes and its UTF-8 bytes.
+//! * A list or a tuple is `l` or `t` followed by its length and its elements.
+//! * A dictionary is `d` followed by its length and its keys and values, alternating, in the
+//!   order of the serialized keys.
+//! * A host value is `x` followed by the length of its tag, its tag, the length of its
+//!   payload and its payload.
+//!
+//! Serialized values are restored with [`from_canonical_bytes`], given factories
+//! for the host types, e.g.:
+//!
+//! ```
+//! # use starlark::values::*;
+//! # use starlark::values::canonical::{canonical_bytes, from_canonical_bytes, Deserializers};
+//! # use starlark::values::error::ValueError;
+//! # use std::convert::TryInto;
+//! # use std::fmt;
+//! # use std::fmt::Write as _;
+//! # use std::iter;
+//! /// A host type holding a version number.
+//! struct Version(u32, u32);
+//!
+//! impl TypedValue for Version {
+//!     type Holder = Immutable<Version>;
+//!     const TYPE: &'static str = "version";
+//!
+//!     fn values_for_descendant_check_and_freeze<'a>(
+//!         &'a self,
+//!     ) -> Box<dyn Iterator<Item = Value> + 'a> {
+//!         Box::new(iter::empty())
+//!     }
+//!
+//!     fn to_repr_impl(&self, buf: &mut String) -> fmt::Result {
+//!         write!(buf, "version({}.{})", self.0, self.1)
+//!     }
+//!
+//!     fn serialize(&self) -> Option<(&'static str, Vec<u8>)> {
+//!         let mut payload = self.0.to_be_bytes().to_vec();
+//!         payload.extend_from_slice(&self.1.to_be_bytes());
+//!         Some(("example.version", payload))
+//!     }
+//! }
+//!
+//! let value = Value::from(vec![Value::new(Version(1, 2)), Value::from("x")]);
+//! let bytes = canonical_bytes(&value).unwrap();
+//!
+//! let mut deserializers = Deserializers::default();
+//! deserializers.register(
+//!     "example.version",
+//!     Box::new(|payload| {
+//!         let major = u32::from_be_bytes(payload[0..4].try_into().unwrap());
+//!         let minor = u32::from_be_bytes(payload[4..8].try_into().unwrap());
+//!         Ok(Value::new(Version(major, minor)))
+//!     }),
+//! );
+//! let restored = from_canonical_bytes(&bytes, &deserializers).unwrap();
+//! assert_eq!("[version(1.2), \"x\"]", restored.to_repr());
+//! ```
+
+use crate::values::bigint::BigInt;
+use crate::values::dict::Dictionary;
+use crate::values::error::{RuntimeError, ValueError, INVALID_SERIALIZED_VALUE_ERROR_CODE};
+use crate::values::none::NoneType;
+use crate::values::tuple::Tuple;
+use crate::values::{Value, ValueResult};
+use std::collections::HashMap;
+use std::convert::TryInto;
+
+/// Serialize a value into its canonical byte representation.
+pub fn canonical_bytes(value: &Value) -> Result<Vec<u8>, ValueError> {
+    let mut out = Vec::new();
+    write_canonical(value, &mut out)?;
+    Ok(out)
+}
+
+fn write_len(len: usize, out: &mut Vec<u8>) {
+    out.extend_from_slice(&(len as u64).to_be_bytes());
+}
+
+fn write_sequence(tag: u8, values: Vec<Value>, out: &mut Vec<u8>) -> Result<(), ValueError> {
+    out.push(tag);
+    write_len(values.len(), out);
+    for v in &values {
+        write_canonical(v, out)?;
+    }
+    Ok(())
+}
+
+fn write_canonical(value: &Value, out: &mut Vec<u8>) -> Result<(), ValueError> {
+    match value.get_type() {
+        "NoneType" => out.push(b'N'),
+        "bool" => out.push(if value.to_bool() { b'T' } else { b'F' }),
+        "int" => match value.downcast_ref::<BigInt>() {
+            Some(big) => {
+                let bytes = big.to_signed_bytes_be();
+                out.push(b'I');
+                write_len(bytes.len(), out);
+                out.extend(bytes);
+            }
+            None => {
+                out.push(b'i');
+                out.extend_from_slice(&value.to_int()?.to_be_bytes());
+            }
+        },
+        "string" => {
+            let s = value.to_str();
+            out.push(b's');
+            write_len(s.len(), out);
+            out.extend_from_slice(s.as_bytes());
+        }
+        "list" => write_sequence(b'l', value.to_vec()?, out)?,
+        "tuple" => write_sequence(b't', value.to_vec()?, out)?,
+        "dict" => {
+            let items = value.downcast_ref::<Dictionary>().unwrap().items();
+            // Entries are ordered by their serialized key so that insertion order
+            // does not affect the result.
+            let mut entries = Vec::with_capacity(items.len());
+            for (k, v) in items {
+                entries.push((canonical_bytes(&k)?, canonical_bytes(&v)?));
+            }
+            entries.sort();
+            out.push(b'd');
+            write_len(entries.len(), out);
+            for (k, v) in entries {
+                out.extend(k);
+                out.extend(v);
+            }
+        }
+        t => match value.serialize() {
+            Some((tag, payload)) => {
+                out.push(b'x');
+                write_len(tag.len(), out);
+                out.extend_from_slice(tag.as_bytes());
+                write_len(payload.len(), out);
+                out.extend(payload);
+            }
+            None => {
+                return Err(ValueError::TypeNotX {
+                    object_type: t.to_owned(),
+                    op: "serializable".to_owned(),
+                })
+            }
+        },
+    }
+    Ok(())
+}
+
+/// A function restoring a host value from its serialized payload.
+pub type Factory = Box<dyn Fn(&[u8]) -> ValueResult>;
+
+/// Factories restoring host values from their serialized payload, by tag.
+#[derive(Default)]
+pub struct Deserializers {
+    factories: HashMap<String, Factory>,
+}
+
+impl Deserializers {
+    /// Register the factory restoring values serialized with `tag` from their payload,
+    /// replacing any factory previously registered for `tag`.
+    pub fn register(&mut self, tag: &str, factory: Factory) {
+        self.factories.insert(tag.to_owned(), factory);
+    }
+}
+
+/// Restore a value serialized by [`canonical_bytes`].
+///
+/// Values of host types are restored by the factory registered for their tag in
+/// `deserializers`. Entries of restored dictionaries are in canonical order rather than in
+/// their original insertion order. It is an error if `bytes` is not a single serialized value or if a
+/// tag has no factory.
+pub fn from_canonical_bytes(bytes: &[u8], deserializers: &Deserializers) -> ValueResult {
+    let mut reader = Reader {
+        bytes,
+        deserializers,
+    };
+    let value = reader.read_value()?;
+    if !reader.bytes.is_empty() {
+        return Err(invalid("trailing bytes after the value"));
+    }
+    Ok(value)
+}
+
+fn invalid(message: &str) -> ValueError {
+    RuntimeError {
+        code: INVALID_SERIALIZED_VALUE_ERROR_CODE,
+        message: format!("Invalid serialized value: {}", message),
+        label: "Invalid serialized value".to_owned(),
+    }
+    .into()
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    deserializers: &'a Deserializers,
+}
+
+impl<'a> Reader<'a> {
+    fn read_bytes(&mut self, len: usize) -> Result<&'a [u8], ValueError> {
+        if self.bytes.len() < len {
+            return Err(invalid("unexpected end of input"));
+        }
+        let (read, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(read)
+    }
+
+    fn read_len(&mut self) -> Result<usize, ValueError> {
+        let len = u64::from_be_bytes(self.read_bytes(8)?.try_into().unwrap());
+        Ok(len as usize)
+    }
+
+    fn read_string(&mut self) -> Result<String, ValueError> {
+        let len = self.read_len()?;
+        match std::str::from_utf8(self.read_bytes(len)?) {
+            Ok(s) => Ok(s.to_owned()),
+            Err(..) => Err(invalid("string is not UTF-8")),
+        }
+    }
+
+    fn read_values(&mut self) -> Result<Vec<Value>, ValueError> {
+        let len = self.read_len()?;
+        let mut values = Vec::new();
+        for _ in 0..len {
+            values.push(self.read_value()?);
+        }
+        Ok(values)
+    }
+
+    fn read_value(&mut self) -> ValueResult {
+        Ok(match self.read_bytes(1)?[0] {
+            b'N' => Value::new(NoneType::None),
+            b'T' => Value::new(true),
+            b'F' => Value::new(false),
+            b'i' => Value::new(i64::from_be_bytes(self.read_bytes(8)?.try_into().unwrap())),
+            b'I' => {
+                let len = self.read_len()?;
+                BigInt::from_signed_bytes_be(self.read_bytes(len)?)?.into_value()
+            }
+            b's' => Value::new(self.read_string()?),
+            b'l' => Value::from(self.read_values()?),
+            b't' => Value::new(Tuple::new(self.read_values()?)),
+            b'd' => {
+                let len = self.read_len()?;
+                let mut dict = Dictionary::new_typed();
+                for _ in 0..len {
+                    let k = self.read_value()?;
+                    let v = self.read_value()?;
+                    dict.insert(k, v)?;
+                }
+                Value::new(dict)
+            }
+            b'x' => {
+                let tag = self.read_string()?;
+                let len = self.read_len()?;
+                let payload = self.read_bytes(len)?;
+                match self.deserializers.factories.get(&tag) {
+                    Some(factory) => factory(payload)?,
+                    None => return Err(invalid(&format!("no factory for tag '{}'", tag))),
+                }
+            }
+            _ => return Err(invalid("unknown value marker")),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_canonical_bytes() {
+        let list = Value::from(vec![Value::new(1), Value::new("a".to_owned())]);
+        let tuple = Value::new(Tuple::new(vec![Value::new(1), Value::new("a".to_owned())]));
+        assert_ne!(
+            canonical_bytes(&list).unwrap(),
+            canonical_bytes(&tuple).unwrap()
+        );
+
+        let mut d1 = Dictionary::new_typed();
+        d1.insert(Value::new(1), Value::new(true)).unwrap();
+        d1.insert(Value::new(2), Value::new(false)).unwrap();
+        let mut d2 = Dictionary::new_typed();
+        d2.insert(Value::new(2), Value::new(false)).unwrap();
+        d2.insert(Value::new(1), Value::new(true)).unwrap();
+        assert_eq!(
+            canonical_bytes(&Value::new(d1)).unwrap(),
+            canonical_bytes(&Value::new(d2)).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut d = Dictionary::new_typed();
+        d.insert(Value::from("a"), Value::from((1, true, NoneType::None)))
+            .unwrap();
+        d.insert(Value::new(-3), Value::from(vec![Value::from("é")]))
+            .unwrap();
+        d.insert(
+            Value::from("big"),
+            Value::from(vec![
+                Value::new(BigInt::from(i64::max_value()).add(&1.into()).unwrap()),
+                Value::new(BigInt::from(i64::min_value()).sub(&1.into()).unwrap()),
+            ]),
+        )
+        .unwrap();
+        let value = Value::new(d);
+        let restored =
+            from_canonical_bytes(&canonical_bytes(&value).unwrap(), &Deserializers::default())
+                .unwrap();
+        assert_eq!(value, restored);
+    }
+
+    #[test]
+    fn test_invalid_bytes() {
+        let deserializers = Deserializers::default();
+        let mut bytes = canonical_bytes(&Value::from("abc")).unwrap();
+        assert!(from_canonical_bytes(&bytes[..bytes.len() - 1], &deserializers).is_err());
+        bytes.push(b'N');
+        assert!(from_canonical_bytes(&bytes, &deserializers).is_err());
+        assert!(from_canonical_bytes(b"?", &deserializers).is_err());
+        // Host values need a registered factory
+        let mut bytes = vec![b'x'];
+        bytes.extend_from_slice(&3u64.to_be_bytes());
+        bytes.extend_from_slice(b"tag");
+        bytes.extend_from_slice(&0u64.to_be_bytes());
+        assert!(from_canonical_bytes(&bytes, &deserializers).is_err());
+    }
+}
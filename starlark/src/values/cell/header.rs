@@ -164,12 +164,24 @@ impl ObjectHeader {
         &IMMUTABLE_OBJECT_HEADER.0
     }
 
-    /// Freeze the object.
-    pub fn freeze(&self) {
+    /// Whether the object is mutable and not frozen.
+    #[cfg(feature = "provenance")]
+    pub fn is_mutable(&self) -> bool {
         match self.get_decoded() {
-            ObjectState::Frozen => {}
-            ObjectState::Immutable => {}
-            ObjectState::Borrowed(0, _) => self.set_decoded(ObjectState::Frozen),
+            ObjectState::Immutable | ObjectState::Frozen => false,
+            ObjectState::Borrowed(..) | ObjectState::BorrowedMut => true,
+        }
+    }
+
+    /// Freeze the object, return `true` if it was mutable before.
+    pub fn freeze(&self) -> bool {
+        match self.get_decoded() {
+            ObjectState::Frozen => false,
+            ObjectState::Immutable => false,
+            ObjectState::Borrowed(0, _) => {
+                self.set_decoded(ObjectState::Frozen);
+                true
+            }
             ObjectState::Borrowed(..) => panic!("cannot freeze, because it is borrowed"),
             ObjectState::BorrowedMut => panic!("cannot freeze, because it is borrowed mutably"),
         }
@@ -158,6 +158,12 @@ impl<T: ?Sized> ObjectCell<T> {
         Ok(unsafe { ObjectRefMut::new(&self.value, borrow) })
     }
 
+    /// Whether the value is mutable and not frozen.
+    #[cfg(feature = "provenance")]
+    pub fn is_mutable(&self) -> bool {
+        self.header.is_mutable()
+    }
+
     pub fn get_ptr(&self) -> *const T {
         self.value.get() as *const T
     }
@@ -167,7 +173,7 @@ impl<T: ?Sized> ObjectCell<T> {
     /// # Panics
     ///
     /// If value is borrowed.
-    pub fn freeze(&self) {
-        self.header.freeze();
+    pub fn freeze(&self) -> bool {
+        self.header.freeze()
     }
 }
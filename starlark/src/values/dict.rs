@@ -13,6 +13,8 @@
 // limitations under the License.
 
 //! Module define the Starlark type Dictionary
+use crate::environment::TypeValues;
+use crate::eval::call_stack::CallStack;
 use crate::values::error::ValueError;
 use crate::values::hashed_value::HashedValue;
 use crate::values::iter::TypedIterable;
@@ -27,12 +29,53 @@ use std::hash::Hash;
 #[derive(Default)]
 pub struct Dictionary {
     content: LinkedHashMap<HashedValue, Value>,
+    /// Function called to produce the value of a missing key on `[]` access,
+    /// for dictionaries created by `defaultdict(factory)`.
+    default_factory: Option<Value>,
+    /// Whether the string keys can be accessed as attributes (`d.key`), for dictionaries
+    /// created by `attrdict()`.
+    attribute_access: bool,
 }
 
 impl Dictionary {
     pub fn new_typed() -> Dictionary {
         Dictionary {
             content: LinkedHashMap::new(),
+            default_factory: None,
+            attribute_access: false,
+        }
+    }
+
+    /// Create an empty dictionary which inserts the result of calling `factory` without
+    /// arguments when a missing key is accessed with `[]`.
+    pub fn new_with_default_factory(factory: Value) -> Dictionary {
+        Dictionary {
+            content: LinkedHashMap::new(),
+            default_factory: Some(factory),
+            attribute_access: false,
+        }
+    }
+
+    /// Create an empty dictionary whose string keys can also be read and written as
+    /// attributes: `d.key` is `d["key"]`.
+    pub fn new_with_attribute_access() -> Dictionary {
+        Dictionary {
+            content: LinkedHashMap::new(),
+            default_factory: None,
+            attribute_access: true,
+        }
+    }
+
+    /// The function producing the value of missing keys, if any.
+    pub fn default_factory(&self) -> Option<&Value> {
+        self.default_factory.as_ref()
+    }
+
+    fn attribute_not_supported(op: String) -> ValueError {
+        ValueError::OperationNotSupported {
+            op,
+            left: Dictionary::TYPE.to_owned(),
+            right: None,
         }
     }
 
@@ -98,9 +141,7 @@ impl<T1: Into<Value> + Hash + Eq + Clone, T2: Into<Value> + Eq + Clone> TryFrom<
     type Error = ValueError;
 
     fn try_from(a: HashMap<T1, T2>) -> Result<Dictionary, ValueError> {
-        let mut result = Dictionary {
-            content: LinkedHashMap::new(),
-        };
+        let mut result = Dictionary::new_typed();
         for (k, v) in a.iter() {
             result
                 .content
@@ -116,9 +157,7 @@ impl<T1: Into<Value> + Hash + Eq + Clone, T2: Into<Value> + Eq + Clone>
     type Error = ValueError;
 
     fn try_from(a: LinkedHashMap<T1, T2>) -> Result<Dictionary, ValueError> {
-        let mut result = Dictionary {
-            content: LinkedHashMap::new(),
-        };
+        let mut result = Dictionary::new_typed();
         for (k, v) in a.iter() {
             result
                 .content
@@ -139,7 +178,8 @@ impl TypedValue for Dictionary {
         Box::new(
             self.content
                 .iter()
-                .flat_map(|(k, v)| vec![k.get_value().clone(), v.clone()].into_iter()),
+                .flat_map(|(k, v)| vec![k.get_value().clone(), v.clone()].into_iter())
+                .chain(self.default_factory.iter().cloned()),
         )
     }
 
@@ -181,6 +221,30 @@ impl TypedValue for Dictionary {
         Ok(true)
     }
 
+    fn compute_at(
+        &self,
+        index: &Value,
+        call_stack: &mut CallStack,
+        type_values: &TypeValues,
+    ) -> Result<Option<Value>, ValueError> {
+        // A missing key of a `defaultdict` is inserted with the result of its factory
+        match self.default_factory {
+            Some(ref factory) if !self.content.contains_key(&HashedValue::new(index.clone())?) => {
+                factory
+                    .call(
+                        call_stack,
+                        type_values,
+                        Vec::new(),
+                        LinkedHashMap::new(),
+                        None,
+                        None,
+                    )
+                    .map(Some)
+            }
+            _ => Ok(None),
+        }
+    }
+
     fn at(&self, index: Value) -> ValueResult {
         match self.content.get(&HashedValue::new(index.clone())?) {
             Some(v) => Ok(v.clone()),
@@ -213,10 +277,47 @@ impl TypedValue for Dictionary {
         Ok(())
     }
 
+    fn get_attr(&self, attribute: &str) -> ValueResult {
+        let missing = || Dictionary::attribute_not_supported(format!(".{}", attribute));
+        if !self.attribute_access {
+            return Err(missing());
+        }
+        self.get(&Value::from(attribute))?
+            .cloned()
+            .ok_or_else(missing)
+    }
+
+    fn has_attr(&self, attribute: &str) -> Result<bool, ValueError> {
+        if !self.attribute_access {
+            return Err(Dictionary::attribute_not_supported("has_attr()".to_owned()));
+        }
+        Ok(self.get(&Value::from(attribute))?.is_some())
+    }
+
+    fn set_attr(&mut self, attribute: &str, new_value: Value) -> Result<(), ValueError> {
+        if !self.attribute_access {
+            return Err(Dictionary::attribute_not_supported(format!(
+                ".{} =",
+                attribute
+            )));
+        }
+        self.set_at(Value::from(attribute), new_value)
+    }
+
+    fn dir_attr(&self) -> Result<Vec<String>, ValueError> {
+        if !self.attribute_access {
+            return Err(Dictionary::attribute_not_supported("dir()".to_owned()));
+        }
+        Ok(self
+            .content
+            .keys()
+            .filter(|k| k.get_value().get_type() == "string")
+            .map(|k| k.get_value().to_str())
+            .collect())
+    }
+
     fn add(&self, other: &Dictionary) -> Result<Dictionary, ValueError> {
-        let mut result = Dictionary {
-            content: LinkedHashMap::new(),
-        };
+        let mut result = Dictionary::new_typed();
         for (k, v) in &self.content {
             result.content.insert(k.clone(), v.clone());
         }
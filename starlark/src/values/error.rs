@@ -19,6 +19,7 @@ use crate::values::string::interpolation::StringInterpolationError;
 use crate::values::*;
 use codemap::Span;
 use codemap_diagnostic::{Diagnostic, SpanLabel, SpanStyle};
+use std::cell::Cell;
 
 // TODO: move that code in some common error code list?
 // CV prefix = Critical Value expression
@@ -40,6 +41,33 @@ pub const INTEGER_OVERFLOW_ERROR_CODE: &str = "CV16";
 pub const INTERPOLATION_UNEXPECTED_EOF_CLOSING_PAREN: &str = "CV17";
 pub const INTERPOLATION_UNEXPECTED_EOF_PERCENT: &str = "CV18";
 pub const INTERPOLATION_UNKNOWN_SPECIFIER: &str = "CV19";
+pub const INVALID_SERIALIZED_VALUE_ERROR_CODE: &str = "CV20";
+pub const NEGATIVE_EXPONENT_ERROR_CODE: &str = "CV21";
+pub const VALUE_TOO_LARGE_ERROR_CODE: &str = "CV22";
+pub const RECURSIVE_LAZY_SEQ_ERROR_CODE: &str = "CV23";
+
+/// Largest length, in characters, bytes or elements, of a value whose size is chosen by the
+/// program, e.g. with the width of a format specifier: larger values fail instead of
+/// exhausting memory.
+pub const MAX_REQUESTED_LENGTH: usize = 1 << 24;
+
+/// Check that `len`, the length of a value created by `what`, is at most
+/// [`MAX_REQUESTED_LENGTH`].
+pub(crate) fn check_requested_length(len: usize, what: &str) -> Result<usize, ValueError> {
+    if len > MAX_REQUESTED_LENGTH {
+        Err(RuntimeError {
+            code: VALUE_TOO_LARGE_ERROR_CODE,
+            message: format!(
+                "{} would create a value of length {}, larger than the limit of {}",
+                what, len, MAX_REQUESTED_LENGTH
+            ),
+            label: "Value too large".to_owned(),
+        }
+        .into())
+    } else {
+        Ok(len)
+    }
+}
 
 /// Error that can be returned by function from the `TypedValue` trait,
 #[derive(Clone, Debug)]
@@ -60,6 +88,12 @@ pub enum ValueError {
     IncorrectParameterType,
     /// Trying to apply incorrect parameter type, e.g. for slicing.
     IncorrectParameterTypeNamed(&'static str),
+    /// The value of an entry of a `**kwargs` parameter has an incorrect type.
+    IncorrectParameterTypeNamedKey {
+        param_name: &'static str,
+        key: String,
+        value_type: String,
+    },
     /// Trying to access an index outside of the value range,
     IndexOutOfBound(i64),
     /// The value is not hashable but was requested for a hash structure (e.g. dictionary).
@@ -82,6 +116,45 @@ pub enum ValueError {
     TypeNotSupported(String),
 }
 
+/// Default for [`Environment::set_max_error_repr_length`](
+/// crate::environment::Environment::set_max_error_repr_length).
+pub const DEFAULT_MAX_ERROR_REPR_LENGTH: usize = 200;
+
+// The limit is thread-local because error messages are built deep in the values, where no
+// evaluation context is available.
+thread_local!(
+    static MAX_ERROR_REPR_LENGTH: Cell<usize> = const { Cell::new(DEFAULT_MAX_ERROR_REPR_LENGTH) }
+);
+
+/// Make `max_len` the maximum length of the representations in error messages until the
+/// returned guard is dropped.
+pub(crate) fn activate_max_error_repr_length(max_len: usize) -> MaxErrorReprLengthGuard {
+    let previous = MAX_ERROR_REPR_LENGTH.with(|c| c.replace(max_len));
+    MaxErrorReprLengthGuard { previous }
+}
+
+/// Restores the previously active limit on drop.
+#[must_use]
+pub(crate) struct MaxErrorReprLengthGuard {
+    previous: usize,
+}
+
+impl Drop for MaxErrorReprLengthGuard {
+    fn drop(&mut self) {
+        MAX_ERROR_REPR_LENGTH.with(|c| c.set(self.previous));
+    }
+}
+
+/// The maximum length of the representations in error messages of the running evaluation.
+pub(crate) fn max_error_repr_length() -> usize {
+    MAX_ERROR_REPR_LENGTH.with(Cell::get)
+}
+
+/// The representation of `value` for an error message, see [`Value::to_error_repr`].
+pub(crate) fn error_repr<T: TypedValue>(value: &T) -> String {
+    truncate_chars(value.to_repr(), max_error_repr_length())
+}
+
 /// A simpler error format to return as a ValueError
 #[derive(Clone, Debug)]
 pub struct RuntimeError {
@@ -145,7 +218,8 @@ impl SyntaxError for ValueError {
                         ValueError::IncorrectParameterType => {
                             "Type of parameters mismatch".to_owned()
                         }
-                        ValueError::IncorrectParameterTypeNamed(_) => {
+                        ValueError::IncorrectParameterTypeNamed(_)
+                        | ValueError::IncorrectParameterTypeNamedKey { .. } => {
                             "Type of parameters mismatch".to_owned()
                         }
                         ValueError::IndexOutOfBound(..) => "Index out of bound".to_owned(),
@@ -190,11 +264,21 @@ impl SyntaxError for ValueError {
                         ValueError::IncorrectParameterTypeNamed(name) => {
                             format!("Type of parameters {} mismatch", name)
                         }
+                        ValueError::IncorrectParameterTypeNamedKey {
+                            param_name,
+                            ref key,
+                            ref value_type,
+                        } => format!(
+                            "Type of parameters {}['{}'] mismatch, got {}",
+                            param_name, key, value_type
+                        ),
                         ValueError::IndexOutOfBound(ref b) => {
                             format!("Index {} is out of bound", b)
                         }
                         ValueError::NotHashableValue => "Value is not hashable".to_owned(),
-                        ValueError::KeyNotFound(ref k) => format!("Key '{}' was not found", k),
+                        ValueError::KeyNotFound(ref k) => {
+                            format!("Key '{}' was not found", k.to_error_str())
+                        }
                         ValueError::TooManyRecursionLevel => "Too many recursion levels".to_owned(),
                         ValueError::UnsupportedRecursiveDataStructure => concat!(
                             "This operation create a recursive data structure. Recursive data",
@@ -214,7 +298,9 @@ impl SyntaxError for ValueError {
                             ValueError::DivisionByZero => DIVISION_BY_ZERO_ERROR_CODE,
                             ValueError::IntegerOverflow => INTEGER_OVERFLOW_ERROR_CODE,
                             ValueError::ObjectBorrowMutError(_) => BORROW_MUT_ERROR_CODE,
-                            ValueError::IncorrectParameterType | ValueError::IncorrectParameterTypeNamed(..) => {
+                            ValueError::IncorrectParameterType
+                            | ValueError::IncorrectParameterTypeNamed(..)
+                            | ValueError::IncorrectParameterTypeNamedKey { .. } => {
                                 INCORRECT_PARAMETER_TYPE_ERROR_CODE
                             }
                             ValueError::IndexOutOfBound(..) => OUT_OF_BOUND_ERROR_CODE,
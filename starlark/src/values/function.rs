@@ -22,18 +22,52 @@ use std::iter;
 use std::mem;
 use std::vec;
 
+/// A parameter of a [`FunctionSignature`].
 #[derive(Debug, Clone)]
-#[doc(hidden)]
 pub enum FunctionParameter {
+    /// Required parameter.
     Normal(String),
+    /// Parameter which may be omitted, bound to `None` in a [`FunctionArg::Optional`].
     Optional(String),
+    /// Parameter with a default value used when it is omitted.
     WithDefaultValue(String, Value),
+    /// Parameter of a `def` whose default value refers to earlier parameters: the function
+    /// evaluates it on each call when the parameter is omitted. It is bound to a
+    /// [`FunctionArg::Optional`].
+    LazyDefaultValue(String),
+    /// `*args` parameter.
     ArgsArray(String),
+    /// `**kwargs` parameter.
     KWArgsDict(String),
+    /// Keyword-only parameter, which can only be filled by a named argument.
+    KWOnlyNormal(String),
+    KWOnlyOptional(String),
+    KWOnlyWithDefaultValue(String, Value),
+    /// Required parameter whose value must be of the given type, as returned by
+    /// [`Value::get_type`], e.g. `"int"`. The type is checked when the arguments are bound.
+    Typed(String, String),
+    /// Same as [`FunctionParameter::Optional`], with the value checked as for
+    /// [`FunctionParameter::Typed`] when it is passed.
+    TypedOptional(String, String),
+    /// Same as [`FunctionParameter::WithDefaultValue`], with the value checked as for
+    /// [`FunctionParameter::Typed`] when it is passed. The default value is not checked.
+    TypedWithDefaultValue(String, String, Value),
 }
 
+impl FunctionParameter {
+    /// Whether this parameter can only be passed by name.
+    pub(crate) fn is_keyword_only(&self) -> bool {
+        matches!(
+            self,
+            FunctionParameter::KWOnlyNormal(..)
+                | FunctionParameter::KWOnlyOptional(..)
+                | FunctionParameter::KWOnlyWithDefaultValue(..)
+        )
+    }
+}
+
+/// The parameters of a function.
 #[derive(Debug, Clone)]
-#[doc(hidden)]
 pub struct FunctionSignature {
     params: Vec<FunctionParameter>,
     /// Number of leading positional-only parameters
@@ -41,16 +75,38 @@ pub struct FunctionSignature {
 }
 
 impl FunctionSignature {
-    pub(crate) fn new(
-        parameters: Vec<FunctionParameter>,
-        positional_count: usize,
-    ) -> FunctionSignature {
+    /// Create a signature with the given parameters, the first `positional_count` of which
+    /// are positional-only.
+    pub fn new(parameters: Vec<FunctionParameter>, positional_count: usize) -> FunctionSignature {
         FunctionSignature {
             params: parameters,
             positional_count,
         }
     }
 
+    /// Minimum and maximum number of positional arguments accepted, the maximum is `None`
+    /// if there is an `*args` parameter.
+    fn positional_range(&self) -> (usize, Option<usize>) {
+        let mut min = 0;
+        let mut max = Some(0);
+        for p in &self.params {
+            match p {
+                FunctionParameter::Normal(..) | FunctionParameter::Typed(..) => {
+                    min += 1;
+                    max = max.map(|m| m + 1);
+                }
+                FunctionParameter::Optional(..)
+                | FunctionParameter::WithDefaultValue(..)
+                | FunctionParameter::LazyDefaultValue(..)
+                | FunctionParameter::TypedOptional(..)
+                | FunctionParameter::TypedWithDefaultValue(..) => max = max.map(|m| m + 1),
+                FunctionParameter::ArgsArray(..) => max = None,
+                _ => {}
+            }
+        }
+        (min, max)
+    }
+
     pub(crate) fn iter<'a>(&'a self) -> impl Iterator<Item = (&'a FunctionParameter, bool)> + 'a {
         let positional_count = self.positional_count;
         self.params
@@ -67,6 +123,7 @@ pub enum FunctionType {
     Def(String, String),
 }
 
+/// An argument bound to a parameter, e.g. by [`bind_arguments`].
 #[derive(Debug, Clone)]
 pub enum FunctionArg {
     Normal(Value),
@@ -124,18 +181,58 @@ impl FunctionArg {
         match self {
             FunctionArg::KWArgsDict(dict) => Ok({
                 let mut r = LinkedHashMap::new();
+                // In insertion order, so that the first incorrect entry is reported
                 for (k, v) in dict {
-                    r.insert(
-                        k,
-                        T::try_from(v)
-                            .map_err(|_| ValueError::IncorrectParameterTypeNamed(param_name))?,
-                    );
+                    let value_type = v.get_type();
+                    match T::try_from(v) {
+                        Ok(v) => r.insert(k, v),
+                        Err(_) => {
+                            return Err(ValueError::IncorrectParameterTypeNamedKey {
+                                param_name,
+                                key: k,
+                                value_type: value_type.to_owned(),
+                            })
+                        }
+                    };
                 }
                 r
             }),
             _ => Err(ValueError::IncorrectParameterType),
         }
     }
+
+    /// The value of a [`FunctionArg::Normal`] argument, without consuming it.
+    pub fn as_normal(&self) -> Option<&Value> {
+        match self {
+            FunctionArg::Normal(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// The value of a [`FunctionArg::Optional`] argument, without consuming it: `Some(None)`
+    /// if the parameter was omitted.
+    pub fn as_optional(&self) -> Option<Option<&Value>> {
+        match self {
+            FunctionArg::Optional(v) => Some(v.as_ref()),
+            _ => None,
+        }
+    }
+
+    /// The values of a [`FunctionArg::ArgsArray`] argument, without consuming it.
+    pub fn as_args_array(&self) -> Option<&[Value]> {
+        match self {
+            FunctionArg::ArgsArray(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// The values of a [`FunctionArg::KWArgsDict`] argument, without consuming it.
+    pub fn as_kw_args_dict(&self) -> Option<&LinkedHashMap<String, Value>> {
+        match self {
+            FunctionArg::KWArgsDict(dict) => Some(dict),
+            _ => None,
+        }
+    }
 }
 
 impl From<FunctionArg> for Value {
@@ -169,6 +266,8 @@ pub struct NativeFunction {
     function: fn(&mut CallStack, &TypeValues, ParameterParser) -> ValueResult,
     signature: FunctionSignature,
     function_type: FunctionType,
+    /// The function has no side effects and its result only depends on its arguments.
+    pure: bool,
 }
 
 // Wrapper for method that have been affected the self object
@@ -179,24 +278,55 @@ pub(crate) struct WrappedMethod {
 
 // TODO: move that code in some common error code list?
 // CV prefix = Critical Function call
-const NOT_ENOUGH_PARAMS_ERROR_CODE: &str = "CF00";
+pub(crate) const NOT_ENOUGH_PARAMS_ERROR_CODE: &str = "CF00";
 const WRONG_ARGS_IDENT_ERROR_CODE: &str = "CF01";
 const ARGS_NOT_ITERABLE_ERROR_CODE: &str = "CF02";
 const KWARGS_NOT_MAPPABLE_ERROR_CODE: &str = "CF03";
 // Not an error: const KWARGS_KEY_IDENT_ERROR_CODE: &str = "CF04";
-const EXTRA_PARAMETER_ERROR_CODE: &str = "CF05";
+pub(crate) const EXTRA_PARAMETER_ERROR_CODE: &str = "CF05";
+pub(crate) const KEYWORD_ONLY_PARAMETER_ERROR_CODE: &str = "CF06";
+pub(crate) const DUPLICATE_PARAMETER_ERROR_CODE: &str = "CF07";
+pub(crate) const MULTIPLE_VALUES_ERROR_CODE: &str = "CF08";
+pub(crate) const INCORRECT_PARAMETER_TYPE_ERROR_CODE: &str = "CF09";
 
 #[derive(Debug, Clone)]
 pub enum FunctionError {
     NotEnoughParameter {
-        missing: String,
+        /// Names of all the required parameters without a value, in signature order.
+        missing: Vec<String>,
         function_type: FunctionType,
         signature: FunctionSignature,
+        /// Number of positional arguments of the call.
+        positional_given: usize,
     },
     ArgsValueIsNotString,
     ArgsArrayIsNotIterable,
     KWArgsDictIsNotMappable,
     ExtraParameter,
+    TooManyPositionalArguments {
+        function_type: FunctionType,
+        signature: FunctionSignature,
+        positional_given: usize,
+    },
+    KeywordOnlyParameterPassedPositionally {
+        name: String,
+        function_type: FunctionType,
+        signature: FunctionSignature,
+    },
+    DuplicateParameter {
+        name: String,
+    },
+    MultipleValues {
+        name: String,
+        function_type: FunctionType,
+    },
+    IncorrectParameterType {
+        name: String,
+        expected: String,
+        actual: String,
+        function_type: FunctionType,
+        signature: FunctionSignature,
+    },
 }
 
 impl Into<RuntimeError> for FunctionError {
@@ -207,7 +337,14 @@ impl Into<RuntimeError> for FunctionError {
                 FunctionError::ArgsValueIsNotString => WRONG_ARGS_IDENT_ERROR_CODE,
                 FunctionError::ArgsArrayIsNotIterable => ARGS_NOT_ITERABLE_ERROR_CODE,
                 FunctionError::KWArgsDictIsNotMappable => KWARGS_NOT_MAPPABLE_ERROR_CODE,
-                FunctionError::ExtraParameter => EXTRA_PARAMETER_ERROR_CODE,
+                FunctionError::ExtraParameter
+                | FunctionError::TooManyPositionalArguments { .. } => EXTRA_PARAMETER_ERROR_CODE,
+                FunctionError::KeywordOnlyParameterPassedPositionally { .. } => {
+                    KEYWORD_ONLY_PARAMETER_ERROR_CODE
+                }
+                FunctionError::DuplicateParameter { .. } => DUPLICATE_PARAMETER_ERROR_CODE,
+                FunctionError::MultipleValues { .. } => MULTIPLE_VALUES_ERROR_CODE,
+                FunctionError::IncorrectParameterType { .. } => INCORRECT_PARAMETER_TYPE_ERROR_CODE,
             },
             label: match self {
                 FunctionError::NotEnoughParameter { .. } => {
@@ -217,17 +354,50 @@ impl Into<RuntimeError> for FunctionError {
                 FunctionError::ArgsArrayIsNotIterable => "*args is not iterable".to_owned(),
                 FunctionError::KWArgsDictIsNotMappable => "**kwargs is not mappable".to_owned(),
                 FunctionError::ExtraParameter => "Extraneous parameter in function call".to_owned(),
+                FunctionError::TooManyPositionalArguments { .. } => {
+                    "Too many positional arguments in function call".to_owned()
+                }
+                FunctionError::KeywordOnlyParameterPassedPositionally { .. } => {
+                    "Keyword-only parameter passed positionally".to_owned()
+                }
+                FunctionError::DuplicateParameter { .. } => {
+                    "Duplicate keyword argument in function call".to_owned()
+                }
+                FunctionError::MultipleValues { .. } => {
+                    "Parameter passed both positionally and by name".to_owned()
+                }
+                FunctionError::IncorrectParameterType { .. } => {
+                    "Incorrect parameter type in function call".to_owned()
+                }
             },
             message: match self {
                 FunctionError::NotEnoughParameter {
                     missing,
                     function_type,
                     signature,
-                } => format!(
-                    "Missing parameter {} for call to {}",
-                    missing.trim_start_matches('$'),
-                    repr(&function_type, &signature)
-                ),
+                    positional_given,
+                } => {
+                    let mut message = format!(
+                        "Missing parameter{} {} for call to {}",
+                        if missing.len() > 1 { "s" } else { "" },
+                        missing
+                            .iter()
+                            .map(|m| m.trim_start_matches('$'))
+                            .collect::<Vec<_>>()
+                            .join(", "),
+                        repr(&function_type, &signature)
+                    );
+                    // Only mention counts if arguments were passed positionally
+                    if positional_given != 0 && positional_given < signature.positional_range().0 {
+                        message.push_str(": ");
+                        message.push_str(&positional_count_message(
+                            &function_type,
+                            &signature,
+                            positional_given,
+                        ));
+                    }
+                    message
+                }
                 FunctionError::ArgsValueIsNotString => {
                     "The argument provided for *args is not an identifier".to_owned()
                 }
@@ -240,11 +410,74 @@ impl Into<RuntimeError> for FunctionError {
                 FunctionError::ExtraParameter => {
                     "Extraneous parameter passed to function call".to_owned()
                 }
+                FunctionError::TooManyPositionalArguments {
+                    function_type,
+                    signature,
+                    positional_given,
+                } => positional_count_message(&function_type, &signature, positional_given),
+                FunctionError::KeywordOnlyParameterPassedPositionally {
+                    name,
+                    function_type,
+                    signature,
+                } => format!(
+                    "Too many positional arguments for call to {}: parameter {} is keyword-only \
+                     and must be passed as {}=...",
+                    repr(&function_type, &signature),
+                    name,
+                    name
+                ),
+                FunctionError::DuplicateParameter { name } => {
+                    format!("Multiple values for keyword argument {}", name)
+                }
+                FunctionError::MultipleValues {
+                    name,
+                    function_type,
+                } => format!(
+                    "{}() got multiple values for argument '{}'",
+                    function_type.to_str(),
+                    name
+                ),
+                FunctionError::IncorrectParameterType {
+                    name,
+                    expected,
+                    actual,
+                    function_type,
+                    signature,
+                } => format!(
+                    "Parameter {} of call to {} expects a value of type {}, got {}",
+                    name,
+                    repr(&function_type, &signature),
+                    expected,
+                    actual
+                ),
             },
         }
     }
 }
 
+/// Python-like description of the positional arguments a function accepts, e.g.
+/// `f() takes 2 positional arguments but 4 were given`.
+fn positional_count_message(
+    function_type: &FunctionType,
+    signature: &FunctionSignature,
+    given: usize,
+) -> String {
+    let (min, max) = signature.positional_range();
+    let accepted = match max {
+        Some(max) if max == min => format!("{}", max),
+        Some(max) => format!("from {} to {}", min, max),
+        None => format!("at least {}", min),
+    };
+    format!(
+        "{}() takes {} positional argument{} but {} {} given",
+        function_type.to_str(),
+        accepted,
+        if min == 1 && max == Some(1) { "" } else { "s" },
+        given,
+        if given == 1 { "was" } else { "were" }
+    )
+}
+
 impl From<FunctionError> for ValueError {
     fn from(e: FunctionError) -> Self {
         ValueError::Runtime(e.into())
@@ -261,8 +494,95 @@ impl NativeFunction {
             function,
             signature,
             function_type: FunctionType::Native(name),
+            pure: false,
+        })
+    }
+
+    /// Same as [`NativeFunction::new`], but mark the function as pure: it has no side effects
+    /// and returns equal results for equal arguments, so calls with constant arguments
+    /// may be cached.
+    pub fn new_pure(
+        name: String,
+        function: fn(&mut CallStack, &TypeValues, ParameterParser) -> ValueResult,
+        signature: FunctionSignature,
+    ) -> Value {
+        Value::new(NativeFunction {
+            function,
+            signature,
+            function_type: FunctionType::Native(name),
+            pure: true,
+        })
+    }
+
+    /// Whether this function was marked as pure.
+    pub fn is_pure(&self) -> bool {
+        self.pure
+    }
+
+    pub(crate) fn signature(&self) -> &FunctionSignature {
+        &self.signature
+    }
+}
+
+pub type NativeClosurePrototype =
+    dyn Fn(&mut CallStack, &TypeValues, ParameterParser) -> ValueResult + Send + Sync;
+
+/// Function implementation for native functions capturing state, e.g. a handle owned by
+/// the embedding application.
+///
+/// Unlike [`NativeFunction`], the function is boxed, so each instance costs an allocation.
+pub struct NativeClosure {
+    function: Box<NativeClosurePrototype>,
+    signature: FunctionSignature,
+    function_type: FunctionType,
+}
+
+impl NativeClosure {
+    /// Create a function value named `name` calling `function` with the arguments
+    /// parsed according to `signature`.
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new<F>(name: String, function: F, signature: FunctionSignature) -> Value
+    where
+        F: Fn(&mut CallStack, &TypeValues, ParameterParser) -> ValueResult + Send + Sync + 'static,
+    {
+        Value::new(NativeClosure {
+            function: Box::new(function),
+            signature,
+            function_type: FunctionType::Native(name),
         })
     }
+
+    pub(crate) fn signature(&self) -> &FunctionSignature {
+        &self.signature
+    }
+}
+
+/// Return a copy of the native function `f` marked as pure, e.g. to mark a function
+/// registered with `starlark_module!`.
+///
+/// Fails if `f` is not a native function.
+pub fn mark_pure(f: &Value) -> ValueResult {
+    match f.downcast_ref::<NativeFunction>() {
+        Some(f) => Ok(Value::new(NativeFunction {
+            function: f.function,
+            signature: f.signature.clone(),
+            function_type: f.function_type.clone(),
+            pure: true,
+        })),
+        None => Err(ValueError::IncorrectParameterType),
+    }
+}
+
+/// Whether `f` is a function marked as pure. Functions defined in Starlark and functions
+/// not explicitly marked are never considered pure.
+pub fn is_pure(f: &Value) -> bool {
+    if let Some(f) = f.downcast_ref::<NativeFunction>() {
+        f.is_pure()
+    } else if let Some(m) = f.downcast_ref::<WrappedMethod>() {
+        is_pure(&m.method)
+    } else {
+        false
+    }
 }
 
 impl WrappedMethod {
@@ -310,6 +630,8 @@ pub(crate) fn str_impl(
     )?;
     write!(buf, "(")?;
 
+    // Whether a `*` or `*args` marker was written, after which parameters are keyword-only
+    let mut seen_star = false;
     for (i, x) in signature.params.iter().enumerate() {
         if i != 0 && i == signature.positional_count {
             write!(buf, ", /")?;
@@ -319,14 +641,36 @@ pub(crate) fn str_impl(
             write!(buf, ", ")?;
         }
 
+        if x.is_keyword_only() && !seen_star {
+            write!(buf, "*, ")?;
+        }
+        if x.is_keyword_only() || matches!(x, FunctionParameter::ArgsArray(..)) {
+            seen_star = true;
+        }
+
         match x {
             FunctionParameter::Normal(ref name) => write!(buf, "{}", name)?,
             FunctionParameter::Optional(ref name) => write!(buf, "?{}", name)?,
             FunctionParameter::WithDefaultValue(ref name, ref value) => {
                 write!(buf, "{} = {}", name, value.to_repr())?;
             }
+            FunctionParameter::LazyDefaultValue(ref name) => write!(buf, "{} = ...", name)?,
             FunctionParameter::ArgsArray(ref name) => write!(buf, "*{}", name)?,
             FunctionParameter::KWArgsDict(ref name) => write!(buf, "**{}", name)?,
+            FunctionParameter::KWOnlyNormal(ref name) => write!(buf, "{}", name)?,
+            FunctionParameter::KWOnlyOptional(ref name) => write!(buf, "?{}", name)?,
+            FunctionParameter::KWOnlyWithDefaultValue(ref name, ref value) => {
+                write!(buf, "{} = {}", name, value.to_repr())?;
+            }
+            FunctionParameter::Typed(ref name, ref expected) => {
+                write!(buf, "{}: {}", name, expected)?
+            }
+            FunctionParameter::TypedOptional(ref name, ref expected) => {
+                write!(buf, "?{}: {}", name, expected)?
+            }
+            FunctionParameter::TypedWithDefaultValue(ref name, ref expected, ref value) => {
+                write!(buf, "{}: {} = {}", name, expected, value.to_repr())?
+            }
         }
     }
 
@@ -351,6 +695,8 @@ pub struct ParameterParser<'a> {
     index: usize,
     function_type: &'a FunctionType,
     positional: vec::IntoIter<Value>,
+    /// Number of positional arguments of the call, including those from `*args`.
+    positional_given: usize,
     kwargs: LinkedHashMap<String, Value>,
 }
 
@@ -371,6 +717,7 @@ impl<'a> ParameterParser<'a> {
                 Err(..) => return Err(FunctionError::ArgsArrayIsNotIterable.into()),
             }
         };
+        let positional_given = av.len();
         let positional = av.into_iter();
         // Collect kwargs
         let mut kwargs = named;
@@ -380,6 +727,9 @@ impl<'a> ParameterParser<'a> {
                     for n in &y {
                         if n.get_type() == "string" {
                             let k = n.to_str();
+                            if kwargs.contains_key(&k) {
+                                return Err(FunctionError::DuplicateParameter { name: k }.into());
+                            }
                             if let Ok(v) = x.at(n) {
                                 kwargs.insert(k, v);
                             } else {
@@ -399,54 +749,120 @@ impl<'a> ParameterParser<'a> {
             index: 0,
             function_type,
             positional,
+            positional_given,
             kwargs,
         })
     }
 
+    /// Take the next positional argument for parameter `name`, failing if the parameter
+    /// is also passed by name.
+    fn next_positional(
+        &mut self,
+        name: &str,
+        positional_only: bool,
+    ) -> Result<Option<Value>, ValueError> {
+        match self.positional.next() {
+            Some(_) if !positional_only && self.kwargs.contains_key(name) => {
+                Err(FunctionError::MultipleValues {
+                    name: name.to_owned(),
+                    function_type: self.function_type.clone(),
+                }
+                .into())
+            }
+            x => Ok(x),
+        }
+    }
+
     pub fn next_normal(&mut self, name: &str, positional_only: bool) -> Result<Value, ValueError> {
-        if let Some(x) = self.positional.next() {
+        if let Some(x) = self.next_positional(name, positional_only)? {
             self.index += 1;
             return Ok(x);
         }
 
         if !positional_only {
-            if let Some(ref r) = self.kwargs.remove(name) {
+            if let Some(r) = self.kwargs.remove(name) {
                 self.index += 1;
-                return Ok(r.clone());
+                return Ok(r);
             }
         }
 
-        Err(FunctionError::NotEnoughParameter {
-            missing: name.to_string(),
+        Err(self.missing_parameters(self.index))
+    }
+
+    /// Error for the required parameter at `index` having no value. Positional arguments are
+    /// exhausted at this point, so it also reports the following required parameters which
+    /// no named argument fills.
+    fn missing_parameters(&self, index: usize) -> ValueError {
+        let missing = self.signature.params[index..]
+            .iter()
+            .enumerate()
+            .filter_map(|(i, p)| match p {
+                FunctionParameter::Normal(ref name)
+                | FunctionParameter::KWOnlyNormal(ref name)
+                | FunctionParameter::Typed(ref name, _)
+                    if i == 0
+                        || index + i < self.signature.positional_count
+                        || !self.kwargs.contains_key(name) =>
+                {
+                    Some(name.clone())
+                }
+                _ => None,
+            })
+            .collect();
+        FunctionError::NotEnoughParameter {
+            missing,
             function_type: self.function_type.clone(),
             signature: self.signature.clone(),
+            positional_given: self.positional_given,
         }
-        .into())
+        .into()
     }
 
-    pub fn next_optional(&mut self, name: &str, positional_only: bool) -> Option<Value> {
+    pub fn next_optional(
+        &mut self,
+        name: &str,
+        positional_only: bool,
+    ) -> Result<Option<Value>, ValueError> {
         self.index += 1;
-        if let Some(x) = self.positional.next() {
-            return Some(x);
+        if let Some(x) = self.next_positional(name, positional_only)? {
+            return Ok(Some(x));
         }
 
         if !positional_only {
-            if let Some(ref r) = self.kwargs.remove(name) {
-                return Some(r.clone());
+            if let Some(r) = self.kwargs.remove(name) {
+                return Ok(Some(r));
             }
         }
 
-        None
+        Ok(None)
     }
 
+    /// Default values are shared: cloning `default_value` only increments its reference
+    /// count.
     pub fn next_with_default_value(
         &mut self,
         name: &str,
         positional_only: bool,
         default_value: &Value,
-    ) -> Value {
-        self.next_optional(name, positional_only)
-            .unwrap_or_else(|| default_value.clone())
+    ) -> Result<Value, ValueError> {
+        Ok(self
+            .next_optional(name, positional_only)?
+            .unwrap_or_else(|| default_value.clone()))
+    }
+
+    /// Next keyword-only parameter: it is only filled from named arguments, and it is an
+    /// error if positional arguments remain to be bound.
+    pub fn next_keyword_only(&mut self, name: &str) -> Result<Option<Value>, ValueError> {
+        self.index += 1;
+        if !self.positional.as_slice().is_empty() {
+            return Err(FunctionError::KeywordOnlyParameterPassedPositionally {
+                name: name.to_owned(),
+                function_type: self.function_type.clone(),
+                signature: self.signature.clone(),
+            }
+            .into());
+        }
+        Ok(self.kwargs.remove(name))
     }
 
     pub fn next_args_array(&mut self) -> Vec<Value> {
@@ -460,7 +876,15 @@ impl<'a> ParameterParser<'a> {
     }
 
     pub fn check_no_more_args(&mut self) -> Result<(), ValueError> {
-        if self.positional.next().is_some() || !self.kwargs.is_empty() {
+        if self.positional.next().is_some() {
+            return Err(FunctionError::TooManyPositionalArguments {
+                function_type: self.function_type.clone(),
+                signature: self.signature.clone(),
+                positional_given: self.positional_given,
+            }
+            .into());
+        }
+        if !self.kwargs.is_empty() {
             return Err(FunctionError::ExtraParameter.into());
         }
         debug_assert_eq!(self.index, self.signature.params.len());
@@ -477,16 +901,105 @@ impl<'a> ParameterParser<'a> {
             FunctionParameter::Normal(ref name) => {
                 FunctionArg::Normal(self.next_normal(name, positional_only)?)
             }
-            FunctionParameter::Optional(ref name) => {
-                FunctionArg::Optional(self.next_optional(name, positional_only))
+            FunctionParameter::Optional(ref name)
+            | FunctionParameter::LazyDefaultValue(ref name) => {
+                FunctionArg::Optional(self.next_optional(name, positional_only)?)
             }
             FunctionParameter::WithDefaultValue(ref name, ref value) => {
-                FunctionArg::Normal(self.next_with_default_value(name, positional_only, value))
+                FunctionArg::Normal(self.next_with_default_value(name, positional_only, value)?)
             }
             FunctionParameter::ArgsArray(..) => FunctionArg::ArgsArray(self.next_args_array()),
             FunctionParameter::KWArgsDict(..) => FunctionArg::KWArgsDict(self.next_kwargs_dict()),
+            FunctionParameter::KWOnlyNormal(ref name) => match self.next_keyword_only(name)? {
+                Some(v) => FunctionArg::Normal(v),
+                None => return Err(self.missing_parameters(self.index - 1)),
+            },
+            FunctionParameter::KWOnlyOptional(ref name) => {
+                FunctionArg::Optional(self.next_keyword_only(name)?)
+            }
+            FunctionParameter::KWOnlyWithDefaultValue(ref name, ref value) => FunctionArg::Normal(
+                self.next_keyword_only(name)?
+                    .unwrap_or_else(|| value.clone()),
+            ),
+            FunctionParameter::Typed(ref name, ref expected) => {
+                let value = self.next_normal(name, positional_only)?;
+                FunctionArg::Normal(self.check_type(name, expected, value)?)
+            }
+            FunctionParameter::TypedOptional(ref name, ref expected) => {
+                FunctionArg::Optional(match self.next_optional(name, positional_only)? {
+                    Some(value) => Some(self.check_type(name, expected, value)?),
+                    None => None,
+                })
+            }
+            FunctionParameter::TypedWithDefaultValue(ref name, ref expected, ref default_value) => {
+                FunctionArg::Normal(match self.next_optional(name, positional_only)? {
+                    Some(value) => self.check_type(name, expected, value)?,
+                    None => default_value.clone(),
+                })
+            }
         })
     }
+
+    /// Check that the value passed for the parameter `name` is of type `expected`.
+    fn check_type(&self, name: &str, expected: &str, value: Value) -> Result<Value, ValueError> {
+        if value.get_type() != expected {
+            return Err(FunctionError::IncorrectParameterType {
+                name: name.to_owned(),
+                expected: expected.to_owned(),
+                actual: value.get_type().to_owned(),
+                function_type: self.function_type.clone(),
+                signature: self.signature.clone(),
+            }
+            .into());
+        }
+        Ok(value)
+    }
+}
+
+/// Bind the arguments of a call to the parameters of `signature`, with the same semantics
+/// as a call to a native function named `name`, which is used in error messages.
+///
+/// The result has one [`FunctionArg`] per parameter of `signature`, in order. It owns the
+/// argument values: they are the values passed by the caller, or the default values of the
+/// signature, shared rather than copied as for any [`Value`] clone.
+///
+/// This allows a custom callable value to implement [`TypedValue::call`] like a function:
+///
+/// ```
+/// # use linked_hash_map::LinkedHashMap;
+/// # use starlark::values::Value;
+/// # use starlark::values::function::*;
+/// let signature = FunctionSignature::new(
+///     vec![
+///         FunctionParameter::Normal("x".to_owned()),
+///         FunctionParameter::WithDefaultValue("y".to_owned(), Value::new(2)),
+///     ],
+///     0,
+/// );
+/// let mut named = LinkedHashMap::new();
+/// named.insert("x".to_owned(), Value::new(1));
+/// let args = bind_arguments("f", &signature, vec![], named, None, None).unwrap();
+/// let args: Vec<Value> = args.into_iter().map(Value::from).collect();
+/// assert_eq!(vec![Value::new(1), Value::new(2)], args);
+/// ```
+pub fn bind_arguments(
+    name: &str,
+    signature: &FunctionSignature,
+    positional: Vec<Value>,
+    named: LinkedHashMap<String, Value>,
+    args: Option<Value>,
+    kwargs: Option<Value>,
+) -> Result<Vec<FunctionArg>, ValueError> {
+    let function_type = FunctionType::Native(name.to_owned());
+    let mut parser =
+        ParameterParser::new(signature, &function_type, positional, named, args, kwargs)?;
+    let bound = signature
+        .params
+        .iter()
+        .map(|_| parser.next_arg())
+        .collect::<Result<Vec<_>, _>>()?;
+    parser.check_no_more_args()?;
+    Ok(bound)
 }
 
 /// Define the function type
@@ -530,6 +1043,46 @@ impl TypedValue for NativeFunction {
     }
 }
 
+impl TypedValue for NativeClosure {
+    type Holder = Immutable<NativeClosure>;
+
+    fn values_for_descendant_check_and_freeze<'a>(
+        &'a self,
+    ) -> Box<dyn Iterator<Item = Value> + 'a> {
+        Box::new(iter::empty())
+    }
+
+    fn to_str_impl(&self, buf: &mut String) -> fmt::Result {
+        str_impl(buf, &self.function_type, &self.signature, StrOrRepr::Str)
+    }
+    fn to_repr_impl(&self, buf: &mut String) -> fmt::Result {
+        str_impl(buf, &self.function_type, &self.signature, StrOrRepr::Repr)
+    }
+
+    const TYPE: &'static str = "function";
+
+    fn call(
+        &self,
+        call_stack: &mut CallStack,
+        type_values: &TypeValues,
+        positional: Vec<Value>,
+        named: LinkedHashMap<String, Value>,
+        args: Option<Value>,
+        kwargs: Option<Value>,
+    ) -> ValueResult {
+        let parser = ParameterParser::new(
+            &self.signature,
+            &self.function_type,
+            positional,
+            named,
+            args,
+            kwargs,
+        )?;
+
+        (self.function)(call_stack, type_values, parser)
+    }
+}
+
 impl TypedValue for WrappedMethod {
     type Holder = Immutable<WrappedMethod>;
 
@@ -672,6 +1225,23 @@ mod test {
         );
     }
 
+    #[test]
+    fn purity_flag() {
+        fn f(_: &mut CallStack, _: &TypeValues, _: ParameterParser) -> ValueResult {
+            Ok(Value::new(NoneType::None))
+        }
+
+        let impure = NativeFunction::new("f".to_owned(), f, FunctionSignature::new(vec![], 0));
+        let pure = NativeFunction::new_pure("f".to_owned(), f, FunctionSignature::new(vec![], 0));
+        assert!(!is_pure(&impure));
+        assert!(is_pure(&pure));
+        assert!(is_pure(&mark_pure(&impure).unwrap()));
+        assert!(!is_pure(&impure));
+        assert!(is_pure(&WrappedMethod::new(Value::new(1), pure)));
+        assert!(!is_pure(&Value::new(1)));
+        assert!(mark_pure(&Value::new(1)).is_err());
+    }
+
     #[test]
     fn fmt_signature_with_default_value() {
         assert_eq!(
@@ -688,4 +1258,252 @@ mod test {
             )
         );
     }
+
+    #[test]
+    fn fmt_signature_keyword_only() {
+        assert_eq!(
+            "<native function f>(a, /, *, b, ?c, d = 1)",
+            repr(
+                &FunctionType::Native("f".to_owned()),
+                &FunctionSignature::new(
+                    vec![
+                        FunctionParameter::Normal("a".to_owned()),
+                        FunctionParameter::KWOnlyNormal("b".to_owned()),
+                        FunctionParameter::KWOnlyOptional("c".to_owned()),
+                        FunctionParameter::KWOnlyWithDefaultValue("d".to_owned(), Value::new(1)),
+                    ],
+                    1,
+                )
+            )
+        );
+        assert_eq!(
+            "<native function f>(*args, b)",
+            repr(
+                &FunctionType::Native("f".to_owned()),
+                &FunctionSignature::new(
+                    vec![
+                        FunctionParameter::ArgsArray("args".to_owned()),
+                        FunctionParameter::KWOnlyNormal("b".to_owned()),
+                    ],
+                    0,
+                )
+            )
+        );
+    }
+
+    #[test]
+    fn native_closure() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let prefix = "item-".to_owned();
+        let f = NativeClosure::new(
+            "label".to_owned(),
+            {
+                let calls = calls.clone();
+                move |_, _, mut parser| {
+                    let x = parser.next_normal("x", false)?;
+                    parser.check_no_more_args()?;
+                    calls.fetch_add(1, Ordering::SeqCst);
+                    Ok(Value::new(format!("{}{}", prefix, x.to_str())))
+                }
+            },
+            FunctionSignature::new(vec![FunctionParameter::Normal("x".to_owned())], 0),
+        );
+        assert_eq!("<native function label>(x)", f.to_repr());
+        let mut call = |positional, named| {
+            f.call(
+                &mut CallStack::default(),
+                &TypeValues::default(),
+                positional,
+                named,
+                None,
+                None,
+            )
+        };
+        assert_eq!(
+            "item-1",
+            call(vec![Value::new(1)], LinkedHashMap::new())
+                .unwrap()
+                .to_str()
+        );
+        let mut named = LinkedHashMap::new();
+        named.insert("x".to_owned(), Value::new("a".to_owned()));
+        assert_eq!("item-a", call(vec![], named).unwrap().to_str());
+        assert!(call(vec![], LinkedHashMap::new()).is_err());
+        assert_eq!(2, calls.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn bind_arguments_like_native_functions() {
+        let signature = FunctionSignature::new(
+            vec![
+                FunctionParameter::Normal("a".to_owned()),
+                FunctionParameter::Optional("b".to_owned()),
+                FunctionParameter::ArgsArray("args".to_owned()),
+                FunctionParameter::KWArgsDict("kwargs".to_owned()),
+            ],
+            1,
+        );
+        let bind = |positional: Vec<i64>, named: Vec<(&str, i64)>| {
+            bind_arguments(
+                "f",
+                &signature,
+                positional.into_iter().map(Value::new).collect(),
+                named
+                    .into_iter()
+                    .map(|(k, v)| (k.to_owned(), Value::new(v)))
+                    .collect(),
+                None,
+                None,
+            )
+            .map(|args| {
+                args.into_iter()
+                    .map(|a| Value::from(a).to_repr())
+                    .collect::<Vec<_>>()
+            })
+        };
+        assert_eq!(
+            vec!["1", "2", "[3]", "{\"c\": 4}"],
+            bind(vec![1, 2, 3], vec![("c", 4)]).unwrap()
+        );
+        assert_eq!(
+            vec!["1", "None", "[]", "{}"],
+            bind(vec![1], vec![]).unwrap()
+        );
+        assert_eq!(
+            vec!["1", "2", "[]", "{\"a\": 3}"],
+            bind(vec![1], vec![("b", 2), ("a", 3)]).unwrap()
+        );
+        assert!(bind(vec![], vec![("a", 1)]).is_err());
+        assert!(bind(vec![1, 2], vec![("b", 3)]).is_err());
+    }
+
+    #[test]
+    fn typed_parameters() {
+        let signature = FunctionSignature::new(
+            vec![
+                FunctionParameter::Typed("x".to_owned(), "int".to_owned()),
+                FunctionParameter::Typed("s".to_owned(), "string".to_owned()),
+            ],
+            0,
+        );
+        assert_eq!(
+            "<native function f>(x: int, s: string)",
+            repr(&FunctionType::Native("f".to_owned()), &signature)
+        );
+
+        let bind = |positional: Vec<Value>, named: Vec<(&str, Value)>| {
+            bind_arguments(
+                "f",
+                &signature,
+                positional,
+                named.into_iter().map(|(k, v)| (k.to_owned(), v)).collect(),
+                None,
+                None,
+            )
+        };
+        let args = bind(vec![Value::new(1)], vec![("s", Value::from("a"))]).unwrap();
+        assert_eq!(
+            vec!["1", "\"a\""],
+            args.into_iter()
+                .map(|a| Value::from(a).to_repr())
+                .collect::<Vec<_>>()
+        );
+
+        match bind(vec![Value::new(1), Value::new(2)], vec![]) {
+            Err(ValueError::Runtime(e)) => {
+                assert_eq!(INCORRECT_PARAMETER_TYPE_ERROR_CODE, e.code);
+                assert_eq!(
+                    "Parameter s of call to <native function f>(x: int, s: string) expects a \
+                     value of type string, got int",
+                    e.message
+                );
+            }
+            r => panic!("unexpected result: {:?}", r),
+        }
+        // Missing typed parameters are reported as any required parameter
+        match bind(vec![], vec![]) {
+            Err(ValueError::Runtime(e)) => assert_eq!(NOT_ENOUGH_PARAMS_ERROR_CODE, e.code),
+            r => panic!("unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn typed_optional_parameters() {
+        let signature = FunctionSignature::new(
+            vec![
+                FunctionParameter::TypedOptional("x".to_owned(), "int".to_owned()),
+                FunctionParameter::TypedWithDefaultValue(
+                    "s".to_owned(),
+                    "string".to_owned(),
+                    Value::new(NoneType::None),
+                ),
+            ],
+            0,
+        );
+        assert_eq!(
+            "<native function f>(?x: int, s: string = None)",
+            repr(&FunctionType::Native("f".to_owned()), &signature)
+        );
+
+        let bind = |positional: Vec<Value>| {
+            bind_arguments(
+                "f",
+                &signature,
+                positional,
+                LinkedHashMap::new(),
+                None,
+                None,
+            )
+        };
+        // The default value is not checked
+        let args = bind(vec![]).unwrap();
+        assert_eq!(Some(None), args[0].as_optional());
+        assert_eq!(Some(&Value::new(NoneType::None)), args[1].as_normal());
+        let args = bind(vec![Value::new(1), Value::from("a")]).unwrap();
+        assert_eq!(Some(Some(&Value::new(1))), args[0].as_optional());
+        assert_eq!(Some(&Value::from("a")), args[1].as_normal());
+
+        for positional in vec![vec![Value::from("a")], vec![Value::new(1), Value::new(2)]] {
+            match bind(positional) {
+                Err(ValueError::Runtime(e)) => {
+                    assert_eq!(INCORRECT_PARAMETER_TYPE_ERROR_CODE, e.code)
+                }
+                r => panic!("unexpected result: {:?}", r),
+            }
+        }
+    }
+
+    #[test]
+    fn function_arg_accessors() {
+        let normal = FunctionArg::Normal(Value::new(1));
+        assert_eq!(Some(&Value::new(1)), normal.as_normal());
+        assert_eq!(None, normal.as_optional());
+        assert_eq!(None, normal.as_args_array());
+        assert!(normal.as_kw_args_dict().is_none());
+        // The argument can still be converted after being inspected
+        assert_eq!(1, normal.into_normal::<i64>("x").unwrap());
+
+        assert_eq!(Some(None), FunctionArg::Optional(None).as_optional());
+        let optional = FunctionArg::Optional(Some(Value::from("a")));
+        assert_eq!(Some(Some(&Value::from("a"))), optional.as_optional());
+        assert_eq!(None, optional.as_normal());
+
+        let args = FunctionArg::ArgsArray(vec![Value::new(1), Value::new(2)]);
+        assert_eq!(
+            Some(&[Value::new(1), Value::new(2)][..]),
+            args.as_args_array()
+        );
+
+        let mut dict = LinkedHashMap::new();
+        dict.insert("a".to_owned(), Value::new(1));
+        let kwargs = FunctionArg::KWArgsDict(dict);
+        assert_eq!(
+            Some(&Value::new(1)),
+            kwargs.as_kw_args_dict().unwrap().get("a")
+        );
+        assert_eq!(None, kwargs.as_normal());
+    }
 }
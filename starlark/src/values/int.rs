@@ -14,7 +14,7 @@
 
 //! Define the int type for Starlark.
 
-use crate::values::error::ValueError;
+use crate::values::error::{RuntimeError, ValueError, NEGATIVE_EXPONENT_ERROR_CODE};
 use crate::values::*;
 use std::cmp::Ordering;
 use std::fmt;
@@ -160,6 +160,27 @@ impl TypedValue for i64 {
             }
         })
     }
+    fn pow(&self, other: Value) -> ValueResult {
+        i64_arith_bin_op(*self, other, "**", |a, b| {
+            if b < 0 {
+                // There is no float type to hold the result.
+                return Err(RuntimeError {
+                    code: NEGATIVE_EXPONENT_ERROR_CODE,
+                    message: format!("Negative exponent in {} ** {}", a, b),
+                    label: "Negative exponent".to_owned(),
+                }
+                .into());
+            }
+            match (a, b) {
+                (_, 0) | (1, _) => Ok(1),
+                (0, _) => Ok(0),
+                (-1, b) => Ok(if b % 2 == 0 { 1 } else { -1 }),
+                // `|a| >= 2` so `a ** 64` does not fit in an i64.
+                (_, b) if b >= 64 => Err(ValueError::IntegerOverflow),
+                (a, b) => a.checked_pow(b as u32).ok_or(ValueError::IntegerOverflow),
+            }
+        })
+    }
 
     fn values_for_descendant_check_and_freeze<'a>(
         &'a self,
@@ -186,5 +207,7 @@ mod test {
                                           // Remainder of the floored division: 5.percent(3) = 5 % 3 = 2
         assert_eq!(2, int_op!(5.percent(3)));
         assert_eq!(3, int_op!(7.div(2))); // 7.div(2) = 7 / 2 = 3
+        assert_eq!(8, int_op!(2.pow(3))); // 2.pow(3) = 2 ** 3 = 8
+        assert_eq!(1, int_op!(3.pow(0))); // 3.pow(0) = 3 ** 0 = 1
     }
 }
@@ -0,0 +1,175 @@
+// Copyright 2019 The Starlark in Rust Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! `lazy_seq()` builtin implementation: a sequence whose elements are computed on demand.
+
+use crate::environment::TypeValues;
+use crate::eval::call_stack::CallStack;
+use crate::values::error::{RuntimeError, ValueError, RECURSIVE_LAZY_SEQ_ERROR_CODE};
+use crate::values::iter::TypedIterable;
+use crate::values::{Immutable, TypedValue, Value, ValueResult};
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fmt::Write as _;
+
+/// Function computing the element at an index of a [`LazySeq`](struct.LazySeq.html), given
+/// the call stack of the caller.
+pub type LazySeqFn = Box<dyn Fn(&mut CallStack, i64) -> ValueResult>;
+
+/// A sequence of a fixed length whose element `i` is computed by a function the first time
+/// it is accessed, and cached afterwards.
+///
+/// Indexing only computes the requested element, and so does a `for` loop or a comprehension
+/// for each element it reaches. Other iterations, e.g. by `list()`, eagerly compute all the
+/// elements which are not cached yet before returning the first one. Indexing expressions,
+/// loops and comprehensions compute the elements with the call stack of the caller; other
+/// accesses use the call stack given at creation. Computed elements are frozen before being cached,
+/// since every later access shares them.
+pub struct LazySeq {
+    length: i64,
+    compute: LazySeqFn,
+    /// Call stack for the accesses without a caller.
+    call_stack: CallStack,
+    cache: RefCell<HashMap<usize, Value>>,
+    /// Indices whose element is being computed, to detect a computation needing itself.
+    computing: RefCell<HashSet<usize>>,
+}
+
+impl LazySeq {
+    /// Create a sequence of `length` elements computed by `compute`, with `call_stack` when
+    /// they are not accessed by an indexing expression.
+    pub fn new(length: i64, compute: LazySeqFn, call_stack: CallStack) -> LazySeq {
+        LazySeq {
+            length,
+            compute,
+            call_stack,
+            cache: RefCell::new(HashMap::new()),
+            computing: RefCell::new(HashSet::new()),
+        }
+    }
+
+    /// Return the element at index `i`, computing it with `call_stack` if it is not cached
+    /// yet.
+    pub fn get(&self, i: usize, call_stack: &mut CallStack) -> ValueResult {
+        if let Some(v) = self.cache.borrow().get(&i) {
+            return Ok(v.clone());
+        }
+        if !self.computing.borrow_mut().insert(i) {
+            return Err(RuntimeError {
+                code: RECURSIVE_LAZY_SEQ_ERROR_CODE,
+                message: format!("Element {} of the lazy_seq is needed to compute itself", i),
+                label: "Recursive lazy_seq element".to_owned(),
+            }
+            .into());
+        }
+        // The cache is not borrowed during the call so that `compute` may access this sequence.
+        let result = (self.compute)(call_stack, i as i64);
+        self.computing.borrow_mut().remove(&i);
+        let mut v = result?;
+        v.freeze();
+        Ok(self.cache.borrow_mut().entry(i).or_insert(v).clone())
+    }
+
+    /// [`get`](#method.get) with the call stack given at creation.
+    fn get_without_caller(&self, i: usize) -> ValueResult {
+        self.get(i, &mut self.call_stack.clone())
+    }
+}
+
+impl TypedValue for LazySeq {
+    type Holder = Immutable<LazySeq>;
+    const TYPE: &'static str = "lazy_seq";
+
+    fn values_for_descendant_check_and_freeze<'a>(
+        &'a self,
+    ) -> Box<dyn Iterator<Item = Value> + 'a> {
+        let cached: Vec<Value> = self.cache.borrow().values().cloned().collect();
+        Box::new(cached.into_iter())
+    }
+
+    fn to_repr_impl(&self, buf: &mut String) -> fmt::Result {
+        write!(buf, "lazy_seq({})", self.length)
+    }
+
+    fn to_bool(&self) -> bool {
+        self.length != 0
+    }
+
+    fn length(&self) -> Result<i64, ValueError> {
+        Ok(self.length)
+    }
+
+    fn compute_at(
+        &self,
+        index: &Value,
+        call_stack: &mut CallStack,
+        _type_values: &TypeValues,
+    ) -> Result<Option<Value>, ValueError> {
+        let i = index.convert_index(self.length)?;
+        self.get(i as usize, call_stack)?;
+        Ok(None)
+    }
+
+    fn at(&self, index: Value) -> ValueResult {
+        let i = index.convert_index(self.length)?;
+        self.get_without_caller(i as usize)
+    }
+
+    fn iterates_by_index(&self) -> bool {
+        true
+    }
+
+    fn iter(&self) -> Result<&dyn TypedIterable, ValueError> {
+        // Iteration cannot fail midway, so compute the missing elements upfront.
+        for i in 0..self.length as usize {
+            self.get_without_caller(i)?;
+        }
+        Ok(self)
+    }
+}
+
+impl TypedIterable for LazySeq {
+    fn to_iter<'a>(&'a self) -> Box<dyn Iterator<Item = Value> + 'a> {
+        Box::new((0..self.length as usize).map(move |i| self.cache.borrow()[&i].clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::rc::Rc;
+
+    #[test]
+    fn test_elements_are_computed_once() {
+        let calls = Rc::new(RefCell::new(Vec::new()));
+        let calls_in_fn = calls.clone();
+        let seq = Value::new(LazySeq::new(
+            4,
+            Box::new(move |_, i| {
+                calls_in_fn.borrow_mut().push(i);
+                Ok(Value::new(i * i))
+            }),
+            CallStack::default(),
+        ));
+        assert_eq!(4, seq.length().unwrap());
+        assert!(calls.borrow().is_empty());
+        assert_eq!(9, seq.at(Value::new(-1)).unwrap().to_int().unwrap());
+        assert_eq!(9, seq.at(Value::new(3)).unwrap().to_int().unwrap());
+        assert_eq!(vec![3], *calls.borrow());
+        assert_eq!("[0, 1, 4, 9]", Value::from(seq.to_vec().unwrap()).to_str());
+        assert_eq!(vec![3, 0, 1, 2], *calls.borrow());
+        assert!(seq.at(Value::new(4)).is_err());
+    }
+}
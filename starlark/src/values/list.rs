@@ -205,11 +205,11 @@ impl TypedValue for List {
     ) -> ValueResult {
         let (start, stop, stride) =
             Value::convert_slice_indices(self.length()?, start, stop, stride)?;
-        Ok(Value::from(tuple::slice_vector(
+        Ok(Value::from(slice_items(
             start,
             stop,
             stride,
-            self.content.iter(),
+            self.content.iter().cloned(),
         )))
     }
 
@@ -83,7 +83,7 @@
 //!     }
 //! }
 //! ```
-use crate::environment::TypeValues;
+use crate::environment::{FreezeEvent, FreezeObserverFn, TypeValues};
 use crate::eval::call_stack;
 use crate::eval::call_stack::CallStack;
 use crate::values::error::ValueError;
@@ -93,6 +93,7 @@ use linked_hash_map::LinkedHashMap;
 use std::cmp::Ordering;
 use std::fmt;
 use std::fmt::Write as _;
+use std::hash::{Hash, Hasher};
 use std::marker;
 use std::rc::Rc;
 use std::usize;
@@ -115,12 +116,71 @@ pub struct Value(ValueInner);
 
 pub type ValueResult = Result<Value, ValueError>;
 
+/// Conversion of the result of a native function declared with
+/// [`starlark_module!`](crate::starlark_module) to a [`ValueResult`].
+///
+/// Besides a `ValueResult`, a native function can declare a result holding a pair or a
+/// triple of values, which is converted to a Starlark tuple, e.g. `return Ok((a, b))`.
+pub trait IntoValueResult {
+    fn into_value_result(self) -> ValueResult;
+}
+
+impl IntoValueResult for ValueResult {
+    fn into_value_result(self) -> ValueResult {
+        self
+    }
+}
+
+impl<A: Into<Value>, B: Into<Value>> IntoValueResult for Result<(A, B), ValueError> {
+    fn into_value_result(self) -> ValueResult {
+        self.map(|t| Value::new(tuple::Tuple::from(t)))
+    }
+}
+
+impl<A: Into<Value>, B: Into<Value>, C: Into<Value>> IntoValueResult
+    for Result<(A, B, C), ValueError>
+{
+    fn into_value_result(self) -> ValueResult {
+        self.map(|t| Value::new(tuple::Tuple::from(t)))
+    }
+}
+
 impl Value {
     /// Create a new `Value` from a static value.
     pub fn new<T: TypedValue>(t: T) -> Value {
         t.new_value()
     }
 
+    /// Create a list with the values of `iter`, without an intermediate collection.
+    ///
+    /// `size` preallocates the list when the number of values is known in advance but not
+    /// reported by the iterator's own size hint.
+    ///
+    /// ```
+    /// # use starlark::values::Value;
+    /// let list = Value::list_from_iter((1..4).map(Value::new), None);
+    /// assert_eq!("[1, 2, 3]", list.to_repr());
+    /// ```
+    pub fn list_from_iter<I: IntoIterator<Item = Value>>(iter: I, size: Option<usize>) -> Value {
+        Value::from(Value::collect_with_size(iter, size))
+    }
+
+    /// Create a tuple with the values of `iter`, like
+    /// [`list_from_iter`](Value::list_from_iter).
+    pub fn tuple_from_iter<I: IntoIterator<Item = Value>>(iter: I, size: Option<usize>) -> Value {
+        Value::new(tuple::Tuple::new(Value::collect_with_size(iter, size)))
+    }
+
+    fn collect_with_size<I: IntoIterator<Item = Value>>(
+        iter: I,
+        size: Option<usize>,
+    ) -> Vec<Value> {
+        let iter = iter.into_iter();
+        let mut values = Vec::with_capacity(size.unwrap_or(0).max(iter.size_hint().0));
+        values.extend(iter);
+        values
+    }
+
     fn try_value_holder(
         &self,
         for_iter: bool,
@@ -141,11 +201,29 @@ impl Value {
         &self,
     ) -> Result<ObjectRefMut<dyn TypedValueDyn>, ObjectBorrowMutError> {
         match &self.0 {
-            ValueInner::Other(rc) => rc.value.try_borrow_mut(),
+            ValueInner::Other(rc) => {
+                let r = rc.value.try_borrow_mut();
+                #[cfg(feature = "provenance")]
+                {
+                    if r.is_ok() {
+                        crate::eval::provenance::record_mutation(rc.data_ptr());
+                    }
+                }
+                r
+            }
             _ => Err(ObjectBorrowMutError::Immutable),
         }
     }
 
+    /// Whether the value can be mutated: it is of a mutable type and not frozen.
+    #[cfg(feature = "provenance")]
+    pub(crate) fn is_mutable(&self) -> bool {
+        match &self.0 {
+            ValueInner::Other(rc) => rc.value.is_mutable(),
+            _ => false,
+        }
+    }
+
     /// Clone for inserting into the other container, using weak reference if we do a
     /// recursive insertion.
     pub fn clone_for_container<T: TypedValue>(&self, container: &T) -> Result<Value, ValueError> {
@@ -258,6 +336,12 @@ impl PartialEq for DataPtr {
     }
 }
 
+impl Hash for DataPtr {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash(state)
+    }
+}
+
 /// Function identity to detect recursion.
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct FunctionId(pub DataPtr);
@@ -277,9 +361,9 @@ impl<T: TypedValue> TypedValueDyn for T {
     }
 
     /// Freezes the current value.
-    fn freeze_dyn(&self) {
+    fn freeze_dyn(&self, observer: Option<&FreezeObserverFn>) {
         for mut value in self.values_for_descendant_check_and_freeze() {
-            value.freeze();
+            value.freeze_observed(observer);
         }
     }
 
@@ -353,6 +437,15 @@ impl<T: TypedValue> TypedValueDyn for T {
         self.at(index)
     }
 
+    fn compute_at_dyn(
+        &self,
+        index: &Value,
+        call_stack: &mut CallStack,
+        type_values: &TypeValues,
+    ) -> Result<Option<Value>, ValueError> {
+        self.compute_at(index, call_stack, type_values)
+    }
+
     fn set_at_dyn(&mut self, index: Value, new_value: Value) -> Result<(), ValueError> {
         self.set_at(index, new_value)
     }
@@ -370,6 +463,10 @@ impl<T: TypedValue> TypedValueDyn for T {
         self.iter()
     }
 
+    fn iterates_by_index_dyn(&self) -> bool {
+        self.iterates_by_index()
+    }
+
     fn length_dyn(&self) -> Result<i64, ValueError> {
         self.length()
     }
@@ -432,9 +529,17 @@ impl<T: TypedValue> TypedValueDyn for T {
         self.floor_div(other)
     }
 
+    fn pow_dyn(&self, other: Value) -> Result<Value, ValueError> {
+        self.pow(other)
+    }
+
     fn pipe_dyn(&self, other: Value) -> Result<Value, ValueError> {
         self.pipe(other)
     }
+
+    fn serialize_dyn(&self) -> Option<(&'static str, Vec<u8>)> {
+        self.serialize()
+    }
 }
 
 struct ValueHolder<T: TypedValueDyn + ?Sized> {
@@ -457,7 +562,7 @@ pub(crate) trait TypedValueDyn: 'static {
     /// Id used to detect recursion (which is prohibited in Starlark)
     fn function_id_dyn(&self) -> FunctionId;
 
-    fn freeze_dyn(&self);
+    fn freeze_dyn(&self, observer: Option<&FreezeObserverFn>);
 
     fn to_str_impl_dyn(&self, buf: &mut String) -> fmt::Result;
 
@@ -488,6 +593,13 @@ pub(crate) trait TypedValueDyn: 'static {
 
     fn at_dyn(&self, index: Value) -> ValueResult;
 
+    fn compute_at_dyn(
+        &self,
+        index: &Value,
+        call_stack: &mut CallStack,
+        type_values: &TypeValues,
+    ) -> Result<Option<Value>, ValueError>;
+
     fn set_at_dyn(&mut self, index: Value, new_value: Value) -> Result<(), ValueError>;
     fn slice_dyn(
         &self,
@@ -498,6 +610,8 @@ pub(crate) trait TypedValueDyn: 'static {
 
     fn iter_dyn(&self) -> Result<&dyn TypedIterable, ValueError>;
 
+    fn iterates_by_index_dyn(&self) -> bool;
+
     fn length_dyn(&self) -> Result<i64, ValueError>;
 
     fn get_attr_dyn(&self, attribute: &str) -> ValueResult;
@@ -526,7 +640,11 @@ pub(crate) trait TypedValueDyn: 'static {
 
     fn floor_div_dyn(&self, other: Value) -> ValueResult;
 
+    fn pow_dyn(&self, other: Value) -> ValueResult;
+
     fn pipe_dyn(&self, other: Value) -> ValueResult;
+
+    fn serialize_dyn(&self) -> Option<(&'static str, Vec<u8>)>;
 }
 
 /// A trait for a value with a type that all variable container
@@ -543,6 +661,7 @@ pub trait TypedValue: Sized + 'static {
     /// This function should be overridden only by builtin types.
     #[doc(hidden)]
     fn new_value(self) -> Value {
+        crate::eval::metrics::record_allocation();
         Value(ValueInner::Other(Rc::new(ValueHolder {
             value: if Self::Holder::MUTABLE {
                 ObjectCell::new_mutable(self)
@@ -607,10 +726,15 @@ pub trait TypedValue: Sized + 'static {
     }
 
     /// Convert self to a Boolean truth value, as returned by the bool() function.
+    ///
+    /// By default a value is false if it has a [`length`](TypedValue::length) of 0, so that
+    /// a collection type only has to implement `length` to be falsy when empty, and true
+    /// otherwise.
     fn to_bool(&self) -> bool {
-        // Return `true` by default, because this is default when implementing
-        // custom types in Python: https://docs.python.org/release/2.5.2/lib/truth.html
-        true
+        // Return `true` for values without a length, because this is default when
+        // implementing custom types in Python:
+        // https://docs.python.org/release/2.5.2/lib/truth.html
+        self.length() != Ok(0)
     }
 
     /// Convert self to a integer value, as returned by the int() function if the type is numeric
@@ -703,6 +827,21 @@ pub trait TypedValue: Sized + 'static {
         })
     }
 
+    /// Compute the element at `index` before `a[index]` is evaluated by a program, if it is
+    /// produced by Starlark functions, which are called with the `call_stack` of the caller.
+    ///
+    /// Types caching their computed elements store them and return `None`, while mutable
+    /// types can return the element to store with [`set_at`](#method.set_at). The element is
+    /// then returned by [`at`](#method.at). The default does nothing.
+    fn compute_at(
+        &self,
+        _index: &Value,
+        _call_stack: &mut CallStack,
+        _type_values: &TypeValues,
+    ) -> Result<Option<Value>, ValueError> {
+        Ok(None)
+    }
+
     /// Set the value at `index` with `new_value`.
     ///
     /// This method should error with `ValueError::CannotMutateImmutableValue` if the value was
@@ -779,7 +918,22 @@ pub trait TypedValue: Sized + 'static {
         })
     }
 
-    /// Returns the length of the value, if this value is a sequence.
+    /// Whether `for` loops and comprehensions iterate over this value by evaluating
+    /// `a[index]` for each index from 0 to its [`length`](#method.length), only when the
+    /// iteration reaches it, instead of with [`iter`](#method.iter).
+    ///
+    /// Types computing their elements with [`compute_at`](#method.compute_at) use it so that
+    /// an iteration stopped early does not compute the remaining elements, and that an
+    /// element failing to be computed stops the iteration with its error. The default is
+    /// `false`.
+    fn iterates_by_index(&self) -> bool {
+        false
+    }
+
+    /// Returns the length of the value, if this value is a sequence, as returned by the
+    /// `len()` function.
+    ///
+    /// The default implementation returns an error, i.e. the value has no length.
     fn length(&self) -> Result<i64, ValueError> {
         Err(ValueError::OperationNotSupported {
             op: "len()".to_owned(),
@@ -1016,6 +1170,25 @@ pub trait TypedValue: Sized + 'static {
         })
     }
 
+    /// Raise the current value to the power `other`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # #[macro_use] extern crate starlark;
+    /// # use starlark::values::*;
+    /// # fn main() {
+    /// assert_eq!(8, int_op!(2.pow(3)));  // 2.pow(3) = 2 ** 3 = 8
+    /// # }
+    /// ```
+    fn pow(&self, other: Value) -> ValueResult {
+        Err(ValueError::OperationNotSupported {
+            op: "**".to_owned(),
+            left: Self::TYPE.to_owned(),
+            right: Some(other.get_type().to_owned()),
+        })
+    }
+
     /// Apply the operator pipe to the current value and `other`.
     ///
     /// This is usually the union on set.
@@ -1026,6 +1199,17 @@ pub trait TypedValue: Sized + 'static {
             right: Some(other.get_type().to_owned()),
         })
     }
+
+    /// Serialize the current value for [`canonical`] serialization, as a tag and a payload.
+    ///
+    /// The tag identifies the type and must be stable across runs. To deserialize the value,
+    /// a factory restoring it from the payload must be registered for the tag with
+    /// [`Deserializers::register`](canonical::Deserializers::register).
+    ///
+    /// The default implementation returns `None`, i.e. the value is not serializable.
+    fn serialize(&self) -> Option<(&'static str, Vec<u8>)> {
+        None
+    }
 }
 
 impl fmt::Debug for Value {
@@ -1036,11 +1220,23 @@ impl fmt::Debug for Value {
 
 impl Value {
     pub fn freeze(&mut self) {
-        match &self.0 {
+        self.freeze_observed(None)
+    }
+
+    /// Freeze the value, calling `observer` for this value and each of its descendants which
+    /// was mutable before.
+    pub(crate) fn freeze_observed(&mut self, observer: Option<&FreezeObserverFn>) {
+        let was_mutable = match &self.0 {
             ValueInner::Other(rc) => rc.value.freeze(),
-            _ => {}
+            _ => false,
+        };
+        if let (true, Some(observer)) = (was_mutable, observer) {
+            observer(&FreezeEvent {
+                value_type: self.get_type(),
+                length: self.length().ok(),
+            });
         }
-        self.value_holder().freeze_dyn();
+        self.value_holder().freeze_dyn(observer);
     }
     pub fn to_str_impl(&self, buf: &mut String) -> fmt::Result {
         self.value_holder().to_str_impl_dyn(buf)
@@ -1058,6 +1254,35 @@ impl Value {
         self.to_repr_impl(&mut buf).unwrap();
         buf
     }
+    /// The representation of this value, cut after `max_len` characters with a note of how
+    /// many were omitted.
+    ///
+    /// ```
+    /// # use starlark::values::Value;
+    /// let list = Value::from((1..10000).collect::<Vec<i64>>());
+    /// assert_eq!("[1, 2, 3, 4... (+58876 more chars)", list.to_repr_truncated(11));
+    /// assert_eq!("[1, 2]", Value::from(vec![1, 2]).to_repr_truncated(11));
+    /// ```
+    pub fn to_repr_truncated(&self, max_len: usize) -> String {
+        truncate_chars(self.to_repr(), max_len)
+    }
+
+    /// The representation of this value for an error message, truncated to the length set
+    /// with [`Environment::set_max_error_repr_length`](
+    /// crate::environment::Environment::set_max_error_repr_length).
+    pub fn to_error_repr(&self) -> String {
+        self.to_repr_truncated(error::max_error_repr_length())
+    }
+
+    /// Same as [`to_error_repr`](Value::to_error_repr), but strings are not quoted.
+    pub fn to_error_str(&self) -> String {
+        if self.get_type() == "string" {
+            truncate_chars(self.to_str(), error::max_error_repr_length())
+        } else {
+            self.to_error_repr()
+        }
+    }
+
     pub fn get_type(&self) -> &'static str {
         self.value_holder().get_type_dyn()
     }
@@ -1071,9 +1296,18 @@ impl Value {
         self.value_holder().get_hash_dyn()
     }
     pub fn equals(&self, other: &Value) -> Result<bool, ValueError> {
+        if let Some((left, right)) = tuple::operands(self, other) {
+            return left.equals(&right);
+        }
         self.value_holder().equals_dyn(other)
     }
     pub fn compare(&self, other: &Value) -> Result<Ordering, ValueError> {
+        if let Some((left, right)) = BigInt::operands(self, other) {
+            return Ok(left.cmp(&right));
+        }
+        if let Some((left, right)) = tuple::operands(self, other) {
+            return left.compare(&right);
+        }
         self.value_holder().compare_dyn(other)
     }
 
@@ -1094,6 +1328,23 @@ impl Value {
         self.value_holder().at_dyn(index)
     }
 
+    /// `self[index]` evaluated by a program with `call_stack`, see
+    /// [`TypedValue::compute_at`].
+    pub fn at_with_call_stack(
+        &self,
+        index: Value,
+        call_stack: &mut CallStack,
+        type_values: &TypeValues,
+    ) -> ValueResult {
+        let computed = self
+            .value_holder()
+            .compute_at_dyn(&index, call_stack, type_values)?;
+        if let Some(v) = computed {
+            self.clone().set_at(index.clone(), v)?;
+        }
+        self.at(index)
+    }
+
     pub fn set_at(&mut self, index: Value, new_value: Value) -> Result<(), ValueError> {
         match self.try_value_holder_mut() {
             Err(ObjectBorrowMutError::Immutable) => {
@@ -1115,6 +1366,10 @@ impl Value {
     ) -> ValueResult {
         self.value_holder().slice_dyn(start, stop, stride)
     }
+    /// See [`TypedValue::iterates_by_index`].
+    pub fn iterates_by_index(&self) -> bool {
+        self.value_holder().iterates_by_index_dyn()
+    }
     pub fn iter(&self) -> Result<RefIterable, ValueError> {
         let borrowed: ObjectRef<dyn TypedValueDyn> = self.try_value_holder(true).unwrap();
         let mut err = Ok(());
@@ -1163,29 +1418,63 @@ impl Value {
         self.value_holder().plus_dyn()
     }
     pub fn minus(&self) -> ValueResult {
+        if let ValueInner::Int(i) = self.0 {
+            if i == i64::MIN {
+                return Ok(BigInt::from(i).neg().into_value());
+            }
+        }
+        if let Some(big) = self.downcast_ref::<BigInt>() {
+            return Ok(big.neg().into_value());
+        }
         self.value_holder().minus_dyn()
     }
     pub fn add(&self, other: Value) -> ValueResult {
-        self.value_holder().add_dyn(other)
+        if let Some(concatenated) = bytes::concat_mixed(self, &other) {
+            return Ok(concatenated);
+        }
+        if let Some((left, right)) = tuple::operands(self, &other) {
+            return Ok(Value::new(left.add(&right)?));
+        }
+        int_bin_op(self, other, BigInt::add, |l, r| l.value_holder().add_dyn(r))
     }
     pub fn sub(&self, other: Value) -> ValueResult {
-        self.value_holder().sub_dyn(other)
+        int_bin_op(self, other, BigInt::sub, |l, r| l.value_holder().sub_dyn(r))
     }
     pub fn mul(&self, other: Value) -> ValueResult {
-        self.value_holder().mul_dyn(other)
+        int_bin_op(self, other, BigInt::mul, |l, r| l.value_holder().mul_dyn(r))
     }
     pub fn percent(&self, other: Value) -> ValueResult {
-        self.value_holder().percent_dyn(other)
+        int_bin_op(self, other, BigInt::percent, |l, r| {
+            l.value_holder().percent_dyn(r)
+        })
     }
     pub fn div(&self, other: Value) -> ValueResult {
-        self.value_holder().div_dyn(other)
+        int_bin_op(self, other, BigInt::floor_div, |l, r| {
+            l.value_holder().div_dyn(r)
+        })
     }
     pub fn floor_div(&self, other: Value) -> ValueResult {
-        self.value_holder().floor_div_dyn(other)
+        int_bin_op(self, other, BigInt::floor_div, |l, r| {
+            l.value_holder().floor_div_dyn(r)
+        })
+    }
+    pub fn pow(&self, other: Value) -> ValueResult {
+        int_bin_op(self, other, BigInt::pow, |l, r| l.value_holder().pow_dyn(r))
     }
     pub fn pipe(&self, other: Value) -> ValueResult {
         self.value_holder().pipe_dyn(other)
     }
+    pub fn serialize(&self) -> Option<(&'static str, Vec<u8>)> {
+        self.value_holder().serialize_dyn()
+    }
+}
+
+/// Cut `s` after `max_len` characters, noting how many were omitted.
+pub(crate) fn truncate_chars(s: String, max_len: usize) -> String {
+    match s.char_indices().nth(max_len) {
+        None => s,
+        Some((i, _)) => format!("{}... (+{} more chars)", &s[..i], s[i..].chars().count()),
+    }
 }
 
 impl fmt::Display for Value {
@@ -1342,6 +1631,34 @@ impl Value {
     }
 }
 
+/// Select the items of `content` in the slice `[start:stop:stride]`, where `start`, `stop`
+/// and `stride` are the indices returned by [`Value::convert_slice_indices`].
+///
+/// This is shared by all the sliceable types so that they handle slice bounds the same way.
+pub(crate) fn slice_items<T, I: Iterator<Item = T>>(
+    start: i64,
+    stop: i64,
+    stride: i64,
+    content: I,
+) -> Vec<T> {
+    let (low, take) = if stride < 0 {
+        (stop + 1, start - stop)
+    } else {
+        (start, stop - start)
+    };
+    if take <= 0 {
+        return Vec::new();
+    }
+    let mut items: Vec<T> = content.skip(low as usize).take(take as usize).collect();
+    if stride < 0 {
+        items.reverse();
+    }
+    items
+        .into_iter()
+        .step_by(stride.unsigned_abs() as usize)
+        .collect()
+}
+
 impl Value {
     /// Get a reference to underlying data or `None`
     /// if contained object has different type than requested.
@@ -1386,7 +1703,10 @@ impl Value {
 }
 
 // Submodules
+pub mod bigint;
 pub mod boolean;
+pub mod bytes;
+pub mod canonical;
 mod cell;
 pub mod dict;
 pub mod error;
@@ -1394,12 +1714,14 @@ pub mod function;
 pub mod hashed_value;
 pub mod int;
 pub mod iter;
+pub mod lazy_seq;
 pub mod list;
 pub mod none;
 pub mod range;
 pub mod string;
 pub mod tuple;
 
+use crate::values::bigint::{int_bin_op, BigInt};
 use crate::values::cell::error::ObjectBorrowError;
 use crate::values::cell::error::ObjectBorrowMutError;
 use crate::values::cell::ObjectCell;
@@ -1413,6 +1735,76 @@ mod tests {
     use super::*;
     use std::iter;
 
+    #[test]
+    fn test_to_repr_truncated() {
+        let long = Value::from((1..=10000).collect::<Vec<i64>>());
+        assert_eq!("[1, 2, 3... (+58886 more chars)", long.to_repr_truncated(8));
+        assert_eq!(
+            "(1, 2... (+7 more chars)",
+            Value::from((1, 2, 3, 4)).to_repr_truncated(5)
+        );
+        let s = Value::from("x".repeat(50));
+        assert_eq!(
+            format!("\"{}... (+42 more chars)", "x".repeat(9)),
+            s.to_repr_truncated(10)
+        );
+        // Short values are untouched
+        for v in &[
+            Value::from(vec![1, 2, 3]),
+            Value::from((1,)),
+            Value::from(()),
+            Value::from("abc"),
+            Value::new(12345),
+        ] {
+            assert_eq!(v.to_repr(), v.to_repr_truncated(10));
+        }
+    }
+
+    #[test]
+    fn test_error_repr_length() {
+        let (mut env, type_values) = crate::stdlib::global_environment_with_extensions();
+        let code = "struct(a=list(range(1000))).b";
+        let message = |env: &mut crate::environment::Environment| {
+            crate::eval::testutil::starlark_no_diagnostic(env, code, &type_values)
+                .unwrap_err()
+                .message
+        };
+        assert!(message(&mut env).len() < error::DEFAULT_MAX_ERROR_REPR_LENGTH + 100);
+        assert!(message(&mut env).contains("more chars)"));
+        env.set_max_error_repr_length(1000000);
+        assert!(message(&mut env).contains("998, 999])"));
+        env.set_max_error_repr_length(error::DEFAULT_MAX_ERROR_REPR_LENGTH);
+        let err =
+            crate::eval::testutil::starlark_no_diagnostic(&mut env, "struct(a=1).b", &type_values)
+                .unwrap_err();
+        assert_eq!("Cannot b on type struct(a=1)", err.message);
+    }
+
+    #[test]
+    fn test_from_iter() {
+        let list = Value::list_from_iter((0..5).map(Value::new), None);
+        assert_eq!("list", list.get_type());
+        assert_eq!(5, list.length().unwrap());
+        let read: Vec<i64> = list
+            .iter()
+            .unwrap()
+            .iter()
+            .map(|v| v.to_int().unwrap())
+            .collect();
+        assert_eq!(vec![0, 1, 2, 3, 4], read);
+        // The size is only a hint
+        let filtered = (0..10).filter(|x| x % 3 == 0).map(Value::new);
+        assert_eq!(
+            "[0, 3, 6, 9]",
+            Value::list_from_iter(filtered, Some(10)).to_repr()
+        );
+
+        let tuple = Value::tuple_from_iter(vec![Value::new(1), Value::from("a")], Some(2));
+        assert_eq!("tuple", tuple.get_type());
+        assert_eq!("(1, \"a\")", tuple.to_repr());
+        assert_eq!("()", Value::tuple_from_iter(iter::empty(), None).to_repr());
+    }
+
     #[test]
     fn test_convert_index() {
         assert_eq!(Ok(6), Value::new(6).convert_index(7));
@@ -1444,6 +1836,99 @@ mod tests {
         );
     }
 
+    /// Indices selected by `[start:stop:stride]` in a sequence of length `len`, computed
+    /// naively following Python semantics.
+    fn expected_slice(len: i64, start: Option<i64>, stop: Option<i64>, stride: i64) -> Vec<i64> {
+        let (min, max) = if stride < 0 { (-1, len - 1) } else { (0, len) };
+        let bound = |i: Option<i64>, default: i64| match i {
+            None => default,
+            Some(i) if i < 0 => (i + len).max(min),
+            Some(i) => i.min(max),
+        };
+        let (mut i, stop) = if stride < 0 {
+            (bound(start, len - 1), bound(stop, -1))
+        } else {
+            (bound(start, 0), bound(stop, len))
+        };
+        let mut indices = Vec::new();
+        while (stride > 0 && i < stop) || (stride < 0 && i > stop) {
+            indices.push(i);
+            i += stride;
+        }
+        indices
+    }
+
+    #[test]
+    fn test_slice_bounds() {
+        use crate::values::range::Range;
+        use crate::values::tuple::Tuple;
+        use std::num::NonZeroI64;
+
+        fn ints(v: Value) -> Vec<i64> {
+            v.iter()
+                .unwrap()
+                .iter()
+                .map(|x| x.to_int().unwrap())
+                .collect()
+        }
+
+        let digits: Vec<Value> = (0..5).map(Value::new).collect();
+        let bounds = [
+            None,
+            Some(-7),
+            Some(-5),
+            Some(-3),
+            Some(-1),
+            Some(0),
+            Some(1),
+            Some(3),
+            Some(5),
+            Some(7),
+        ];
+        let strides = [
+            None,
+            Some(1),
+            Some(2),
+            Some(3),
+            Some(-1),
+            Some(-2),
+            Some(-3),
+        ];
+        for &start in &bounds {
+            for &stop in &bounds {
+                for &stride in &strides {
+                    let expected = expected_slice(5, start, stop, stride.unwrap_or(1));
+                    let slice = |v: Value| {
+                        v.slice(
+                            start.map(Value::new),
+                            stop.map(Value::new),
+                            stride.map(Value::new),
+                        )
+                        .unwrap()
+                    };
+                    let what = format!("[{:?}:{:?}:{:?}]", start, stop, stride);
+
+                    let s: String = expected
+                        .iter()
+                        .map(|&i| b"abcde"[i as usize] as char)
+                        .collect();
+                    assert_eq!(s, slice(Value::from("abcde")).to_str(), "{}", what);
+                    assert_eq!(
+                        expected,
+                        ints(slice(Value::from(digits.clone()))),
+                        "{}",
+                        what
+                    );
+                    let tuple = Value::new(Tuple::new(digits.clone()));
+                    assert_eq!(expected, ints(slice(tuple)), "{}", what);
+                    let range = Value::new(Range::new(0, 10, NonZeroI64::new(2).unwrap()));
+                    let doubled: Vec<i64> = expected.iter().map(|i| i * 2).collect();
+                    assert_eq!(doubled, ints(slice(range)), "{}", what);
+                }
+            }
+        }
+    }
+
     #[test]
     fn can_implement_compare() {
         #[derive(Debug, PartialEq, Eq, Ord, PartialOrd)]
@@ -1486,6 +1971,73 @@ mod tests {
         assert_eq!(two.compare(&one), Ok(Greater));
     }
 
+    #[test]
+    fn can_implement_length() {
+        struct Bag(Vec<Value>);
+
+        impl TypedValue for Bag {
+            const TYPE: &'static str = "bag";
+            type Holder = Immutable<Bag>;
+
+            fn values_for_descendant_check_and_freeze<'a>(
+                &'a self,
+            ) -> Box<dyn Iterator<Item = Value> + 'a> {
+                Box::new(self.0.iter().cloned())
+            }
+
+            fn length(&self) -> Result<i64, ValueError> {
+                Ok(self.0.len() as i64)
+            }
+        }
+
+        let empty = Value::new(Bag(Vec::new()));
+        let full = Value::new(Bag(vec![Value::new(1), Value::new(2)]));
+        assert_eq!(Ok(0), empty.length());
+        assert_eq!(Ok(2), full.length());
+        assert!(!empty.to_bool());
+        assert!(full.to_bool());
+
+        let (env, type_values) = crate::stdlib::global_environment();
+        let mut env = env.freeze().child("test");
+        env.set("empty", empty).unwrap();
+        env.set("full", full).unwrap();
+        env.set("no_length", Value::new(NoLength)).unwrap();
+        let mut eval = |code| {
+            crate::eval::noload::eval(
+                &std::sync::Arc::new(std::sync::Mutex::new(codemap::CodeMap::new())),
+                "test.bzl",
+                code,
+                crate::syntax::dialect::Dialect::Bzl,
+                &mut env,
+                &type_values,
+            )
+        };
+        assert!(eval(
+            "(len(empty) == 0 and len(full) == 2 and not empty and bool(full) \
+             and [x for x in [empty, full] if x] == [full])"
+        )
+        .unwrap()
+        .to_bool());
+
+        // Values without a length are always true, and len() fails on them
+        assert!(eval("bool(no_length)").unwrap().to_bool());
+        let err = eval("len(no_length)").unwrap_err();
+        assert_eq!("Cannot len() on type no_length", err.message);
+    }
+
+    struct NoLength;
+
+    impl TypedValue for NoLength {
+        const TYPE: &'static str = "no_length";
+        type Holder = Immutable<NoLength>;
+
+        fn values_for_descendant_check_and_freeze<'a>(
+            &'a self,
+        ) -> Box<dyn Iterator<Item = Value> + 'a> {
+            Box::new(iter::empty())
+        }
+    }
+
     #[test]
     fn compare_between_different_types() {
         assert!(Value::new(1).compare(&Value::new(false)).is_err());
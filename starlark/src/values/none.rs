@@ -21,6 +21,11 @@ use std::fmt;
 use std::iter;
 
 /// Define the NoneType type
+///
+/// `None` renders as `None` through both `str()` and `repr()`. It is equal only to itself,
+/// and it is ordered only against itself: `None` compares equal to `None`, while comparing
+/// it with a value of any other type (e.g. when sorting a list mixing `None` and ints) is an
+/// error rather than an arbitrary order.
 #[derive(Debug, Clone, Copy)]
 pub enum NoneType {
     None,
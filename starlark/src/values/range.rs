@@ -106,6 +106,9 @@ impl TypedValue for Range {
         Ok(Value::new(self.start + self.step.get() * index))
     }
 
+    /// Two ranges are equal if they produce the same sequence of ints, regardless of the
+    /// arguments they were created with: `range(0) == range(2, 1)`. A range is never equal
+    /// to a value of another type, even a list with the same elements.
     fn equals(&self, other: &Range) -> Result<bool, ValueError> {
         let self_length = self.length()?;
         let other_length = other.length()?;
@@ -224,7 +227,7 @@ impl TypedIterable for Range {
 #[cfg(test)]
 mod test {
     use crate::values::range::Range;
-    use crate::values::{TypedValue, ValueError};
+    use crate::values::{TypedValue, Value, ValueError};
     use std::i64;
     use std::num::NonZeroI64;
 
@@ -277,5 +280,17 @@ mod test {
     #[test]
     fn eq() {
         assert_eq!(range_stop(0), range(2, 1, 3));
+        assert_eq!(range(0, 4, 2), range(0, 3, 2));
+        assert_eq!(range(7, 8, 1), range(7, 9, 5));
+        assert_ne!(range_stop(3), range_stop(4));
+        assert_ne!(range(0, 6, 2), range(0, 6, 3));
+    }
+
+    #[test]
+    fn not_equal_to_list() {
+        let r = Value::new(range_stop(3));
+        let l = Value::from(vec![0, 1, 2]);
+        assert_eq!(Ok(false), r.equals(&l));
+        assert_eq!(Ok(true), Value::from(r.to_vec().unwrap()).equals(&l));
     }
 }
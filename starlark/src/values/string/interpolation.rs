@@ -15,6 +15,7 @@
 //! String interpolation-related code.
 
 use crate::syntax::errors::SyntaxError;
+use crate::values::bigint::BigInt;
 use crate::values::error::*;
 use crate::values::Value;
 use codemap::Span;
@@ -137,37 +138,49 @@ impl ArgFormat {
         match self {
             ArgFormat::Str => write!(out, "{}", arg.to_str()).unwrap(),
             ArgFormat::Repr => write!(out, "{}", arg.to_repr()).unwrap(),
-            ArgFormat::Dec => write!(out, "{}", arg.to_int()?).unwrap(),
-            ArgFormat::Oct => {
-                let v = arg.to_int()?;
-                write!(
-                    out,
-                    "{}{:o}",
-                    if v < 0 { "-" } else { "" },
-                    v.wrapping_abs() as u64
-                )
-                .unwrap();
-            }
-            ArgFormat::HexLower => {
-                let v = arg.to_int()?;
-                write!(
-                    out,
-                    "{}{:x}",
-                    if v < 0 { "-" } else { "" },
-                    v.wrapping_abs() as u64
-                )
-                .unwrap();
-            }
-            ArgFormat::HexUpper => {
-                let v = arg.to_int()?;
-                write!(
-                    out,
-                    "{}{:X}",
-                    if v < 0 { "-" } else { "" },
-                    v.wrapping_abs() as u64
-                )
-                .unwrap();
-            }
+            ArgFormat::Dec => match arg.downcast_ref::<BigInt>() {
+                Some(big) => write!(out, "{}", *big).unwrap(),
+                None => write!(out, "{}", arg.to_int()?).unwrap(),
+            },
+            ArgFormat::Oct => match arg.downcast_ref::<BigInt>() {
+                Some(big) => write!(out, "{:o}", *big).unwrap(),
+                None => {
+                    let v = arg.to_int()?;
+                    write!(
+                        out,
+                        "{}{:o}",
+                        if v < 0 { "-" } else { "" },
+                        v.wrapping_abs() as u64
+                    )
+                    .unwrap();
+                }
+            },
+            ArgFormat::HexLower => match arg.downcast_ref::<BigInt>() {
+                Some(big) => write!(out, "{:x}", *big).unwrap(),
+                None => {
+                    let v = arg.to_int()?;
+                    write!(
+                        out,
+                        "{}{:x}",
+                        if v < 0 { "-" } else { "" },
+                        v.wrapping_abs() as u64
+                    )
+                    .unwrap();
+                }
+            },
+            ArgFormat::HexUpper => match arg.downcast_ref::<BigInt>() {
+                Some(big) => write!(out, "{:X}", *big).unwrap(),
+                None => {
+                    let v = arg.to_int()?;
+                    write!(
+                        out,
+                        "{}{:X}",
+                        if v < 0 { "-" } else { "" },
+                        v.wrapping_abs() as u64
+                    )
+                    .unwrap();
+                }
+            },
             ArgFormat::Char => match arg.get_type() {
                 "string" => {
                     if arg.length()? != 1 {
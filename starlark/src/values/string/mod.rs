@@ -92,44 +92,9 @@ impl TypedValue for String {
     ) -> ValueResult {
         let (start, stop, stride) =
             Value::convert_slice_indices(self.len() as i64, start, stop, stride)?;
-        let (low, take, astride) = if stride < 0 {
-            (stop + 1, start - stop, -stride)
-        } else {
-            (start, stop - start, stride)
-        };
-        if take <= 0 {
-            return Ok(Value::from(""));
-        };
-
-        let v: String = self
-            .chars()
-            .skip(low as usize)
-            .take(take as usize)
+        let v: String = slice_items(start, stop, stride, self.chars())
+            .into_iter()
             .collect();
-        let v: String = if stride > 0 {
-            v.chars()
-                .enumerate()
-                .filter_map(|x| {
-                    if 0 == (x.0 as i64 % astride) {
-                        Some(x.1)
-                    } else {
-                        None
-                    }
-                })
-                .collect()
-        } else {
-            v.chars()
-                .rev()
-                .enumerate()
-                .filter_map(|x| {
-                    if 0 == (x.0 as i64 % astride) {
-                        Some(x.1)
-                    } else {
-                        None
-                    }
-                })
-                .collect()
-        };
         Ok(Value::new(v))
     }
 
@@ -13,13 +13,20 @@
 // limitations under the License.
 
 //! Define the tuple type for Starlark.
-use crate::values::error::ValueError;
+use crate::environment::TypeValues;
+use crate::eval::call_stack::CallStack;
+use crate::values::error::{error_repr, ValueError};
+use crate::values::function::ParameterParser;
+use crate::values::function::{FunctionParameter, FunctionSignature, FunctionType};
 use crate::values::iter::TypedIterable;
 use crate::values::*;
+use linked_hash_map::LinkedHashMap;
 use std::cmp::Ordering;
 use std::collections::hash_map::DefaultHasher;
 use std::fmt;
 use std::hash::Hasher;
+use std::iter;
+use std::rc::Rc;
 
 /// A starlark tuple
 #[derive(Debug, Clone)]
@@ -27,74 +34,182 @@ pub struct Tuple {
     content: Vec<Value>,
 }
 
-pub(crate) fn slice_vector<'a, I: Iterator<Item = &'a Value>>(
-    start: i64,
-    stop: i64,
-    stride: i64,
-    content: I,
-) -> Vec<Value> {
-    let (low, take, astride) = if stride < 0 {
-        (stop + 1, start - stop, -stride)
-    } else {
-        (start, stop - start, stride)
+impl Tuple {
+    pub fn new(values: Vec<Value>) -> Tuple {
+        Tuple { content: values }
+    }
+}
+
+/// The name and field names of a named tuple type, shared by all its instances.
+#[derive(Debug)]
+struct NamedTupleNames {
+    typename: String,
+    fields: Vec<String>,
+}
+
+/// An instance of a [`NamedTupleType`]: a tuple whose elements can also be accessed as
+/// attributes.
+///
+/// Its type is `tuple`, and it is equal to the plain tuple with the same elements.
+#[derive(Debug, Clone)]
+pub struct NamedTuple {
+    tuple: Tuple,
+    names: Rc<NamedTupleNames>,
+}
+
+impl NamedTuple {
+    /// The name of the named tuple type of this tuple.
+    pub fn typename(&self) -> &str {
+        &self.names.typename
+    }
+
+    /// The field names of this tuple.
+    pub fn field_names(&self) -> &[String] {
+        &self.names.fields
+    }
+
+    fn field_index(&self, attribute: &str) -> Option<usize> {
+        self.names.fields.iter().position(|f| f == attribute)
+    }
+}
+
+/// The plain tuples to use for an operation between `left` and `right` when at least one
+/// is a named tuple and the other is a tuple.
+pub(crate) fn operands(left: &Value, right: &Value) -> Option<(Tuple, Tuple)> {
+    let as_tuple = |v: &Value| match v.downcast_ref::<NamedTuple>() {
+        Some(t) => Some((t.tuple.clone(), true)),
+        None => v.downcast_ref::<Tuple>().map(|t| (t.clone(), false)),
     };
-    if take <= 0 {
-        return Vec::new();
-    }
-    let mut v: Vec<Value> = content
-        .skip(low as usize)
-        .take(take as usize)
-        .cloned()
-        .collect();
-    if stride < 0 {
-        v.reverse();
-    }
-    v.into_iter()
-        .enumerate()
-        .filter_map(|x| {
-            if 0 == (x.0 as i64 % astride) {
-                Some(x.1)
-            } else {
-                None
-            }
+    match (as_tuple(left), as_tuple(right)) {
+        (Some((l, l_named)), Some((r, r_named))) if l_named || r_named => Some((l, r)),
+        _ => None,
+    }
+}
+
+/// A named tuple type, as created by `namedtuple(typename, fields)`.
+///
+/// Calling it with one argument per field, positionally or by name, creates a tuple whose
+/// elements can also be accessed as attributes. Native functions can use it to return
+/// tuples with named elements, e.g.:
+///
+/// ```
+/// # use starlark::values::*;
+/// # use starlark::values::tuple::NamedTupleType;
+/// let t = NamedTupleType::new("point", vec!["x".to_owned(), "y".to_owned()]);
+/// let p = t.instance(vec![Value::new(1), Value::new(2)]);
+/// assert_eq!("point(x=1, y=2)", p.to_repr());
+/// assert_eq!(2, p.get_attr("y").unwrap().to_int().unwrap());
+/// assert_eq!(p, Value::from((1, 2)));
+/// ```
+#[derive(Debug, Clone)]
+pub struct NamedTupleType {
+    names: Rc<NamedTupleNames>,
+    signature: FunctionSignature,
+    function_type: FunctionType,
+}
+
+impl NamedTupleType {
+    /// Create a named tuple type with the given name and field names.
+    pub fn new(typename: &str, fields: Vec<String>) -> NamedTupleType {
+        let signature = FunctionSignature::new(
+            fields
+                .iter()
+                .map(|f| FunctionParameter::Normal(f.clone()))
+                .collect(),
+            0,
+        );
+        NamedTupleType {
+            names: Rc::new(NamedTupleNames {
+                typename: typename.to_owned(),
+                fields,
+            }),
+            signature,
+            function_type: FunctionType::Native(typename.to_owned()),
+        }
+    }
+
+    /// Create an instance of this type, `values` must have one element per field.
+    pub fn instance(&self, values: Vec<Value>) -> Value {
+        assert_eq!(self.names.fields.len(), values.len());
+        Value::new(NamedTuple {
+            tuple: Tuple::new(values),
+            names: self.names.clone(),
         })
-        .collect()
+    }
 }
 
-impl Tuple {
-    pub fn new(values: Vec<Value>) -> Tuple {
-        Tuple { content: values }
+impl TypedValue for NamedTupleType {
+    type Holder = Immutable<NamedTupleType>;
+
+    fn values_for_descendant_check_and_freeze<'a>(
+        &'a self,
+    ) -> Box<dyn Iterator<Item = Value> + 'a> {
+        Box::new(iter::empty())
+    }
+
+    fn to_repr_impl(&self, buf: &mut String) -> fmt::Result {
+        write!(buf, "namedtuple(")?;
+        Value::from(self.names.typename.as_str()).to_repr_impl(buf)?;
+        write!(buf, ", [")?;
+        for (i, field) in self.names.fields.iter().enumerate() {
+            if i != 0 {
+                write!(buf, ", ")?;
+            }
+            Value::from(field.as_str()).to_repr_impl(buf)?;
+        }
+        write!(buf, "])")
+    }
+
+    const TYPE: &'static str = "namedtuple";
+
+    fn call(
+        &self,
+        _call_stack: &mut CallStack,
+        _type_values: &TypeValues,
+        positional: Vec<Value>,
+        named: LinkedHashMap<String, Value>,
+        args: Option<Value>,
+        kwargs: Option<Value>,
+    ) -> ValueResult {
+        let mut parser = ParameterParser::new(
+            &self.signature,
+            &self.function_type,
+            positional,
+            named,
+            args,
+            kwargs,
+        )?;
+        let mut values = Vec::with_capacity(self.names.fields.len());
+        for field in &self.names.fields {
+            values.push(parser.next_normal(field, false)?);
+        }
+        parser.check_no_more_args()?;
+        Ok(self.instance(values))
     }
 }
 
 impl From<()> for Tuple {
     fn from(_a: ()) -> Tuple {
-        Tuple { content: vec![] }
+        Tuple::new(vec![])
     }
 }
 
 // TODO: Can we do that with macro? i.e. generating the index number automatically?
 impl<T: Into<Value>> From<(T,)> for Tuple {
     fn from(a: (T,)) -> Tuple {
-        Tuple {
-            content: vec![a.0.into()],
-        }
+        Tuple::new(vec![a.0.into()])
     }
 }
 
 impl<T1: Into<Value>, T2: Into<Value>> From<(T1, T2)> for Tuple {
     fn from(a: (T1, T2)) -> Tuple {
-        Tuple {
-            content: vec![a.0.into(), a.1.into()],
-        }
+        Tuple::new(vec![a.0.into(), a.1.into()])
     }
 }
 
 impl<T1: Into<Value>, T2: Into<Value>, T3: Into<Value>> From<(T1, T2, T3)> for Tuple {
     fn from(a: (T1, T2, T3)) -> Tuple {
-        Tuple {
-            content: vec![a.0.into(), a.1.into(), a.2.into()],
-        }
+        Tuple::new(vec![a.0.into(), a.1.into(), a.2.into()])
     }
 }
 
@@ -102,9 +217,7 @@ impl<T1: Into<Value>, T2: Into<Value>, T3: Into<Value>, T4: Into<Value>> From<(T
     for Tuple
 {
     fn from(a: (T1, T2, T3, T4)) -> Tuple {
-        Tuple {
-            content: vec![a.0.into(), a.1.into(), a.2.into(), a.3.into()],
-        }
+        Tuple::new(vec![a.0.into(), a.1.into(), a.2.into(), a.3.into()])
     }
 }
 
@@ -112,9 +225,13 @@ impl<T1: Into<Value>, T2: Into<Value>, T3: Into<Value>, T4: Into<Value>, T5: Int
     From<(T1, T2, T3, T4, T5)> for Tuple
 {
     fn from(a: (T1, T2, T3, T4, T5)) -> Tuple {
-        Tuple {
-            content: vec![a.0.into(), a.1.into(), a.2.into(), a.3.into(), a.4.into()],
-        }
+        Tuple::new(vec![
+            a.0.into(),
+            a.1.into(),
+            a.2.into(),
+            a.3.into(),
+            a.4.into(),
+        ])
     }
 }
 
@@ -128,16 +245,14 @@ impl<
     > From<(T1, T2, T3, T4, T5, T6)> for Tuple
 {
     fn from(a: (T1, T2, T3, T4, T5, T6)) -> Tuple {
-        Tuple {
-            content: vec![
-                a.0.into(),
-                a.1.into(),
-                a.2.into(),
-                a.3.into(),
-                a.4.into(),
-                a.5.into(),
-            ],
-        }
+        Tuple::new(vec![
+            a.0.into(),
+            a.1.into(),
+            a.2.into(),
+            a.3.into(),
+            a.4.into(),
+            a.5.into(),
+        ])
     }
 }
 
@@ -152,17 +267,15 @@ impl<
     > From<(T1, T2, T3, T4, T5, T6, T7)> for Tuple
 {
     fn from(a: (T1, T2, T3, T4, T5, T6, T7)) -> Tuple {
-        Tuple {
-            content: vec![
-                a.0.into(),
-                a.1.into(),
-                a.2.into(),
-                a.3.into(),
-                a.4.into(),
-                a.5.into(),
-                a.6.into(),
-            ],
-        }
+        Tuple::new(vec![
+            a.0.into(),
+            a.1.into(),
+            a.2.into(),
+            a.3.into(),
+            a.4.into(),
+            a.5.into(),
+            a.6.into(),
+        ])
     }
 }
 
@@ -178,18 +291,16 @@ impl<
     > From<(T1, T2, T3, T4, T5, T6, T7, T8)> for Tuple
 {
     fn from(a: (T1, T2, T3, T4, T5, T6, T7, T8)) -> Tuple {
-        Tuple {
-            content: vec![
-                a.0.into(),
-                a.1.into(),
-                a.2.into(),
-                a.3.into(),
-                a.4.into(),
-                a.5.into(),
-                a.6.into(),
-                a.7.into(),
-            ],
-        }
+        Tuple::new(vec![
+            a.0.into(),
+            a.1.into(),
+            a.2.into(),
+            a.3.into(),
+            a.4.into(),
+            a.5.into(),
+            a.6.into(),
+            a.7.into(),
+        ])
     }
 }
 
@@ -206,19 +317,17 @@ impl<
     > From<(T1, T2, T3, T4, T5, T6, T7, T8, T9)> for Tuple
 {
     fn from(a: (T1, T2, T3, T4, T5, T6, T7, T8, T9)) -> Tuple {
-        Tuple {
-            content: vec![
-                a.0.into(),
-                a.1.into(),
-                a.2.into(),
-                a.3.into(),
-                a.4.into(),
-                a.5.into(),
-                a.6.into(),
-                a.7.into(),
-                a.8.into(),
-            ],
-        }
+        Tuple::new(vec![
+            a.0.into(),
+            a.1.into(),
+            a.2.into(),
+            a.3.into(),
+            a.4.into(),
+            a.5.into(),
+            a.6.into(),
+            a.7.into(),
+            a.8.into(),
+        ])
     }
 }
 
@@ -236,20 +345,18 @@ impl<
     > From<(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10)> for Tuple
 {
     fn from(a: (T1, T2, T3, T4, T5, T6, T7, T8, T9, T10)) -> Tuple {
-        Tuple {
-            content: vec![
-                a.0.into(),
-                a.1.into(),
-                a.2.into(),
-                a.3.into(),
-                a.4.into(),
-                a.5.into(),
-                a.6.into(),
-                a.7.into(),
-                a.8.into(),
-                a.9.into(),
-            ],
-        }
+        Tuple::new(vec![
+            a.0.into(),
+            a.1.into(),
+            a.2.into(),
+            a.3.into(),
+            a.4.into(),
+            a.5.into(),
+            a.6.into(),
+            a.7.into(),
+            a.8.into(),
+            a.9.into(),
+        ])
     }
 }
 
@@ -356,11 +463,11 @@ impl TypedValue for Tuple {
     ) -> ValueResult {
         let (start, stop, stride) =
             Value::convert_slice_indices(self.length()?, start, stop, stride)?;
-        Ok(Value::new(Tuple::new(slice_vector(
+        Ok(Value::new(Tuple::new(slice_items(
             start,
             stop,
             stride,
-            self.content.iter(),
+            self.content.iter().cloned(),
         ))))
     }
 
@@ -383,9 +490,7 @@ impl TypedValue for Tuple {
     /// # );
     /// ```
     fn add(&self, other: &Tuple) -> Result<Tuple, ValueError> {
-        let mut result = Tuple {
-            content: Vec::with_capacity(self.content.len() + other.content.len()),
-        };
+        let mut result = Tuple::new(Vec::with_capacity(self.content.len() + other.content.len()));
         for x in &self.content {
             result.content.push(x.clone());
         }
@@ -413,9 +518,7 @@ impl TypedValue for Tuple {
     fn mul(&self, other: Value) -> ValueResult {
         match other.downcast_ref::<i64>() {
             Some(l) => {
-                let mut result = Tuple {
-                    content: Vec::new(),
-                };
+                let mut result = Tuple::new(Vec::new());
                 for _i in 0..*l {
                     result.content.extend(self.content.iter().cloned());
                 }
@@ -426,6 +529,97 @@ impl TypedValue for Tuple {
     }
 }
 
+impl TypedValue for NamedTuple {
+    type Holder = Immutable<NamedTuple>;
+
+    fn values_for_descendant_check_and_freeze<'a>(
+        &'a self,
+    ) -> Box<dyn Iterator<Item = Value> + 'a> {
+        self.tuple.values_for_descendant_check_and_freeze()
+    }
+
+    fn to_repr_impl(&self, buf: &mut String) -> fmt::Result {
+        write!(buf, "{}(", self.names.typename)?;
+        for (i, (name, v)) in self
+            .names
+            .fields
+            .iter()
+            .zip(&self.tuple.content)
+            .enumerate()
+        {
+            if i != 0 {
+                write!(buf, ", ")?;
+            }
+            write!(buf, "{}=", name)?;
+            v.to_repr_impl(buf)?;
+        }
+        write!(buf, ")")
+    }
+    const TYPE: &'static str = "tuple";
+    fn to_bool(&self) -> bool {
+        self.tuple.to_bool()
+    }
+    fn get_hash(&self) -> Result<u64, ValueError> {
+        self.tuple.get_hash()
+    }
+
+    fn equals(&self, other: &NamedTuple) -> Result<bool, ValueError> {
+        self.tuple.equals(&other.tuple)
+    }
+
+    fn compare(&self, other: &NamedTuple) -> Result<Ordering, ValueError> {
+        self.tuple.compare(&other.tuple)
+    }
+
+    fn at(&self, index: Value) -> ValueResult {
+        self.tuple.at(index)
+    }
+
+    fn length(&self) -> Result<i64, ValueError> {
+        self.tuple.length()
+    }
+
+    fn get_attr(&self, attribute: &str) -> ValueResult {
+        match self.field_index(attribute) {
+            Some(i) => Ok(self.tuple.content[i].clone()),
+            None => Err(ValueError::OperationNotSupported {
+                op: format!(".{}", attribute),
+                left: error_repr(self),
+                right: None,
+            }),
+        }
+    }
+
+    fn has_attr(&self, attribute: &str) -> Result<bool, ValueError> {
+        Ok(self.field_index(attribute).is_some())
+    }
+
+    fn dir_attr(&self) -> Result<Vec<String>, ValueError> {
+        Ok(self.names.fields.clone())
+    }
+
+    fn is_in(&self, other: &Value) -> Result<bool, ValueError> {
+        self.tuple.is_in(other)
+    }
+
+    fn slice(
+        &self,
+        start: Option<Value>,
+        stop: Option<Value>,
+        stride: Option<Value>,
+    ) -> ValueResult {
+        self.tuple.slice(start, stop, stride)
+    }
+
+    fn iter(&self) -> Result<&dyn TypedIterable, ValueError> {
+        Ok(&self.tuple)
+    }
+
+    fn mul(&self, other: Value) -> ValueResult {
+        self.tuple.mul(other)
+    }
+}
+
 impl TypedIterable for Tuple {
     fn to_iter<'a>(&'a self) -> Box<dyn Iterator<Item = Value> + 'a> {
         Box::new(self.content.iter().cloned())
@@ -528,4 +722,25 @@ mod tests {
         assert!(!v1.is_descendant_value(&v2));
         assert!(!v1.is_descendant_value(&v3));
     }
+
+    #[test]
+    fn test_named_tuple() {
+        let t = NamedTupleType::new("pair", vec!["first".to_owned(), "second".to_owned()]);
+        let p = t.instance(vec![Value::new(1), Value::from("a")]);
+        assert_eq!("tuple", p.get_type());
+        assert_eq!("pair(first=1, second=\"a\")", p.to_repr());
+        assert_eq!(Value::new(1), p.at(Value::new(0)).unwrap());
+        assert_eq!(Value::from("a"), p.get_attr("second").unwrap());
+        assert!(p.get_attr("third").is_err());
+        assert_eq!(vec!["first", "second"], p.dir_attr().unwrap());
+        assert_eq!(p, Value::from((1, "a")));
+        assert_eq!(Value::from((1, "a")), p);
+        assert_eq!(Ordering::Less, p.compare(&Value::from((1, "b"))).unwrap());
+        // Derived tuples are plain tuples
+        assert_eq!("(1, \"a\", 2)", p.add(Value::from((2,))).unwrap().to_repr());
+        assert_eq!(
+            "(1,)",
+            p.slice(None, Some(Value::new(1)), None).unwrap().to_repr()
+        );
+    }
 }